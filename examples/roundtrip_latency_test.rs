@@ -1,12 +1,10 @@
 use std::env;
 
 use web_audio_api::context::{
-    AudioContext, AudioContextLatencyCategory, AudioContextOptions, BaseAudioContext,
-};
-use web_audio_api::media_devices::{
-    enumerate_devices_sync, get_user_media_sync, MediaDeviceInfo, MediaDeviceInfoKind,
-    MediaStreamConstraints, MediaTrackConstraints,
+    AudioContext, AudioContextInputOptions, AudioContextLatencyCategory, AudioContextOptions,
+    BaseAudioContext,
 };
+use web_audio_api::media_devices::{enumerate_devices_sync, MediaDeviceInfo, MediaDeviceInfoKind};
 use web_audio_api::node::AudioNode;
 use web_audio_api::worklet::{
     AudioParamValues, AudioWorkletGlobalScope, AudioWorkletNode, AudioWorkletNodeOptions,
@@ -19,6 +17,9 @@ use std::sync::Arc;
 // Estimate the audio round-trip latency. Just plug your audio output into the
 // audio input with some cable.
 //
+// The input is captured in the same stream as the output, so the measurement can be
+// compared with the reported input and output latency of the context.
+//
 // `cargo run --release --example roundtrip_latency_test`
 //
 // For testing purposes, a feedback delay line of 17ms is created to emulate audio
@@ -137,6 +138,7 @@ fn main() {
     }
 
     let estimated_latency = Arc::new(AtomicF64::new(0.));
+    let mut reported_context = None;
 
     if test {
         // emulate loopback
@@ -200,6 +202,10 @@ fn main() {
             latency_hint,
             sample_rate: Some(48000.),
             sink_id,
+            input: Some(AudioContextInputOptions {
+                device_id: source_id.unwrap_or_default(),
+                ..AudioContextInputOptions::default()
+            }),
             ..AudioContextOptions::default()
         });
 
@@ -210,15 +216,11 @@ fn main() {
         let latency_tester = AudioWorkletNode::new::<LatencyTesterProcessor>(&context, options);
         latency_tester.connect(&context.destination());
 
-        // open mic stream and pipe into latency_tester
-        let mut constraints = MediaTrackConstraints::default();
-        constraints.device_id = source_id;
-        let stream_constraints = MediaStreamConstraints::AudioWithConstraints(constraints);
-        let mic = get_user_media_sync(stream_constraints);
+        // pipe the input, which is aligned with the output, into latency_tester
+        let input_source = context.create_input_source();
+        input_source.connect(&latency_tester);
 
-        // create media stream source node with mic stream
-        let stream_source = context.create_media_stream_source(&mic);
-        stream_source.connect(&latency_tester);
+        reported_context = Some(context);
     };
 
     loop {
@@ -230,6 +232,11 @@ fn main() {
             println!("- Estimated latency {:?}", latency);
         }
 
+        if let Some(context) = &reported_context {
+            let reported = context.input_latency() + context.output_latency();
+            println!("- Reported latency {:?}", reported);
+        }
+
         std::thread::sleep(std::time::Duration::from_secs(1));
     }
 }
@@ -8,7 +8,7 @@ use std::sync::{Arc, Mutex};
 
 use realfft::{num_complex::Complex, RealFftPlanner};
 
-use crate::{AtomicF32, RENDER_QUANTUM_SIZE};
+use crate::AtomicF32;
 
 /// Blackman window values iterator with alpha = 0.16
 fn generate_blackman(size: usize) -> impl Iterator<Item = f32> {
@@ -73,7 +73,9 @@ fn assert_valid_decibels(min_decibels: f64, max_decibels: f64) {
 
 // as the queue is composed of AtomicF32 having only 1 render quantum of extra
 // room should be enough
-const RING_BUFFER_SIZE: usize = MAX_FFT_SIZE + RENDER_QUANTUM_SIZE;
+fn ring_buffer_size(render_quantum_size: usize) -> usize {
+    MAX_FFT_SIZE + render_quantum_size
+}
 
 // single producer / multiple consumer ring buffer
 #[derive(Clone)]
@@ -83,9 +85,10 @@ pub(crate) struct AnalyserRingBuffer {
 }
 
 impl AnalyserRingBuffer {
-    pub fn new() -> Self {
-        let mut buffer = Vec::with_capacity(RING_BUFFER_SIZE);
-        buffer.resize_with(RING_BUFFER_SIZE, || AtomicF32::new(0.));
+    pub fn new(render_quantum_size: usize) -> Self {
+        let size = ring_buffer_size(render_quantum_size);
+        let mut buffer = Vec::with_capacity(size);
+        buffer.resize_with(size, || AtomicF32::new(0.));
 
         Self {
             buffer: buffer.into(),
@@ -96,16 +99,17 @@ impl AnalyserRingBuffer {
     pub fn write(&self, src: &[f32]) {
         let mut write_index = self.write_index.load(Ordering::SeqCst);
         let len = src.len();
+        let size = self.buffer.len();
 
         src.iter().enumerate().for_each(|(index, value)| {
-            let position = (write_index + index) % RING_BUFFER_SIZE;
+            let position = (write_index + index) % size;
             self.buffer[position].store(*value, Ordering::Relaxed);
         });
 
         write_index += len;
 
-        if write_index >= RING_BUFFER_SIZE {
-            write_index -= RING_BUFFER_SIZE;
+        if write_index >= size {
+            write_index -= size;
         }
 
         self.write_index.store(write_index, Ordering::SeqCst);
@@ -115,13 +119,14 @@ impl AnalyserRingBuffer {
         let write_index = self.write_index.load(Ordering::SeqCst);
         // let fft_size = self.fft_size.load(Ordering::SeqCst);
         let len = dst.len().min(max_len);
+        let size = self.buffer.len();
 
         dst.iter_mut()
             .take(len)
             .enumerate()
             .for_each(|(index, value)| {
-                // offset calculation by RING_BUFFER_SIZE so we can't negative values
-                let position = (RING_BUFFER_SIZE + write_index - len + index) % RING_BUFFER_SIZE;
+                // offset calculation by the buffer size so we can't negative values
+                let position = (size + write_index - len + index) % size;
                 *value = self.buffer[position].load(Ordering::Relaxed);
             });
     }
@@ -129,7 +134,7 @@ impl AnalyserRingBuffer {
     // to simply share tests with the unsafe version
     #[cfg(test)]
     fn raw(&self) -> Vec<f32> {
-        let mut slice = vec![0.; RING_BUFFER_SIZE];
+        let mut slice = vec![0.; self.buffer.len()];
 
         self.buffer.iter().zip(slice.iter_mut()).for_each(|(a, b)| {
             *b = a.load(Ordering::SeqCst);
@@ -168,8 +173,8 @@ impl std::fmt::Debug for Analyser {
 }
 
 impl Analyser {
-    pub fn new() -> Self {
-        let ring_buffer = AnalyserRingBuffer::new();
+    pub fn new(render_quantum_size: usize) -> Self {
+        let ring_buffer = AnalyserRingBuffer::new(render_quantum_size);
         // FFT utils
         let mut fft_planner = RealFftPlanner::<f32>::new();
         let max_fft = fft_planner.plan_fft_forward(MAX_FFT_SIZE);
@@ -410,6 +415,9 @@ mod tests {
     use rand::Rng;
 
     use super::*;
+    use crate::RENDER_QUANTUM_SIZE;
+
+    const RING_BUFFER_SIZE: usize = MAX_FFT_SIZE + RENDER_QUANTUM_SIZE;
 
     #[test]
     fn test_blackman() {
@@ -438,7 +446,7 @@ mod tests {
 
     #[test]
     fn test_ring_buffer_write_simple() {
-        let ring_buffer = AnalyserRingBuffer::new();
+        let ring_buffer = AnalyserRingBuffer::new(RENDER_QUANTUM_SIZE);
 
         // check index update
         {
@@ -468,7 +476,7 @@ mod tests {
     fn test_ring_buffer_write_wrap() {
         // check values are written in right place
         {
-            let ring_buffer = AnalyserRingBuffer::new();
+            let ring_buffer = AnalyserRingBuffer::new(RENDER_QUANTUM_SIZE);
 
             let offset = 10;
             ring_buffer
@@ -493,7 +501,7 @@ mod tests {
 
         // check values are written in right order
         {
-            let ring_buffer = AnalyserRingBuffer::new();
+            let ring_buffer = AnalyserRingBuffer::new(RENDER_QUANTUM_SIZE);
             let offset = 2;
             ring_buffer
                 .write_index
@@ -514,7 +522,7 @@ mod tests {
 
     #[test]
     fn test_ring_buffer_read_simple() {
-        let ring_buffer = Arc::new(AnalyserRingBuffer::new());
+        let ring_buffer = Arc::new(AnalyserRingBuffer::new(RENDER_QUANTUM_SIZE));
 
         // first pass
         let data = [1.; RENDER_QUANTUM_SIZE];
@@ -561,7 +569,7 @@ mod tests {
     fn test_ring_buffer_read_unwrap() {
         // check values are read from right place
         {
-            let ring_buffer = AnalyserRingBuffer::new();
+            let ring_buffer = AnalyserRingBuffer::new(RENDER_QUANTUM_SIZE);
 
             let offset = 10;
             ring_buffer
@@ -579,7 +587,7 @@ mod tests {
 
         // check values are read from right place and written in right order
         {
-            let ring_buffer = AnalyserRingBuffer::new();
+            let ring_buffer = AnalyserRingBuffer::new(RENDER_QUANTUM_SIZE);
             let offset = 2;
             ring_buffer
                 .write_index
@@ -597,7 +605,7 @@ mod tests {
 
     #[test]
     fn test_set_decibels() {
-        let mut analyser = Analyser::new();
+        let mut analyser = Analyser::new(RENDER_QUANTUM_SIZE);
         analyser.set_decibels(-20., 10.);
         assert_eq!(analyser.min_decibels(), -20.);
         assert_eq!(analyser.max_decibels(), 10.);
@@ -606,49 +614,49 @@ mod tests {
     #[test]
     #[should_panic]
     fn test_fft_size_constraints_power_of_two() {
-        let mut analyser = Analyser::new();
+        let mut analyser = Analyser::new(RENDER_QUANTUM_SIZE);
         analyser.set_fft_size(13);
     }
 
     #[test]
     #[should_panic]
     fn test_fft_size_constraints_ge_min_fft_size() {
-        let mut analyser = Analyser::new();
+        let mut analyser = Analyser::new(RENDER_QUANTUM_SIZE);
         analyser.set_fft_size(MIN_FFT_SIZE / 2);
     }
 
     #[test]
     #[should_panic]
     fn test_fft_size_constraints_le_max_fft_size() {
-        let mut analyser = Analyser::new();
+        let mut analyser = Analyser::new(RENDER_QUANTUM_SIZE);
         analyser.set_fft_size(MAX_FFT_SIZE * 2);
     }
 
     #[test]
     #[should_panic]
     fn test_smoothing_time_constant_constraints_lt_zero() {
-        let mut analyser = Analyser::new();
+        let mut analyser = Analyser::new(RENDER_QUANTUM_SIZE);
         analyser.set_smoothing_time_constant(-1.);
     }
 
     #[test]
     #[should_panic]
     fn test_smoothing_time_constant_constraints_gt_one() {
-        let mut analyser = Analyser::new();
+        let mut analyser = Analyser::new(RENDER_QUANTUM_SIZE);
         analyser.set_smoothing_time_constant(2.);
     }
 
     #[test]
     #[should_panic]
     fn test_min_decibels_constraints_lt_max_decibels() {
-        let mut analyser = Analyser::new();
+        let mut analyser = Analyser::new(RENDER_QUANTUM_SIZE);
         analyser.set_decibels(DEFAULT_MAX_DECIBELS, analyser.max_decibels());
     }
 
     #[test]
     #[should_panic]
     fn test_max_decibels_constraints_lt_min_decibels() {
-        let mut analyser = Analyser::new();
+        let mut analyser = Analyser::new(RENDER_QUANTUM_SIZE);
         analyser.set_decibels(analyser.min_decibels(), DEFAULT_MIN_DECIBELS);
     }
 
@@ -656,7 +664,7 @@ mod tests {
     fn test_get_float_time_domain_data_vs_fft_size() {
         // dst is bigger than fft_size
         {
-            let mut analyser = Analyser::new();
+            let mut analyser = Analyser::new(RENDER_QUANTUM_SIZE);
             analyser.set_fft_size(32);
 
             let data = [1.; RENDER_QUANTUM_SIZE];
@@ -674,7 +682,7 @@ mod tests {
 
         // dst is smaller than fft_size
         {
-            let mut analyser = Analyser::new();
+            let mut analyser = Analyser::new(RENDER_QUANTUM_SIZE);
             analyser.set_fft_size(128);
 
             let data = [1.; RENDER_QUANTUM_SIZE];
@@ -692,7 +700,7 @@ mod tests {
 
     #[test]
     fn get_byte_time_domain_data() {
-        let analyser = Analyser::new();
+        let analyser = Analyser::new(RENDER_QUANTUM_SIZE);
 
         let data = [1.; RENDER_QUANTUM_SIZE];
         let buffer = analyser.get_ring_buffer_clone();
@@ -734,7 +742,7 @@ mod tests {
             // @note (tbc): bin 0 seems to represent freq_resolution / 2
             let freq = freq_resolution * num_bin as f32;
 
-            let mut analyser = Analyser::new();
+            let mut analyser = Analyser::new(RENDER_QUANTUM_SIZE);
             analyser.set_fft_size(fft_size);
 
             let mut signal = Vec::<f32>::with_capacity(fft_size);
@@ -763,7 +771,7 @@ mod tests {
 
     #[test]
     fn test_get_float_frequency_data_vs_frequenc_bin_count() {
-        let mut analyser = Analyser::new();
+        let mut analyser = Analyser::new(RENDER_QUANTUM_SIZE);
         analyser.set_fft_size(RENDER_QUANTUM_SIZE);
 
         // get data, should be zero (negative infinity decibel)
@@ -783,7 +791,7 @@ mod tests {
 
     #[test]
     fn test_get_byte_frequency_data_vs_frequenc_bin_count() {
-        let mut analyser = Analyser::new();
+        let mut analyser = Analyser::new(RENDER_QUANTUM_SIZE);
         analyser.set_fft_size(RENDER_QUANTUM_SIZE);
 
         // get data, should be zero (negative infinity decibel)
@@ -800,7 +808,7 @@ mod tests {
     // in an accurante way, other tests are there for such thing
     #[test]
     fn test_ring_buffer_concurrency() {
-        let analyser = Arc::new(Analyser::new());
+        let analyser = Arc::new(Analyser::new(RENDER_QUANTUM_SIZE));
         let ring_buffer = analyser.get_ring_buffer_clone();
         let num_loops = 10_000;
         let (sender, receiver) = crossbeam_channel::bounded(1);
@@ -830,7 +838,7 @@ mod tests {
 
     #[test]
     fn test_thread_safety() {
-        let analyser = Arc::new(RwLock::new(Analyser::new()));
+        let analyser = Arc::new(RwLock::new(Analyser::new(RENDER_QUANTUM_SIZE)));
 
         let handle = thread::spawn(move || {
             analyser.write().unwrap().set_fft_size(MIN_FFT_SIZE);
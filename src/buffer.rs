@@ -2,8 +2,8 @@
 use std::sync::Arc;
 
 use crate::{
-    assert_valid_buffer_length, assert_valid_channel_number, assert_valid_number_of_channels,
-    assert_valid_sample_rate,
+    assert_valid_sample_rate, check_valid_buffer_length, check_valid_channel_number,
+    check_valid_number_of_channels, check_valid_sample_rate, expect_valid, DomException,
 };
 
 /// Options for constructing an [`AudioBuffer`]
@@ -81,16 +81,25 @@ impl AudioBuffer {
     /// - the given number of channels is outside the [1, 32] range,
     /// 32 being defined by the MAX_CHANNELS constant.
     pub fn new(options: AudioBufferOptions) -> Self {
-        assert_valid_sample_rate(options.sample_rate);
-        assert_valid_buffer_length(options.length);
-        assert_valid_number_of_channels(options.number_of_channels);
+        expect_valid(Self::try_new(options))
+    }
+
+    /// Allocate a silent audiobuffer with [`AudioBufferOptions`], see [`Self::new`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotSupportedError` if the sample rate, length or number of channels is invalid
+    pub fn try_new(options: AudioBufferOptions) -> Result<Self, DomException> {
+        check_valid_sample_rate(options.sample_rate)?;
+        check_valid_buffer_length(options.length)?;
+        check_valid_number_of_channels(options.number_of_channels)?;
 
         let silence = ChannelData::new(options.length);
 
-        Self {
+        Ok(Self {
             channels: vec![silence; options.number_of_channels],
             sample_rate: options.sample_rate,
-        }
+        })
     }
 
     /// Convert raw samples to an AudioBuffer
@@ -105,18 +114,30 @@ impl AudioBuffer {
     ///   [1, 32] range, 32 being defined by the MAX_CHANNELS constant.
     /// - any of its items have different lengths
     pub fn from(samples: Vec<Vec<f32>>, sample_rate: f32) -> Self {
-        assert_valid_sample_rate(sample_rate);
-        assert_valid_number_of_channels(samples.len());
+        expect_valid(Self::try_from(samples, sample_rate))
+    }
+
+    /// Convert raw samples to an AudioBuffer, see [`Self::from`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotSupportedError` if the sample rate or number of channels is invalid, or if
+    /// the channels have different lengths
+    pub fn try_from(samples: Vec<Vec<f32>>, sample_rate: f32) -> Result<Self, DomException> {
+        check_valid_sample_rate(sample_rate)?;
+        check_valid_number_of_channels(samples.len())?;
 
         let channels: Vec<_> = samples.into_iter().map(ChannelData::from).collect();
         if !channels.iter().all(|c| c.len() == channels[0].len()) {
-            panic!("Trying to create AudioBuffer from channel data with unequal length");
+            return Err(DomException::NotSupportedError(String::from(
+                "Trying to create AudioBuffer from channel data with unequal length",
+            )));
         }
 
-        Self {
+        Ok(Self {
             channels,
             sample_rate,
-        }
+        })
     }
 
     /// Number of channels in this `AudioBuffer`
@@ -146,8 +167,6 @@ impl AudioBuffer {
     /// This function will panic if `channel_number` is greater or equal than
     /// `AudioBuffer::number_of_channels()`
     pub fn copy_from_channel(&self, destination: &mut [f32], channel_number: usize) {
-        assert_valid_channel_number(channel_number, self.number_of_channels());
-
         self.copy_from_channel_with_offset(destination, channel_number, 0);
     }
 
@@ -163,7 +182,23 @@ impl AudioBuffer {
         channel_number: usize,
         offset: usize,
     ) {
-        assert_valid_channel_number(channel_number, self.number_of_channels());
+        expect_valid(self.try_copy_from_channel_with_offset(destination, channel_number, offset));
+    }
+
+    /// Copy data from a given channel to the given `Vec` starting at `offset`, see
+    /// [`Self::copy_from_channel_with_offset`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `IndexSizeError` if the given channel number is greater than or equal to the
+    /// number of channels.
+    pub fn try_copy_from_channel_with_offset(
+        &self,
+        destination: &mut [f32],
+        channel_number: usize,
+        offset: usize,
+    ) -> Result<(), DomException> {
+        check_valid_channel_number(channel_number, self.number_of_channels())?;
         let offset = offset.min(self.length());
         // [spec] Let buffer be the AudioBuffer with 𝑁𝑏 frames, let 𝑁𝑓 be the number
         // of elements in the destination array, and 𝑘 be the value of bufferOffset.
@@ -174,6 +209,7 @@ impl AudioBuffer {
         let channel = self.channel_data(channel_number).as_slice();
 
         destination[..max_frame].copy_from_slice(&channel[offset..(max_frame + offset)]);
+        Ok(())
     }
 
     /// Copy data from a given source to the given channel.
@@ -183,8 +219,6 @@ impl AudioBuffer {
     /// This function will panic if:
     /// - the given channel number is greater than or equal to the given number of channels.
     pub fn copy_to_channel(&mut self, source: &[f32], channel_number: usize) {
-        assert_valid_channel_number(channel_number, self.number_of_channels());
-
         self.copy_to_channel_with_offset(source, channel_number, 0);
     }
 
@@ -200,7 +234,23 @@ impl AudioBuffer {
         channel_number: usize,
         offset: usize,
     ) {
-        assert_valid_channel_number(channel_number, self.number_of_channels());
+        expect_valid(self.try_copy_to_channel_with_offset(source, channel_number, offset));
+    }
+
+    /// Copy data from a given source to the given channel starting at `offset`, see
+    /// [`Self::copy_to_channel_with_offset`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `IndexSizeError` if the given channel number is greater than or equal to the
+    /// number of channels.
+    pub fn try_copy_to_channel_with_offset(
+        &mut self,
+        source: &[f32],
+        channel_number: usize,
+        offset: usize,
+    ) -> Result<(), DomException> {
+        check_valid_channel_number(channel_number, self.number_of_channels())?;
         let offset = offset.min(self.length());
         // [spec] Let buffer be the AudioBuffer with 𝑁𝑏 frames, let 𝑁𝑓 be the number
         // of elements in the source array, and 𝑘 be the value of bufferOffset. Then
@@ -211,6 +261,7 @@ impl AudioBuffer {
         let channel = self.channel_data_mut(channel_number).as_mut_slice();
 
         channel[offset..(max_frame + offset)].copy_from_slice(&source[..max_frame]);
+        Ok(())
     }
 
     /// Return a read-only copy of the underlying data of the channel
@@ -220,10 +271,21 @@ impl AudioBuffer {
     /// This function will panic if:
     /// - the given channel number is greater than or equal to the given number of channels.
     pub fn get_channel_data(&self, channel_number: usize) -> &[f32] {
-        assert_valid_channel_number(channel_number, self.number_of_channels());
+        expect_valid(self.try_get_channel_data(channel_number))
+    }
+
+    /// Return a read-only copy of the underlying data of the channel, see
+    /// [`Self::get_channel_data`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `IndexSizeError` if the given channel number is greater than or equal to the
+    /// number of channels.
+    pub fn try_get_channel_data(&self, channel_number: usize) -> Result<&[f32], DomException> {
+        check_valid_channel_number(channel_number, self.number_of_channels())?;
         // [spec] According to the rules described in acquire the content either allow writing
         // into or getting a copy of the bytes stored in [[internal data]] in a new Float32Array
-        self.channel_data(channel_number).as_slice()
+        Ok(self.channel_data(channel_number).as_slice())
     }
 
     /// Return a mutable slice of the underlying data of the channel
@@ -233,8 +295,22 @@ impl AudioBuffer {
     /// This function will panic if:
     /// - the given channel number is greater than or equal to the given number of channels.
     pub fn get_channel_data_mut(&mut self, channel_number: usize) -> &mut [f32] {
-        assert_valid_channel_number(channel_number, self.number_of_channels());
-        self.channel_data_mut(channel_number).as_mut_slice()
+        expect_valid(self.try_get_channel_data_mut(channel_number))
+    }
+
+    /// Return a mutable slice of the underlying data of the channel, see
+    /// [`Self::get_channel_data_mut`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `IndexSizeError` if the given channel number is greater than or equal to the
+    /// number of channels.
+    pub fn try_get_channel_data_mut(
+        &mut self,
+        channel_number: usize,
+    ) -> Result<&mut [f32], DomException> {
+        check_valid_channel_number(channel_number, self.number_of_channels())?;
+        Ok(self.channel_data_mut(channel_number).as_mut_slice())
     }
 
     /// Create a multi-channel audiobuffer directly from `ChannelData`s.
@@ -666,6 +742,40 @@ mod tests {
         audio_buffer.get_channel_data(1);
     }
 
+    #[test]
+    fn test_try_variants() {
+        let options = AudioBufferOptions {
+            number_of_channels: 0,
+            length: 10,
+            sample_rate: 48000.,
+        };
+        let error = AudioBuffer::try_new(options).unwrap_err();
+        assert!(matches!(error, DomException::NotSupportedError(_)));
+
+        let error = AudioBuffer::try_from(vec![vec![0.; 2], vec![0.; 3]], 48000.).unwrap_err();
+        assert!(matches!(error, DomException::NotSupportedError(_)));
+
+        let mut audio_buffer = AudioBuffer::try_from(vec![vec![0.; 2]], 48000.).unwrap();
+        let error = audio_buffer.try_get_channel_data(1).unwrap_err();
+        assert!(matches!(error, DomException::IndexSizeError(_)));
+        assert!(audio_buffer.try_get_channel_data_mut(1).is_err());
+        assert!(audio_buffer
+            .try_copy_to_channel_with_offset(&[1.], 1, 0)
+            .is_err());
+        assert!(audio_buffer
+            .try_copy_to_channel_with_offset(&[1.], 0, 1)
+            .is_ok());
+
+        let mut dest = [0.; 2];
+        assert!(audio_buffer
+            .try_copy_from_channel_with_offset(&mut dest, 1, 0)
+            .is_err());
+        assert!(audio_buffer
+            .try_copy_from_channel_with_offset(&mut dest, 0, 0)
+            .is_ok());
+        assert_float_eq!(dest[..], [0., 1.][..], abs_all <= 0.);
+    }
+
     // internal API
     #[test]
     fn test_silent() {
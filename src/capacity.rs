@@ -1,8 +1,9 @@
 use crossbeam_channel::{Receiver, Sender};
+use std::ops::ControlFlow;
 use std::sync::{Arc, Mutex};
 
 use crate::context::{BaseAudioContext, ConcreteBaseAudioContext};
-use crate::events::{EventDispatch, EventHandler, EventPayload, EventType};
+use crate::events::{EventDispatch, EventHandler, EventPayload, EventStream, EventType};
 use crate::Event;
 
 #[derive(Copy, Clone, Debug)]
@@ -11,6 +12,111 @@ pub(crate) struct AudioRenderCapacityLoad {
     pub load_value: f64,
 }
 
+/// Playout statistics of an `AudioContext`, as returned by [`AudioContext::playout_stats`]
+///
+/// Durations and latencies are expressed in seconds. Only the system-level audio callbacks that
+/// happen while the context is running are taken into account.
+///
+/// The audio backends do not report buffer underruns (xruns), so the underruns are estimated by
+/// the render thread from the time it takes to render each callback: when rendering takes
+/// longer than the duration of the callback buffer, the excess is counted as underrun frames.
+/// Underruns caused by the audio device or the operating system scheduling the callbacks late
+/// are not detected.
+///
+/// [`AudioContext::playout_stats`]: crate::context::AudioContext::playout_stats
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AudioPlaybackStats {
+    /// Total number of sample frames played by the audio output device
+    pub total_frames: u64,
+    /// Duration of `total_frames`
+    pub total_duration: f64,
+    /// Estimated number of sample frames that could not be rendered in time, i.e. frames for
+    /// which the audio output device played fallback (silent) audio
+    pub estimated_underrun_frames: u64,
+    /// Duration of `estimated_underrun_frames`
+    pub estimated_underrun_duration: f64,
+    /// Estimated number of distinct buffer underruns, i.e. callbacks that were rendered too late
+    pub estimated_underrun_events: u64,
+    /// Average output latency since the creation of the context or the last latency reset
+    pub average_latency: f64,
+    /// Minimum output latency since the creation of the context or the last latency reset
+    pub minimum_latency: f64,
+    /// Maximum output latency since the creation of the context or the last latency reset
+    pub maximum_latency: f64,
+}
+
+/// Accumulator for the playout statistics, filled by the render thread
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct PlayoutStats {
+    total_frames: u64,
+    underrun_frames: u64,
+    underrun_events: u64,
+    latency_sum: f64,
+    latency_count: u64,
+    minimum_latency: f64,
+    maximum_latency: f64,
+}
+
+impl PlayoutStats {
+    /// Record a single system-level audio callback
+    pub fn record(&mut self, frames: u64, underrun_frames: u64, latency: f64) {
+        self.merge(&Self {
+            total_frames: frames,
+            underrun_frames,
+            underrun_events: u64::from(underrun_frames > 0),
+            latency_sum: latency,
+            latency_count: 1,
+            minimum_latency: latency,
+            maximum_latency: latency,
+        });
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        self.total_frames += other.total_frames;
+        self.underrun_frames += other.underrun_frames;
+        self.underrun_events += other.underrun_events;
+
+        if other.latency_count == 0 {
+            return;
+        }
+        if self.latency_count == 0 {
+            self.minimum_latency = other.minimum_latency;
+            self.maximum_latency = other.maximum_latency;
+        } else {
+            self.minimum_latency = self.minimum_latency.min(other.minimum_latency);
+            self.maximum_latency = self.maximum_latency.max(other.maximum_latency);
+        }
+        self.latency_sum += other.latency_sum;
+        self.latency_count += other.latency_count;
+    }
+
+    pub fn reset_latency(&mut self) {
+        self.latency_sum = 0.;
+        self.latency_count = 0;
+        self.minimum_latency = 0.;
+        self.maximum_latency = 0.;
+    }
+
+    pub fn snapshot(&self, sample_rate: f32) -> AudioPlaybackStats {
+        let average_latency = if self.latency_count == 0 {
+            0.
+        } else {
+            self.latency_sum / self.latency_count as f64
+        };
+
+        AudioPlaybackStats {
+            total_frames: self.total_frames,
+            total_duration: self.total_frames as f64 / sample_rate as f64,
+            estimated_underrun_frames: self.underrun_frames,
+            estimated_underrun_duration: self.underrun_frames as f64 / sample_rate as f64,
+            estimated_underrun_events: self.underrun_events,
+            average_latency,
+            minimum_latency: self.minimum_latency,
+            maximum_latency: self.maximum_latency,
+        }
+    }
+}
+
 /// Options for constructing an `AudioRenderCapacity`
 #[derive(Clone, Debug)]
 pub struct AudioRenderCapacityOptions {
@@ -194,12 +300,53 @@ impl AudioRenderCapacity {
     pub fn clear_onupdate(&self) {
         self.context.clear_event_handler(EventType::RenderCapacity);
     }
+
+    /// Stream of [`AudioRenderCapacityEvent`]s
+    ///
+    /// Events are only dispatched while the metric collection is running, see
+    /// [`start`](Self::start).
+    pub fn update_stream(&self) -> EventStream<AudioRenderCapacityEvent> {
+        EventStream::new(&self.context, EventType::RenderCapacity, |v| match v {
+            EventPayload::RenderCapacity(v) => ControlFlow::Continue(Some(v)),
+            _ => unreachable!(),
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::context::{AudioContext, AudioContextOptions};
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_playout_stats() {
+        let mut stats = PlayoutStats::default();
+        assert_eq!(stats.snapshot(48000.), AudioPlaybackStats::default());
+
+        stats.record(480, 0, 0.02);
+        stats.record(480, 48, 0.04);
+        stats.record(480, 96, 0.03);
+
+        let snapshot = stats.snapshot(48000.);
+        assert_eq!(snapshot.total_frames, 1440);
+        assert_float_eq!(snapshot.total_duration, 0.03, abs <= 1e-12);
+        assert_eq!(snapshot.estimated_underrun_frames, 144);
+        assert_float_eq!(snapshot.estimated_underrun_duration, 0.003, abs <= 1e-12);
+        assert_eq!(snapshot.estimated_underrun_events, 2);
+        assert_float_eq!(snapshot.average_latency, 0.03, abs <= 1e-12);
+        assert_float_eq!(snapshot.minimum_latency, 0.02, abs <= 1e-12);
+        assert_float_eq!(snapshot.maximum_latency, 0.04, abs <= 1e-12);
+
+        stats.reset_latency();
+        stats.record(480, 0, 0.05);
+        let snapshot = stats.snapshot(48000.);
+        assert_eq!(snapshot.total_frames, 1920);
+        assert_eq!(snapshot.estimated_underrun_events, 2);
+        assert_float_eq!(snapshot.average_latency, 0.05, abs <= 1e-12);
+        assert_float_eq!(snapshot.minimum_latency, 0.05, abs <= 1e-12);
+        assert_float_eq!(snapshot.maximum_latency, 0.05, abs <= 1e-12);
+    }
 
     #[test]
     fn test_same_instance() {
@@ -0,0 +1,387 @@
+use crossbeam_channel::{Receiver, SendError, Sender, TrySendError};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock, RwLockWriteGuard};
+
+use crate::events::EventDispatch;
+use crate::message::ControlMessage;
+
+/// Behavior of the control thread when the message channel to the render thread is full
+///
+/// The render thread handles a limited number of control messages (add/remove node, settings,
+/// ..) per render quantum. Creating many nodes at once can exceed this capacity.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ControlOverflowPolicy {
+    /// Block the control thread until the render thread has made room (default)
+    #[default]
+    Block,
+    /// Store excess messages in an unbounded overflow queue. A dedicated thread forwards them to
+    /// the render thread in order, so the render thread never allocates or frees the queue.
+    Grow,
+    /// Reject excess automation events of the fallible `try_` methods of
+    /// [`AudioParam`](crate::AudioParam), e.g.
+    /// [`AudioParam::try_set_value_at_time`](crate::AudioParam::try_set_value_at_time). These
+    /// return a [`DomException::QuotaExceededError`](crate::DomException) without any effect, so
+    /// the call can be retried later. Rejected messages are counted in
+    /// [`MessageChannelStats::control_messages_rejected`].
+    ///
+    /// All other messages are never discarded, so the state of the render thread keeps matching
+    /// the control thread. For these the control thread blocks until the render thread has made
+    /// room.
+    Reject,
+}
+
+/// Behavior of the render thread when the event channel to the control thread is full
+///
+/// The render thread never blocks on the event channel.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum EventOverflowPolicy {
+    /// Drop excess events (default), they are counted in [`MessageChannelStats::events_dropped`]
+    #[default]
+    Drop,
+    /// Store excess events in an unbounded queue. A dedicated thread moves the events from the
+    /// render thread into this queue, so the render thread never allocates or frees the queue.
+    Grow,
+}
+
+/// Counters of the message channels between the control and render thread, as returned by
+/// [`AudioContext::message_channel_stats`](crate::context::AudioContext::message_channel_stats)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MessageChannelStats {
+    /// Number of control messages queued for the render thread
+    pub control_messages_queued: u64,
+    /// Number of control messages that were stored in the overflow queue
+    pub control_messages_overflowed: u64,
+    /// Number of control messages currently waiting in the overflow queue
+    pub control_messages_pending: u64,
+    /// Number of control messages that were discarded because the channel was full
+    pub control_messages_rejected: u64,
+    /// Number of events queued for the control thread
+    pub events_queued: u64,
+    /// Number of events that were dropped because the channel was full
+    pub events_dropped: u64,
+}
+
+/// Message counters, shared between the control and render thread
+#[derive(Debug, Default)]
+pub(crate) struct ChannelCounters {
+    control_queued: AtomicU64,
+    control_overflowed: AtomicU64,
+    control_pending: AtomicU64,
+    control_rejected: AtomicU64,
+    events_queued: AtomicU64,
+    events_dropped: AtomicU64,
+}
+
+impl ChannelCounters {
+    pub fn snapshot(&self) -> MessageChannelStats {
+        MessageChannelStats {
+            control_messages_queued: self.control_queued.load(Ordering::Relaxed),
+            control_messages_overflowed: self.control_overflowed.load(Ordering::Relaxed),
+            control_messages_pending: self.control_pending.load(Ordering::Relaxed),
+            control_messages_rejected: self.control_rejected.load(Ordering::Relaxed),
+            events_queued: self.events_queued.load(Ordering::Relaxed),
+            events_dropped: self.events_dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Reason a control message could not be sent, the message is discarded
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ControlSendError {
+    /// The channel is full (`Reject` policy and [`ControlSender::try_send`] only, never for
+    /// topology changes)
+    Full,
+    /// The render thread is closed
+    Disconnected,
+}
+
+/// Sending half of the control message channel, applying the [`ControlOverflowPolicy`]
+pub(crate) struct ControlSender {
+    /// The channel to the render thread, locked exclusively while the render thread is replaced
+    sender: Arc<RwLock<Sender<ControlMessage>>>,
+    policy: ControlOverflowPolicy,
+    /// Overflow queue, consumed by the forwarding thread (`Grow` policy only)
+    overflow: Option<Sender<ControlMessage>>,
+    counters: Arc<ChannelCounters>,
+}
+
+impl std::fmt::Debug for ControlSender {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ControlSender")
+            .field("policy", &self.policy)
+            .field("counters", &self.counters)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ControlSender {
+    pub fn new(
+        sender: Sender<ControlMessage>,
+        policy: ControlOverflowPolicy,
+        counters: Arc<ChannelCounters>,
+    ) -> Self {
+        let sender = Arc::new(RwLock::new(sender));
+
+        let overflow = (policy == ControlOverflowPolicy::Grow).then(|| {
+            let (overflow_send, overflow_recv) = crossbeam_channel::unbounded();
+            let sender = Arc::clone(&sender);
+            let counters = Arc::clone(&counters);
+            std::thread::spawn(move || {
+                forward_control_messages(&overflow_recv, &sender, &counters)
+            });
+            overflow_send
+        });
+
+        Self {
+            sender,
+            policy,
+            overflow,
+            counters,
+        }
+    }
+
+    /// Send a message to the render thread
+    ///
+    /// Returns an error when the render thread is closed. The message is never rejected, with
+    /// the `Reject` policy the control thread blocks until the render thread has made room.
+    pub fn send(&self, msg: ControlMessage) -> Result<(), ControlSendError> {
+        self.send_inner(msg, false)
+    }
+
+    /// Send a message to the render thread, or reject it when the channel is full
+    ///
+    /// Returns an error when the render thread is closed, or when the channel is full with the
+    /// `Reject` policy and the message does not change the graph topology. Callers must not have
+    /// changed any state for this message yet.
+    pub fn try_send(&self, msg: ControlMessage) -> Result<(), ControlSendError> {
+        self.send_inner(msg, true)
+    }
+
+    fn send_inner(&self, msg: ControlMessage, reject: bool) -> Result<(), ControlSendError> {
+        let sender = self.sender.read().unwrap();
+
+        let result = match &self.overflow {
+            // keep the order of messages: bypass the channel while the overflow queue is in use
+            Some(_) if self.counters.control_pending.load(Ordering::Acquire) > 0 => {
+                Err(TrySendError::Full(msg))
+            }
+            // topology changes are never rejected, wait for the render thread instead
+            _ if self.policy == ControlOverflowPolicy::Block
+                || (self.policy == ControlOverflowPolicy::Reject
+                    && (!reject || msg.edits_topology())) =>
+            {
+                sender
+                    .send(msg)
+                    .map_err(|SendError(msg)| TrySendError::Disconnected(msg))
+            }
+            _ => sender.try_send(msg),
+        };
+
+        let result = match (result, &self.overflow) {
+            (Err(TrySendError::Full(msg)), Some(overflow)) => {
+                self.counters.control_pending.fetch_add(1, Ordering::AcqRel);
+                self.counters
+                    .control_overflowed
+                    .fetch_add(1, Ordering::Relaxed);
+                // the forwarding thread only exits when this sender is dropped
+                overflow.send(msg).unwrap();
+                Ok(())
+            }
+            (Err(TrySendError::Full(_)), None) => {
+                self.counters
+                    .control_rejected
+                    .fetch_add(1, Ordering::Relaxed);
+                return Err(ControlSendError::Full);
+            }
+            (Err(TrySendError::Disconnected(_)), _) => Err(ControlSendError::Disconnected),
+            (Ok(()), _) => Ok(()),
+        };
+
+        if result.is_ok() {
+            self.counters.control_queued.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+
+    pub fn stats(&self) -> MessageChannelStats {
+        self.counters.snapshot()
+    }
+
+    /// Acquire exclusive access to the channel, blocking all other senders
+    pub fn lock(&self) -> RwLockWriteGuard<'_, Sender<ControlMessage>> {
+        self.sender.write().unwrap()
+    }
+}
+
+/// Move messages from the overflow queue to the render thread, blocking when the channel is full
+fn forward_control_messages(
+    overflow: &Receiver<ControlMessage>,
+    sender: &RwLock<Sender<ControlMessage>>,
+    counters: &ChannelCounters,
+) {
+    for msg in overflow.iter() {
+        let result = sender.read().unwrap().send(msg);
+        counters.control_pending.fetch_sub(1, Ordering::AcqRel);
+        if result.is_err() {
+            log::warn!("Discarding control message - render thread is closed");
+        }
+    }
+}
+
+/// Sending half of the event channel, counting the queued and dropped events
+#[derive(Clone, Debug)]
+pub(crate) struct EventSender {
+    sender: Sender<EventDispatch>,
+    counters: Arc<ChannelCounters>,
+}
+
+impl EventSender {
+    pub fn new(sender: Sender<EventDispatch>, counters: Arc<ChannelCounters>) -> Self {
+        Self { sender, counters }
+    }
+
+    /// Send an event without blocking, used by the render thread
+    pub fn try_send(&self, event: EventDispatch) -> Result<(), TrySendError<EventDispatch>> {
+        let result = self.sender.try_send(event);
+        match &result {
+            Ok(()) => self.counters.events_queued.fetch_add(1, Ordering::Relaxed),
+            Err(_) => self.counters.events_dropped.fetch_add(1, Ordering::Relaxed),
+        };
+        result
+    }
+}
+
+impl From<Sender<EventDispatch>> for EventSender {
+    fn from(sender: Sender<EventDispatch>) -> Self {
+        Self::new(sender, Arc::default())
+    }
+}
+
+/// Set up the event channel from the render thread to the control thread
+///
+/// With the `Grow` policy, a dedicated thread moves the events from the bounded channel into an
+/// unbounded channel that is consumed by the event loop.
+pub(crate) fn event_channel(
+    capacity: usize,
+    policy: EventOverflowPolicy,
+    counters: Arc<ChannelCounters>,
+) -> (EventSender, Receiver<EventDispatch>) {
+    let (event_send, event_recv) = crossbeam_channel::bounded(capacity);
+    let event_send = EventSender::new(event_send, counters);
+
+    match policy {
+        EventOverflowPolicy::Drop => (event_send, event_recv),
+        EventOverflowPolicy::Grow => {
+            let (grow_send, grow_recv) = crossbeam_channel::unbounded();
+            std::thread::spawn(move || {
+                for event in event_recv.iter() {
+                    if grow_send.send(event).is_err() {
+                        return; // event loop has terminated
+                    }
+                }
+            });
+            (event_send, grow_recv)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::AudioNodeId;
+
+    fn message() -> ControlMessage {
+        ControlMessage::SetProfiling { enabled: true }
+    }
+
+    #[test]
+    fn test_reject_policy() {
+        let (send, recv) = crossbeam_channel::bounded(1);
+        let counters = Arc::new(ChannelCounters::default());
+        let sender = ControlSender::new(send, ControlOverflowPolicy::Reject, Arc::clone(&counters));
+
+        assert!(sender.try_send(message()).is_ok());
+        assert_eq!(sender.try_send(message()), Err(ControlSendError::Full));
+
+        let stats = counters.snapshot();
+        assert_eq!(stats.control_messages_queued, 1);
+        assert_eq!(stats.control_messages_rejected, 1);
+        assert_eq!(recv.try_iter().count(), 1);
+
+        // the infallible path waits for the render thread instead
+        assert!(sender.try_send(message()).is_ok());
+        let receiver = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            recv.iter().count()
+        });
+        assert!(sender.send(message()).is_ok());
+        drop(sender);
+        assert_eq!(receiver.join().unwrap(), 2);
+        assert_eq!(counters.snapshot().control_messages_rejected, 1);
+    }
+
+    #[test]
+    fn test_reject_policy_topology() {
+        let (send, recv) = crossbeam_channel::bounded(1);
+        let counters = Arc::new(ChannelCounters::default());
+        let sender = ControlSender::new(send, ControlOverflowPolicy::Reject, Arc::clone(&counters));
+
+        assert!(sender.send(message()).is_ok());
+
+        // topology changes wait for the render thread instead of being rejected
+        let receiver = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            recv.iter().count()
+        });
+        let msg = ControlMessage::ControlHandleDropped { id: AudioNodeId(0) };
+        assert!(sender.try_send(msg).is_ok());
+        drop(sender);
+        assert_eq!(receiver.join().unwrap(), 2);
+
+        let stats = counters.snapshot();
+        assert_eq!(stats.control_messages_queued, 2);
+        assert_eq!(stats.control_messages_rejected, 0);
+    }
+
+    #[test]
+    fn test_grow_policy() {
+        let (send, recv) = crossbeam_channel::bounded(1);
+        let counters = Arc::new(ChannelCounters::default());
+        let sender = ControlSender::new(send, ControlOverflowPolicy::Grow, Arc::clone(&counters));
+
+        // the control thread never blocks
+        for id in 0..10 {
+            let msg = ControlMessage::ControlHandleDropped {
+                id: AudioNodeId(id),
+            };
+            assert!(sender.send(msg).is_ok());
+        }
+
+        let stats = counters.snapshot();
+        assert_eq!(stats.control_messages_queued, 10);
+        assert_eq!(stats.control_messages_overflowed, 9);
+        assert_eq!(stats.control_messages_rejected, 0);
+
+        // all messages arrive in order
+        let ids: Vec<_> = (0..10)
+            .map(|_| match recv.recv().unwrap() {
+                ControlMessage::ControlHandleDropped { id } => id.0,
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(ids, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_event_channel() {
+        let counters = Arc::new(ChannelCounters::default());
+        let (send, recv) = event_channel(1, EventOverflowPolicy::Drop, Arc::clone(&counters));
+        assert!(send.try_send(EventDispatch::sink_change()).is_ok());
+        assert!(send.try_send(EventDispatch::sink_change()).is_err());
+        assert_eq!(recv.try_iter().count(), 1);
+
+        let stats = counters.snapshot();
+        assert_eq!(stats.events_queued, 1);
+        assert_eq!(stats.events_dropped, 1);
+    }
+}
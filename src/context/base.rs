@@ -6,11 +6,17 @@ use crate::context::{
     DESTINATION_NODE_ID,
 };
 use crate::decoding::MediaDecoder;
-use crate::events::{Event, EventHandler, EventType};
+use crate::events::{
+    AddEventListenerOptions, AudioContextEventType, Event, EventHandler, EventListenerHandle,
+    EventPayload, EventStream, EventType, LocalEventHandler,
+};
+use crate::message::ControlMessage;
 use crate::node::{AudioNode, AudioNodeOptions};
 use crate::param::AudioParamDescriptor;
 use crate::periodic_wave::{PeriodicWave, PeriodicWaveOptions};
-use crate::{node, AudioListener};
+use crate::{node, AudioGraphDiagnostics, AudioListener, DomException};
+
+use std::ops::ControlFlow;
 
 /// The interface representing an audio-processing graph built from audio modules linked together,
 /// each represented by an `AudioNode`.
@@ -103,6 +109,26 @@ pub trait BaseAudioContext {
         AudioBuffer::new(options)
     }
 
+    /// Create an new "in-memory" `AudioBuffer`, see [`Self::create_buffer`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotSupportedError` if the number of channels, length or sample rate is invalid
+    fn try_create_buffer(
+        &self,
+        number_of_channels: usize,
+        length: usize,
+        sample_rate: f32,
+    ) -> Result<AudioBuffer, DomException> {
+        let options = AudioBufferOptions {
+            number_of_channels,
+            length,
+            sample_rate,
+        };
+
+        AudioBuffer::try_new(options)
+    }
+
     /// Creates a `AnalyserNode`
     #[must_use]
     fn create_analyser(&self) -> node::AnalyserNode {
@@ -143,6 +169,22 @@ pub trait BaseAudioContext {
         node::ChannelMergerNode::new(self.base(), opts)
     }
 
+    /// Creates a `ChannelMergerNode`, see [`Self::create_channel_merger`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `IndexSizeError` if the number of inputs is outside the [1, 32] range
+    fn try_create_channel_merger(
+        &self,
+        number_of_inputs: usize,
+    ) -> Result<node::ChannelMergerNode, DomException> {
+        let opts = node::ChannelMergerOptions {
+            number_of_inputs,
+            ..node::ChannelMergerOptions::default()
+        };
+        node::ChannelMergerNode::try_new(self.base(), opts)
+    }
+
     /// Creates a `ChannelSplitterNode`
     #[must_use]
     fn create_channel_splitter(&self, number_of_outputs: usize) -> node::ChannelSplitterNode {
@@ -153,6 +195,22 @@ pub trait BaseAudioContext {
         node::ChannelSplitterNode::new(self.base(), opts)
     }
 
+    /// Creates a `ChannelSplitterNode`, see [`Self::create_channel_splitter`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `IndexSizeError` if the number of outputs is outside the [1, 32] range
+    fn try_create_channel_splitter(
+        &self,
+        number_of_outputs: usize,
+    ) -> Result<node::ChannelSplitterNode, DomException> {
+        let opts = node::ChannelSplitterOptions {
+            number_of_outputs,
+            ..node::ChannelSplitterOptions::default()
+        };
+        node::ChannelSplitterNode::try_new(self.base(), opts)
+    }
+
     /// Creates a `DelayNode`, delaying the audio signal
     #[must_use]
     fn create_delay(&self, max_delay_time: f64) -> node::DelayNode {
@@ -163,6 +221,19 @@ pub trait BaseAudioContext {
         node::DelayNode::new(self.base(), opts)
     }
 
+    /// Creates a `DelayNode`, see [`Self::create_delay`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotSupportedError` if the max delay time is not in the (0, 180) range
+    fn try_create_delay(&self, max_delay_time: f64) -> Result<node::DelayNode, DomException> {
+        let opts = node::DelayOptions {
+            max_delay_time,
+            ..node::DelayOptions::default()
+        };
+        node::DelayNode::try_new(self.base(), opts)
+    }
+
     /// Creates a `DynamicsCompressorNode`, compressing the audio signal
     #[must_use]
     fn create_dynamics_compressor(&self) -> node::DynamicsCompressorNode {
@@ -193,6 +264,24 @@ pub trait BaseAudioContext {
         node::IIRFilterNode::new(self.base(), options)
     }
 
+    /// Creates an `IirFilterNode`, see [`Self::create_iir_filter`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotSupportedError` or `InvalidStateError` if the coefficients are invalid
+    fn try_create_iir_filter(
+        &self,
+        feedforward: Vec<f64>,
+        feedback: Vec<f64>,
+    ) -> Result<node::IIRFilterNode, DomException> {
+        let options = node::IIRFilterOptions {
+            audio_node_options: AudioNodeOptions::default(),
+            feedforward,
+            feedback,
+        };
+        node::IIRFilterNode::try_new(self.base(), options)
+    }
+
     /// Creates an `OscillatorNode`, a source representing a periodic waveform.
     #[must_use]
     fn create_oscillator(&self) -> node::OscillatorNode {
@@ -274,6 +363,16 @@ pub trait BaseAudioContext {
         self.base().sample_rate()
     }
 
+    /// The number of sample frames in a render quantum of this context, i.e. the block size at
+    /// which the audio graph is rendered.
+    ///
+    /// This is 128 by default and can be requested with the `render_size_hint` of the context
+    /// options.
+    #[must_use]
+    fn render_quantum_size(&self) -> usize {
+        self.base().render_quantum_size()
+    }
+
     /// Returns state of current context
     #[must_use]
     fn state(&self) -> AudioContextState {
@@ -312,7 +411,8 @@ pub trait BaseAudioContext {
     /// Register callback to run when the state of the AudioContext has changed
     ///
     /// Only a single event handler is active at any time. Calling this method multiple times will
-    /// override the previous event handler.
+    /// override the previous event handler. Use
+    /// [`add_event_listener`](Self::add_event_listener) to register additional callbacks.
     fn set_onstatechange<F: FnMut(Event) + Send + 'static>(&self, mut callback: F) {
         let callback = move |_| {
             callback(Event {
@@ -331,6 +431,159 @@ pub trait BaseAudioContext {
         self.base().clear_event_handler(EventType::StateChange);
     }
 
+    /// Register an additional callback to run when an event of the given type is fired
+    ///
+    /// Unlike the `set_on*` methods, multiple listeners can be active at the same time. Listeners
+    /// and the `set_on*` handler run in the order they were first registered. Use the returned
+    /// handle to remove the listener with [`remove_event_listener`](Self::remove_event_listener).
+    fn add_event_listener<F: FnMut(Event) + Send + 'static>(
+        &self,
+        type_: AudioContextEventType,
+        options: AddEventListenerOptions,
+        mut callback: F,
+    ) -> EventListenerHandle {
+        let (event_type, name) = match type_ {
+            AudioContextEventType::StateChange => (EventType::StateChange, "statechange"),
+            AudioContextEventType::SinkChange => (EventType::SinkChange, "sinkchange"),
+            AudioContextEventType::Error => (EventType::Error, "error"),
+        };
+        let callback = move |_| callback(Event { type_: name });
+
+        let handler = if options.once {
+            EventHandler::Once(Box::new(callback))
+        } else {
+            EventHandler::Multiple(Box::new(callback))
+        };
+        self.base().add_event_handler(event_type, handler)
+    }
+
+    /// Register a callback that need not be `Send` to run when an event of the given type is fired
+    ///
+    /// The callback only runs when the events are dispatched on the thread that registered it:
+    /// - for an [`AudioContext`](crate::context::AudioContext) created with
+    ///   [`EventDispatchMode::Manual`](crate::EventDispatchMode::Manual), when this thread calls
+    ///   [`dispatch_pending_events`](crate::context::AudioContext::dispatch_pending_events)
+    /// - for an [`OfflineAudioContext`](crate::context::OfflineAudioContext), when this thread
+    ///   renders the context
+    ///
+    /// Local listeners run after the other handlers of the event. Remove the listener with
+    /// [`remove_event_listener`](Self::remove_event_listener) on the same thread. The listeners
+    /// that are left are removed when the context is dropped on this thread, or else with the
+    /// next local listener registered on this thread.
+    ///
+    /// Local listeners are available for the events of the context and for the `ended` event of
+    /// source nodes, see
+    /// [`add_local_ended_listener`](crate::node::AudioScheduledSourceNode::add_local_ended_listener).
+    /// The other events, e.g. the render capacity updates, messages and processor errors, only
+    /// accept `Send` handlers.
+    fn add_local_event_listener<F: FnMut(Event) + 'static>(
+        &self,
+        type_: AudioContextEventType,
+        options: AddEventListenerOptions,
+        mut callback: F,
+    ) -> EventListenerHandle {
+        let (event_type, name) = match type_ {
+            AudioContextEventType::StateChange => (EventType::StateChange, "statechange"),
+            AudioContextEventType::SinkChange => (EventType::SinkChange, "sinkchange"),
+            AudioContextEventType::Error => (EventType::Error, "error"),
+        };
+        let callback = move |_| callback(Event { type_: name });
+
+        let handler = if options.once {
+            LocalEventHandler::Once(Box::new(callback))
+        } else {
+            LocalEventHandler::Multiple(Box::new(callback))
+        };
+        self.base().add_local_event_handler(event_type, handler)
+    }
+
+    /// Stream of the state changes of the AudioContext
+    ///
+    /// The stream ends after the context has been closed.
+    fn state_change_stream(&self) -> EventStream<AudioContextState> {
+        let mut previous = None;
+        EventStream::new(self.base(), EventType::StateChange, move |v| match v {
+            // skip repeated notifications of the same state
+            EventPayload::AudioContextState(state) if previous == Some(state) => {
+                ControlFlow::Continue(None)
+            }
+            EventPayload::AudioContextState(state) => {
+                previous = Some(state);
+                ControlFlow::Continue(Some(state))
+            }
+            // the closed state is dispatched without payload
+            EventPayload::None => ControlFlow::Break(AudioContextState::Closed),
+            _ => unreachable!(),
+        })
+    }
+
+    /// Remove a listener that was registered with [`add_event_listener`](Self::add_event_listener)
+    /// or [`add_ended_listener`](crate::node::AudioScheduledSourceNode::add_ended_listener)
+    ///
+    /// Removing a listener that has already been removed has no effect.
+    fn remove_event_listener(&self, handle: EventListenerHandle) {
+        self.base().remove_event_handler(handle);
+    }
+
+    /// Apply the changes to the audio graph made by `f` at once
+    ///
+    /// Connections, disconnections, channel configuration changes and node updates (e.g.
+    /// starting a source) made by `f` on the current thread are sent to the render thread as a
+    /// single batch, which is applied at the start of a single render quantum. Large rewires (e.g.
+    /// swapping an effect chain) therefore never render in a half-connected state. Nodes created
+    /// by `f` are added to the audio graph right away, they are silent until connected.
+    ///
+    /// Transactions can be nested, the inner transactions are part of the outer transaction.
+    fn transaction<R, F: FnOnce() -> R>(&self, f: F) -> R {
+        self.base().transaction(None, f)
+    }
+
+    /// Apply the changes to the audio graph made by `f` at once, at the given time
+    ///
+    /// The changes are applied at the start of the first render quantum that starts at or after
+    /// `when`, in seconds in the same time coordinate system as
+    /// [`current_time`](Self::current_time). See [`transaction`](Self::transaction) for details.
+    ///
+    /// Only the changes made by `f` are held back, graph changes made after this call are applied
+    /// right away.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `when` is negative or not finite
+    fn transaction_at<R, F: FnOnce() -> R>(&self, when: f64, f: F) -> R {
+        crate::assert_valid_time_value(when);
+        self.base().transaction(Some(when), f)
+    }
+
+    /// Take a snapshot of the audio graph as seen by the render thread
+    ///
+    /// The snapshot lists all nodes that are alive in the render thread, including nodes whose
+    /// control handle has been dropped but that are still playing, and all their connections. Use
+    /// [`AudioGraphDiagnostics::to_dot`] or [`AudioGraphDiagnostics::to_json`] to export it.
+    ///
+    /// The render thread only copies the graph state, the callback runs on the event thread once
+    /// the render thread has processed the request. This happens at the next render quantum, so
+    /// the callback will not run while the context is suspended, or before an
+    /// `OfflineAudioContext` has started rendering.
+    ///
+    /// Copying the graph state never waits for a lock, but it does allocate memory on the render
+    /// thread, so avoid taking snapshots at a high rate on a running context. The snapshot is
+    /// dropped when the event channel is full.
+    fn graph_diagnostics<F: FnOnce(AudioGraphDiagnostics) + Send + 'static>(&self, callback: F) {
+        let callback = move |v| match v {
+            EventPayload::GraphDiagnostics(v) => callback(v),
+            _ => unreachable!(),
+        };
+
+        self.base().set_event_handler(
+            EventType::GraphDiagnostics,
+            EventHandler::Once(Box::new(callback)),
+        );
+
+        self.base()
+            .send_control_msg(ControlMessage::RunGraphDiagnostics);
+    }
+
     #[cfg(test)]
     fn mock_registration(&self) -> AudioContextRegistration {
         AudioContextRegistration {
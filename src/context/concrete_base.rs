@@ -1,22 +1,28 @@
 //! The `ConcreteBaseAudioContext` type
 
+use crate::channel::{ControlSendError, ControlSender, EventSender, MessageChannelStats};
 use crate::context::{
     AudioContextRegistration, AudioContextState, AudioNodeId, BaseAudioContext,
     DESTINATION_NODE_ID, LISTENER_NODE_ID, LISTENER_PARAM_IDS,
 };
-use crate::events::{EventDispatch, EventHandler, EventLoop, EventType};
+use crate::events::{
+    EventDispatch, EventHandler, EventListenerHandle, EventLoop, EventType, LocalEventHandler,
+};
 use crate::message::ControlMessage;
 use crate::node::{AudioDestinationNode, AudioNode, AudioNodeOptions, ChannelConfig};
 use crate::param::AudioParam;
+use crate::render::graph::EdgeFade;
 use crate::render::AudioProcessor;
 use crate::spatial::AudioListenerParams;
 
-use crate::AudioListener;
+use crate::{AudioListener, DomException};
 
-use crossbeam_channel::{SendError, Sender};
-use std::collections::HashSet;
+use crossbeam_channel::{Sender, TrySendError};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
-use std::sync::{Arc, Mutex, RwLock, RwLockWriteGuard};
+use std::sync::{Arc, Mutex, RwLockWriteGuard, Weak};
+use std::thread::ThreadId;
 
 /// This struct assigns new [`AudioNodeId`]s for [`AudioNode`]s
 ///
@@ -73,6 +79,7 @@ impl std::fmt::Debug for ConcreteBaseAudioContext {
             .field("id", &self.address())
             .field("state", &self.state())
             .field("sample_rate", &self.sample_rate())
+            .field("render_quantum_size", &self.render_quantum_size())
             .field("current_time", &self.current_time())
             .field("max_channel_count", &self.max_channel_count())
             .field("offline", &self.offline())
@@ -80,12 +87,27 @@ impl std::fmt::Debug for ConcreteBaseAudioContext {
     }
 }
 
+/// Non-owning reference to a [`ConcreteBaseAudioContext`], e.g. for event handlers that are
+/// owned by the context itself
+#[derive(Clone)]
+pub(crate) struct WeakConcreteBaseAudioContext(Weak<ConcreteBaseAudioContextInner>);
+
+impl WeakConcreteBaseAudioContext {
+    pub(crate) fn upgrade(&self) -> Option<ConcreteBaseAudioContext> {
+        self.0
+            .upgrade()
+            .map(|inner| ConcreteBaseAudioContext { inner })
+    }
+}
+
 /// Inner representation of the `ConcreteBaseAudioContext`
 ///
 /// These fields are wrapped inside an `Arc` in the actual `ConcreteBaseAudioContext`.
 struct ConcreteBaseAudioContextInner {
     /// sample rate in Hertz
     sample_rate: f32,
+    /// number of sample frames in a render quantum
+    render_quantum_size: usize,
     /// max number of speaker output channels
     max_channel_count: usize,
     /// provider for new AudioNodeIds
@@ -93,7 +115,7 @@ struct ConcreteBaseAudioContextInner {
     /// destination node's current channel count
     destination_channel_config: ChannelConfig,
     /// message channel from control to render thread
-    render_channel: RwLock<Sender<ControlMessage>>,
+    render_channel: ControlSender,
     /// control messages that cannot be sent immediately
     queued_messages: Mutex<Vec<ControlMessage>>,
     /// number of frames played
@@ -109,9 +131,37 @@ struct ConcreteBaseAudioContextInner {
     /// Stores the event handlers
     event_loop: EventLoop,
     /// Sender for events that will be handled by the EventLoop
-    event_send: Sender<EventDispatch>,
+    event_send: EventSender,
     /// Current audio graph connections (from node, output port, to node, input port)
     connections: Mutex<HashSet<(AudioNodeId, usize, AudioNodeId, usize)>>,
+    /// Graph edits of the open transactions, per thread
+    transactions: Mutex<HashMap<ThreadId, Vec<ControlMessage>>>,
+}
+
+/// Sends the collected graph edits of a transaction to the render thread when dropped
+struct TransactionGuard<'a> {
+    context: &'a ConcreteBaseAudioContext,
+    thread: ThreadId,
+    when: Option<f64>,
+}
+
+impl Drop for TransactionGuard<'_> {
+    fn drop(&mut self) {
+        let messages = self
+            .context
+            .inner
+            .transactions
+            .lock()
+            .unwrap()
+            .remove(&self.thread)
+            .unwrap_or_default();
+
+        if !messages.is_empty() {
+            let when = self.when;
+            self.context
+                .send_control_msg(ControlMessage::Transaction { messages, when });
+        }
+    }
 }
 
 impl BaseAudioContext for ConcreteBaseAudioContext {
@@ -125,11 +175,12 @@ impl ConcreteBaseAudioContext {
     #[allow(clippy::too_many_arguments)] // TODO refactor with builder pattern
     pub(super) fn new(
         sample_rate: f32,
+        render_quantum_size: usize,
         max_channel_count: usize,
         state: Arc<AtomicU8>,
         frames_played: Arc<AtomicU64>,
-        render_channel: Sender<ControlMessage>,
-        event_send: Sender<EventDispatch>,
+        render_channel: ControlSender,
+        event_send: EventSender,
         event_loop: EventLoop,
         offline: bool,
         node_id_consumer: llq::Consumer<AudioNodeId>,
@@ -138,8 +189,9 @@ impl ConcreteBaseAudioContext {
 
         let base_inner = ConcreteBaseAudioContextInner {
             sample_rate,
+            render_quantum_size,
             max_channel_count,
-            render_channel: RwLock::new(render_channel),
+            render_channel,
             queued_messages: Mutex::new(Vec::new()),
             audio_node_id_provider,
             destination_channel_config: AudioNodeOptions::default().into(),
@@ -151,6 +203,7 @@ impl ConcreteBaseAudioContext {
             event_loop,
             event_send,
             connections: Mutex::new(HashSet::new()),
+            transactions: Mutex::new(HashMap::new()),
         };
         let base = Self {
             inner: Arc::new(base_inner),
@@ -215,7 +268,7 @@ impl ConcreteBaseAudioContext {
 
         // For an online AudioContext, pre-create the HRTF-database for panner nodes
         if !offline {
-            crate::node::load_hrtf_processor(sample_rate as u32);
+            crate::node::load_hrtf_processor(sample_rate as u32, render_quantum_size);
         }
 
         base
@@ -225,6 +278,11 @@ impl ConcreteBaseAudioContext {
         Arc::as_ptr(&self.inner) as usize
     }
 
+    /// Reference to this context that does not keep it alive
+    pub(crate) fn downgrade(&self) -> WeakConcreteBaseAudioContext {
+        WeakConcreteBaseAudioContext(Arc::downgrade(&self.inner))
+    }
+
     /// Construct a new pair of [`AudioNode`] and [`AudioProcessor`]
     pub(crate) fn register<
         T: AudioNode,
@@ -268,23 +326,92 @@ impl ConcreteBaseAudioContext {
 
     /// Send a control message to the render thread
     ///
-    /// When the render thread is closed or crashed, the message is discarded and a log warning is
-    /// emitted.
+    /// The message is never rejected, the control thread blocks when the message channel is full
+    /// (unless the [`ControlOverflowPolicy::Grow`](crate::ControlOverflowPolicy::Grow) policy is
+    /// used). When the render thread is closed or crashed, the message is discarded and a log
+    /// warning is emitted.
     pub(crate) fn send_control_msg(&self, msg: ControlMessage) {
-        if self.state() != AudioContextState::Closed {
-            let result = self.inner.render_channel.read().unwrap().send(msg);
-            if result.is_err() {
+        // infallible, only closed render threads are reported
+        let _ = self.send_control_msg_inner(msg, false);
+    }
+
+    /// Send a control message to the render thread, rejecting it when the message channel is
+    /// full with the [`ControlOverflowPolicy::Reject`](crate::ControlOverflowPolicy::Reject)
+    /// policy
+    ///
+    /// The caller must not change any control side state before the message is accepted. When
+    /// the render thread is closed or crashed, the message is discarded and a log warning is
+    /// emitted like [`Self::send_control_msg`].
+    pub(crate) fn try_send_control_msg(&self, msg: ControlMessage) -> Result<(), ControlSendError> {
+        self.send_control_msg_inner(msg, true)
+    }
+
+    fn send_control_msg_inner(
+        &self,
+        msg: ControlMessage,
+        reject: bool,
+    ) -> Result<(), ControlSendError> {
+        // graph edits are collected when a transaction is open on the current thread, new nodes
+        // are registered right away since they are silent until connected
+        if msg.edits_graph() && !matches!(msg, ControlMessage::RegisterNode { .. }) {
+            let mut transactions = self.inner.transactions.lock().unwrap();
+            if let Some(messages) = transactions.get_mut(&std::thread::current().id()) {
+                messages.push(msg);
+                return Ok(());
+            }
+        }
+
+        if self.state() == AudioContextState::Closed {
+            return Ok(());
+        }
+
+        let result = if reject {
+            self.inner.render_channel.try_send(msg)
+        } else {
+            self.inner.render_channel.send(msg)
+        };
+        match result {
+            Err(ControlSendError::Disconnected) => {
                 log::warn!("Discarding control message - render thread is closed");
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    /// Run `f` and apply the graph edits it makes on the current thread in a single render quantum
+    ///
+    /// Nested transactions are part of the outer transaction, their `when` value is ignored.
+    pub(crate) fn transaction<R>(&self, when: Option<f64>, f: impl FnOnce() -> R) -> R {
+        let thread = std::thread::current().id();
+        match self.inner.transactions.lock().unwrap().entry(thread) {
+            Entry::Occupied(_) => return f(),
+            Entry::Vacant(entry) => {
+                entry.insert(Vec::new());
             }
         }
+
+        // commit the transaction, even when `f` panics, so the render thread stays in sync
+        let _guard = TransactionGuard {
+            context: self,
+            thread,
+            when,
+        };
+        f()
     }
 
-    pub(crate) fn send_event(&self, msg: EventDispatch) -> Result<(), SendError<EventDispatch>> {
-        self.inner.event_send.send(msg)
+    /// Send an event without blocking, it is dropped when the event channel is full
+    pub(crate) fn send_event(&self, msg: EventDispatch) -> Result<(), TrySendError<EventDispatch>> {
+        self.inner.event_send.try_send(msg)
     }
 
     pub(crate) fn lock_control_msg_sender(&self) -> RwLockWriteGuard<'_, Sender<ControlMessage>> {
-        self.inner.render_channel.write().unwrap()
+        self.inner.render_channel.lock()
+    }
+
+    /// Counters of the message channels between the control and render thread
+    pub(crate) fn message_channel_stats(&self) -> MessageChannelStats {
+        self.inner.render_channel.stats()
     }
 
     pub(super) fn mark_node_dropped(&self, id: AudioNodeId) {
@@ -366,6 +493,12 @@ impl ConcreteBaseAudioContext {
         self.inner.sample_rate
     }
 
+    /// The number of sample frames in a render quantum of this context
+    #[must_use]
+    pub(crate) fn render_quantum_size(&self) -> usize {
+        self.inner.render_quantum_size
+    }
+
     /// This is the time in seconds of the sample frame immediately following the last sample-frame
     /// in the block of audio most recently processed by the context’s rendering graph.
     #[must_use]
@@ -415,6 +548,42 @@ impl ConcreteBaseAudioContext {
         self.send_control_msg(message);
     }
 
+    /// Connects the output of the `from` audio node to the input of the `to` audio node at the
+    /// given time, fading in the connection over `fade_time` seconds
+    pub(crate) fn connect_at(
+        &self,
+        from: AudioNodeId,
+        to: AudioNodeId,
+        output: usize,
+        input: usize,
+        when: f64,
+        fade_time: f64,
+    ) {
+        self.inner
+            .connections
+            .lock()
+            .unwrap()
+            .insert((from, output, to, input));
+        let message = ControlMessage::ConnectNodeAt {
+            from,
+            to,
+            output,
+            input,
+            fade: self.edge_fade(when, fade_time, true),
+        };
+        self.send_control_msg(message);
+    }
+
+    /// Convert the time and duration of a scheduled (dis)connection to frames
+    fn edge_fade(&self, when: f64, fade_time: f64, fade_in: bool) -> EdgeFade {
+        let sample_rate = f64::from(self.sample_rate());
+        EdgeFade {
+            start_frame: (when * sample_rate).round() as u64,
+            fade_frames: (fade_time * sample_rate).round() as u64,
+            fade_in,
+        }
+    }
+
     /// Schedule a connection of an `AudioParam` to the `AudioNode` it belongs to
     ///
     /// It is not performed immediately as the `AudioNode` is not registered at this point.
@@ -431,14 +600,44 @@ impl ConcreteBaseAudioContext {
     }
 
     /// Disconnects outputs of the audio node, possibly filtered by output node, input, output.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidAccessError` if an output node is given but no connection to it exists
     pub(crate) fn disconnect(
         &self,
         from: AudioNodeId,
         output: Option<usize>,
         to: Option<AudioNodeId>,
         input: Option<usize>,
-    ) {
-        // check if the node was connected, otherwise panic
+    ) -> Result<(), DomException> {
+        self.disconnect_with_fade(from, output, to, input, None)
+    }
+
+    /// Disconnects outputs of the audio node at the given time, fading out the connections over
+    /// `fade_time` seconds. Filtering is identical to [`Self::disconnect`].
+    pub(crate) fn disconnect_at(
+        &self,
+        from: AudioNodeId,
+        output: Option<usize>,
+        to: Option<AudioNodeId>,
+        input: Option<usize>,
+        when: f64,
+        fade_time: f64,
+    ) -> Result<(), DomException> {
+        let fade = self.edge_fade(when, fade_time, false);
+        self.disconnect_with_fade(from, output, to, input, Some(fade))
+    }
+
+    fn disconnect_with_fade(
+        &self,
+        from: AudioNodeId,
+        output: Option<usize>,
+        to: Option<AudioNodeId>,
+        input: Option<usize>,
+        fade: Option<EdgeFade>,
+    ) -> Result<(), DomException> {
+        // check if the node was connected, otherwise return an error
         let mut has_disconnected = false;
         let mut connections = self.inner.connections.lock().unwrap();
         connections.retain(|&(c_from, c_output, c_to, c_input)| {
@@ -448,23 +647,33 @@ impl ConcreteBaseAudioContext {
                 || c_input != input.unwrap_or(c_input);
             if !retain {
                 has_disconnected = true;
-                let message = ControlMessage::DisconnectNode {
-                    from,
-                    to: c_to,
-                    input: c_input,
-                    output: c_output,
+                let message = match fade {
+                    None => ControlMessage::DisconnectNode {
+                        from,
+                        to: c_to,
+                        input: c_input,
+                        output: c_output,
+                    },
+                    Some(fade) => ControlMessage::DisconnectNodeAt {
+                        from,
+                        to: c_to,
+                        input: c_input,
+                        output: c_output,
+                        fade,
+                    },
                 };
                 self.send_control_msg(message);
             }
             retain
         });
 
-        // make sure to drop the MutexGuard before the panic to avoid poisoning
-        drop(connections);
-
         if !has_disconnected && to.is_some() {
-            panic!("InvalidAccessError - attempting to disconnect unconnected nodes");
+            return Err(DomException::InvalidAccessError(String::from(
+                "attempting to disconnect unconnected nodes",
+            )));
         }
+
+        Ok(())
     }
 
     /// Connect the `AudioListener` to a `PannerNode`
@@ -505,6 +714,40 @@ impl ConcreteBaseAudioContext {
     pub(crate) fn clear_event_handler(&self, event: EventType) {
         self.inner.event_loop.clear_handler(event);
     }
+
+    pub(crate) fn add_event_handler(
+        &self,
+        event: EventType,
+        callback: EventHandler,
+    ) -> EventListenerHandle {
+        self.inner.event_loop.add_listener(event, callback)
+    }
+
+    pub(crate) fn add_exclusive_event_handler(
+        &self,
+        event: EventType,
+        callback: EventHandler,
+    ) -> EventListenerHandle {
+        self.inner
+            .event_loop
+            .add_exclusive_listener(event, callback)
+    }
+
+    pub(crate) fn add_local_event_handler(
+        &self,
+        event: EventType,
+        callback: LocalEventHandler,
+    ) -> EventListenerHandle {
+        self.inner.event_loop.add_local_listener(event, callback)
+    }
+
+    pub(crate) fn handle_pending_events(&self) -> bool {
+        self.inner.event_loop.handle_pending_events()
+    }
+
+    pub(crate) fn remove_event_handler(&self, handle: EventListenerHandle) {
+        self.inner.event_loop.remove_listener_by_handle(handle);
+    }
 }
 
 #[cfg(test)]
@@ -559,6 +802,34 @@ mod tests {
         node1.disconnect_dest(&node2);
     }
 
+    #[test]
+    fn test_try_connect_disconnect() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let other_context = OfflineAudioContext::new(1, 128, 48000.);
+        let node1 = context.create_constant_source();
+        let node2 = context.create_gain();
+        let other = other_context.create_gain();
+
+        let result = node1.try_disconnect_dest(&node2);
+        assert!(matches!(result, Err(DomException::InvalidAccessError(_))));
+
+        let result = node1.try_connect(&other);
+        assert!(matches!(result, Err(DomException::InvalidAccessError(_))));
+
+        let result = node1.try_connect_from_output_to_input(&node2, 1, 0);
+        assert!(matches!(result, Err(DomException::IndexSizeError(_))));
+
+        let result = node1.try_connect_at(&node2, -1., 0.);
+        assert!(matches!(result, Err(DomException::RangeError(_))));
+
+        // failed calls leave the connections untouched
+        assert!(context.base().inner.connections.lock().unwrap().is_empty());
+
+        assert!(node1.try_connect(&node2).is_ok());
+        assert!(node1.try_disconnect_dest(&node2).is_ok());
+        assert!(context.base().inner.connections.lock().unwrap().is_empty());
+    }
+
     #[test]
     fn test_mark_node_dropped() {
         let context = OfflineAudioContext::new(1, 128, 48000.);
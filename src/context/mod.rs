@@ -2,6 +2,8 @@
 
 use std::{any::Any, ops::Range};
 
+use crate::DomException;
+
 mod base;
 pub use base::*;
 
@@ -14,6 +16,9 @@ pub use offline::*;
 mod online;
 pub use online::*;
 
+mod shared;
+pub use shared::*;
+
 // magic node values
 /// Destination node id is always at index 0
 pub(crate) const DESTINATION_NODE_ID: AudioNodeId = AudioNodeId(0);
@@ -133,6 +138,27 @@ impl AudioContextRegistration {
         };
         self.context.send_control_msg(wrapped);
     }
+
+    /// Send a message to the corresponding audio processor of this node, see
+    /// [`Self::post_message`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a `QuotaExceededError` when the message channel is full with the
+    /// [`ControlOverflowPolicy::Reject`](crate::ControlOverflowPolicy::Reject) policy. The
+    /// message is then discarded, so callers must only change their state when it is accepted.
+    pub(crate) fn try_post_message<M: Any + Send + 'static>(
+        &self,
+        msg: M,
+    ) -> Result<(), DomException> {
+        let wrapped = crate::message::ControlMessage::NodeMessage {
+            id: self.id,
+            msg: llq::Node::new(Box::new(msg)),
+        };
+        self.context.try_send_control_msg(wrapped).map_err(|_| {
+            DomException::QuotaExceededError(String::from("the control message channel is full"))
+        })
+    }
 }
 
 impl Drop for AudioContextRegistration {
@@ -1,26 +1,53 @@
 //! The `OfflineAudioContext` type
 
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, Write};
+use std::path::Path;
 use std::sync::atomic::{AtomicU64, AtomicU8};
 use std::sync::{Arc, Mutex};
 
 use crate::buffer::AudioBuffer;
-use crate::context::{AudioContextState, BaseAudioContext, ConcreteBaseAudioContext};
+use crate::channel::{ChannelCounters, ControlOverflowPolicy, ControlSender, EventSender};
+use crate::context::{
+    AudioContextRenderSizeCategory, AudioContextState, BaseAudioContext, ConcreteBaseAudioContext,
+};
+use crate::encoding::{AudioFileEncoder, AudioFileFormat};
 use crate::events::{
     Event, EventDispatch, EventHandler, EventPayload, EventType, OfflineAudioCompletionEvent,
 };
 use crate::render::RenderThread;
 use crate::{
-    assert_valid_buffer_length, assert_valid_number_of_channels, assert_valid_sample_rate,
-    RENDER_QUANTUM_SIZE,
+    check_valid_buffer_length, check_valid_number_of_channels, check_valid_render_quantum_size,
+    check_valid_sample_rate, expect_valid, DomException, RENDER_QUANTUM_SIZE,
 };
 
 use crate::events::EventLoop;
 use futures_channel::{mpsc, oneshot};
 use futures_util::SinkExt as _;
 
+/// Number of render quanta per chunk when rendering into an audio file
+const ENCODER_CHUNK_QUANTA: usize = 32;
+
 pub(crate) type OfflineAudioContextCallback =
     dyn FnOnce(&mut OfflineAudioContext) + Send + Sync + 'static;
 
+/// Specify the rendering configuration for the [`OfflineAudioContext::with_options`] constructor.
+#[derive(Clone, Debug)]
+pub struct OfflineAudioContextOptions {
+    /// Number of output channels to render
+    pub number_of_channels: usize,
+    /// Length of the rendering audio buffer in sample-frames
+    pub length: usize,
+    /// Output sample rate
+    pub sample_rate: f32,
+    /// Option to request a default or specific render quantum size. There is no audio hardware
+    /// involved, so `Hardware` falls back to the default value.
+    pub render_size_hint: AudioContextRenderSizeCategory,
+    /// Number of worker threads to render independent branches of the audio graph in parallel.
+    /// Use `0` to disable parallel rendering. The rendered output does not depend on this setting.
+    pub render_worker_threads: usize,
+}
+
 /// The `OfflineAudioContext` doesn't render the audio to the device hardware; instead, it generates
 /// it, as fast as it can, and outputs the result to an `AudioBuffer`.
 // the naming comes from the web audio specification
@@ -75,16 +102,101 @@ impl OfflineAudioContext {
     #[must_use]
     #[allow(clippy::missing_panics_doc)]
     pub fn new(number_of_channels: usize, length: usize, sample_rate: f32) -> Self {
-        assert_valid_number_of_channels(number_of_channels);
-        assert_valid_buffer_length(length);
-        assert_valid_sample_rate(sample_rate);
+        Self::with_options(OfflineAudioContextOptions {
+            number_of_channels,
+            length,
+            sample_rate,
+            render_size_hint: AudioContextRenderSizeCategory::default(),
+            render_worker_threads: 0,
+        })
+    }
+
+    /// Creates an `OfflineAudioContext` instance from the given options
+    ///
+    /// ```
+    /// use web_audio_api::context::{
+    ///     AudioContextRenderSizeCategory, BaseAudioContext, OfflineAudioContext,
+    ///     OfflineAudioContextOptions,
+    /// };
+    ///
+    /// // Render 1 second of stereo audio in blocks of 512 frames
+    /// let context = OfflineAudioContext::with_options(OfflineAudioContextOptions {
+    ///     number_of_channels: 2,
+    ///     length: 44_100,
+    ///     sample_rate: 44_100.,
+    ///     render_size_hint: AudioContextRenderSizeCategory::Custom(512),
+    ///     render_worker_threads: 0,
+    /// });
+    /// assert_eq!(context.render_quantum_size(), 512);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of channels, length, sample rate or custom render quantum size is
+    /// invalid.
+    #[must_use]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn with_options(options: OfflineAudioContextOptions) -> Self {
+        expect_valid(Self::try_with_options(options))
+    }
+
+    /// Creates an `OfflineAudioContext` instance, see [`Self::new`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotSupportedError` if the number of channels, length or sample rate is invalid.
+    pub fn try_new(
+        number_of_channels: usize,
+        length: usize,
+        sample_rate: f32,
+    ) -> Result<Self, DomException> {
+        Self::try_with_options(OfflineAudioContextOptions {
+            number_of_channels,
+            length,
+            sample_rate,
+            render_size_hint: AudioContextRenderSizeCategory::default(),
+            render_worker_threads: 0,
+        })
+    }
+
+    /// Creates an `OfflineAudioContext` instance from the given options, see
+    /// [`Self::with_options`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotSupportedError` if the number of channels, length, sample rate or custom
+    /// render quantum size is invalid.
+    #[allow(clippy::needless_pass_by_value, clippy::missing_panics_doc)]
+    pub fn try_with_options(options: OfflineAudioContextOptions) -> Result<Self, DomException> {
+        let OfflineAudioContextOptions {
+            number_of_channels,
+            length,
+            sample_rate,
+            render_size_hint,
+            render_worker_threads,
+        } = options;
+
+        check_valid_number_of_channels(number_of_channels)?;
+        check_valid_buffer_length(length)?;
+        check_valid_sample_rate(sample_rate)?;
+
+        let render_quantum_size = match render_size_hint {
+            AudioContextRenderSizeCategory::Default | AudioContextRenderSizeCategory::Hardware => {
+                RENDER_QUANTUM_SIZE
+            }
+            AudioContextRenderSizeCategory::Custom(size) => {
+                check_valid_render_quantum_size(size)?;
+                size
+            }
+        };
 
         // communication channel to the render thread,
         // unbounded is fine because it does not need to be realtime safe
         let (sender, receiver) = crossbeam_channel::unbounded();
 
         let (node_id_producer, node_id_consumer) = llq::Queue::new().split();
-        let graph = crate::render::graph::Graph::new(node_id_producer);
+        let mut graph = crate::render::graph::Graph::new(node_id_producer, render_quantum_size);
+        graph.set_render_worker_threads(render_worker_threads);
         let message = crate::message::ControlMessage::Startup { graph };
         sender.send(message).unwrap();
 
@@ -99,9 +211,16 @@ impl OfflineAudioContext {
         let (event_send, event_recv) = crossbeam_channel::unbounded();
         let event_loop = EventLoop::new(event_recv);
 
+        // the channels are unbounded, so the overflow policies never apply
+        let counters = Arc::new(ChannelCounters::default());
+        let sender =
+            ControlSender::new(sender, ControlOverflowPolicy::Block, Arc::clone(&counters));
+        let event_send = EventSender::new(event_send, counters);
+
         // setup the render 'thread', which will run inside the control thread
         let renderer = RenderThread::new(
             sample_rate,
+            render_quantum_size,
             number_of_channels,
             receiver,
             state_clone,
@@ -112,6 +231,7 @@ impl OfflineAudioContext {
         // first, setup the base audio context
         let base = ConcreteBaseAudioContext::new(
             sample_rate,
+            render_quantum_size,
             number_of_channels,
             state,
             frames_played,
@@ -132,12 +252,12 @@ impl OfflineAudioContext {
             event_loop,
         };
 
-        Self {
+        Ok(Self {
             base,
             length,
             renderer: Mutex::new(Some(renderer)),
             resume_sender,
-        }
+        })
     }
 
     /// Given the current connections and scheduled changes, starts rendering audio.
@@ -227,6 +347,295 @@ impl OfflineAudioContext {
         result
     }
 
+    /// Given the current connections and scheduled changes, starts rendering audio in chunks.
+    ///
+    /// Instead of returning a single `AudioBuffer` of the full length, the rendered audio is
+    /// handed to the `on_chunk` callback in consecutive `AudioBuffer`s of `chunk_length`
+    /// sample-frames, so the memory usage does not depend on the render length. The last chunk
+    /// holds the remaining sample-frames and may be shorter.
+    ///
+    /// This function will block the current thread until the rendering has completed. The
+    /// `complete` event is emitted with an empty `rendered_buffer` since the audio has already
+    /// been handed out.
+    ///
+    /// This method will only adhere to scheduled suspensions via [`Self::suspend_sync`] and
+    /// will ignore those provided via [`Self::suspend`].
+    ///
+    /// ```
+    /// use web_audio_api::context::{BaseAudioContext, OfflineAudioContext};
+    /// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+    ///
+    /// let mut context = OfflineAudioContext::new(2, 44_100, 44_100.);
+    /// let mut osc = context.create_oscillator();
+    /// osc.connect(&context.destination());
+    /// osc.start();
+    ///
+    /// let mut total_length = 0;
+    /// context.start_rendering_chunked_sync(4096, |chunk| {
+    ///     assert!(chunk.length() <= 4096);
+    ///     total_length += chunk.length();
+    /// });
+    /// assert_eq!(total_length, 44_100);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if this method is called multiple times, or if `chunk_length` is not a positive
+    /// multiple of the render quantum size.
+    pub fn start_rendering_chunked_sync<F: FnMut(AudioBuffer)>(
+        &mut self,
+        chunk_length: usize,
+        on_chunk: F,
+    ) {
+        self.assert_valid_chunk_length(chunk_length);
+
+        let renderer = self
+            .renderer
+            .lock()
+            .unwrap()
+            .take()
+            .expect("InvalidStateError - Cannot call `startRendering` twice");
+
+        let OfflineAudioContextRenderer {
+            renderer,
+            suspend_callbacks,
+            event_loop,
+            ..
+        } = renderer;
+
+        self.base.set_state(AudioContextState::Running);
+
+        renderer.render_audiobuffer_chunks_sync(
+            self,
+            suspend_callbacks,
+            &event_loop,
+            chunk_length,
+            on_chunk,
+        );
+
+        self.complete_chunked_rendering(&event_loop);
+    }
+
+    /// Given the current connections and scheduled changes, starts rendering audio in chunks.
+    ///
+    /// This is the async version of [`Self::start_rendering_chunked_sync`]. Rendering is purely
+    /// CPU bound and contains no `await` points, so calling this method will block the executor
+    /// until completion or until the context is suspended.
+    ///
+    /// This method will only adhere to scheduled suspensions via [`Self::suspend`] and will
+    /// ignore those provided via [`Self::suspend_sync`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if this method is called multiple times, or if `chunk_length` is not a positive
+    /// multiple of the render quantum size.
+    pub async fn start_rendering_chunked<F: FnMut(AudioBuffer)>(
+        &self,
+        chunk_length: usize,
+        on_chunk: F,
+    ) {
+        self.assert_valid_chunk_length(chunk_length);
+
+        // We are mixing async with a std Mutex, so be sure not to `await` while the lock is held
+        let renderer = self
+            .renderer
+            .lock()
+            .unwrap()
+            .take()
+            .expect("InvalidStateError - Cannot call `startRendering` twice");
+
+        let OfflineAudioContextRenderer {
+            renderer,
+            suspend_promises,
+            resume_receiver,
+            event_loop,
+            ..
+        } = renderer;
+
+        self.base.set_state(AudioContextState::Running);
+
+        renderer
+            .render_audiobuffer_chunks(
+                self.length,
+                suspend_promises,
+                resume_receiver,
+                &event_loop,
+                chunk_length,
+                on_chunk,
+            )
+            .await;
+
+        self.complete_chunked_rendering(&event_loop);
+    }
+
+    /// Given the current connections and scheduled changes, starts rendering audio into the audio
+    /// file at the given path.
+    ///
+    /// The file is created, or truncated if it exists. The audio is rendered and encoded in
+    /// chunks, so the memory usage does not depend on the render length. See
+    /// [`Self::start_rendering_to_writer_sync`] for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be created or written to, or if the format is FLAC and
+    /// the context has more than 8 channels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this method is called multiple times.
+    pub fn start_rendering_to_file_sync<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        format: AudioFileFormat,
+    ) -> io::Result<()> {
+        // do not truncate the file when rendering was already started
+        self.assert_not_started();
+        let file = BufWriter::new(File::create(path)?);
+        self.start_rendering_to_writer_sync(file, format)
+    }
+
+    /// Given the current connections and scheduled changes, starts rendering audio into the
+    /// given writer, encoded in the given audio file format.
+    ///
+    /// This function will block the current thread until the rendering has completed and the file
+    /// headers have been finalized. Encoding stops at the first write error, but rendering
+    /// continues until the end.
+    ///
+    /// The `complete` event is emitted with an empty `rendered_buffer`. This method will only
+    /// adhere to scheduled suspensions via [`Self::suspend_sync`] and will ignore those provided
+    /// via [`Self::suspend`].
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use web_audio_api::context::{BaseAudioContext, OfflineAudioContext};
+    /// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+    /// use web_audio_api::AudioFileFormat;
+    ///
+    /// let mut context = OfflineAudioContext::new(2, 44_100, 44_100.);
+    /// let mut osc = context.create_oscillator();
+    /// osc.connect(&context.destination());
+    /// osc.start();
+    ///
+    /// let mut file = Cursor::new(vec![]);
+    /// context
+    ///     .start_rendering_to_writer_sync(&mut file, AudioFileFormat::WavInt24)
+    ///     .unwrap();
+    /// // WAV header followed by 44100 frames of 2 channels of 3 bytes
+    /// assert!(file.into_inner().len() > 44_100 * 2 * 3);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to the writer fails, or if the format is FLAC and the context
+    /// has more than 8 channels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this method is called multiple times.
+    pub fn start_rendering_to_writer_sync<W: Write + Seek>(
+        &mut self,
+        writer: W,
+        format: AudioFileFormat,
+    ) -> io::Result<()> {
+        // do not write the file header when rendering was already started
+        self.assert_not_started();
+        let mut encoder = AudioFileEncoder::new(
+            writer,
+            format,
+            self.base.max_channel_count(),
+            self.sample_rate(),
+        )?;
+
+        let mut result = Ok(());
+        let chunk_length = ENCODER_CHUNK_QUANTA * self.base.render_quantum_size();
+        self.start_rendering_chunked_sync(chunk_length, |chunk| {
+            if result.is_ok() {
+                result = encoder.write(&chunk);
+            }
+        });
+
+        result?;
+        encoder.finalize()
+    }
+
+    /// Given the current connections and scheduled changes, starts rendering audio into the
+    /// given writer, encoded in the given audio file format.
+    ///
+    /// This is the async version of [`Self::start_rendering_to_writer_sync`]. Rendering is purely
+    /// CPU bound and contains no `await` points, so calling this method will block the executor
+    /// until completion or until the context is suspended.
+    ///
+    /// This method will only adhere to scheduled suspensions via [`Self::suspend`] and will
+    /// ignore those provided via [`Self::suspend_sync`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to the writer fails, or if the format is FLAC and the context
+    /// has more than 8 channels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this method is called multiple times.
+    pub async fn start_rendering_to_writer<W: Write + Seek>(
+        &self,
+        writer: W,
+        format: AudioFileFormat,
+    ) -> io::Result<()> {
+        // do not write the file header when rendering was already started
+        self.assert_not_started();
+        let mut encoder = AudioFileEncoder::new(
+            writer,
+            format,
+            self.base.max_channel_count(),
+            self.sample_rate(),
+        )?;
+
+        let mut result = Ok(());
+        let chunk_length = ENCODER_CHUNK_QUANTA * self.base.render_quantum_size();
+        self.start_rendering_chunked(chunk_length, |chunk| {
+            if result.is_ok() {
+                result = encoder.write(&chunk);
+            }
+        })
+        .await;
+
+        result?;
+        encoder.finalize()
+    }
+
+    #[track_caller]
+    fn assert_not_started(&self) {
+        assert!(
+            self.renderer.lock().unwrap().is_some(),
+            "InvalidStateError - Cannot call `startRendering` twice"
+        );
+    }
+
+    #[track_caller]
+    fn assert_valid_chunk_length(&self, chunk_length: usize) {
+        let render_quantum_size = self.base.render_quantum_size();
+        assert!(
+            chunk_length > 0 && chunk_length % render_quantum_size == 0,
+            "NotSupportedError - chunk length {:?} is not a positive multiple of the render quantum size {:?}",
+            chunk_length,
+            render_quantum_size,
+        );
+    }
+
+    fn complete_chunked_rendering(&self, event_loop: &EventLoop) {
+        self.base.set_state(AudioContextState::Closed);
+
+        // the audio has been handed out in chunks, so the complete event carries an empty buffer
+        let empty = AudioBuffer::from(
+            vec![vec![]; self.base.max_channel_count()],
+            self.sample_rate(),
+        );
+        let _ = self.base.send_event(EventDispatch::complete(empty));
+
+        // spin the event loop once more to handle the statechange/complete events
+        event_loop.handle_pending_events();
+    }
+
     /// get the length of rendering audio buffer
     // false positive: OfflineAudioContext is not const
     #[allow(clippy::missing_const_for_fn, clippy::unused_self)]
@@ -245,7 +654,8 @@ impl OfflineAudioContext {
             suspend_time < self.length as f64 / self.sample_rate() as f64,
             "InvalidStateError: suspendTime cannot be greater than or equal to the total render duration"
         );
-        (suspend_time * self.base.sample_rate() as f64 / RENDER_QUANTUM_SIZE as f64).ceil() as usize
+        let render_quantum_size = self.base.render_quantum_size() as f64;
+        (suspend_time * self.base.sample_rate() as f64 / render_quantum_size).ceil() as usize
     }
 
     /// Schedules a suspension of the time progression in the audio context at the specified time
@@ -433,6 +843,22 @@ mod tests {
     use crate::node::AudioNode;
     use crate::node::AudioScheduledSourceNode;
 
+    #[test]
+    fn test_try_new() {
+        let error = OfflineAudioContext::try_new(0, 128, 44_100.).unwrap_err();
+        assert!(matches!(error, DomException::NotSupportedError(_)));
+        let error = OfflineAudioContext::try_new(1, 0, 44_100.).unwrap_err();
+        assert!(matches!(error, DomException::NotSupportedError(_)));
+        let error = OfflineAudioContext::try_new(1, 128, 0.).unwrap_err();
+        assert!(matches!(error, DomException::NotSupportedError(_)));
+
+        let context = OfflineAudioContext::try_new(1, 128, 44_100.).unwrap();
+        assert!(context.try_create_buffer(1, 0, 44_100.).is_err());
+        assert!(context.try_create_channel_merger(33).is_err());
+        assert!(context.try_create_channel_splitter(0).is_err());
+        assert!(context.try_create_iir_filter(vec![], vec![1.]).is_err());
+    }
+
     #[test]
     fn render_empty_graph() {
         let mut context = OfflineAudioContext::new(2, 555, 44_100.);
@@ -631,6 +1057,118 @@ mod tests {
         assert!(complete.load(Ordering::Relaxed));
     }
 
+    #[test]
+    fn test_graph_diagnostics() {
+        let mut context = OfflineAudioContext::new(2, 555, 44_100.);
+
+        let delay = context.create_delay(1.);
+        delay.connect(&context.destination());
+        let gain = context.create_gain();
+        gain.connect(&delay);
+        delay.connect(&gain);
+
+        let diagnostics = Arc::new(Mutex::new(None));
+        let diagnostics_clone = Arc::clone(&diagnostics);
+        context.graph_diagnostics(move |d| *diagnostics_clone.lock().unwrap() = Some(d));
+
+        let _ = context.start_rendering_sync();
+
+        let diagnostics = diagnostics.lock().unwrap().take().unwrap();
+        let cycle_breakers: Vec<_> = diagnostics
+            .nodes
+            .iter()
+            .filter(|n| n.cycle_breaker)
+            .collect();
+        assert_eq!(cycle_breakers.len(), 1);
+        assert!(cycle_breakers[0].name.ends_with("DelayWriter"));
+        assert!(diagnostics.nodes.iter().all(|n| !n.muted));
+
+        let dot = diagnostics.to_dot();
+        assert!(dot.starts_with("digraph audio_graph {"));
+        assert!(dot.contains("peripheries=2"));
+        let json = diagnostics.to_json();
+        assert!(json.contains("\"cycle_breaker\":true"));
+    }
+
+    fn setup_chunked_context() -> OfflineAudioContext {
+        let mut context = OfflineAudioContext::new(2, 555, 44_100.);
+
+        context.suspend_sync(128. / 44_100., |context| {
+            let mut src = context.create_constant_source();
+            src.connect(&context.destination());
+            src.start();
+        });
+
+        context
+    }
+
+    #[test]
+    fn test_render_chunked() {
+        let expected = setup_chunked_context().start_rendering_sync();
+
+        let mut context = setup_chunked_context();
+        let complete = Arc::new(AtomicBool::new(false));
+        let complete_clone = Arc::clone(&complete);
+        context.set_oncomplete(move |event| {
+            assert_eq!(event.rendered_buffer.length(), 0);
+            complete_clone.store(true, Ordering::Relaxed);
+        });
+
+        let mut chunks = vec![];
+        context.start_rendering_chunked_sync(256, |chunk| chunks.push(chunk));
+
+        assert_eq!(context.state(), AudioContextState::Closed);
+        assert!(complete.load(Ordering::Relaxed));
+
+        let lengths: Vec<_> = chunks.iter().map(AudioBuffer::length).collect();
+        assert_eq!(lengths, [256, 256, 43]);
+
+        for channel in 0..2 {
+            let rendered: Vec<f32> = chunks
+                .iter()
+                .flat_map(|c| c.get_channel_data(channel).to_vec())
+                .collect();
+            assert_float_eq!(
+                &rendered[..],
+                expected.get_channel_data(channel),
+                abs_all <= 0.
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_chunked_async() {
+        use futures::executor;
+        use futures::{join, FutureExt as _};
+
+        let context = Arc::new(OfflineAudioContext::new(1, 512, 44_100.));
+        let context_clone = Arc::clone(&context);
+
+        let suspend_promise = context.suspend(128. / 44_100.).then(|_| async move {
+            let mut src = context_clone.create_constant_source();
+            src.connect(&context_clone.destination());
+            src.start();
+            context_clone.resume().await;
+        });
+
+        let mut rendered = vec![];
+        let render_promise = context.start_rendering_chunked(128, |chunk| {
+            assert_eq!(chunk.length(), 128);
+            rendered.extend_from_slice(chunk.get_channel_data(0));
+        });
+        executor::block_on(async { join!(suspend_promise, render_promise) });
+
+        assert_float_eq!(rendered[..128], [0.; 128][..], abs_all <= 0.);
+        assert_float_eq!(rendered[128..], [1.; 384][..], abs_all <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_render_chunked_invalid_length() {
+        let mut context = OfflineAudioContext::new(1, 512, 44_100.);
+        context.start_rendering_chunked_sync(100, |_| ());
+    }
+
     fn require_send_sync<T: Send + Sync>(_: T) {}
 
     #[test]
@@ -1,32 +1,166 @@
 //! The `AudioContext` type and constructor options
 use std::error::Error;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use crate::context::{AudioContextState, BaseAudioContext, ConcreteBaseAudioContext};
 use crate::events::{EventDispatch, EventHandler, EventLoop, EventPayload, EventType};
-use crate::io::{self, AudioBackendManager, ControlThreadInit, NoneBackend, RenderThreadInit};
+pub use crate::io::Loopback;
+use crate::io::{
+    self, AudioBackendManager, ControlThreadInit, DuplexInput, NoneBackend, PullRenderer,
+    RenderThreadInit,
+};
 use crate::media_devices::{enumerate_devices_sync, MediaDeviceInfoKind};
 use crate::media_streams::{MediaStream, MediaStreamTrack};
 use crate::message::{ControlMessage, OneshotNotify};
 use crate::node::{self, AudioNodeOptions};
 use crate::render::graph::Graph;
 use crate::MediaElement;
-use crate::{AudioRenderCapacity, Event};
+use crate::{
+    AudioPlaybackStats, AudioRenderCapacity, AudioRenderProfiler, ControlOverflowPolicy,
+    DomException, ErrorEvent, Event, EventDispatchMode, EventOverflowPolicy, MessageChannelStats,
+};
 
 use futures_channel::oneshot;
 
 /// Check if the provided sink_id is available for playback
 ///
-/// It should be "", "none" or a valid output `sinkId` returned from [`enumerate_devices_sync`]
-fn is_valid_sink_id(sink_id: &str) -> bool {
-    if sink_id.is_empty() || sink_id == "none" {
-        true
+/// It should be "", "none", "pull", "loopback", a `"file:<path>"` that can be created or a
+/// valid output `sinkId` returned from [`enumerate_devices_sync`]. The output file of a file sink
+/// is created by this check.
+fn check_valid_sink_id(sink_id: &str) -> Result<(), DomException> {
+    let valid =
+        if sink_id.is_empty() || sink_id == "none" || sink_id == "pull" || sink_id == "loopback" {
+            true
+        } else if sink_id.starts_with(io::FILE_SINK_PREFIX) {
+            io::create_file_sink(sink_id)?;
+            true
+        } else {
+            enumerate_devices_sync()
+                .into_iter()
+                .filter(|d| d.kind() == MediaDeviceInfoKind::AudioOutput)
+                .any(|d| d.device_id() == sink_id)
+        };
+
+    if valid {
+        Ok(())
     } else {
-        enumerate_devices_sync()
+        Err(DomException::NotFoundError(format!(
+            "invalid sinkId {:?}",
+            sink_id
+        )))
+    }
+}
+
+/// Check if the provided device_id is available for capturing
+///
+/// It should be "" or a valid input `deviceId` returned from [`enumerate_devices_sync`]
+fn is_valid_input_device_id(device_id: &str) -> bool {
+    device_id.is_empty()
+        || enumerate_devices_sync()
             .into_iter()
-            .filter(|d| d.kind() == MediaDeviceInfoKind::AudioOutput)
-            .any(|d| d.device_id() == sink_id)
+            .filter(|d| d.kind() == MediaDeviceInfoKind::AudioInput)
+            .any(|d| d.device_id() == device_id)
+}
+
+/// Interval of the sink fallback thread to check if the context is still alive
+const SINK_FALLBACK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Move the audio graph to a new audio backend playing to `sink_id`
+///
+/// The graph is recycled from the render thread of the current backend. The current backend may
+/// have stopped rendering, e.g. when the output device has failed: closing the backend drops the
+/// render thread, which hands over the graph.
+#[allow(clippy::needless_collect)]
+fn switch_sink(
+    base: &ConcreteBaseAudioContext,
+    backend_manager: &Mutex<Box<dyn AudioBackendManager>>,
+    render_thread_init: &RenderThreadInit,
+    latency_hint: AudioContextLatencyCategory,
+    sink_id: String,
+) {
+    log::debug!("SinkChange: locking backend manager");
+    let mut backend_manager_guard = backend_manager.lock().unwrap();
+    let original_state = base.state();
+    if original_state == AudioContextState::Closed {
+        log::debug!("SinkChange: context is closed");
+        return;
+    }
+
+    // Acquire exclusive lock on ctrl msg sender
+    log::debug!("SinkChange: locking message channel");
+    let ctrl_msg_send = base.lock_control_msg_sender();
+
+    // Flush out the ctrl msg receiver, cache
+    let mut pending_msgs: Vec<_> = render_thread_init.ctrl_msg_recv.try_iter().collect();
+
+    // Acquire the active audio graph from the current render thread, shutting it down
+    let graph = if matches!(pending_msgs.first(), Some(ControlMessage::Startup { .. })) {
+        // Handle the edge case where the previous backend was suspended for its entire lifetime.
+        // In this case, the `Startup` control message was never processed.
+        log::debug!("SinkChange: recover unstarted graph");
+
+        log::debug!("SinkChange: closing audio stream");
+        backend_manager_guard.close();
+
+        let msg = pending_msgs.remove(0);
+        match msg {
+            ControlMessage::Startup { graph } => graph,
+            _ => unreachable!(),
+        }
+    } else {
+        // Acquire the audio graph from the current render thread, shutting it down
+        log::debug!("SinkChange: recover graph from render thread");
+
+        let (graph_send, graph_recv) = crossbeam_channel::bounded(1);
+        let message = ControlMessage::CloseAndRecycle { sender: graph_send };
+        ctrl_msg_send.send(message).unwrap();
+
+        // The render thread hands over the graph in its next render call, or when it is dropped
+        // by closing the stream. The latter is required when the render thread has stopped, i.e.
+        // when the context is suspended or the output device has failed.
+        log::debug!("SinkChange: closing audio stream");
+        backend_manager_guard.close();
+        graph_recv.recv().unwrap()
+    };
+
+    // hotswap the backend, the render quantum size of the audio graph must remain unchanged
+    let options = AudioContextOptions {
+        sample_rate: Some(base.sample_rate()),
+        latency_hint,
+        sink_id,
+        render_size_hint: AudioContextRenderSizeCategory::Custom(base.render_quantum_size()),
+        // the worker threads are owned by the audio graph, which is recycled
+        render_worker_threads: 0,
+        // the message channels are reused
+        ..AudioContextOptions::default()
+    };
+    log::debug!("SinkChange: starting audio stream");
+    *backend_manager_guard = io::build_output(options, render_thread_init.clone());
+
+    // if the previous backend state was suspend, suspend the new one before shipping the graph
+    if original_state == AudioContextState::Suspended {
+        log::debug!("SinkChange: suspending audio stream");
+        backend_manager_guard.suspend();
     }
+
+    // send the audio graph to the new render thread
+    let message = ControlMessage::Startup { graph };
+    ctrl_msg_send.send(message).unwrap();
+
+    // flush the cached msgs through the locked sender, in order
+    pending_msgs
+        .into_iter()
+        .for_each(|m| ctrl_msg_send.send(m).unwrap());
+
+    // explicitly release the lock to prevent concurrent render threads
+    drop(backend_manager_guard);
+
+    // trigger event when all the work is done, without blocking on a full event channel as the
+    // application may be the one to drain it
+    let _ = base.send_event(EventDispatch::sink_change());
+
+    log::debug!("SinkChange: done");
 }
 
 /// Identify the type of playback, which affects tradeoffs
@@ -56,11 +190,18 @@ impl Default for AudioContextLatencyCategory {
 #[derive(Copy, Clone, Debug)]
 #[non_exhaustive]
 /// This allows users to ask for a particular render quantum size.
-///
-/// Currently, only the default value is available
 pub enum AudioContextRenderSizeCategory {
     /// The default value of 128 frames
     Default,
+    /// Use the preferred buffer size of the audio hardware, so the rendered audio can be handed
+    /// to the audio backend without re-buffering
+    Hardware,
+    /// Specify the number of frames of a render quantum
+    ///
+    /// # Panics
+    ///
+    /// The context constructor will panic if the value is zero or greater than 16384
+    Custom(usize),
 }
 
 impl Default for AudioContextRenderSizeCategory {
@@ -69,6 +210,30 @@ impl Default for AudioContextRenderSizeCategory {
     }
 }
 
+/// Pacing of the rendering for the file sink, see [`AudioContextOptions::sink_id`]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum FileSinkPacing {
+    /// Render at the rate of an audio output device. This is the default.
+    #[default]
+    RealTime,
+    /// Render as fast as possible, without waiting for the output to be played
+    FreeRunning,
+}
+
+/// Audio input device that is captured in the same stream as the audio output, see
+/// [`AudioContextOptions::input`]
+#[derive(Clone, Debug, Default)]
+pub struct AudioContextInputOptions {
+    /// The audio input device
+    /// - use `""` for the default audio input device
+    /// - use `"deviceId"` to use the specified audio input device, obtained with
+    ///   [`enumerate_devices_sync`]
+    pub device_id: String,
+
+    /// Number of channels to capture. Use `None` for the default of the input device.
+    pub number_of_channels: Option<u32>,
+}
+
 /// Specify the playback configuration for the [`AudioContext`] constructor.
 ///
 /// All fields are optional and will default to the value best suited for interactive playback on
@@ -96,11 +261,80 @@ pub struct AudioContextOptions {
     /// The audio output device
     /// - use `""` for the default audio output device
     /// - use `"none"` to process the audio graph without playing through an audio output device.
+    /// - use `"pull"` to render the audio graph from the audio callback of the host application,
+    ///   see [`AudioContext::render_into`]
+    /// - use `"loopback"` to deliver the rendered audio to the host application on a virtual
+    ///   clock, for testing. See [`AudioContext::loopback`]
+    /// - use `"file:<path>"` to write the output to a `.wav` (32-bit float) or `.flac` (24-bit)
+    ///   file, the file is finalized when the context is closed or dropped. See
+    ///   [`Self::file_sink_pacing`]
     /// - use `"sinkId"` to use the specified audio sink id, obtained with [`enumerate_devices_sync`]
     pub sink_id: String,
 
-    /// Option to request a default, optimized or specific render quantum size. It is a hint that might not be honored.
+    /// Option to request a default, optimized or specific render quantum size.
+    ///
+    /// A `Custom` size is always honored, the `Hardware` size depends on the capabilities of the
+    /// audio backend. Use [`BaseAudioContext::render_quantum_size`] to inspect the actual value.
     pub render_size_hint: AudioContextRenderSizeCategory,
+
+    /// Number of worker threads to render independent branches of the audio graph in parallel.
+    ///
+    /// The default value `0` disables parallel rendering. The rendered output does not depend on
+    /// this setting.
+    pub render_worker_threads: usize,
+
+    /// Behavior of the control thread when the message channel to the render thread is full.
+    ///
+    /// The default policy blocks the control thread until the render thread has made room.
+    pub control_overflow_policy: ControlOverflowPolicy,
+
+    /// Behavior of the render thread when the event channel to the control thread is full.
+    ///
+    /// The default policy drops the excess events.
+    pub event_overflow_policy: EventOverflowPolicy,
+
+    /// How the event handlers are run.
+    ///
+    /// By default a dedicated thread runs the event handlers. With
+    /// [`EventDispatchMode::Manual`] the host application runs them by calling
+    /// [`AudioContext::dispatch_pending_events`].
+    pub event_dispatch_mode: EventDispatchMode,
+
+    /// Continue playback on the default audio output device when the current device fails, e.g.
+    /// when it is unplugged.
+    ///
+    /// The audio graph is moved to the new device like with [`AudioContext::set_sink_id_sync`],
+    /// and both the `error` and the `sinkchange` events are fired. By default the playback stops
+    /// when the device fails and only the `error` event is fired.
+    pub fallback_to_default_sink: bool,
+
+    /// Rendering rate of the `"file:<path>"` sink.
+    ///
+    /// By default the output is rendered at the rate of an audio output device, including the
+    /// silence played when rendering is too slow. A `FreeRunning` sink renders as fast as
+    /// possible. This setting is retained when the sink is changed.
+    pub file_sink_pacing: FileSinkPacing,
+
+    /// Capture an audio input device in the same stream as the audio output.
+    ///
+    /// The input is played by the node returned from [`AudioContext::create_input_source`]. With
+    /// a duplex stream of the `cubeb` backend or the `"loopback"` sink, its samples are aligned
+    /// with the render quanta of the audio graph, the `cpal` backend captures the input with a
+    /// separate stream. This is only supported by the audio output devices and the `"loopback"`
+    /// sink, the other sinks capture silence.
+    /// The input device is retained when the sink is changed. By default no input is captured.
+    pub input: Option<AudioContextInputOptions>,
+}
+
+/// Pair of corresponding timestamps in the audio stream and on the system clock, as returned by
+/// [`AudioContext::get_output_timestamp`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AudioTimestamp {
+    /// Time in the coordinate system of the `current_time` of the `AudioContext`
+    pub context_time: f64,
+    /// The instant at which the sample frame at `context_time` was (or will be) played by the
+    /// audio output device
+    pub performance_time: Instant,
 }
 
 /// This interface represents an audio graph whose `AudioDestinationNode` is routed to a real-time
@@ -111,11 +345,15 @@ pub struct AudioContext {
     /// represents the underlying `BaseAudioContext`
     base: ConcreteBaseAudioContext,
     /// audio backend (play/pause functionality)
-    backend_manager: Mutex<Box<dyn AudioBackendManager>>,
+    backend_manager: Arc<Mutex<Box<dyn AudioBackendManager>>>,
     /// Provider for rendering performance metrics
     render_capacity: AudioRenderCapacity,
+    /// Provider for per node rendering performance metrics
+    render_profiler: AudioRenderProfiler,
     /// Initializer for the render thread (when restart is required)
     render_thread_init: RenderThreadInit,
+    /// Requested latency category (reused when restart is required)
+    latency_hint: AudioContextLatencyCategory,
 }
 
 impl std::fmt::Debug for AudioContext {
@@ -133,8 +371,14 @@ impl Drop for AudioContext {
     fn drop(&mut self) {
         // Continue playing the stream if the AudioContext goes out of scope
         if self.state() == AudioContextState::Running {
+            let mut backend_manager_guard = self.backend_manager.lock().unwrap();
+            // nobody listens to the output file, finalize it rather than writing forever
+            if io::parse_file_sink_id(backend_manager_guard.sink_id()).is_some() {
+                backend_manager_guard.close();
+                return;
+            }
             let tombstone = Box::new(NoneBackend::void());
-            let original = std::mem::replace(self.backend_manager.get_mut().unwrap(), tombstone);
+            let original = std::mem::replace(&mut *backend_manager_guard, tombstone);
             Box::leak(original);
         }
     }
@@ -176,20 +420,90 @@ impl AudioContext {
     /// # Panics
     ///
     /// The `AudioContext` constructor will panic when an invalid `sinkId` is provided in the
-    /// `AudioContextOptions`. In a future version, a `try_new` constructor will be introduced that
-    /// never panics.
+    /// `AudioContextOptions`, see [`Self::try_new`] for a fallible alternative.
+    ///
+    /// It will also panic when an invalid custom render quantum size is requested.
     #[allow(clippy::needless_pass_by_value)]
     #[must_use]
     pub fn new(mut options: AudioContextOptions) -> Self {
         // Log, but ignore invalid sinks
-        if !is_valid_sink_id(&options.sink_id) {
-            log::error!("NotFoundError: invalid sinkId {:?}", options.sink_id);
+        if let Err(e) = check_valid_sink_id(&options.sink_id) {
+            log::error!("{}", e);
             options.sink_id = String::from("");
         }
 
+        if let AudioContextRenderSizeCategory::Custom(size) = options.render_size_hint {
+            crate::assert_valid_render_quantum_size(size);
+        }
+
+        if let Some(input) = options.input.as_mut() {
+            if !is_valid_input_device_id(&input.device_id) {
+                log::error!(
+                    "NotFoundError: invalid input deviceId {:?}",
+                    input.device_id
+                );
+                input.device_id = String::from("");
+            }
+            if let Some(number_of_channels) = input.number_of_channels {
+                crate::assert_valid_number_of_channels(number_of_channels as usize);
+            }
+        }
+
+        Self::new_unchecked(options)
+    }
+
+    /// Creates and returns a new `AudioContext` object, see [`Self::new`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotFoundError` when an invalid `sinkId` is provided or the output file of a
+    /// `"file:<path>"` sink can not be created, and a `NotSupportedError` when an invalid sample
+    /// rate or custom render quantum size is requested.
+    pub fn try_new(options: AudioContextOptions) -> Result<Self, DomException> {
+        check_valid_sink_id(&options.sink_id)?;
+
+        if let Some(sample_rate) = options.sample_rate {
+            crate::check_valid_sample_rate(sample_rate)?;
+        }
+
+        if let AudioContextRenderSizeCategory::Custom(size) = options.render_size_hint {
+            crate::check_valid_render_quantum_size(size)?;
+        }
+
+        if let Some(input) = &options.input {
+            if !is_valid_input_device_id(&input.device_id) {
+                return Err(DomException::NotFoundError(format!(
+                    "invalid input deviceId {:?}",
+                    input.device_id
+                )));
+            }
+            if let Some(number_of_channels) = input.number_of_channels {
+                crate::check_valid_number_of_channels(number_of_channels as usize)?;
+            }
+        }
+
+        Ok(Self::new_unchecked(options))
+    }
+
+    /// Set up the context from validated options
+    fn new_unchecked(options: AudioContextOptions) -> Self {
+        Self::with_backend(options, io::build_output)
+    }
+
+    /// Set up the context from validated options, with the audio backend returned by
+    /// `build_output`
+    pub(crate) fn with_backend<F>(options: AudioContextOptions, build_output: F) -> Self
+    where
+        F: FnOnce(AudioContextOptions, RenderThreadInit) -> Box<dyn AudioBackendManager>,
+    {
+        let latency_hint = options.latency_hint;
+        let render_worker_threads = options.render_worker_threads;
+        let event_dispatch_mode = options.event_dispatch_mode;
+        let fallback_to_default_sink = options.fallback_to_default_sink;
+
         // Set up the audio output thread
-        let (control_thread_init, render_thread_init) = io::thread_init();
-        let backend = io::build_output(options, render_thread_init.clone());
+        let (control_thread_init, render_thread_init) = io::thread_init(&options);
+        let backend = build_output(options, render_thread_init.clone());
 
         let ControlThreadInit {
             state,
@@ -202,7 +516,8 @@ impl AudioContext {
 
         // Construct the audio Graph and hand it to the render thread
         let (node_id_producer, node_id_consumer) = llq::Queue::new().split();
-        let graph = Graph::new(node_id_producer);
+        let mut graph = Graph::new(node_id_producer, backend.render_quantum_size());
+        graph.set_render_worker_threads(render_worker_threads);
         let message = ControlMessage::Startup { graph };
         ctrl_msg_send.send(message).unwrap();
 
@@ -212,6 +527,7 @@ impl AudioContext {
         // Put everything together in the BaseAudioContext (shared with offline context)
         let base = ConcreteBaseAudioContext::new(
             backend.sample_rate(),
+            backend.render_quantum_size(),
             backend.number_of_channels(),
             state,
             frames_played,
@@ -225,17 +541,76 @@ impl AudioContext {
         // Setup AudioRenderCapacity for this context
         let base_clone = base.clone();
         let render_capacity = AudioRenderCapacity::new(base_clone, load_value_recv);
+        let render_profiler = AudioRenderProfiler::new(base.clone());
 
         // As the final step, spawn a thread for the event loop. If we do this earlier we may miss
         // event handling of the initial events that are emitted right after render thread
         // construction.
-        event_loop.run_in_thread();
+        if event_dispatch_mode == EventDispatchMode::Thread {
+            event_loop.run_in_thread();
+        }
 
-        Self {
+        let context = Self {
             base,
-            backend_manager: Mutex::new(backend),
+            backend_manager: Arc::new(Mutex::new(backend)),
             render_capacity,
+            render_profiler,
             render_thread_init,
+            latency_hint,
+        };
+
+        if fallback_to_default_sink {
+            context.install_sink_fallback(String::new());
+        }
+
+        context
+    }
+
+    /// Move the audio graph to `sink_id` when the audio output device fails
+    ///
+    /// The switch runs on a dedicated thread, so it does not hold up the event loop and does not
+    /// depend on the event dispatch mode. The thread terminates once the context is closed or
+    /// dropped.
+    fn install_sink_fallback(&self, sink_id: String) {
+        // The thread must not keep the context alive
+        let base = self.base.downgrade();
+        let backend_manager = Arc::downgrade(&self.backend_manager);
+        let render_thread_init = self.render_thread_init.clone();
+        let latency_hint = self.latency_hint;
+
+        let fallback = move || loop {
+            let failed = render_thread_init
+                .device_failure
+                .wait_timeout(SINK_FALLBACK_POLL_INTERVAL);
+            let (Some(base), Some(backend_manager)) = (base.upgrade(), backend_manager.upgrade())
+            else {
+                break;
+            };
+            if base.state() == AudioContextState::Closed {
+                break;
+            }
+            if !failed {
+                continue;
+            }
+
+            log::warn!(
+                "SinkChange: output device failed, fallback to {:?}",
+                sink_id
+            );
+            switch_sink(
+                &base,
+                &backend_manager,
+                &render_thread_init,
+                latency_hint,
+                sink_id.clone(),
+            );
+        };
+
+        let spawned = std::thread::Builder::new()
+            .name("sink fallback".into())
+            .spawn(fallback);
+        if let Err(e) = spawned {
+            log::error!("Failed to start the sink fallback thread: {}", e);
         }
     }
 
@@ -260,6 +635,73 @@ impl AudioContext {
         self.backend_manager.lock().unwrap().output_latency()
     }
 
+    /// The estimation in seconds of audio input latency of the
+    /// [`InputAudioSourceNode`](node::InputAudioSourceNode), i.e., the time a sound takes from the
+    /// audio input device to the audio graph, measured against the audio output.
+    ///
+    /// A sound captured by the input device and passed through the audio graph is played by the
+    /// output device after the sum of the input and the output latency. This value includes the
+    /// delay to align the input with the render quanta, see [`AudioContextOptions::input`].
+    /// Returns zero when the context does not capture an input.
+    #[must_use]
+    pub fn input_latency(&self) -> f64 {
+        self.duplex_input().map_or(0., DuplexInput::latency)
+    }
+
+    /// Returns the time of the sample frame that is currently being played by the audio output
+    /// device, paired with the corresponding system clock instant.
+    ///
+    /// This can be used to map a context time to an [`Instant`], e.g. for audio/video
+    /// synchronization. The value is updated on every callback of the audio backend, so it is
+    /// accurate to within one callback buffer. For the `"none"` sink the instants follow the
+    /// emulated callback clock and do not depend on the scheduling of the render thread.
+    ///
+    /// Returns `None` when the context has not started playing yet (the specification returns
+    /// zero values in that case).
+    #[must_use]
+    #[allow(clippy::missing_panics_doc)]
+    pub fn get_output_timestamp(&self) -> Option<AudioTimestamp> {
+        *self.render_thread_init.output_timestamp.lock().unwrap()
+    }
+
+    /// Returns the playout statistics of this context: the number of frames played, the buffer
+    /// underruns and the output latency.
+    ///
+    /// The statistics are collected by the render thread on every callback of the audio backend
+    /// and can be read at any time. The underruns are estimated from the render time of each
+    /// callback, see [`AudioPlaybackStats`].
+    #[must_use]
+    #[allow(clippy::missing_panics_doc)]
+    pub fn playout_stats(&self) -> AudioPlaybackStats {
+        self.render_thread_init
+            .playout_stats
+            .lock()
+            .unwrap()
+            .snapshot(self.sample_rate())
+    }
+
+    /// Returns the counters of the message channels between the control and render thread: the
+    /// number of messages queued, stored in the overflow queue, rejected and dropped.
+    ///
+    /// See [`AudioContextOptions::control_overflow_policy`] and
+    /// [`AudioContextOptions::event_overflow_policy`] to configure the behavior when a channel is
+    /// full.
+    #[must_use]
+    pub fn message_channel_stats(&self) -> MessageChannelStats {
+        self.base.message_channel_stats()
+    }
+
+    /// Reset the latency statistics of [`Self::playout_stats`], so that the minimum, maximum and
+    /// average latency only cover the callbacks from now on.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn reset_playout_latency(&self) {
+        self.render_thread_init
+            .playout_stats
+            .lock()
+            .unwrap()
+            .reset_latency();
+    }
+
     /// Identifier or the information of the current audio output device.
     ///
     /// The initial value is `""`, which means the default audio output device.
@@ -268,22 +710,91 @@ impl AudioContext {
         self.backend_manager.lock().unwrap().sink_id().to_owned()
     }
 
+    /// Render the next frames of the audio graph into an interleaved buffer
+    ///
+    /// This is only effective for an `AudioContext` with the `"pull"` sink, which does not open
+    /// an audio stream itself. Call this method from the audio callback of the host application
+    /// to drive the rendering. The `output` buffer is filled with silence for any other sink, or
+    /// when the sink is being changed.
+    ///
+    /// This method is real-time safe: it will not block, allocate or perform I/O.
+    ///
+    /// The host application should keep calling this method while the context is running, as
+    /// control operations such as [`suspend_sync`](Self::suspend_sync),
+    /// [`resume_sync`](Self::resume_sync), [`close_sync`](Self::close_sync) and
+    /// [`set_sink_id_sync`](Self::set_sink_id_sync) take effect during rendering.
+    ///
+    /// ```
+    /// use web_audio_api::context::{AudioContext, AudioContextOptions, BaseAudioContext};
+    /// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+    ///
+    /// let context = AudioContext::new(AudioContextOptions {
+    ///     sink_id: "pull".into(),
+    ///     ..AudioContextOptions::default()
+    /// });
+    ///
+    /// let mut osc = context.create_oscillator();
+    /// osc.connect(&context.destination());
+    /// osc.start();
+    ///
+    /// // in the audio callback of the host application
+    /// let mut output = vec![0.; 2 * 512];
+    /// context.render_into(&mut output, 2);
+    /// ```
+    ///
+    /// The `output` buffer is also filled with silence, and a warning is logged, when
+    /// `number_of_channels` is zero or greater than [`crate::MAX_CHANNELS`], or when the length of
+    /// `output` is not a multiple of `number_of_channels`.
+    pub fn render_into(&self, output: &mut [f32], number_of_channels: usize) {
+        self.render_thread_init
+            .pull_renderer
+            .render_into(output, number_of_channels);
+    }
+
+    /// Returns the handle to the `"loopback"` sink of this context
+    ///
+    /// The handle drives the virtual clock of the sink and receives the rendered audio. It can
+    /// be obtained before switching to the `"loopback"` sink and remains valid afterwards. See
+    /// [`Loopback`] for an example.
+    #[must_use]
+    pub fn loopback(&self) -> Loopback {
+        self.render_thread_init.loopback.clone()
+    }
+
+    /// Handle to the render thread of the `"pull"` sink, which is also used by shared outputs
+    pub(crate) fn pull_renderer(&self) -> &PullRenderer {
+        &self.render_thread_init.pull_renderer
+    }
+
+    /// Input captured in the same stream as the output, if requested
+    pub(crate) fn duplex_input(&self) -> Option<&DuplexInput> {
+        self.render_thread_init.duplex_input.as_ref()
+    }
+
     /// Returns an [`AudioRenderCapacity`] instance associated with an AudioContext.
     #[must_use]
     pub fn render_capacity(&self) -> AudioRenderCapacity {
         self.render_capacity.clone()
     }
 
+    /// Returns an [`AudioRenderProfiler`] instance associated with an AudioContext.
+    #[must_use]
+    pub fn render_profiler(&self) -> AudioRenderProfiler {
+        self.render_profiler.clone()
+    }
+
     /// Update the current audio output device.
     ///
     /// The provided `sink_id` string must match a device name `enumerate_devices_sync`.
     ///
     /// Supplying `"none"` for the `sink_id` will process the audio graph without playing through an
-    /// audio output device.
+    /// audio output device. Supplying `"pull"` will hand over the rendering to the host
+    /// application, see [`render_into`](Self::render_into). Supplying `"loopback"` will deliver
+    /// the rendered audio to the host application, see [`loopback`](Self::loopback).
     ///
     /// This function operates synchronously and might block the current thread. An async version
     /// is currently not implemented.
-    #[allow(clippy::needless_collect, clippy::missing_panics_doc)]
+    #[allow(clippy::missing_panics_doc)]
     pub fn set_sink_id_sync(&self, sink_id: String) -> Result<(), Box<dyn Error>> {
         log::debug!("SinkChange requested");
         if self.sink_id() == sink_id {
@@ -291,93 +802,33 @@ impl AudioContext {
             return Ok(()); // sink is already active
         }
 
-        if !is_valid_sink_id(&sink_id) {
-            Err(format!("NotFoundError: invalid sinkId {sink_id}"))?;
-        };
-
-        log::debug!("SinkChange: locking backend manager");
-        let mut backend_manager_guard = self.backend_manager.lock().unwrap();
-        let original_state = self.state();
-        if original_state == AudioContextState::Closed {
-            log::debug!("SinkChange: context is closed");
-            return Ok(());
-        }
-
-        // Acquire exclusive lock on ctrl msg sender
-        log::debug!("SinkChange: locking message channel");
-        let ctrl_msg_send = self.base.lock_control_msg_sender();
+        check_valid_sink_id(&sink_id)?;
 
-        // Flush out the ctrl msg receiver, cache
-        let mut pending_msgs: Vec<_> = self.render_thread_init.ctrl_msg_recv.try_iter().collect();
-
-        // Acquire the active audio graph from the current render thread, shutting it down
-        let graph = if matches!(pending_msgs.first(), Some(ControlMessage::Startup { .. })) {
-            // Handle the edge case where the previous backend was suspended for its entire lifetime.
-            // In this case, the `Startup` control message was never processed.
-            log::debug!("SinkChange: recover unstarted graph");
-
-            let msg = pending_msgs.remove(0);
-            match msg {
-                ControlMessage::Startup { graph } => graph,
-                _ => unreachable!(),
-            }
-        } else {
-            // Acquire the audio graph from the current render thread, shutting it down
-            log::debug!("SinkChange: recover graph from render thread");
-
-            let (graph_send, graph_recv) = crossbeam_channel::bounded(1);
-            let message = ControlMessage::CloseAndRecycle { sender: graph_send };
-            ctrl_msg_send.send(message).unwrap();
-            if original_state == AudioContextState::Suspended {
-                // We must wake up the render thread to be able to handle the shutdown.
-                // No new audio will be produced because it will receive the shutdown command first.
-                backend_manager_guard.resume();
-            }
-            graph_recv.recv().unwrap()
-        };
-
-        log::debug!("SinkChange: closing audio stream");
-        backend_manager_guard.close();
-
-        // hotswap the backend
-        let options = AudioContextOptions {
-            sample_rate: Some(self.sample_rate()),
-            latency_hint: AudioContextLatencyCategory::default(), // todo reuse existing setting
+        switch_sink(
+            &self.base,
+            &self.backend_manager,
+            &self.render_thread_init,
+            self.latency_hint,
             sink_id,
-            render_size_hint: AudioContextRenderSizeCategory::default(), // todo reuse existing setting
-        };
-        log::debug!("SinkChange: starting audio stream");
-        *backend_manager_guard = io::build_output(options, self.render_thread_init.clone());
-
-        // if the previous backend state was suspend, suspend the new one before shipping the graph
-        if original_state == AudioContextState::Suspended {
-            log::debug!("SinkChange: suspending audio stream");
-            backend_manager_guard.suspend();
-        }
-
-        // send the audio graph to the new render thread
-        let message = ControlMessage::Startup { graph };
-        ctrl_msg_send.send(message).unwrap();
-
-        // flush the cached msgs
-        pending_msgs
-            .into_iter()
-            .for_each(|m| self.base().send_control_msg(m));
-
-        // explicitly release the lock to prevent concurrent render threads
-        drop(backend_manager_guard);
-
-        // trigger event when all the work is done
-        let _ = self.base.send_event(EventDispatch::sink_change());
-
-        log::debug!("SinkChange: done");
+        );
         Ok(())
     }
 
+    /// Run the handlers of the pending events on the current thread
+    ///
+    /// Use this method with [`EventDispatchMode::Manual`] to dispatch the events from the main
+    /// loop of the host application. This is the only way to run listeners registered with
+    /// [`add_local_event_listener`](BaseAudioContext::add_local_event_listener). Returns `true`
+    /// if any events were dispatched.
+    pub fn dispatch_pending_events(&self) -> bool {
+        self.base().handle_pending_events()
+    }
+
     /// Register callback to run when the audio sink has changed
     ///
     /// Only a single event handler is active at any time. Calling this method multiple times will
-    /// override the previous event handler.
+    /// override the previous event handler. Use
+    /// [`add_event_listener`](BaseAudioContext::add_event_listener) to register additional callbacks.
     pub fn set_onsinkchange<F: FnMut(Event) + Send + 'static>(&self, mut callback: F) {
         let callback = move |_| {
             callback(Event {
@@ -396,6 +847,35 @@ impl AudioContext {
         self.base().clear_event_handler(EventType::SinkChange);
     }
 
+    /// Register callback to run when the audio output device has failed, e.g. when it is
+    /// unplugged
+    ///
+    /// The playback stops, unless [`AudioContextOptions::fallback_to_default_sink`] is set.
+    ///
+    /// Only a single event handler is active at any time. Calling this method multiple times will
+    /// override the previous event handler. Use
+    /// [`add_event_listener`](BaseAudioContext::add_event_listener) to register additional callbacks.
+    pub fn set_onerror<F: FnMut(ErrorEvent) + Send + 'static>(&self, mut callback: F) {
+        let callback = move |v| match v {
+            EventPayload::Error(message) => callback(ErrorEvent {
+                message: message.clone(),
+                error: Box::new(message),
+                event: Event {
+                    type_: "ErrorEvent",
+                },
+            }),
+            _ => unreachable!(),
+        };
+
+        self.base()
+            .set_event_handler(EventType::Error, EventHandler::Multiple(Box::new(callback)));
+    }
+
+    /// Unset the callback to run when the audio output device has failed
+    pub fn clear_onerror(&self) {
+        self.base().clear_event_handler(EventType::Error);
+    }
+
     #[allow(clippy::missing_panics_doc)]
     #[doc(hidden)] // Method signature might change in the future
     pub fn run_diagnostics<F: Fn(String) + Send + 'static>(&self, callback: F) {
@@ -543,8 +1023,9 @@ impl AudioContext {
         log::debug!("Suspended audio graph. Closing audio stream..");
         self.backend_manager.lock().unwrap().close();
 
-        // Stop the AudioRenderCapacity collection thread
+        // Stop the AudioRenderCapacity and AudioRenderProfiler collection threads
         self.render_capacity.stop();
+        self.render_profiler.stop();
 
         log::debug!("Closed audio stream");
     }
@@ -669,12 +1150,24 @@ impl AudioContext {
         log::debug!("Suspended audio graph. Closing audio stream..");
         backend_manager_guard.close();
 
-        // Stop the AudioRenderCapacity collection thread
+        // Stop the AudioRenderCapacity and AudioRenderProfiler collection threads
         self.render_capacity.stop();
+        self.render_profiler.stop();
 
         log::debug!("Closed audio stream");
     }
 
+    /// Creates an [`InputAudioSourceNode`](node::InputAudioSourceNode) that plays the input
+    /// captured in the same stream as the output, see [`AudioContextOptions::input`]
+    ///
+    /// # Panics
+    ///
+    /// This method will panic when the context does not capture an input.
+    #[must_use]
+    pub fn create_input_source(&self) -> node::InputAudioSourceNode {
+        node::InputAudioSourceNode::new(self)
+    }
+
     /// Creates a [`MediaStreamAudioSourceNode`](node::MediaStreamAudioSourceNode) from a
     /// [`MediaStream`]
     #[must_use]
@@ -723,6 +1216,7 @@ impl AudioContext {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::node::AudioNode;
     use futures::executor;
 
     #[test]
@@ -769,6 +1263,70 @@ mod tests {
         assert_eq!(time5, time4); // no progression of time
     }
 
+    #[test]
+    fn test_sink_fallback() {
+        let options = AudioContextOptions {
+            sink_id: "none".into(),
+            ..AudioContextOptions::default()
+        };
+        let context = AudioContext::new(options);
+        context.install_sink_fallback(String::from("none"));
+
+        let (error_send, error_recv) = crossbeam_channel::unbounded();
+        context.set_onerror(move |e| error_send.send(e.message).unwrap());
+        let (sink_send, sink_recv) = crossbeam_channel::unbounded();
+        context.set_onsinkchange(move |_| sink_send.send(()).unwrap());
+
+        // a suspended render thread does not render anymore, like the one of a failed device
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        executor::block_on(context.suspend());
+        let time1 = context.current_time();
+
+        // these messages are still queued when the sink falls back
+        let gain = context.create_gain();
+        gain.connect(&context.destination());
+
+        let message = String::from("device is gone");
+        context
+            .base()
+            .send_event(EventDispatch::error(message.clone()))
+            .unwrap();
+        context.render_thread_init.device_failure.notify();
+
+        let timeout = std::time::Duration::from_secs(1);
+        assert_eq!(error_recv.recv_timeout(timeout).unwrap(), message);
+        sink_recv.recv_timeout(timeout).unwrap();
+        assert_eq!(context.state(), AudioContextState::Suspended);
+
+        // the recycled audio graph is rendered by the new backend
+        executor::block_on(context.resume());
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        assert!(context.current_time() > time1);
+    }
+
+    #[test]
+    fn test_sink_fallback_manual_dispatch() {
+        let options = AudioContextOptions {
+            sink_id: "none".into(),
+            event_dispatch_mode: EventDispatchMode::Manual,
+            ..AudioContextOptions::default()
+        };
+        let context = AudioContext::new(options);
+        context.install_sink_fallback(String::from("none"));
+
+        let (sink_send, sink_recv) = crossbeam_channel::unbounded();
+        context.set_onsinkchange(move |_| sink_send.send(()).unwrap());
+
+        // the fallback does not wait for the application to dispatch the error event
+        context.render_thread_init.device_failure.notify();
+        let start = Instant::now();
+        while sink_recv.try_recv().is_err() {
+            assert!(start.elapsed() < Duration::from_secs(1));
+            std::thread::sleep(Duration::from_millis(10));
+            context.dispatch_pending_events();
+        }
+    }
+
     fn require_send_sync<T: Send + Sync>(_: T) {}
 
     #[test]
@@ -0,0 +1,503 @@
+//! Multiple `AudioContext`s playing through one audio output stream
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+
+use crate::buffer::AudioBuffer;
+use crate::context::{
+    AudioContext, AudioContextOptions, AudioContextRegistration, AudioContextState,
+    BaseAudioContext,
+};
+use crate::io::{AudioBackendManager, PullBackend, PullRenderer, RenderThreadInit};
+use crate::media_devices::MediaDeviceInfo;
+use crate::node::{AudioNode, ChannelConfig, GainNode};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::DomException;
+
+use crossbeam_channel::Receiver;
+
+/// Audio output stream that is shared by multiple [`AudioContext`]s
+///
+/// Every `AudioContext` usually opens its own stream on the audio output device. Contexts created
+/// with [`SharedOutput::create_context`] render into a single stream instead, where their outputs
+/// are mixed with a per-context gain. Each context keeps its own state, `current_time` and
+/// suspend/resume behavior.
+///
+/// The stream is closed when the `SharedOutput` and its clones have been dropped, the contexts
+/// that are left stop rendering then. Changing the sink of a context with
+/// [`set_sink_id_sync`](AudioContext::set_sink_id_sync) detaches it from the shared output.
+/// A context that is dropped while running keeps playing in the mix, like a regular
+/// `AudioContext` keeps playing.
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContextOptions, BaseAudioContext, SharedOutput};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+///
+/// let output = SharedOutput::new(AudioContextOptions::default());
+///
+/// let music = output.create_context(AudioContextOptions::default());
+/// let ui = output.create_context(AudioContextOptions::default());
+/// output.set_gain(&music, 0.5).unwrap();
+///
+/// let mut osc = music.create_oscillator();
+/// osc.connect(&music.destination());
+/// osc.start();
+///
+/// // suspending one context does not affect the others
+/// ui.suspend_sync();
+/// ```
+#[derive(Clone)]
+pub struct SharedOutput {
+    inner: Arc<SharedOutputInner>,
+}
+
+impl std::fmt::Debug for SharedOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedOutput")
+            .field("sink_id", &self.sink_id())
+            .field("sample_rate", &self.sample_rate())
+            .finish_non_exhaustive()
+    }
+}
+
+struct SharedOutputInner {
+    /// The context that owns the output stream and mixes the contexts
+    mixer: AudioContext,
+    members: Mutex<Vec<Member>>,
+}
+
+/// A context rendering into the shared output
+struct Member {
+    renderer: PullRenderer,
+    source: SharedOutputSourceNode,
+    gain: GainNode,
+    /// Cleared when the context leaves the mix, so the source node can be freed
+    active: Arc<AtomicBool>,
+}
+
+impl Drop for SharedOutputInner {
+    fn drop(&mut self) {
+        self.mixer.close_sync();
+    }
+}
+
+impl SharedOutputInner {
+    fn add_member(&self, renderer: PullRenderer, number_of_channels: usize) {
+        let active = Arc::new(AtomicBool::new(true));
+        let source = SharedOutputSourceNode::new(
+            &self.mixer,
+            renderer.clone(),
+            number_of_channels,
+            Arc::clone(&active),
+        );
+        let gain = self.mixer.create_gain();
+        source.connect(&gain);
+        gain.connect(&self.mixer.destination());
+
+        let member = Member {
+            renderer,
+            source,
+            gain,
+            active,
+        };
+        self.members.lock().unwrap().push(member);
+    }
+
+    fn remove_member(&self, renderer: &PullRenderer) {
+        let mut members = self.members.lock().unwrap();
+        if let Some(index) = members.iter().position(|m| m.renderer.ptr_eq(renderer)) {
+            let member = members.remove(index);
+            member.active.store(false, Ordering::Relaxed);
+            member.source.disconnect();
+            member.gain.disconnect();
+        }
+    }
+}
+
+impl SharedOutput {
+    /// Open a stream on the audio output device
+    ///
+    /// The `sink_id`, `sample_rate` and `latency_hint` of the `options` apply to the stream, and
+    /// thereby to all contexts that render into it.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`AudioContext::new`].
+    #[must_use]
+    pub fn new(options: AudioContextOptions) -> Self {
+        let inner = SharedOutputInner {
+            mixer: AudioContext::new(options),
+            members: Mutex::new(Vec::new()),
+        };
+
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+
+    /// Create an `AudioContext` that renders into this shared output
+    ///
+    /// The `sink_id` and `sample_rate` of the `options` are ignored, the context uses the sink
+    /// and sample rate of the shared output. The output of the context is mixed in with a gain
+    /// of 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`AudioContext::new`].
+    #[must_use]
+    pub fn create_context(&self, mut options: AudioContextOptions) -> AudioContext {
+        let mixer = &self.inner.mixer;
+        options.sink_id = mixer.sink_id();
+        options.sample_rate = Some(mixer.sample_rate());
+        let number_of_channels = mixer.destination().channel_count();
+
+        let inner = Arc::downgrade(&self.inner);
+        AudioContext::with_backend(options, move |options, render_thread_init| {
+            let backend =
+                SharedBackend::new(options, render_thread_init, number_of_channels, inner);
+            Box::new(backend)
+        })
+    }
+
+    /// Set the gain applied to the output of `context` in the mix
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidAccessError` if the context does not render into this shared output,
+    /// e.g. when it has been closed or moved to another sink, and a `TypeError` if `gain` is not
+    /// finite.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn set_gain(&self, context: &AudioContext, gain: f32) -> Result<(), DomException> {
+        let members = self.inner.members.lock().unwrap();
+        let member = members
+            .iter()
+            .find(|m| m.renderer.ptr_eq(context.pull_renderer()))
+            .ok_or_else(|| {
+                DomException::InvalidAccessError(String::from(
+                    "context does not render into this shared output",
+                ))
+            })?;
+        member.gain.gain().try_set_value(gain)?;
+        Ok(())
+    }
+
+    /// The audio output device of the shared stream
+    #[must_use]
+    pub fn sink_id(&self) -> String {
+        self.inner.mixer.sink_id()
+    }
+
+    /// The sample rate of the shared stream, which is used by all contexts
+    #[must_use]
+    pub fn sample_rate(&self) -> f32 {
+        self.inner.mixer.sample_rate()
+    }
+
+    /// The state of the shared stream
+    ///
+    /// The stream keeps running when the contexts are suspended.
+    #[must_use]
+    pub fn state(&self) -> AudioContextState {
+        self.inner.mixer.state()
+    }
+}
+
+/// Audio backend of a context that renders into a [`SharedOutput`]
+///
+/// The rendering is driven by the audio graph of the mixer context, like a pull backend.
+struct SharedBackend {
+    backend: PullBackend,
+    renderer: PullRenderer,
+    sink_id: String,
+    number_of_channels: usize,
+    /// The members do not keep the shared output alive, a context that is dropped while running
+    /// is never closed
+    output: Weak<SharedOutputInner>,
+}
+
+impl SharedBackend {
+    fn new(
+        options: AudioContextOptions,
+        render_thread_init: RenderThreadInit,
+        number_of_channels: usize,
+        output: Weak<SharedOutputInner>,
+    ) -> Self {
+        let renderer = render_thread_init.pull_renderer.clone();
+        let sink_id = options.sink_id.clone();
+        let backend = PullBackend::build_output(options, render_thread_init);
+        // the shared output is alive while it creates the context
+        if let Some(output) = output.upgrade() {
+            output.add_member(renderer.clone(), number_of_channels);
+        }
+
+        Self {
+            backend,
+            renderer,
+            sink_id,
+            number_of_channels,
+            output,
+        }
+    }
+}
+
+impl AudioBackendManager for SharedBackend {
+    /// Setup a new output stream (speakers)
+    fn build_output(_options: AudioContextOptions, _render_thread_init: RenderThreadInit) -> Self
+    where
+        Self: Sized,
+    {
+        unreachable!("the backend is created by the shared output")
+    }
+
+    /// Setup a new input stream (microphone capture)
+    fn build_input(
+        _options: AudioContextOptions,
+        _number_of_channels: Option<u32>,
+    ) -> Result<(Self, Receiver<AudioBuffer>), DomException>
+    where
+        Self: Sized,
+    {
+        unimplemented!()
+    }
+
+    /// Resume or start the stream
+    fn resume(&self) -> bool {
+        self.backend.resume()
+    }
+
+    /// Suspend the stream
+    fn suspend(&self) -> bool {
+        self.backend.suspend()
+    }
+
+    /// Close the stream, freeing all resources. It cannot be started again after closing.
+    fn close(&self) {
+        self.backend.close();
+        if let Some(output) = self.output.upgrade() {
+            output.remove_member(&self.renderer);
+        }
+    }
+
+    /// Sample rate of the stream
+    fn sample_rate(&self) -> f32 {
+        self.backend.sample_rate()
+    }
+
+    /// Number of channels of the stream
+    fn number_of_channels(&self) -> usize {
+        self.number_of_channels
+    }
+
+    /// Number of sample frames the render thread renders per render quantum
+    fn render_quantum_size(&self) -> usize {
+        self.backend.render_quantum_size()
+    }
+
+    /// Output latency of the stream in seconds
+    ///
+    /// This is the difference between the time the backend acquires the data in the callback and
+    /// the listener can hear the sound.
+    fn output_latency(&self) -> f64 {
+        self.output
+            .upgrade()
+            .map_or(0., |output| output.mixer.output_latency())
+    }
+
+    /// The audio output device
+    fn sink_id(&self) -> &str {
+        &self.sink_id
+    }
+
+    fn enumerate_devices_sync() -> Vec<MediaDeviceInfo>
+    where
+        Self: Sized,
+    {
+        unimplemented!()
+    }
+}
+
+/// Source node in the mixer context that plays the output of a member context
+struct SharedOutputSourceNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+}
+
+impl AudioNode for SharedOutputSourceNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        0
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl SharedOutputSourceNode {
+    fn new<C: BaseAudioContext>(
+        context: &C,
+        renderer: PullRenderer,
+        number_of_channels: usize,
+        active: Arc<AtomicBool>,
+    ) -> Self {
+        context.base().register(move |registration| {
+            let node = SharedOutputSourceNode {
+                registration,
+                channel_config: ChannelConfig::default(),
+            };
+
+            let render = SharedOutputRenderer {
+                renderer,
+                number_of_channels,
+                active,
+                buffer: vec![0.; context.render_quantum_size() * number_of_channels],
+            };
+
+            (node, Box::new(render))
+        })
+    }
+}
+
+struct SharedOutputRenderer {
+    renderer: PullRenderer,
+    number_of_channels: usize,
+    active: Arc<AtomicBool>,
+    /// Interleaved output of the member context
+    buffer: Vec<f32>,
+}
+
+impl AudioProcessor for SharedOutputRenderer {
+    fn process(
+        &mut self,
+        _inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single output node
+        let output = &mut outputs[0];
+
+        // the member context has left the mix
+        if !self.active.load(Ordering::Relaxed) {
+            output.make_silent();
+            return false;
+        }
+
+        // render the member context, this is real-time safe
+        self.renderer
+            .render_into(&mut self.buffer, self.number_of_channels);
+
+        output.set_number_of_channels(self.number_of_channels);
+        output
+            .channels_mut()
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, channel)| {
+                let samples = self.buffer.iter().skip(i).step_by(self.number_of_channels);
+                channel.iter_mut().zip(samples).for_each(|(o, i)| *o = *i);
+            });
+
+        // source node, until the member context leaves the mix
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::AudioScheduledSourceNode;
+
+    /// Render the shared output until `f` returns, which may wait for the member contexts
+    fn advance_while<F: FnOnce() + Send>(output: &SharedOutput, f: F) {
+        let loopback = output.inner.mixer.loopback();
+        std::thread::scope(|s| {
+            let handle = s.spawn(f);
+            while !handle.is_finished() {
+                loopback.advance(128);
+            }
+        });
+    }
+
+    #[test]
+    fn test_shared_output() {
+        let output = SharedOutput::new(AudioContextOptions {
+            sink_id: "loopback".into(),
+            ..AudioContextOptions::default()
+        });
+        let loopback = output.inner.mixer.loopback();
+        assert_eq!(output.sink_id(), "loopback");
+
+        let music = output.create_context(AudioContextOptions::default());
+        let voice = output.create_context(AudioContextOptions::default());
+        assert_eq!(music.sink_id(), "loopback");
+        assert_eq!(music.sample_rate(), output.sample_rate());
+
+        let mut src = music.create_constant_source();
+        src.offset().set_value(0.25);
+        src.connect(&music.destination());
+        src.start();
+
+        let mut src = voice.create_constant_source();
+        src.offset().set_value(0.5);
+        src.connect(&voice.destination());
+        src.start();
+        output.set_gain(&voice, 0.5).unwrap();
+
+        // the outputs are mixed with the gain of each context
+        loopback.advance(128 * 4);
+        let block = loopback.drain().last().unwrap();
+        assert!(block.get_channel_data(0).iter().all(|&v| v == 0.5));
+        assert_eq!(music.state(), AudioContextState::Running);
+        assert_eq!(voice.state(), AudioContextState::Running);
+
+        // each context is suspended independently
+        advance_while(&output, || voice.suspend_sync());
+        assert_eq!(voice.state(), AudioContextState::Suspended);
+        let voice_time = voice.current_time();
+        let music_time = music.current_time();
+
+        loopback.drain().for_each(drop);
+        loopback.advance(128);
+        let block = loopback.try_recv().unwrap();
+        assert!(block.get_channel_data(0).iter().all(|&v| v == 0.25));
+        assert_eq!(voice.current_time(), voice_time);
+        assert!(music.current_time() > music_time);
+
+        advance_while(&output, || voice.resume_sync());
+        assert_eq!(voice.state(), AudioContextState::Running);
+
+        // a closed context leaves the mix
+        advance_while(&output, || music.close_sync());
+        let error = output.set_gain(&music, 1.).unwrap_err();
+        assert!(matches!(error, DomException::InvalidAccessError(_)));
+        output.set_gain(&voice, 1.).unwrap();
+        loopback.drain().for_each(drop);
+        loopback.advance(128);
+        let block = loopback.try_recv().unwrap();
+        assert!(block.get_channel_data(0).iter().all(|&v| v == 0.5));
+        assert_eq!(output.inner.members.lock().unwrap().len(), 1);
+        assert_eq!(output.state(), AudioContextState::Running);
+    }
+
+    #[test]
+    fn test_members_do_not_keep_output_alive() {
+        let output = SharedOutput::new(AudioContextOptions {
+            sink_id: "loopback".into(),
+            ..AudioContextOptions::default()
+        });
+        let context = output.create_context(AudioContextOptions::default());
+        let inner = Arc::downgrade(&output.inner);
+
+        drop(output);
+        assert!(inner.upgrade().is_none());
+        assert_eq!(context.output_latency(), 0.);
+    }
+}
@@ -0,0 +1,282 @@
+use std::fmt::Write;
+
+use crate::node::{ChannelCountMode, ChannelInterpretation};
+
+/// Snapshot of the audio graph on the render thread, as delivered by
+/// [`BaseAudioContext::graph_diagnostics`](crate::context::BaseAudioContext::graph_diagnostics)
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AudioGraphDiagnostics {
+    /// All nodes that are currently alive in the audio graph, sorted by id
+    pub nodes: Vec<AudioNodeDiagnostics>,
+    /// Number of audio buffers that are available for reuse by the render thread
+    pub buffer_pool_size: usize,
+}
+
+/// Render thread state of a single node in the audio graph
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AudioNodeDiagnostics {
+    /// Unique identifier of the node within its context
+    pub id: u64,
+    /// Type name of the node's render thread processor
+    pub name: &'static str,
+    /// Number of channels used when up/down-mixing the inputs
+    pub channel_count: usize,
+    /// How the number of channels of the inputs is determined
+    pub channel_count_mode: ChannelCountMode,
+    /// How the inputs are up/down-mixed
+    pub channel_interpretation: ChannelInterpretation,
+    /// Outgoing connections of this node
+    pub outgoing_edges: Vec<AudioEdgeDiagnostics>,
+    /// Indicates if the control thread has dropped its handle to this node
+    pub control_handle_dropped: bool,
+    /// Indicates if the node can break cycles in the graph (only DelayNode for now)
+    pub cycle_breaker: bool,
+    /// Indicates if the node is part of a cycle without cycle breaker, and is thus muted
+    pub muted: bool,
+    /// Position of the node in the render order, `None` when the node is muted
+    pub render_order: Option<usize>,
+}
+
+/// Connection from an output of a node to another node in the audio graph
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AudioEdgeDiagnostics {
+    /// Index of the output of the source node
+    pub output: usize,
+    /// Identifier of the destination node
+    pub destination: u64,
+    /// Index of the input of the destination node, `None` for the
+    /// connection of an `AudioParam` to the node it belongs to
+    pub input: Option<usize>,
+}
+
+impl AudioGraphDiagnostics {
+    /// Look up the node with the given id
+    pub fn node(&self, id: u64) -> Option<&AudioNodeDiagnostics> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    /// Iterate over all nodes whose processor has the given name
+    pub fn nodes_by_name<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a AudioNodeDiagnostics> + 'a {
+        self.nodes.iter().filter(move |node| node.name == name)
+    }
+
+    /// Serialize the graph topology to the Graphviz DOT format
+    ///
+    /// Connections of an `AudioParam` to its node and muted nodes (part of a cycle without cycle
+    /// breaker) are drawn dashed, cycle breakers are drawn with a double border.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph audio_graph {\n");
+
+        for node in &self.nodes {
+            let _ = write!(
+                dot,
+                "    n{} [label=\"{} #{}\"",
+                node.id,
+                escape(node.name),
+                node.id
+            );
+            if node.muted {
+                dot.push_str(", style=dashed");
+            }
+            if node.cycle_breaker {
+                dot.push_str(", peripheries=2");
+            }
+            dot.push_str("];\n");
+        }
+
+        for node in &self.nodes {
+            for edge in &node.outgoing_edges {
+                let _ = match edge.input {
+                    Some(input) => writeln!(
+                        dot,
+                        "    n{} -> n{} [label=\"{}:{}\"];",
+                        node.id, edge.destination, edge.output, input
+                    ),
+                    None => writeln!(
+                        dot,
+                        "    n{} -> n{} [label=\"{}:param\", style=dashed];",
+                        node.id, edge.destination, edge.output
+                    ),
+                };
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Serialize the graph topology to JSON
+    ///
+    /// The output is an object with the `buffer_pool_size`, a `nodes` array and an `edges` array.
+    /// Connections of an `AudioParam` to its node have a `null` input.
+    pub fn to_json(&self) -> String {
+        let mut json = format!(
+            "{{\"buffer_pool_size\":{},\"nodes\":[",
+            self.buffer_pool_size
+        );
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            let _ = write!(
+                json,
+                "{{\"id\":{},\"name\":\"{}\",\"channel_count\":{},\
+                \"channel_count_mode\":\"{}\",\"channel_interpretation\":\"{}\",\
+                \"control_handle_dropped\":{},\"cycle_breaker\":{},\"muted\":{},\
+                \"render_order\":{}}}",
+                node.id,
+                escape(node.name),
+                node.channel_count,
+                count_mode_str(node.channel_count_mode),
+                interpretation_str(node.channel_interpretation),
+                node.control_handle_dropped,
+                node.cycle_breaker,
+                node.muted,
+                optional(node.render_order),
+            );
+        }
+
+        json.push_str("],\"edges\":[");
+        let edges = self
+            .nodes
+            .iter()
+            .flat_map(|node| node.outgoing_edges.iter().map(move |edge| (node.id, edge)));
+        for (i, (source, edge)) in edges.enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            let _ = write!(
+                json,
+                "{{\"source\":{},\"output\":{},\"destination\":{},\"input\":{}}}",
+                source,
+                edge.output,
+                edge.destination,
+                optional(edge.input),
+            );
+        }
+
+        json.push_str("]}");
+        json
+    }
+}
+
+/// Escape a string for use inside double quotes, in both DOT and JSON
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn optional(value: Option<usize>) -> String {
+    value.map_or_else(|| String::from("null"), |v| v.to_string())
+}
+
+fn count_mode_str(mode: ChannelCountMode) -> &'static str {
+    match mode {
+        ChannelCountMode::Max => "max",
+        ChannelCountMode::ClampedMax => "clamped-max",
+        ChannelCountMode::Explicit => "explicit",
+    }
+}
+
+fn interpretation_str(interpretation: ChannelInterpretation) -> &'static str {
+    match interpretation {
+        ChannelInterpretation::Speakers => "speakers",
+        ChannelInterpretation::Discrete => "discrete",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostics() -> AudioGraphDiagnostics {
+        let node = |id, name, outgoing_edges| AudioNodeDiagnostics {
+            id,
+            name,
+            channel_count: 2,
+            channel_count_mode: ChannelCountMode::Max,
+            channel_interpretation: ChannelInterpretation::Speakers,
+            outgoing_edges,
+            control_handle_dropped: false,
+            cycle_breaker: false,
+            muted: false,
+            render_order: Some(id as usize),
+        };
+
+        AudioGraphDiagnostics {
+            nodes: vec![
+                node(
+                    0,
+                    "Param",
+                    vec![AudioEdgeDiagnostics {
+                        output: 0,
+                        destination: 1,
+                        input: None,
+                    }],
+                ),
+                node(
+                    1,
+                    "Renderer<\"quoted\">",
+                    vec![AudioEdgeDiagnostics {
+                        output: 0,
+                        destination: 2,
+                        input: Some(1),
+                    }],
+                ),
+                node(2, "Destination", vec![]),
+            ],
+            buffer_pool_size: 3,
+        }
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let expected = "digraph audio_graph {
+    n0 [label=\"Param #0\"];
+    n1 [label=\"Renderer<\\\"quoted\\\"> #1\"];
+    n2 [label=\"Destination #2\"];
+    n0 -> n1 [label=\"0:param\", style=dashed];
+    n1 -> n2 [label=\"0:1\"];
+}
+";
+        assert_eq!(diagnostics().to_dot(), expected);
+    }
+
+    #[test]
+    fn test_to_json() {
+        let json = diagnostics().to_json();
+        assert!(
+            json.starts_with("{\"buffer_pool_size\":3,\"nodes\":[{\"id\":0,\"name\":\"Param\",")
+        );
+        assert!(json.contains("\"name\":\"Renderer<\\\"quoted\\\">\""));
+        assert!(json.contains("\"channel_count_mode\":\"max\""));
+        assert!(json.contains("\"channel_interpretation\":\"speakers\""));
+        assert!(json.ends_with(
+            "\"edges\":[{\"source\":0,\"output\":0,\"destination\":1,\"input\":null},\
+            {\"source\":1,\"output\":0,\"destination\":2,\"input\":1}]}"
+        ));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a\\b\n\u{1}"), "a\\\\b\\n\\u0001");
+    }
+}
@@ -0,0 +1,628 @@
+//! Encoding of rendered audio into WAV and FLAC files
+
+use std::io::{self, Seek, SeekFrom, Write};
+
+use crate::buffer::AudioBuffer;
+
+/// Container and sample format of an encoded audio file
+///
+/// See [`OfflineAudioContext::start_rendering_to_file_sync`](crate::context::OfflineAudioContext::start_rendering_to_file_sync)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AudioFileFormat {
+    /// WAV file with 16-bit signed integer samples
+    WavInt16,
+    /// WAV file with 24-bit signed integer samples
+    WavInt24,
+    /// WAV file with 32-bit signed integer samples
+    WavInt32,
+    /// WAV file with 32-bit floating point samples
+    WavFloat32,
+    /// FLAC file with 16-bit samples
+    Flac16,
+    /// FLAC file with 24-bit samples
+    Flac24,
+}
+
+/// Convert a floating point sample to a signed integer sample of the given bit depth
+///
+/// Values outside the nominal range `[-1, 1]` are clipped.
+fn to_int_sample(value: f32, bits_per_sample: u32) -> i32 {
+    let scale = (1_i64 << (bits_per_sample - 1)) as f64;
+    // NaN is mapped to zero by the saturating cast
+    (value as f64 * scale).round().clamp(-scale, scale - 1.) as i32
+}
+
+fn hound_error(error: hound::Error) -> io::Error {
+    match error {
+        hound::Error::IoError(e) => e,
+        e => io::Error::new(io::ErrorKind::Other, e),
+    }
+}
+
+/// Encoder that writes `AudioBuffer`s to an audio file
+pub(crate) enum AudioFileEncoder<W: Write + Seek> {
+    Wav {
+        writer: hound::WavWriter<W>,
+        bits_per_sample: u32,
+        float: bool,
+    },
+    Flac(FlacEncoder<W>),
+}
+
+impl<W: Write + Seek> AudioFileEncoder<W> {
+    pub fn new(
+        writer: W,
+        format: AudioFileFormat,
+        number_of_channels: usize,
+        sample_rate: f32,
+    ) -> io::Result<Self> {
+        let (bits_per_sample, float) = match format {
+            AudioFileFormat::WavInt16 | AudioFileFormat::Flac16 => (16, false),
+            AudioFileFormat::WavInt24 | AudioFileFormat::Flac24 => (24, false),
+            AudioFileFormat::WavInt32 => (32, false),
+            AudioFileFormat::WavFloat32 => (32, true),
+        };
+
+        let encoder = match format {
+            AudioFileFormat::Flac16 | AudioFileFormat::Flac24 => Self::Flac(FlacEncoder::new(
+                writer,
+                number_of_channels,
+                sample_rate as u32,
+                bits_per_sample,
+            )?),
+            _ => {
+                let spec = hound::WavSpec {
+                    channels: number_of_channels as u16,
+                    sample_rate: sample_rate as u32,
+                    bits_per_sample: bits_per_sample as u16,
+                    sample_format: if float {
+                        hound::SampleFormat::Float
+                    } else {
+                        hound::SampleFormat::Int
+                    },
+                };
+                let writer = hound::WavWriter::new(writer, spec).map_err(hound_error)?;
+                Self::Wav {
+                    writer,
+                    bits_per_sample,
+                    float,
+                }
+            }
+        };
+
+        Ok(encoder)
+    }
+
+    /// Encode the (planar) buffer as interleaved samples
+    pub fn write(&mut self, buffer: &AudioBuffer) -> io::Result<()> {
+        let channels: Vec<_> = (0..buffer.number_of_channels())
+            .map(|c| buffer.get_channel_data(c))
+            .collect();
+
+        match self {
+            Self::Wav {
+                writer,
+                bits_per_sample,
+                float,
+            } => {
+                for i in 0..buffer.length() {
+                    for channel in &channels {
+                        let result = if *float {
+                            writer.write_sample(channel[i])
+                        } else {
+                            writer.write_sample(to_int_sample(channel[i], *bits_per_sample))
+                        };
+                        result.map_err(hound_error)?;
+                    }
+                }
+                Ok(())
+            }
+            Self::Flac(encoder) => encoder.write(&channels),
+        }
+    }
+
+    /// Flush the remaining samples and update the file headers
+    pub fn finalize(self) -> io::Result<()> {
+        match self {
+            Self::Wav { writer, .. } => writer.finalize().map_err(hound_error),
+            Self::Flac(encoder) => encoder.finalize(),
+        }
+    }
+}
+
+/// Number of inter-channel samples in a FLAC frame
+const FLAC_BLOCK_SIZE: usize = 4096;
+
+/// Minimal FLAC encoder
+///
+/// Every channel is encoded independently with the best fitting fixed linear predictor and Rice
+/// coded residuals. This achieves a reasonable compression ratio without the complexity of LPC
+/// analysis. The MD5 signature of the audio data is not computed.
+pub(crate) struct FlacEncoder<W: Write + Seek> {
+    writer: W,
+    /// Position of the STREAMINFO block, to be updated when finalizing
+    stream_info_pos: u64,
+    number_of_channels: usize,
+    sample_rate: u32,
+    bits_per_sample: u32,
+    /// Samples of the current block, per channel
+    block: Vec<Vec<i32>>,
+    /// Number of frames written
+    frame_number: u64,
+    total_samples: u64,
+    min_frame_size: u32,
+    max_frame_size: u32,
+    /// Scratch buffers
+    bits: BitWriter,
+    residual: Vec<i64>,
+}
+
+impl<W: Write + Seek> FlacEncoder<W> {
+    fn new(
+        mut writer: W,
+        number_of_channels: usize,
+        sample_rate: u32,
+        bits_per_sample: u32,
+    ) -> io::Result<Self> {
+        if !(1..=8).contains(&number_of_channels) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "NotSupportedError - FLAC supports up to 8 channels, got {:?}",
+                    number_of_channels
+                ),
+            ));
+        }
+
+        writer.write_all(b"fLaC")?;
+        let stream_info_pos = writer.stream_position()?;
+
+        let mut encoder = Self {
+            writer,
+            stream_info_pos,
+            number_of_channels,
+            sample_rate,
+            bits_per_sample,
+            block: vec![Vec::with_capacity(FLAC_BLOCK_SIZE); number_of_channels],
+            frame_number: 0,
+            total_samples: 0,
+            min_frame_size: 0,
+            max_frame_size: 0,
+            bits: BitWriter::default(),
+            residual: Vec::with_capacity(FLAC_BLOCK_SIZE),
+        };
+        encoder.write_stream_info()?;
+
+        Ok(encoder)
+    }
+
+    /// Write the metadata block header and STREAMINFO block
+    fn write_stream_info(&mut self) -> io::Result<()> {
+        let mut bits = BitWriter::default();
+        // last metadata block, type STREAMINFO, length 34 bytes
+        bits.write(1, 1);
+        bits.write(0, 7);
+        bits.write(34, 24);
+        // min and max block size
+        bits.write(FLAC_BLOCK_SIZE as u64, 16);
+        bits.write(FLAC_BLOCK_SIZE as u64, 16);
+        // min and max frame size, zero when unknown
+        bits.write(self.min_frame_size as u64, 24);
+        bits.write(self.max_frame_size as u64, 24);
+        bits.write(self.sample_rate as u64, 20);
+        bits.write(self.number_of_channels as u64 - 1, 3);
+        bits.write(self.bits_per_sample as u64 - 1, 5);
+        bits.write(self.total_samples, 36);
+        // MD5 signature, zero when not computed
+        bits.write(0, 64);
+        bits.write(0, 64);
+
+        self.writer.write_all(&bits.bytes)
+    }
+
+    fn write(&mut self, channels: &[&[f32]]) -> io::Result<()> {
+        let length = channels.first().map_or(0, |c| c.len());
+        let mut offset = 0;
+
+        while offset < length {
+            let count = (FLAC_BLOCK_SIZE - self.block[0].len()).min(length - offset);
+            for (block, channel) in self.block.iter_mut().zip(channels) {
+                block.extend(
+                    channel[offset..offset + count]
+                        .iter()
+                        .map(|&v| to_int_sample(v, self.bits_per_sample)),
+                );
+            }
+            offset += count;
+
+            if self.block[0].len() == FLAC_BLOCK_SIZE {
+                self.write_frame()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Encode the current block as a FLAC frame
+    fn write_frame(&mut self) -> io::Result<()> {
+        let block_size = self.block[0].len();
+        let mut bits = std::mem::take(&mut self.bits);
+        bits.clear();
+
+        // frame header: sync code, fixed block size, block size as 16 bits value at the end of
+        // the header, sample rate and sample size from STREAMINFO, independent channels
+        bits.write(0b11_1111_1111_1110, 14);
+        bits.write(0, 1);
+        bits.write(0, 1);
+        bits.write(0b0111, 4);
+        bits.write(0b0000, 4);
+        bits.write(self.number_of_channels as u64 - 1, 4);
+        bits.write(0b000, 3);
+        bits.write(0, 1);
+        bits.write_utf8(self.frame_number);
+        bits.write(block_size as u64 - 1, 16);
+        let crc = crc8(&bits.bytes);
+        bits.write(crc as u64, 8);
+
+        for channel in 0..self.number_of_channels {
+            encode_subframe(
+                &mut bits,
+                &self.block[channel],
+                self.bits_per_sample,
+                &mut self.residual,
+            );
+        }
+
+        bits.align();
+        let crc = crc16(&bits.bytes);
+        bits.write(crc as u64, 16);
+
+        self.writer.write_all(&bits.bytes)?;
+
+        let frame_size = bits.bytes.len() as u32;
+        if self.frame_number == 0 {
+            self.min_frame_size = frame_size;
+            self.max_frame_size = frame_size;
+        } else {
+            self.min_frame_size = self.min_frame_size.min(frame_size);
+            self.max_frame_size = self.max_frame_size.max(frame_size);
+        }
+
+        self.frame_number += 1;
+        self.total_samples += block_size as u64;
+        self.block.iter_mut().for_each(Vec::clear);
+        self.bits = bits;
+
+        Ok(())
+    }
+
+    fn finalize(mut self) -> io::Result<()> {
+        if !self.block[0].is_empty() {
+            self.write_frame()?;
+        }
+
+        let end = self.writer.stream_position()?;
+        self.writer.seek(SeekFrom::Start(self.stream_info_pos))?;
+        self.write_stream_info()?;
+        self.writer.seek(SeekFrom::Start(end))?;
+        self.writer.flush()
+    }
+}
+
+const SUBFRAME_CONSTANT: u64 = 0b00_0000;
+const SUBFRAME_VERBATIM: u64 = 0b00_0001;
+const SUBFRAME_FIXED: u64 = 0b00_1000;
+
+/// Encode the samples of a single channel as CONSTANT, FIXED or VERBATIM subframe, whichever is
+/// the smallest
+fn encode_subframe(
+    bits: &mut BitWriter,
+    samples: &[i32],
+    bits_per_sample: u32,
+    residual: &mut Vec<i64>,
+) {
+    if samples.iter().all(|&s| s == samples[0]) {
+        bits.write_subframe_header(SUBFRAME_CONSTANT);
+        bits.write_signed(samples[0] as i64, bits_per_sample);
+        return;
+    }
+
+    let verbatim_size = samples.len() as u64 * bits_per_sample as u64;
+    let mut best: Option<(usize, u32, u64)> = None;
+    for order in 0..=4.min(samples.len() - 1) {
+        fixed_residual(samples, order, residual);
+        let (parameter, size) = rice_parameter(residual);
+        let size = size + order as u64 * bits_per_sample as u64;
+        if best.map_or(true, |(_, _, best_size)| size < best_size) {
+            best = Some((order, parameter, size));
+        }
+    }
+
+    match best {
+        Some((order, parameter, size)) if size < verbatim_size => {
+            bits.write_subframe_header(SUBFRAME_FIXED | order as u64);
+            samples[..order]
+                .iter()
+                .for_each(|&s| bits.write_signed(s as i64, bits_per_sample));
+
+            fixed_residual(samples, order, residual);
+            // residual coding method: Rice with 4 or 5 bit parameters, partition order 0
+            if parameter < 15 {
+                bits.write(0b00, 2);
+                bits.write(0, 4);
+                bits.write(parameter as u64, 4);
+            } else {
+                bits.write(0b01, 2);
+                bits.write(0, 4);
+                bits.write(parameter as u64, 5);
+            }
+            residual.iter().for_each(|&r| bits.write_rice(r, parameter));
+        }
+        _ => {
+            bits.write_subframe_header(SUBFRAME_VERBATIM);
+            samples
+                .iter()
+                .for_each(|&s| bits.write_signed(s as i64, bits_per_sample));
+        }
+    }
+}
+
+/// Compute the residual of the fixed linear predictor of the given order
+fn fixed_residual(samples: &[i32], order: usize, residual: &mut Vec<i64>) {
+    residual.clear();
+    residual.extend((order..samples.len()).map(|i| {
+        let s = |j: usize| samples[i - j] as i64;
+        match order {
+            0 => s(0),
+            1 => s(0) - s(1),
+            2 => s(0) - 2 * s(1) + s(2),
+            3 => s(0) - 3 * s(1) + 3 * s(2) - s(3),
+            _ => s(0) - 4 * s(1) + 6 * s(2) - 4 * s(3) + s(4),
+        }
+    }));
+}
+
+/// Map a signed value to an unsigned value (0, -1, 1, -2, 2, ..)
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// Find the Rice parameter for the residual, returns the parameter and the size in bits
+fn rice_parameter(residual: &[i64]) -> (u32, u64) {
+    let size = |parameter: u32| {
+        residual
+            .iter()
+            .map(|&r| (zigzag(r) >> parameter) + 1 + parameter as u64)
+            .sum::<u64>()
+    };
+
+    // start from an estimate based on the mean value, and check its neighbours
+    let mean = residual.iter().map(|&r| zigzag(r)).sum::<u64>() / residual.len().max(1) as u64;
+    let estimate = (64 - mean.leading_zeros()).min(30);
+
+    (estimate.saturating_sub(1)..=(estimate + 1).min(30))
+        .map(|parameter| (parameter, size(parameter)))
+        .min_by_key(|&(_, size)| size)
+        .unwrap()
+}
+
+/// CRC-8, polynomial x^8 + x^2 + x^1 + x^0, initialized with 0
+fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0, |mut crc, &byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+/// CRC-16, polynomial x^16 + x^15 + x^2 + x^0, initialized with 0
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0, |mut crc, &byte| {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+/// Big endian bit writer
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    /// Pending bits, right aligned
+    pending: u64,
+    pending_count: u32,
+}
+
+impl BitWriter {
+    fn clear(&mut self) {
+        self.bytes.clear();
+        self.pending = 0;
+        self.pending_count = 0;
+    }
+
+    /// Write the lowest `count` bits of the value (at most 64)
+    fn write(&mut self, value: u64, count: u32) {
+        if count > 32 {
+            self.write(value >> 32, count - 32);
+            self.write(value & 0xFFFF_FFFF, 32);
+            return;
+        }
+
+        let mask = if count == 0 {
+            0
+        } else {
+            u64::MAX >> (64 - count)
+        };
+        self.pending = (self.pending << count) | (value & mask);
+        self.pending_count += count;
+
+        while self.pending_count >= 8 {
+            self.pending_count -= 8;
+            self.bytes.push((self.pending >> self.pending_count) as u8);
+        }
+        self.pending &= (1 << self.pending_count) - 1;
+    }
+
+    /// Write the subframe header of the given type, without wasted bits
+    fn write_subframe_header(&mut self, subframe_type: u64) {
+        self.write(0, 1);
+        self.write(subframe_type, 6);
+        self.write(0, 1);
+    }
+
+    /// Write a two's complement signed value of `count` bits
+    fn write_signed(&mut self, value: i64, count: u32) {
+        self.write(value as u64, count);
+    }
+
+    /// Write a Rice coded signed value with the given parameter
+    fn write_rice(&mut self, value: i64, parameter: u32) {
+        let value = zigzag(value);
+        // unary coded quotient
+        let mut quotient = value >> parameter;
+        while quotient >= 32 {
+            self.write(0, 32);
+            quotient -= 32;
+        }
+        self.write(1, quotient as u32 + 1);
+        self.write(value, parameter);
+    }
+
+    /// Write a value in the UTF-8 like coding of FLAC frame numbers
+    fn write_utf8(&mut self, value: u64) {
+        if value < 0x80 {
+            self.write(value, 8);
+            return;
+        }
+
+        let bytes = match value {
+            0..=0x7FF => 2,
+            0x800..=0xFFFF => 3,
+            0x1_0000..=0x1F_FFFF => 4,
+            0x20_0000..=0x3FF_FFFF => 5,
+            0x400_0000..=0x7FFF_FFFF => 6,
+            _ => 7,
+        };
+
+        let prefix = (0xFF00_u64 >> bytes) & 0xFF;
+        let shift = 6 * (bytes - 1);
+        self.write(prefix | (value >> shift), 8);
+        for i in (0..bytes - 1).rev() {
+            self.write(0x80 | ((value >> (6 * i)) & 0x3F), 8);
+        }
+    }
+
+    /// Pad with zero bits up to the next byte boundary
+    fn align(&mut self) {
+        if self.pending_count > 0 {
+            self.write(0, 8 - self.pending_count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use float_eq::assert_float_eq;
+    use std::io::Cursor;
+
+    fn test_signal() -> AudioBuffer {
+        let left: Vec<f32> = (0..10_000).map(|i| (i as f32 * 0.01).sin() * 0.5).collect();
+        let right: Vec<f32> = (0..10_000)
+            .map(|i| {
+                if i < 5000 {
+                    0.
+                } else {
+                    ((i * 7919) % 200) as f32 / 100. - 1.
+                }
+            })
+            .collect();
+        AudioBuffer::from(vec![left, right], 44_100.)
+    }
+
+    fn roundtrip(format: AudioFileFormat) -> AudioBuffer {
+        let input = test_signal();
+        let mut file = Cursor::new(vec![]);
+        let mut encoder = AudioFileEncoder::new(&mut file, format, 2, 44_100.).unwrap();
+
+        // write in a few uneven chunks
+        for range in [0..1000, 1000..5555, 5555..10_000] {
+            let chunk: Vec<_> = (0..2)
+                .map(|c| input.get_channel_data(c)[range.clone()].to_vec())
+                .collect();
+            encoder.write(&AudioBuffer::from(chunk, 44_100.)).unwrap();
+        }
+        encoder.finalize().unwrap();
+
+        let context = OfflineAudioContext::new(1, 1, 44_100.);
+        context
+            .decode_audio_data_sync(Cursor::new(file.into_inner()))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_to_int_sample() {
+        assert_eq!(to_int_sample(0., 16), 0);
+        assert_eq!(to_int_sample(1., 16), i16::MAX as i32);
+        assert_eq!(to_int_sample(-1., 16), i16::MIN as i32);
+        assert_eq!(to_int_sample(2., 24), (1 << 23) - 1);
+        assert_eq!(to_int_sample(-1., 32), i32::MIN);
+        assert_eq!(to_int_sample(f32::NAN, 16), 0);
+    }
+
+    #[test]
+    fn test_wav_and_flac_roundtrip() {
+        let input = test_signal();
+
+        for (format, tolerance) in [
+            (AudioFileFormat::WavInt16, 1. / 32768.),
+            (AudioFileFormat::WavInt24, 1. / 8_388_608.),
+            (AudioFileFormat::WavInt32, 1e-7),
+            (AudioFileFormat::WavFloat32, 0.),
+            (AudioFileFormat::Flac16, 1. / 32768.),
+            (AudioFileFormat::Flac24, 1. / 8_388_608.),
+        ] {
+            let output = roundtrip(format);
+            assert_eq!(output.number_of_channels(), 2, "{:?}", format);
+            assert_eq!(output.length(), input.length(), "{:?}", format);
+            for c in 0..2 {
+                assert_float_eq!(
+                    output.get_channel_data(c),
+                    input.get_channel_data(c),
+                    abs_all <= tolerance,
+                    "{:?}",
+                    format
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_flac_too_many_channels() {
+        let result =
+            AudioFileEncoder::new(Cursor::new(vec![]), AudioFileFormat::Flac16, 9, 44_100.);
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_utf8_frame_number() {
+        let mut bits = BitWriter::default();
+        bits.write_utf8(0x7F);
+        bits.write_utf8(0x80);
+        bits.write_utf8(0x1_0000);
+        assert_eq!(bits.bytes, [0x7F, 0xC2, 0x80, 0xF0, 0x90, 0x80, 0x80]);
+    }
+}
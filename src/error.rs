@@ -0,0 +1,100 @@
+use std::fmt;
+
+/// Error returned by the fallible `try_` methods
+///
+/// The variants are named after the `DOMException` names and ECMAScript error types that the Web
+/// Audio API specification throws. The panicking counterparts of the `try_` methods panic with
+/// the [`Display`](fmt::Display) representation of this error, e.g.
+/// `"NotSupportedError - Invalid length: 0 is less than or equal to minimum bound (0)"`.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DomException {
+    /// An index or size is out of the allowed range
+    IndexSizeError(String),
+    /// The object does not support the operation or argument, e.g. nodes of different contexts
+    InvalidAccessError(String),
+    /// The object is in an invalid state for the operation
+    InvalidStateError(String),
+    /// The object can not be found, e.g. an unknown audio device
+    NotFoundError(String),
+    /// The operation or argument is not supported
+    NotSupportedError(String),
+    /// The requested media constraints can not be satisfied by the device
+    OverconstrainedError(String),
+    /// The operation exceeds a resource limit, e.g. a full control message channel
+    QuotaExceededError(String),
+    /// A numeric value is outside its allowed range
+    RangeError(String),
+    /// A value is not of the expected type, e.g. a non-finite number
+    TypeError(String),
+}
+
+impl DomException {
+    /// The name of the error, e.g. `"NotSupportedError"`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::IndexSizeError(_) => "IndexSizeError",
+            Self::InvalidAccessError(_) => "InvalidAccessError",
+            Self::InvalidStateError(_) => "InvalidStateError",
+            Self::NotFoundError(_) => "NotFoundError",
+            Self::NotSupportedError(_) => "NotSupportedError",
+            Self::OverconstrainedError(_) => "OverconstrainedError",
+            Self::QuotaExceededError(_) => "QuotaExceededError",
+            Self::RangeError(_) => "RangeError",
+            Self::TypeError(_) => "TypeError",
+        }
+    }
+
+    /// The human readable description of the error
+    pub fn message(&self) -> &str {
+        match self {
+            Self::IndexSizeError(message)
+            | Self::InvalidAccessError(message)
+            | Self::InvalidStateError(message)
+            | Self::NotFoundError(message)
+            | Self::NotSupportedError(message)
+            | Self::OverconstrainedError(message)
+            | Self::QuotaExceededError(message)
+            | Self::RangeError(message)
+            | Self::TypeError(message) => message,
+        }
+    }
+}
+
+impl fmt::Display for DomException {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} - {}", self.name(), self.message())
+    }
+}
+
+impl std::error::Error for DomException {}
+
+/// Unwrap the result of a validation, panicking with the error message
+///
+/// Used by the panicking counterparts of the `try_` methods.
+#[track_caller]
+pub(crate) fn expect_valid<T>(result: Result<T, DomException>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(error) => panic!("{}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let error = DomException::IndexSizeError(String::from("out of bounds"));
+        assert_eq!(error.name(), "IndexSizeError");
+        assert_eq!(error.message(), "out of bounds");
+        assert_eq!(error.to_string(), "IndexSizeError - out of bounds");
+    }
+
+    #[test]
+    #[should_panic(expected = "RangeError - negative")]
+    fn test_expect_valid() {
+        expect_valid::<()>(Err(DomException::RangeError(String::from("negative"))));
+    }
+}
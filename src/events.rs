@@ -1,14 +1,19 @@
 use crate::context::ConcreteBaseAudioContext;
 use crate::context::{AudioContextState, AudioNodeId};
-use crate::{AudioBuffer, AudioRenderCapacityEvent};
+use crate::{AudioBuffer, AudioGraphDiagnostics, AudioRenderCapacityEvent, RenderProfile};
 
 use std::any::Any;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::hash::Hash;
 use std::ops::ControlFlow;
-use std::sync::{Arc, Mutex};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
 
 use crossbeam_channel::Receiver;
+use futures_channel::mpsc;
+use futures_core::Stream;
 
 /// The Event interface
 #[derive(Debug, Clone)]
@@ -17,19 +22,68 @@ pub struct Event {
     pub type_: &'static str,
 }
 
-#[derive(Hash, Eq, PartialEq, Debug)]
+#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
 pub(crate) enum EventType {
     Ended(AudioNodeId),
     SinkChange,
     StateChange,
+    Error,
     RenderCapacity,
+    RenderProfile,
     ProcessorError(AudioNodeId),
     Diagnostics,
+    GraphDiagnostics,
     Message(AudioNodeId),
     Complete,
     AudioProcessing(AudioNodeId),
 }
 
+/// Types of [`BaseAudioContext`](crate::context::BaseAudioContext) events that support multiple
+/// listeners, see
+/// [`add_event_listener`](crate::context::BaseAudioContext::add_event_listener)
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AudioContextEventType {
+    /// The state of the context has changed (`statechange`)
+    StateChange,
+    /// The audio output device has changed (`sinkchange`), only fired by the
+    /// [`AudioContext`](crate::context::AudioContext)
+    SinkChange,
+    /// The audio output device has failed (`error`), only fired by the
+    /// [`AudioContext`](crate::context::AudioContext)
+    Error,
+}
+
+/// How the events of an [`AudioContext`](crate::context::AudioContext) are dispatched
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum EventDispatchMode {
+    /// Run the event handlers on a dedicated event thread (default)
+    #[default]
+    Thread,
+    /// Run the event handlers when the host calls
+    /// [`dispatch_pending_events`](crate::context::AudioContext::dispatch_pending_events)
+    ///
+    /// Events are queued until they are dispatched. The queue has a limited capacity, so the
+    /// host should dispatch the events regularly, e.g. once per frame of its main loop.
+    Manual,
+}
+
+/// Options for registering an event listener
+#[derive(Copy, Clone, Debug, Default)]
+pub struct AddEventListenerOptions {
+    /// Remove the listener after it has been invoked once
+    pub once: bool,
+}
+
+/// Handle to a registered event listener, used to remove the listener again
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EventListenerHandle {
+    type_: EventType,
+    id: u64,
+    /// Whether the listener is stored in the registry of the registering thread
+    local: bool,
+}
+
 /// The Error Event interface
 #[non_exhaustive]
 #[derive(Debug)]
@@ -82,14 +136,31 @@ pub struct OfflineAudioCompletionEvent {
 pub(crate) enum EventPayload {
     None,
     RenderCapacity(AudioRenderCapacityEvent),
+    RenderProfile(RenderProfile),
     ProcessorError(ErrorEvent),
     Diagnostics(Vec<u8>),
+    GraphDiagnostics(AudioGraphDiagnostics),
     Message(Box<dyn Any + Send + 'static>),
     AudioContextState(AudioContextState),
+    Error(String),
     Complete(AudioBuffer),
     AudioProcessing(AudioProcessingEvent),
 }
 
+impl EventPayload {
+    /// Copy of the payload for additional listeners, if the payload can be shared
+    fn try_clone(&self) -> Option<Self> {
+        match self {
+            Self::None => Some(Self::None),
+            Self::AudioContextState(state) => Some(Self::AudioContextState(*state)),
+            Self::Error(message) => Some(Self::Error(message.clone())),
+            Self::Complete(buffer) => Some(Self::Complete(buffer.clone())),
+            Self::RenderCapacity(event) => Some(Self::RenderCapacity(event.clone())),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct EventDispatch {
     type_: EventType,
@@ -118,6 +189,13 @@ impl EventDispatch {
         }
     }
 
+    pub fn error(message: String) -> Self {
+        EventDispatch {
+            type_: EventType::Error,
+            payload: EventPayload::Error(message),
+        }
+    }
+
     pub fn render_capacity(value: AudioRenderCapacityEvent) -> Self {
         EventDispatch {
             type_: EventType::RenderCapacity,
@@ -125,6 +203,13 @@ impl EventDispatch {
         }
     }
 
+    pub fn render_profile(value: RenderProfile) -> Self {
+        EventDispatch {
+            type_: EventType::RenderProfile,
+            payload: EventPayload::RenderProfile(value),
+        }
+    }
+
     pub fn processor_error(id: AudioNodeId, value: ErrorEvent) -> Self {
         EventDispatch {
             type_: EventType::ProcessorError(id),
@@ -139,6 +224,13 @@ impl EventDispatch {
         }
     }
 
+    pub fn graph_diagnostics(value: AudioGraphDiagnostics) -> Self {
+        EventDispatch {
+            type_: EventType::GraphDiagnostics,
+            payload: EventPayload::GraphDiagnostics(value),
+        }
+    }
+
     pub fn message(id: AudioNodeId, value: Box<dyn Any + Send + 'static>) -> Self {
         EventDispatch {
             type_: EventType::Message(id),
@@ -166,15 +258,260 @@ pub(crate) enum EventHandler {
     Multiple(Box<dyn FnMut(EventPayload) + Send + 'static>),
 }
 
+/// Asynchronous stream of events
+///
+/// The stream is backed by an event listener, see
+/// [`add_event_listener`](crate::context::BaseAudioContext::add_event_listener), that is removed
+/// when the stream is dropped. Events are buffered until they are polled.
+pub struct EventStream<T> {
+    receiver: mpsc::UnboundedReceiver<T>,
+    context: ConcreteBaseAudioContext,
+    handle: EventListenerHandle,
+}
+
+impl<T> std::fmt::Debug for EventStream<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventStream")
+            .field("handle", &self.handle)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> EventStream<T> {
+    /// Register a listener for the event type that forwards the mapped payloads to the stream
+    ///
+    /// The `map` function returns `Break` with the final item of the stream, or `Continue` with
+    /// an optional item.
+    pub(crate) fn new<F>(context: &ConcreteBaseAudioContext, type_: EventType, map: F) -> Self
+    where
+        T: Send + 'static,
+        F: FnMut(EventPayload) -> ControlFlow<T, Option<T>> + Send + 'static,
+    {
+        Self::with_listener(context, type_, false, map)
+    }
+
+    /// Register an exclusive listener for an event type with a payload that can not be shared
+    ///
+    /// While the stream is alive it takes all events of the type, the `on` handler and other
+    /// listeners do not receive them.
+    pub(crate) fn new_exclusive<F>(
+        context: &ConcreteBaseAudioContext,
+        type_: EventType,
+        map: F,
+    ) -> Self
+    where
+        T: Send + 'static,
+        F: FnMut(EventPayload) -> ControlFlow<T, Option<T>> + Send + 'static,
+    {
+        Self::with_listener(context, type_, true, map)
+    }
+
+    fn with_listener<F>(
+        context: &ConcreteBaseAudioContext,
+        type_: EventType,
+        exclusive: bool,
+        mut map: F,
+    ) -> Self
+    where
+        T: Send + 'static,
+        F: FnMut(EventPayload) -> ControlFlow<T, Option<T>> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::unbounded();
+        // the receiver may have been dropped while the event was in flight, ignore send errors
+        let callback = move |payload| match map(payload) {
+            ControlFlow::Continue(item) => {
+                if let Some(item) = item {
+                    let _ = sender.unbounded_send(item);
+                }
+            }
+            ControlFlow::Break(item) => {
+                let _ = sender.unbounded_send(item);
+                sender.close_channel();
+            }
+        };
+
+        let callback = EventHandler::Multiple(Box::new(callback));
+        let handle = if exclusive {
+            context.add_exclusive_event_handler(type_, callback)
+        } else {
+            context.add_event_handler(type_, callback)
+        };
+
+        Self {
+            receiver,
+            context: context.clone(),
+            handle,
+        }
+    }
+}
+
+impl<T> Stream for EventStream<T> {
+    type Item = T;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<T>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+impl<T> Drop for EventStream<T> {
+    fn drop(&mut self) {
+        self.context.remove_event_handler(self.handle);
+    }
+}
+
+/// Event handler that does not need to be `Send`, it runs on the thread that registered it
+pub(crate) enum LocalEventHandler {
+    Once(Box<dyn FnOnce(EventPayload) + 'static>),
+    Multiple(Box<dyn FnMut(EventPayload) + 'static>),
+}
+
+/// Registered handler of an event type, either the `on` slot or a listener
+struct EventListener<H> {
+    /// Unique id of this registration
+    id: u64,
+    /// Whether this entry is the single `set_on*` handler
+    on_slot: bool,
+    /// Whether this entry takes the events from all other handlers
+    exclusive: bool,
+    /// The callback, taken out while it runs
+    handler: Option<H>,
+}
+
+type Listeners<K, H> = HashMap<K, Vec<EventListener<H>>>;
+
+/// Take the handlers of the event type out, leaving the entries in place to preserve their
+/// order and to detect removals
+///
+/// When an exclusive listener is registered, only the most recent one is taken and the returned
+/// flag is set.
+fn take_handlers<K: Hash + Eq, H>(
+    listeners: &mut Listeners<K, H>,
+    key: &K,
+) -> (Vec<(u64, H)>, bool) {
+    let Some(listeners) = listeners.get_mut(key) else {
+        return (vec![], false);
+    };
+    if let Some(listener) = listeners.iter_mut().rev().find(|l| l.exclusive) {
+        let handler = listener.handler.take().map(|h| (listener.id, h));
+        return (handler.into_iter().collect(), true);
+    }
+    let handlers = listeners
+        .iter_mut()
+        .filter_map(|l| l.handler.take().map(|h| (l.id, h)))
+        .collect();
+    (handlers, false)
+}
+
+fn is_registered<K: Hash + Eq, H>(listeners: &Listeners<K, H>, key: &K, id: u64) -> bool {
+    listeners
+        .get(key)
+        .is_some_and(|listeners| listeners.iter().any(|l| l.id == id))
+}
+
+/// Put the handler back in place after it has run, unless it was removed or replaced
+fn restore_handler<K: Hash + Eq, H>(listeners: &mut Listeners<K, H>, key: &K, id: u64, handler: H) {
+    let listener = listeners
+        .get_mut(key)
+        .and_then(|listeners| listeners.iter_mut().find(|l| l.id == id));
+    if let Some(listener) = listener {
+        listener.handler = Some(handler);
+    }
+}
+
+fn remove_handler<K: Hash + Eq, H>(listeners: &mut Listeners<K, H>, key: &K, id: u64) {
+    if let Some(entries) = listeners.get_mut(key) {
+        entries.retain(|l| l.id != id);
+        if entries.is_empty() {
+            listeners.remove(key);
+        }
+    }
+}
+
+type LocalListeners = Vec<EventListener<LocalEventHandler>>;
+
+/// Local handlers of all event loops on a thread
+#[derive(Default)]
+struct LocalHandlers {
+    /// Handlers keyed by the event loop id and event type
+    listeners: Listeners<(u64, EventType), LocalEventHandler>,
+    /// Event loops with handlers on this thread, to detect the dropped ones
+    event_loops: HashMap<u64, Weak<EventLoopId>>,
+}
+
+impl LocalHandlers {
+    /// Remove the handlers of the event loop, they are returned to be dropped after the borrow
+    /// of the thread local ends
+    fn remove_event_loop(&mut self, id: u64) -> Vec<LocalListeners> {
+        self.event_loops.remove(&id);
+        let keys: Vec<_> = self
+            .listeners
+            .keys()
+            .filter(|k| k.0 == id)
+            .copied()
+            .collect();
+        keys.iter()
+            .filter_map(|k| self.listeners.remove(k))
+            .collect()
+    }
+
+    /// Remove the handlers of the event loops that were dropped on another thread
+    fn remove_dropped(&mut self) -> Vec<LocalListeners> {
+        let dropped: Vec<_> = self
+            .event_loops
+            .iter()
+            .filter(|(_, event_loop)| event_loop.strong_count() == 0)
+            .map(|(&id, _)| id)
+            .collect();
+        dropped
+            .into_iter()
+            .flat_map(|id| self.remove_event_loop(id))
+            .collect()
+    }
+}
+
+thread_local! {
+    static LOCAL_HANDLERS: RefCell<LocalHandlers> = RefCell::default();
+}
+
+/// Source of unique event loop and listener ids
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Unique id of an event loop, shared by its clones
+///
+/// When the last clone is dropped, the local handlers of the event loop on the current thread
+/// are removed. The handlers on other threads are removed when that thread registers its next
+/// local handler, or when it exits.
+struct EventLoopId(u64);
+
+impl Drop for EventLoopId {
+    fn drop(&mut self) {
+        // The handlers are borrowed when the last clone is dropped by a handler that is being
+        // removed, leave the cleanup to the next registration in that case
+        let removed = LOCAL_HANDLERS.try_with(|l| {
+            l.try_borrow_mut()
+                .map(|mut l| l.remove_event_loop(self.0))
+                .unwrap_or_default()
+        });
+        drop(removed);
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct EventLoop {
+    /// Unique id of this event loop, to look up the local handlers
+    id: Arc<EventLoopId>,
     event_recv: Receiver<EventDispatch>,
-    event_handlers: Arc<Mutex<HashMap<EventType, EventHandler>>>,
+    /// Handlers per event type, in invocation order
+    event_handlers: Arc<Mutex<Listeners<EventType, EventHandler>>>,
 }
 
 impl EventLoop {
     pub fn new(event_recv: Receiver<EventDispatch>) -> Self {
         Self {
+            id: Arc::new(EventLoopId(NEXT_ID.fetch_add(1, Ordering::Relaxed))),
             event_recv,
             event_handlers: Default::default(),
         }
@@ -191,19 +528,79 @@ impl EventLoop {
             result = ControlFlow::Break(());
         }
 
+        // Take the handlers out, so no lock is held while running the callbacks. The handlers
+        // registered on this thread without `Send` bound run after the shared handlers, unless
+        // an exclusive listener takes the event.
+        let type_ = event.type_;
+        let local_key = (self.id.0, type_);
         let mut event_handler_lock = self.event_handlers.lock().unwrap();
-        let callback_option = event_handler_lock.remove(&event.type_);
-        drop(event_handler_lock); // release Mutex while running callback
+        let (callbacks, exclusive) = take_handlers(&mut event_handler_lock, &type_);
+        drop(event_handler_lock);
+        let local_callbacks = if exclusive {
+            vec![]
+        } else {
+            LOCAL_HANDLERS.with(|l| take_handlers(&mut l.borrow_mut().listeners, &local_key).0)
+        };
+
+        // the last handler receives the original payload
+        let mut remaining = callbacks.len() + local_callbacks.len();
+        let mut payload = Some(event.payload);
+        let mut next_payload = || {
+            remaining -= 1;
+            if remaining == 0 {
+                return payload.take();
+            }
+            let copy = payload.as_ref().unwrap().try_clone();
+            if copy.is_none() {
+                log::warn!("Cannot share payload of {:?} event", type_);
+            }
+            copy
+        };
+
+        for (id, callback) in callbacks {
+            // skip listeners that were removed by a previous callback
+            let registered = is_registered(&self.event_handlers.lock().unwrap(), &type_, id);
+            let Some(payload) = next_payload().filter(|_| registered) else {
+                continue;
+            };
 
-        if let Some(callback) = callback_option {
             match callback {
-                EventHandler::Once(f) => (f)(event.payload),
+                EventHandler::Once(f) => {
+                    (f)(payload);
+                    remove_handler(&mut self.event_handlers.lock().unwrap(), &type_, id);
+                }
                 EventHandler::Multiple(mut f) => {
-                    (f)(event.payload);
-                    self.event_handlers
-                        .lock()
-                        .unwrap()
-                        .insert(event.type_, EventHandler::Multiple(f));
+                    (f)(payload);
+                    let handler = EventHandler::Multiple(f);
+                    restore_handler(
+                        &mut self.event_handlers.lock().unwrap(),
+                        &type_,
+                        id,
+                        handler,
+                    );
+                }
+            };
+        }
+
+        for (id, callback) in local_callbacks {
+            let registered =
+                LOCAL_HANDLERS.with(|l| is_registered(&l.borrow().listeners, &local_key, id));
+            let Some(payload) = next_payload().filter(|_| registered) else {
+                continue;
+            };
+
+            match callback {
+                LocalEventHandler::Once(f) => {
+                    (f)(payload);
+                    LOCAL_HANDLERS
+                        .with(|l| remove_handler(&mut l.borrow_mut().listeners, &local_key, id));
+                }
+                LocalEventHandler::Multiple(mut f) => {
+                    (f)(payload);
+                    let handler = LocalEventHandler::Multiple(f);
+                    LOCAL_HANDLERS.with(|l| {
+                        restore_handler(&mut l.borrow_mut().listeners, &local_key, id, handler)
+                    });
                 }
             };
         }
@@ -241,11 +638,305 @@ impl EventLoop {
         });
     }
 
+    /// Set the `on` slot of the event type, replacing the previous handler in place
     pub fn set_handler(&self, event: EventType, callback: EventHandler) {
-        self.event_handlers.lock().unwrap().insert(event, callback);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let mut event_handlers = self.event_handlers.lock().unwrap();
+        let listeners = event_handlers.entry(event).or_default();
+        let listener = EventListener {
+            id,
+            on_slot: true,
+            exclusive: false,
+            handler: Some(callback),
+        };
+        match listeners.iter_mut().find(|l| l.on_slot) {
+            Some(slot) => *slot = listener,
+            None => listeners.push(listener),
+        }
     }
 
+    /// Clear the `on` slot of the event type, other listeners are kept
     pub fn clear_handler(&self, event: EventType) {
-        self.event_handlers.lock().unwrap().remove(&event);
+        let mut event_handlers = self.event_handlers.lock().unwrap();
+        if let Some(listeners) = event_handlers.get_mut(&event) {
+            listeners.retain(|l| !l.on_slot);
+            if listeners.is_empty() {
+                event_handlers.remove(&event);
+            }
+        }
+    }
+
+    /// Append a listener to the event type, it runs after the handlers registered before
+    pub fn add_listener(&self, event: EventType, callback: EventHandler) -> EventListenerHandle {
+        self.push_listener(event, callback, false)
+    }
+
+    /// Append a listener that takes the events of the type from all other handlers, for payloads
+    /// that can not be shared
+    ///
+    /// While it is registered, the `on` slot and other listeners of the event type do not run.
+    /// When multiple exclusive listeners are registered, the most recent one receives the events.
+    pub fn add_exclusive_listener(
+        &self,
+        event: EventType,
+        callback: EventHandler,
+    ) -> EventListenerHandle {
+        self.push_listener(event, callback, true)
+    }
+
+    fn push_listener(
+        &self,
+        event: EventType,
+        callback: EventHandler,
+        exclusive: bool,
+    ) -> EventListenerHandle {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        self.event_handlers
+            .lock()
+            .unwrap()
+            .entry(event)
+            .or_default()
+            .push(EventListener {
+                id,
+                on_slot: false,
+                exclusive,
+                handler: Some(callback),
+            });
+
+        EventListenerHandle {
+            type_: event,
+            id,
+            local: false,
+        }
+    }
+
+    /// Append a listener to the event type that only runs when events are handled on the
+    /// current thread
+    pub fn add_local_listener(
+        &self,
+        event: EventType,
+        callback: LocalEventHandler,
+    ) -> EventListenerHandle {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let dropped = LOCAL_HANDLERS.with(|l| {
+            let mut l = l.borrow_mut();
+            let dropped = l.remove_dropped();
+            l.event_loops
+                .entry(self.id.0)
+                .or_insert_with(|| Arc::downgrade(&self.id));
+            l.listeners
+                .entry((self.id.0, event))
+                .or_default()
+                .push(EventListener {
+                    id,
+                    on_slot: false,
+                    exclusive: false,
+                    handler: Some(callback),
+                });
+            dropped
+        });
+        drop(dropped);
+
+        EventListenerHandle {
+            type_: event,
+            id,
+            local: true,
+        }
+    }
+
+    /// Remove a listener, this is a no-op if it was already removed
+    ///
+    /// Local listeners can only be removed on the thread that registered them.
+    pub fn remove_listener_by_handle(&self, handle: EventListenerHandle) {
+        if handle.local {
+            let key = (self.id.0, handle.type_);
+            LOCAL_HANDLERS.with(|l| remove_handler(&mut l.borrow_mut().listeners, &key, handle.id));
+        } else {
+            remove_handler(
+                &mut self.event_handlers.lock().unwrap(),
+                &handle.type_,
+                handle.id,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counter() -> (Arc<AtomicU64>, impl FnMut(EventPayload) + Send + 'static) {
+        let count = Arc::new(AtomicU64::new(0));
+        let count_clone = Arc::clone(&count);
+        let callback = move |_| {
+            count_clone.fetch_add(1, Ordering::SeqCst);
+        };
+        (count, callback)
+    }
+
+    fn dispatch(event_loop: &EventLoop, send: &crossbeam_channel::Sender<EventDispatch>) {
+        send.send(EventDispatch::sink_change()).unwrap();
+        event_loop.handle_pending_events();
+    }
+
+    #[test]
+    fn test_listeners_and_on_slot() {
+        let (send, recv) = crossbeam_channel::unbounded();
+        let event_loop = EventLoop::new(recv);
+
+        let (on_count, on_callback) = counter();
+        let (listener_count, listener_callback) = counter();
+        let (once_count, once_callback) = counter();
+
+        event_loop.set_handler(
+            EventType::SinkChange,
+            EventHandler::Multiple(Box::new(on_callback)),
+        );
+        let handle = event_loop.add_listener(
+            EventType::SinkChange,
+            EventHandler::Multiple(Box::new(listener_callback)),
+        );
+        event_loop.add_listener(
+            EventType::SinkChange,
+            EventHandler::Once(Box::new(once_callback)),
+        );
+
+        dispatch(&event_loop, &send);
+        dispatch(&event_loop, &send);
+        assert_eq!(on_count.load(Ordering::SeqCst), 2);
+        assert_eq!(listener_count.load(Ordering::SeqCst), 2);
+        assert_eq!(once_count.load(Ordering::SeqCst), 1);
+
+        // clearing the on slot keeps the listeners
+        event_loop.clear_handler(EventType::SinkChange);
+        dispatch(&event_loop, &send);
+        assert_eq!(on_count.load(Ordering::SeqCst), 2);
+        assert_eq!(listener_count.load(Ordering::SeqCst), 3);
+
+        event_loop.remove_listener_by_handle(handle);
+        dispatch(&event_loop, &send);
+        assert_eq!(listener_count.load(Ordering::SeqCst), 3);
+        assert!(event_loop.event_handlers.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_local_listeners() {
+        let (send, recv) = crossbeam_channel::unbounded();
+        let event_loop = EventLoop::new(recv);
+        let order = std::rc::Rc::new(RefCell::new(vec![]));
+
+        let order_clone = std::rc::Rc::clone(&order);
+        let handle = event_loop.add_local_listener(
+            EventType::SinkChange,
+            LocalEventHandler::Multiple(Box::new(move |_| order_clone.borrow_mut().push("local"))),
+        );
+        let (count, callback) = counter();
+        event_loop.add_listener(
+            EventType::SinkChange,
+            EventHandler::Multiple(Box::new(callback)),
+        );
+
+        dispatch(&event_loop, &send);
+        assert_eq!(*order.borrow(), ["local"]);
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        // local listeners only run on the registering thread
+        let event_loop_clone = event_loop.clone();
+        let send_clone = send.clone();
+        std::thread::spawn(move || dispatch(&event_loop_clone, &send_clone))
+            .join()
+            .unwrap();
+        assert_eq!(order.borrow().len(), 1);
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+
+        event_loop.remove_listener_by_handle(handle);
+        dispatch(&event_loop, &send);
+        assert_eq!(order.borrow().len(), 1);
+    }
+
+    #[test]
+    fn test_local_listeners_removed_on_drop() {
+        let local_listener = || LocalEventHandler::Multiple(Box::new(|_| ()));
+        let is_empty = || LOCAL_HANDLERS.with(|l| l.borrow().listeners.is_empty());
+
+        let (_send, recv) = crossbeam_channel::unbounded();
+        let event_loop = EventLoop::new(recv);
+        event_loop.add_local_listener(EventType::SinkChange, local_listener());
+        let event_loop_clone = event_loop.clone();
+        drop(event_loop);
+        assert!(!is_empty());
+        drop(event_loop_clone);
+        assert!(is_empty());
+
+        // dropped on another thread, removed with the next registration
+        let (_send, recv) = crossbeam_channel::unbounded();
+        let event_loop = EventLoop::new(recv);
+        event_loop.add_local_listener(EventType::SinkChange, local_listener());
+        std::thread::spawn(move || drop(event_loop)).join().unwrap();
+        assert!(!is_empty());
+
+        let (_send, recv) = crossbeam_channel::unbounded();
+        let event_loop = EventLoop::new(recv);
+        let handle = event_loop.add_local_listener(EventType::SinkChange, local_listener());
+        event_loop.remove_listener_by_handle(handle);
+        assert!(is_empty());
+    }
+
+    #[test]
+    fn test_listener_order() {
+        let (send, recv) = crossbeam_channel::unbounded();
+        let event_loop = EventLoop::new(recv);
+        let order = Arc::new(Mutex::new(vec![]));
+
+        let push = |name: &'static str| {
+            let order = Arc::clone(&order);
+            EventHandler::Multiple(Box::new(move |_| order.lock().unwrap().push(name)))
+        };
+
+        event_loop.add_listener(EventType::SinkChange, push("first"));
+        event_loop.set_handler(EventType::SinkChange, push("on"));
+        event_loop.add_listener(EventType::SinkChange, push("last"));
+        // replacing the on slot keeps its position
+        event_loop.set_handler(EventType::SinkChange, push("on2"));
+
+        dispatch(&event_loop, &send);
+        assert_eq!(*order.lock().unwrap(), ["first", "on2", "last"]);
+    }
+
+    #[test]
+    fn test_exclusive_listener() {
+        let (send, recv) = crossbeam_channel::unbounded();
+        let event_loop = EventLoop::new(recv);
+        let (on_count, on_callback) = counter();
+        let (first_count, first_callback) = counter();
+        let (last_count, last_callback) = counter();
+
+        event_loop.set_handler(
+            EventType::SinkChange,
+            EventHandler::Multiple(Box::new(on_callback)),
+        );
+        let first = event_loop.add_exclusive_listener(
+            EventType::SinkChange,
+            EventHandler::Multiple(Box::new(first_callback)),
+        );
+        let last = event_loop.add_exclusive_listener(
+            EventType::SinkChange,
+            EventHandler::Multiple(Box::new(last_callback)),
+        );
+
+        // the most recent exclusive listener takes the event
+        dispatch(&event_loop, &send);
+        assert_eq!(on_count.load(Ordering::SeqCst), 0);
+        assert_eq!(first_count.load(Ordering::SeqCst), 0);
+        assert_eq!(last_count.load(Ordering::SeqCst), 1);
+
+        event_loop.remove_listener_by_handle(last);
+        dispatch(&event_loop, &send);
+        assert_eq!(first_count.load(Ordering::SeqCst), 1);
+
+        // the on slot runs again once the exclusive listeners are removed
+        event_loop.remove_listener_by_handle(first);
+        dispatch(&event_loop, &send);
+        assert_eq!(on_count.load(Ordering::SeqCst), 1);
     }
 }
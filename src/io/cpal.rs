@@ -2,22 +2,24 @@
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use cpal::{
     traits::{DeviceTrait, HostTrait, StreamTrait},
-    BuildStreamError, Device, OutputCallbackInfo, SampleFormat, Stream, StreamConfig,
-    SupportedBufferSize,
+    BuildStreamError, Device, InputCallbackInfo, OutputCallbackInfo, SampleFormat, Stream,
+    StreamConfig, StreamError, SupportedBufferSize, SupportedStreamConfig,
 };
 use crossbeam_channel::Receiver;
 
-use super::{AudioBackendManager, RenderThreadInit};
+use super::ring_buffer::SampleRingBuffer;
+use super::{AudioBackendManager, DuplexInput, RenderThreadInit};
 
 use crate::buffer::AudioBuffer;
 use crate::context::AudioContextOptions;
 use crate::io::microphone::MicrophoneRender;
 use crate::media_devices::{MediaDeviceInfo, MediaDeviceInfoKind};
 use crate::render::RenderThread;
-use crate::{AtomicF64, MAX_CHANNELS};
+use crate::{AtomicF64, DomException, MAX_CHANNELS};
 
 // I doubt this construct is entirely safe. Stream is not Send/Sync (probably for a good reason) so
 // it should be managed from a single thread instead.
@@ -103,13 +105,144 @@ fn get_host() -> cpal::Host {
     cpal::default_host()
 }
 
+/// Number of frames of the ring buffer of a duplex input, a few callbacks worth of the largest
+/// buffer sizes
+const DUPLEX_CAPTURE_FRAMES: usize = 8 * 4096;
+
+/// Number of samples moved from the ring buffer to the duplex input at once
+const DUPLEX_CAPTURE_CHUNK: usize = 1024;
+
+/// Input device of a duplex stream, see [`AudioContextOptions::input`]
+///
+/// cpal does not provide duplex streams, so the input is captured by a separate stream on the
+/// same host. The captured frames are handed to the output callback through a preallocated ring
+/// buffer, and pushed into the duplex input right before rendering.
+struct DuplexCapture {
+    device: Device,
+    config: SupportedStreamConfig,
+    number_of_channels: usize,
+    ring_buffer: Arc<SampleRingBuffer>,
+    receiver: DuplexInputReceiver,
+}
+
+impl DuplexCapture {
+    fn new(host: &cpal::Host, duplex_input: DuplexInput) -> Self {
+        let device_id = &duplex_input.options().device_id;
+        let device = if device_id.is_empty() {
+            host.default_input_device()
+                .expect("InvalidStateError - no input device available")
+        } else {
+            CpalBackend::enumerate_devices_sync()
+                .into_iter()
+                .find(|e| e.device_id() == *device_id)
+                .and_then(MediaDeviceInfo::device)
+                .map(|d| *d.downcast::<cpal::Device>().unwrap())
+                .unwrap_or_else(|| {
+                    host.default_input_device()
+                        .expect("InvalidStateError - no input device available")
+                })
+        };
+
+        log::info!("Duplex input device: {:?}", device.name());
+
+        let config = device
+            .default_input_config()
+            .expect("InvalidStateError - error while querying device input config");
+
+        let number_of_channels = duplex_input
+            .options()
+            .number_of_channels
+            .map_or(usize::from(config.channels()), |n| n as usize);
+
+        // A few callbacks worth of input may be pending, when the callbacks of the input and
+        // output stream are not evenly interleaved
+        let ring_buffer = Arc::new(SampleRingBuffer::new(
+            DUPLEX_CAPTURE_FRAMES * number_of_channels,
+        ));
+        let receiver = DuplexInputReceiver {
+            receiver: Some((Arc::clone(&ring_buffer), duplex_input)),
+            number_of_channels,
+        };
+
+        Self {
+            device,
+            config,
+            number_of_channels,
+            ring_buffer,
+            receiver,
+        }
+    }
+
+    /// Start capturing at the sample rate and buffer size of the output stream
+    fn spawn(self, sample_rate: f32, buffer_size: u32, render_quantum_size: usize) -> Stream {
+        let config = StreamConfig {
+            channels: self.number_of_channels as u16,
+            sample_rate: cpal::SampleRate(sample_rate as u32),
+            buffer_size: cpal::BufferSize::Fixed(buffer_size),
+        };
+
+        let (_, duplex_input) = self.receiver.receiver.as_ref().unwrap();
+        duplex_input.start(
+            self.number_of_channels,
+            sample_rate,
+            render_quantum_size,
+            0.,
+        );
+
+        log::debug!("Attempt duplex input stream with config: {:?}", &config);
+
+        let stream = spawn_duplex_input_stream(
+            &self.device,
+            self.config.sample_format(),
+            &config,
+            self.ring_buffer,
+            duplex_input.clone(),
+        )
+        .expect("InvalidStateError - Unable to spawn duplex input stream");
+
+        stream
+            .play()
+            .expect("InvalidStateError - Duplex input stream refused to play");
+
+        stream
+    }
+}
+
+/// Receiving end of the input of a duplex stream, owned by the output callback
+#[derive(Clone, Default)]
+struct DuplexInputReceiver {
+    receiver: Option<(Arc<SampleRingBuffer>, DuplexInput)>,
+    number_of_channels: usize,
+}
+
+impl DuplexInputReceiver {
+    /// Push the frames captured since the previous output callback into the duplex input
+    fn capture(&self) {
+        if let Some((ring_buffer, duplex_input)) = &self.receiver {
+            let mut samples = [0.; DUPLEX_CAPTURE_CHUNK];
+            // only move whole frames at once
+            let len = DUPLEX_CAPTURE_CHUNK - DUPLEX_CAPTURE_CHUNK % self.number_of_channels;
+            loop {
+                let count = ring_buffer.pop_into(&mut samples[..len]);
+                if count == 0 {
+                    break;
+                }
+                duplex_input.push(&samples[..count], self.number_of_channels);
+            }
+        }
+    }
+}
+
 /// Audio backend using the `cpal` library
 #[derive(Clone)]
 pub(crate) struct CpalBackend {
     stream: ThreadSafeClosableStream,
+    /// Input stream of a duplex stream, see [`AudioContextOptions::input`]
+    input_stream: Option<ThreadSafeClosableStream>,
     output_latency: Arc<AtomicF64>,
     sample_rate: f32,
     number_of_channels: usize,
+    render_quantum_size: usize,
     sink_id: String,
 }
 
@@ -128,8 +261,21 @@ impl AudioBackendManager for CpalBackend {
             ctrl_msg_recv,
             load_value_send,
             event_send,
+            output_timestamp,
+            playout_stats,
+            duplex_input,
+            device_failure,
+            ..
         } = render_thread_init;
 
+        let capture = duplex_input
+            .clone()
+            .map(|duplex_input| DuplexCapture::new(&host, duplex_input));
+        let input = capture
+            .as_ref()
+            .map(|capture| capture.receiver.clone())
+            .unwrap_or_default();
+
         let device = if options.sink_id.is_empty() {
             host.default_output_device()
                 .expect("InvalidStateError - no output device available")
@@ -137,7 +283,8 @@ impl AudioBackendManager for CpalBackend {
             Self::enumerate_devices_sync()
                 .into_iter()
                 .find(|e| e.device_id() == options.sink_id)
-                .map(|e| *e.device().downcast::<cpal::Device>().unwrap())
+                .and_then(MediaDeviceInfo::device)
+                .map(|d| *d.downcast::<cpal::Device>().unwrap())
                 .unwrap_or_else(|| {
                     host.default_output_device()
                         .expect("InvalidStateError - no output device available")
@@ -179,6 +326,12 @@ impl AudioBackendManager for CpalBackend {
 
         preferred_config.buffer_size = cpal::BufferSize::Fixed(clamped_buffer_size);
 
+        // render in chunks of the hardware buffer size if requested, to prevent re-buffering
+        let render_quantum_size = super::render_quantum_size_for_category(
+            options.render_size_hint,
+            clamped_buffer_size as usize,
+        );
+
         // report the picked sample rate to the render thread, i.e. if the requested
         // sample rate is not supported by the hardware, it will fallback to the
         // default device sample rate
@@ -189,6 +342,7 @@ impl AudioBackendManager for CpalBackend {
 
         let mut renderer = RenderThread::new(
             sample_rate,
+            render_quantum_size,
             preferred_config.channels as usize,
             ctrl_msg_recv.clone(),
             Arc::clone(&state),
@@ -196,6 +350,12 @@ impl AudioBackendManager for CpalBackend {
             event_send.clone(),
         );
         renderer.set_load_value_sender(load_value_send.clone());
+        renderer.set_output_timestamp(Arc::clone(&output_timestamp));
+        renderer.set_playout_stats(Arc::clone(&playout_stats));
+        renderer.set_device_failure(device_failure.clone());
+        if let Some(duplex_input) = &duplex_input {
+            renderer.set_duplex_input(duplex_input.clone());
+        }
         renderer.spawn_garbage_collector_thread();
 
         log::debug!(
@@ -209,6 +369,7 @@ impl AudioBackendManager for CpalBackend {
             &preferred_config,
             renderer,
             Arc::clone(&output_latency),
+            input.clone(),
         );
 
         let stream = match spawned {
@@ -232,6 +393,7 @@ impl AudioBackendManager for CpalBackend {
 
                 let mut renderer = RenderThread::new(
                     sample_rate,
+                    render_quantum_size,
                     supported_config.channels as usize,
                     ctrl_msg_recv,
                     state,
@@ -239,6 +401,12 @@ impl AudioBackendManager for CpalBackend {
                     event_send,
                 );
                 renderer.set_load_value_sender(load_value_send);
+                renderer.set_output_timestamp(output_timestamp);
+                renderer.set_playout_stats(playout_stats);
+                renderer.set_device_failure(device_failure);
+                if let Some(duplex_input) = duplex_input {
+                    renderer.set_duplex_input(duplex_input);
+                }
                 renderer.spawn_garbage_collector_thread();
 
                 let spawned = spawn_output_stream(
@@ -247,6 +415,7 @@ impl AudioBackendManager for CpalBackend {
                     &supported_config,
                     renderer,
                     Arc::clone(&output_latency),
+                    input,
                 );
 
                 spawned
@@ -259,11 +428,18 @@ impl AudioBackendManager for CpalBackend {
             .play()
             .expect("InvalidStateError - Output stream refused to play");
 
+        let input_stream = capture.map(|capture| {
+            let input_stream = capture.spawn(sample_rate, clamped_buffer_size, render_quantum_size);
+            ThreadSafeClosableStream::new(input_stream)
+        });
+
         CpalBackend {
             stream: ThreadSafeClosableStream::new(stream),
+            input_stream,
             output_latency,
             sample_rate,
             number_of_channels,
+            render_quantum_size,
             sink_id: options.sink_id,
         }
     }
@@ -271,7 +447,7 @@ impl AudioBackendManager for CpalBackend {
     fn build_input(
         options: AudioContextOptions,
         number_of_channels: Option<u32>,
-    ) -> (Self, Receiver<AudioBuffer>)
+    ) -> Result<(Self, Receiver<AudioBuffer>), DomException>
     where
         Self: Sized,
     {
@@ -280,24 +456,28 @@ impl AudioBackendManager for CpalBackend {
         log::info!("Audio Input Host: cpal {:?}", host.id());
 
         let device = if options.sink_id.is_empty() {
-            host.default_input_device()
-                .expect("InvalidStateError - no input device available")
+            host.default_input_device().ok_or_else(|| {
+                DomException::NotFoundError(String::from("no input device available"))
+            })?
         } else {
             Self::enumerate_devices_sync()
                 .into_iter()
                 .find(|e| e.device_id() == options.sink_id)
-                .map(|e| *e.device().downcast::<cpal::Device>().unwrap())
-                .unwrap_or_else(|| {
-                    host.default_input_device()
-                        .expect("InvalidStateError - no input device available")
-                })
+                .and_then(MediaDeviceInfo::device)
+                .map(|d| *d.downcast::<cpal::Device>().unwrap())
+                .ok_or_else(|| {
+                    DomException::NotFoundError(format!("invalid deviceId {:?}", options.sink_id))
+                })?
         };
 
         log::info!("Input device: {:?}", device.name());
 
-        let supported = device
-            .default_input_config()
-            .expect("InvalidStateError - error while querying device input config");
+        let supported = device.default_input_config().map_err(|e| {
+            DomException::InvalidStateError(format!(
+                "error while querying device input config: {}",
+                e
+            ))
+        })?;
 
         // clone the config, we may need to fall back on it later
         let mut preferred: StreamConfig = supported.clone().into();
@@ -308,7 +488,8 @@ impl AudioBackendManager for CpalBackend {
 
         // set specific sample rate if requested
         if let Some(sample_rate) = options.sample_rate {
-            crate::assert_valid_sample_rate(sample_rate);
+            crate::check_valid_sample_rate(sample_rate)
+                .map_err(|e| DomException::OverconstrainedError(e.message().to_string()))?;
             preferred.sample_rate.0 = sample_rate as u32;
         }
 
@@ -346,15 +527,26 @@ impl AudioBackendManager for CpalBackend {
                 stream
             }
             Err(e) => {
-                log::warn!("Output stream build failed with preferred config: {}", e);
+                log::warn!("Input stream build failed with preferred config: {}", e);
 
                 let supported_config: StreamConfig = supported.clone().into();
+
+                // the fallback config should still match the requested constraints
+                if preferred.sample_rate != supported_config.sample_rate
+                    || preferred.channels != supported_config.channels
+                {
+                    return Err(DomException::OverconstrainedError(format!(
+                        "unable to open input stream with the requested constraints: {}",
+                        e
+                    )));
+                }
+
                 // fallback to device default sample rate and channel count
                 number_of_channels = usize::from(supported_config.channels);
                 sample_rate = supported_config.sample_rate.0 as f32;
 
                 log::debug!(
-                    "Attempt output stream with fallback config: {:?}",
+                    "Attempt input stream with fallback config: {:?}",
                     &supported_config
                 );
 
@@ -370,36 +562,51 @@ impl AudioBackendManager for CpalBackend {
                     &supported_config,
                     renderer,
                 );
-                spawned
-                    .expect("InvalidStateError - Unable to spawn input stream with default config")
+                spawned.map_err(|e| {
+                    DomException::InvalidStateError(format!(
+                        "unable to spawn input stream with default config: {}",
+                        e
+                    ))
+                })?
             }
         };
 
         // Required because some hosts don't play the stream automatically
-        stream
-            .play()
-            .expect("InvalidStateError - Input stream refused to play");
+        stream.play().map_err(|e| {
+            DomException::InvalidStateError(format!("input stream refused to play: {}", e))
+        })?;
 
         let backend = CpalBackend {
             stream: ThreadSafeClosableStream::new(stream),
+            input_stream: None,
             output_latency: Arc::new(AtomicF64::new(0.)),
             sample_rate,
             number_of_channels,
+            render_quantum_size: crate::RENDER_QUANTUM_SIZE,
             sink_id: options.sink_id,
         };
 
-        (backend, receiver)
+        Ok((backend, receiver))
     }
 
     fn resume(&self) -> bool {
+        if let Some(input_stream) = &self.input_stream {
+            input_stream.resume();
+        }
         self.stream.resume()
     }
 
     fn suspend(&self) -> bool {
+        if let Some(input_stream) = &self.input_stream {
+            input_stream.suspend();
+        }
         self.stream.suspend()
     }
 
     fn close(&self) {
+        if let Some(input_stream) = &self.input_stream {
+            input_stream.close();
+        }
         self.stream.close()
     }
 
@@ -411,6 +618,10 @@ impl AudioBackendManager for CpalBackend {
         self.number_of_channels
     }
 
+    fn render_quantum_size(&self) -> usize {
+        self.render_quantum_size
+    }
+
     fn output_latency(&self) -> f64 {
         self.output_latency.load(Ordering::Relaxed)
     }
@@ -480,6 +691,14 @@ fn latency_in_seconds(infos: &OutputCallbackInfo) -> f64 {
         .unwrap_or(0.0)
 }
 
+/// Estimate the instant at which the first frame of the current callback buffer is played
+///
+/// The cpal timestamps use a host specific clock, so the latency is applied to the system clock at
+/// the time of the callback instead.
+fn playback_instant(latency: f64) -> Instant {
+    Instant::now() + Duration::from_secs_f64(latency)
+}
+
 /// Creates an output stream
 ///
 /// # Arguments:
@@ -488,21 +707,33 @@ fn latency_in_seconds(infos: &OutputCallbackInfo) -> f64 {
 /// * `sample_format` - audio sample format of the stream
 /// * `config` - stream configuration
 /// * `render` - the render thread which process the audio data
+/// * `output_latency` - shared value to report the output latency
+/// * `input` - the input of a duplex stream, captured before rendering each callback
 fn spawn_output_stream(
     device: &Device,
     sample_format: SampleFormat,
     config: &StreamConfig,
     mut render: RenderThread,
     output_latency: Arc<AtomicF64>,
+    input: DuplexInputReceiver,
 ) -> Result<Stream, BuildStreamError> {
-    let err_fn = |err| log::error!("an error occurred on the output audio stream: {}", err);
+    let mut report_device_error = render.device_error_handler();
+    let err_fn = move |err: StreamError| {
+        log::error!("an error occurred on the output audio stream: {}", err);
+        // the stream will not produce audio anymore when the device is gone
+        if matches!(err, StreamError::DeviceNotAvailable) {
+            report_device_error(err.to_string());
+        }
+    };
 
     match sample_format {
         SampleFormat::F32 => device.build_output_stream(
             config,
             move |d: &mut [f32], i: &OutputCallbackInfo| {
-                render.render(d);
-                output_latency.store(latency_in_seconds(i), Ordering::Relaxed);
+                input.capture();
+                let latency = latency_in_seconds(i);
+                render.render_timestamped(d, playback_instant(latency));
+                output_latency.store(latency, Ordering::Relaxed);
             },
             err_fn,
             None,
@@ -510,8 +741,10 @@ fn spawn_output_stream(
         SampleFormat::F64 => device.build_output_stream(
             config,
             move |d: &mut [f64], i: &OutputCallbackInfo| {
-                render.render(d);
-                output_latency.store(latency_in_seconds(i), Ordering::Relaxed);
+                input.capture();
+                let latency = latency_in_seconds(i);
+                render.render_timestamped(d, playback_instant(latency));
+                output_latency.store(latency, Ordering::Relaxed);
             },
             err_fn,
             None,
@@ -519,8 +752,10 @@ fn spawn_output_stream(
         SampleFormat::U8 => device.build_output_stream(
             config,
             move |d: &mut [u8], i: &OutputCallbackInfo| {
-                render.render(d);
-                output_latency.store(latency_in_seconds(i), Ordering::Relaxed);
+                input.capture();
+                let latency = latency_in_seconds(i);
+                render.render_timestamped(d, playback_instant(latency));
+                output_latency.store(latency, Ordering::Relaxed);
             },
             err_fn,
             None,
@@ -528,8 +763,10 @@ fn spawn_output_stream(
         SampleFormat::U16 => device.build_output_stream(
             config,
             move |d: &mut [u16], i: &OutputCallbackInfo| {
-                render.render(d);
-                output_latency.store(latency_in_seconds(i), Ordering::Relaxed);
+                input.capture();
+                let latency = latency_in_seconds(i);
+                render.render_timestamped(d, playback_instant(latency));
+                output_latency.store(latency, Ordering::Relaxed);
             },
             err_fn,
             None,
@@ -537,8 +774,10 @@ fn spawn_output_stream(
         SampleFormat::U32 => device.build_output_stream(
             config,
             move |d: &mut [u32], i: &OutputCallbackInfo| {
-                render.render(d);
-                output_latency.store(latency_in_seconds(i), Ordering::Relaxed);
+                input.capture();
+                let latency = latency_in_seconds(i);
+                render.render_timestamped(d, playback_instant(latency));
+                output_latency.store(latency, Ordering::Relaxed);
             },
             err_fn,
             None,
@@ -546,8 +785,10 @@ fn spawn_output_stream(
         SampleFormat::U64 => device.build_output_stream(
             config,
             move |d: &mut [u64], i: &OutputCallbackInfo| {
-                render.render(d);
-                output_latency.store(latency_in_seconds(i), Ordering::Relaxed);
+                input.capture();
+                let latency = latency_in_seconds(i);
+                render.render_timestamped(d, playback_instant(latency));
+                output_latency.store(latency, Ordering::Relaxed);
             },
             err_fn,
             None,
@@ -555,8 +796,10 @@ fn spawn_output_stream(
         SampleFormat::I8 => device.build_output_stream(
             config,
             move |d: &mut [i8], i: &OutputCallbackInfo| {
-                render.render(d);
-                output_latency.store(latency_in_seconds(i), Ordering::Relaxed);
+                input.capture();
+                let latency = latency_in_seconds(i);
+                render.render_timestamped(d, playback_instant(latency));
+                output_latency.store(latency, Ordering::Relaxed);
             },
             err_fn,
             None,
@@ -564,8 +807,10 @@ fn spawn_output_stream(
         SampleFormat::I16 => device.build_output_stream(
             config,
             move |d: &mut [i16], i: &OutputCallbackInfo| {
-                render.render(d);
-                output_latency.store(latency_in_seconds(i), Ordering::Relaxed);
+                input.capture();
+                let latency = latency_in_seconds(i);
+                render.render_timestamped(d, playback_instant(latency));
+                output_latency.store(latency, Ordering::Relaxed);
             },
             err_fn,
             None,
@@ -573,8 +818,10 @@ fn spawn_output_stream(
         SampleFormat::I32 => device.build_output_stream(
             config,
             move |d: &mut [i32], i: &OutputCallbackInfo| {
-                render.render(d);
-                output_latency.store(latency_in_seconds(i), Ordering::Relaxed);
+                input.capture();
+                let latency = latency_in_seconds(i);
+                render.render_timestamped(d, playback_instant(latency));
+                output_latency.store(latency, Ordering::Relaxed);
             },
             err_fn,
             None,
@@ -582,8 +829,10 @@ fn spawn_output_stream(
         SampleFormat::I64 => device.build_output_stream(
             config,
             move |d: &mut [i64], i: &OutputCallbackInfo| {
-                render.render(d);
-                output_latency.store(latency_in_seconds(i), Ordering::Relaxed);
+                input.capture();
+                let latency = latency_in_seconds(i);
+                render.render_timestamped(d, playback_instant(latency));
+                output_latency.store(latency, Ordering::Relaxed);
             },
             err_fn,
             None,
@@ -642,3 +891,106 @@ fn spawn_input_stream(
         _ => panic!("Unknown cpal input sample format"),
     }
 }
+
+/// Creates the input stream of a duplex stream
+///
+/// # Arguments:
+///
+/// * `device` - the input audio device on which the stream is created
+/// * `sample_format` - audio sample format of the stream
+/// * `config` - stream configuration
+/// * `ring_buffer` - ring buffer read by the output callback
+/// * `duplex_input` - the duplex input, to report the input latency
+fn spawn_duplex_input_stream(
+    device: &Device,
+    sample_format: SampleFormat,
+    config: &StreamConfig,
+    ring_buffer: Arc<SampleRingBuffer>,
+    duplex_input: DuplexInput,
+) -> Result<Stream, BuildStreamError> {
+    let err_fn = |err| log::error!("an error occurred on the duplex input stream: {}", err);
+
+    match sample_format {
+        SampleFormat::F32 => device.build_input_stream(
+            config,
+            duplex_input_callback::<f32>(ring_buffer, duplex_input),
+            err_fn,
+            None,
+        ),
+        SampleFormat::F64 => device.build_input_stream(
+            config,
+            duplex_input_callback::<f64>(ring_buffer, duplex_input),
+            err_fn,
+            None,
+        ),
+        SampleFormat::U8 => device.build_input_stream(
+            config,
+            duplex_input_callback::<u8>(ring_buffer, duplex_input),
+            err_fn,
+            None,
+        ),
+        SampleFormat::U16 => device.build_input_stream(
+            config,
+            duplex_input_callback::<u16>(ring_buffer, duplex_input),
+            err_fn,
+            None,
+        ),
+        SampleFormat::U32 => device.build_input_stream(
+            config,
+            duplex_input_callback::<u32>(ring_buffer, duplex_input),
+            err_fn,
+            None,
+        ),
+        SampleFormat::U64 => device.build_input_stream(
+            config,
+            duplex_input_callback::<u64>(ring_buffer, duplex_input),
+            err_fn,
+            None,
+        ),
+        SampleFormat::I8 => device.build_input_stream(
+            config,
+            duplex_input_callback::<i8>(ring_buffer, duplex_input),
+            err_fn,
+            None,
+        ),
+        SampleFormat::I16 => device.build_input_stream(
+            config,
+            duplex_input_callback::<i16>(ring_buffer, duplex_input),
+            err_fn,
+            None,
+        ),
+        SampleFormat::I32 => device.build_input_stream(
+            config,
+            duplex_input_callback::<i32>(ring_buffer, duplex_input),
+            err_fn,
+            None,
+        ),
+        SampleFormat::I64 => device.build_input_stream(
+            config,
+            duplex_input_callback::<i64>(ring_buffer, duplex_input),
+            err_fn,
+            None,
+        ),
+        _ => panic!("Unknown cpal input sample format"),
+    }
+}
+
+/// Data callback of the input stream of a duplex stream
+fn duplex_input_callback<S: dasp_sample::ToSample<f32> + Copy>(
+    ring_buffer: Arc<SampleRingBuffer>,
+    duplex_input: DuplexInput,
+) -> impl FnMut(&[S], &InputCallbackInfo) + Send + 'static {
+    move |data, info| {
+        let timestamp = info.timestamp();
+        let latency = timestamp
+            .callback
+            .duration_since(&timestamp.capture)
+            .map_or(0., |delta| delta.as_secs_f64());
+        duplex_input.set_device_latency(latency);
+
+        // the samples of a callback are written all at once, so frames are never split
+        if !ring_buffer.push(data.iter().map(|v| v.to_sample_())) {
+            log::debug!("duplex input frames dropped");
+        }
+    }
+}
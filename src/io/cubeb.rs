@@ -1,13 +1,15 @@
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use super::{AudioBackendManager, RenderThreadInit};
+use super::{AudioBackendManager, DuplexInput, RenderThreadInit};
 
 use crate::buffer::AudioBuffer;
 use crate::context::AudioContextOptions;
 use crate::io::microphone::MicrophoneRender;
 use crate::media_devices::{MediaDeviceInfo, MediaDeviceInfoKind};
 use crate::render::RenderThread;
-use crate::{MAX_CHANNELS, RENDER_QUANTUM_SIZE};
+use crate::{AtomicF64, DomException, MAX_CHANNELS, MAX_RENDER_QUANTUM_SIZE, RENDER_QUANTUM_SIZE};
 
 use cubeb::{Context, DeviceId, DeviceType, StereoFrame, Stream, StreamParams};
 
@@ -20,6 +22,7 @@ trait CubebStream {
     fn delegate_start(&self) -> Result<(), cubeb::Error>;
     fn delegate_stop(&self) -> Result<(), cubeb::Error>;
     fn delegate_latency(&self) -> Result<u32, cubeb::Error>;
+    fn delegate_input_latency(&self) -> Result<u32, cubeb::Error>;
 }
 
 impl<F> CubebStream for Stream<F> {
@@ -32,6 +35,9 @@ impl<F> CubebStream for Stream<F> {
     fn delegate_latency(&self) -> Result<u32, cubeb::Error> {
         self.latency()
     }
+    fn delegate_input_latency(&self) -> Result<u32, cubeb::Error> {
+        self.input_latency()
+    }
 }
 
 // I doubt this construct is entirely safe. Stream is not Send/Sync (probably for a good reason) so
@@ -88,6 +94,17 @@ mod private {
 
             0.
         }
+
+        pub fn input_latency(&self, sample_rate: f32) -> f64 {
+            if let Some(s) = self.0.lock().unwrap().as_ref() {
+                // output-only streams have no input latency
+                if let Ok(frames) = s.0.delegate_input_latency() {
+                    return frames as f64 / sample_rate as f64;
+                }
+            }
+
+            0.
+        }
     }
 
     // SAFETY:
@@ -98,43 +115,81 @@ mod private {
 }
 use private::ThreadSafeClosableStream;
 
+/// Set up the output stream, which also captures the input of a duplex stream if requested
+///
+/// The input of a duplex stream has the same channel count as the output, the frames are pushed
+/// into the duplex input before rendering each callback.
 fn init_output_backend<const N: usize>(
     ctx: &Context,
     params: StreamParams,
     buffer_size: u32,
     device: Option<DeviceId>,
+    input: Option<(Option<DeviceId>, DuplexInput)>,
     mut renderer: RenderThread,
 ) -> ThreadSafeClosableStream {
     let mut builder = cubeb::StreamBuilder::<[f32; N]>::new();
+    let mut report_device_error = renderer.device_error_handler();
+
+    // The output latency is only known after the stream is initialized, share it with the callback
+    let output_latency = Arc::new(AtomicF64::new(0.));
+    let callback_output_latency = Arc::clone(&output_latency);
 
     match device {
         None => builder.default_output(&params),
         Some(devid) => builder.output(devid, &params),
     };
 
+    let duplex_input = input.map(|(device, duplex_input)| {
+        match device {
+            None => builder.default_input(&params),
+            Some(devid) => builder.input(devid, &params),
+        };
+        renderer.set_duplex_input(duplex_input.clone());
+        duplex_input
+    });
+
     builder
         .name("Cubeb web_audio_api")
         .latency(buffer_size)
-        .data_callback(move |_input, output| {
+        .data_callback(move |input, output| {
+            // capture the input of this callback before rendering
+            if let Some(duplex_input) = &duplex_input {
+                let input: &[f32] =
+                    // SAFETY: `[T]` is layout-identical to `[T; N]`
+                    unsafe { std::slice::from_raw_parts(input.as_ptr().cast(), input.len() * N) };
+                duplex_input.push(input, N);
+            }
+
             // `output` is `&mut [[f32; N]]`, a slice of slices.
             // The renderer just wants a single slice, flatten it.
             // Inspired by the unstable feature <https://github.com/rust-lang/rust/pull/95579>
             {
                 let output: &mut [f32] =
                     // SAFETY: `[T]` is layout-identical to `[T; N]`
-                    unsafe { std::slice::from_raw_parts_mut(output.as_mut_ptr().cast(), RENDER_QUANTUM_SIZE * N) };
-                renderer.render(output);
+                    unsafe { std::slice::from_raw_parts_mut(output.as_mut_ptr().cast(), output.len() * N) };
+                let latency = callback_output_latency.load(Ordering::Relaxed);
+                let playback_time = Instant::now() + Duration::from_secs_f64(latency);
+                renderer.render_timestamped(output, playback_time);
             }
 
             output.len() as isize
         })
-        .state_callback(|state| {
+        .state_callback(move |state| {
             println!("stream state changed: {state:?}");
+            // the stream will not produce audio anymore, e.g. when the device is gone
+            if matches!(state, cubeb::State::Error) {
+                report_device_error(String::from("the audio output stream has failed"));
+            }
         });
 
     let stream = builder
         .init(ctx)
         .expect("InvalidStateError - Failed to create cubeb stream");
+    let latency_frames = stream.latency().unwrap_or(0);
+    output_latency.store(
+        latency_frames as f64 / params.rate() as f64,
+        Ordering::Relaxed,
+    );
     ThreadSafeClosableStream::new(stream)
 }
 
@@ -144,6 +199,7 @@ pub(crate) struct CubebBackend {
     stream: ThreadSafeClosableStream,
     sample_rate: f32,
     number_of_channels: usize,
+    render_quantum_size: usize,
     sink_id: String,
 }
 
@@ -158,6 +214,11 @@ impl AudioBackendManager for CubebBackend {
             ctrl_msg_recv,
             load_value_send,
             event_send,
+            output_timestamp,
+            playout_stats,
+            duplex_input,
+            device_failure,
+            ..
         } = render_thread_init;
 
         // Set up cubeb context
@@ -185,17 +246,6 @@ impl AudioBackendManager for CubebBackend {
             _ => cubeb::ChannelLayout::UNDEFINED, // TODO, does this work?
         };
 
-        let mut renderer = RenderThread::new(
-            sample_rate,
-            number_of_channels,
-            ctrl_msg_recv,
-            state,
-            frames_played,
-            event_send,
-        );
-        renderer.set_load_value_sender(load_value_send);
-        renderer.spawn_garbage_collector_thread();
-
         let params = cubeb::StreamParamsBuilder::new()
             .format(cubeb::SampleFormat::Float32NE) // use float (native endian)
             .rate(sample_rate as u32)
@@ -212,56 +262,111 @@ impl AudioBackendManager for CubebBackend {
             .unwrap_or(RENDER_QUANTUM_SIZE as u32);
         let buffer_size = buffer_size_req.max(min_latency);
 
+        // render in chunks of the hardware buffer size if requested, to prevent re-buffering
+        let render_quantum_size =
+            super::render_quantum_size_for_category(options.render_size_hint, buffer_size as usize);
+
+        let mut renderer = RenderThread::new(
+            sample_rate,
+            render_quantum_size,
+            number_of_channels,
+            ctrl_msg_recv,
+            state,
+            frames_played,
+            event_send,
+        );
+        renderer.set_load_value_sender(load_value_send);
+        renderer.set_output_timestamp(output_timestamp);
+        renderer.set_playout_stats(playout_stats);
+        renderer.set_device_failure(device_failure);
+        renderer.spawn_garbage_collector_thread();
+
         let device = if options.sink_id.is_empty() {
             None
         } else {
             Self::enumerate_devices_sync()
                 .into_iter()
                 .find(|e| e.device_id() == options.sink_id)
-                .map(|e| *e.device().downcast::<DeviceId>().unwrap())
+                .and_then(MediaDeviceInfo::device)
+                .map(|d| *d.downcast::<DeviceId>().unwrap())
         };
 
+        // Capture the input device in the same stream, see `AudioContextOptions::input`
+        let input = duplex_input.map(|duplex_input| {
+            let device_id = &duplex_input.options().device_id;
+            let device = if device_id.is_empty() {
+                None
+            } else {
+                Self::enumerate_devices_sync()
+                    .into_iter()
+                    .find(|e| e.device_id() == *device_id)
+                    .and_then(MediaDeviceInfo::device)
+                    .map(|d| *d.downcast::<DeviceId>().unwrap())
+            };
+            (device, duplex_input)
+        });
+        let duplex_input = input.as_ref().map(|(_, duplex_input)| duplex_input.clone());
+
         let stream = match number_of_channels {
             // so sorry, but I need to constify the non-const `number_of_channels`
-            1 => init_output_backend::<1>(&ctx, params, buffer_size, device, renderer),
-            2 => init_output_backend::<2>(&ctx, params, buffer_size, device, renderer),
-            3 => init_output_backend::<3>(&ctx, params, buffer_size, device, renderer),
-            4 => init_output_backend::<4>(&ctx, params, buffer_size, device, renderer),
-            5 => init_output_backend::<5>(&ctx, params, buffer_size, device, renderer),
-            6 => init_output_backend::<6>(&ctx, params, buffer_size, device, renderer),
-            7 => init_output_backend::<7>(&ctx, params, buffer_size, device, renderer),
-            8 => init_output_backend::<8>(&ctx, params, buffer_size, device, renderer),
-            9 => init_output_backend::<9>(&ctx, params, buffer_size, device, renderer),
-            10 => init_output_backend::<10>(&ctx, params, buffer_size, device, renderer),
-            11 => init_output_backend::<11>(&ctx, params, buffer_size, device, renderer),
-            12 => init_output_backend::<12>(&ctx, params, buffer_size, device, renderer),
-            13 => init_output_backend::<13>(&ctx, params, buffer_size, device, renderer),
-            14 => init_output_backend::<14>(&ctx, params, buffer_size, device, renderer),
-            15 => init_output_backend::<15>(&ctx, params, buffer_size, device, renderer),
-            16 => init_output_backend::<16>(&ctx, params, buffer_size, device, renderer),
-            17 => init_output_backend::<17>(&ctx, params, buffer_size, device, renderer),
-            18 => init_output_backend::<18>(&ctx, params, buffer_size, device, renderer),
-            19 => init_output_backend::<19>(&ctx, params, buffer_size, device, renderer),
-            20 => init_output_backend::<20>(&ctx, params, buffer_size, device, renderer),
-            21 => init_output_backend::<21>(&ctx, params, buffer_size, device, renderer),
-            22 => init_output_backend::<22>(&ctx, params, buffer_size, device, renderer),
-            23 => init_output_backend::<23>(&ctx, params, buffer_size, device, renderer),
-            24 => init_output_backend::<24>(&ctx, params, buffer_size, device, renderer),
-            25 => init_output_backend::<25>(&ctx, params, buffer_size, device, renderer),
-            26 => init_output_backend::<26>(&ctx, params, buffer_size, device, renderer),
-            27 => init_output_backend::<27>(&ctx, params, buffer_size, device, renderer),
-            28 => init_output_backend::<28>(&ctx, params, buffer_size, device, renderer),
-            29 => init_output_backend::<29>(&ctx, params, buffer_size, device, renderer),
-            30 => init_output_backend::<30>(&ctx, params, buffer_size, device, renderer),
-            31 => init_output_backend::<31>(&ctx, params, buffer_size, device, renderer),
-            32 => init_output_backend::<32>(&ctx, params, buffer_size, device, renderer),
+            1 => init_output_backend::<1>(&ctx, params, buffer_size, device, input, renderer),
+            2 => init_output_backend::<2>(&ctx, params, buffer_size, device, input, renderer),
+            3 => init_output_backend::<3>(&ctx, params, buffer_size, device, input, renderer),
+            4 => init_output_backend::<4>(&ctx, params, buffer_size, device, input, renderer),
+            5 => init_output_backend::<5>(&ctx, params, buffer_size, device, input, renderer),
+            6 => init_output_backend::<6>(&ctx, params, buffer_size, device, input, renderer),
+            7 => init_output_backend::<7>(&ctx, params, buffer_size, device, input, renderer),
+            8 => init_output_backend::<8>(&ctx, params, buffer_size, device, input, renderer),
+            9 => init_output_backend::<9>(&ctx, params, buffer_size, device, input, renderer),
+            10 => init_output_backend::<10>(&ctx, params, buffer_size, device, input, renderer),
+            11 => init_output_backend::<11>(&ctx, params, buffer_size, device, input, renderer),
+            12 => init_output_backend::<12>(&ctx, params, buffer_size, device, input, renderer),
+            13 => init_output_backend::<13>(&ctx, params, buffer_size, device, input, renderer),
+            14 => init_output_backend::<14>(&ctx, params, buffer_size, device, input, renderer),
+            15 => init_output_backend::<15>(&ctx, params, buffer_size, device, input, renderer),
+            16 => init_output_backend::<16>(&ctx, params, buffer_size, device, input, renderer),
+            17 => init_output_backend::<17>(&ctx, params, buffer_size, device, input, renderer),
+            18 => init_output_backend::<18>(&ctx, params, buffer_size, device, input, renderer),
+            19 => init_output_backend::<19>(&ctx, params, buffer_size, device, input, renderer),
+            20 => init_output_backend::<20>(&ctx, params, buffer_size, device, input, renderer),
+            21 => init_output_backend::<21>(&ctx, params, buffer_size, device, input, renderer),
+            22 => init_output_backend::<22>(&ctx, params, buffer_size, device, input, renderer),
+            23 => init_output_backend::<23>(&ctx, params, buffer_size, device, input, renderer),
+            24 => init_output_backend::<24>(&ctx, params, buffer_size, device, input, renderer),
+            25 => init_output_backend::<25>(&ctx, params, buffer_size, device, input, renderer),
+            26 => init_output_backend::<26>(&ctx, params, buffer_size, device, input, renderer),
+            27 => init_output_backend::<27>(&ctx, params, buffer_size, device, input, renderer),
+            28 => init_output_backend::<28>(&ctx, params, buffer_size, device, input, renderer),
+            29 => init_output_backend::<29>(&ctx, params, buffer_size, device, input, renderer),
+            30 => init_output_backend::<30>(&ctx, params, buffer_size, device, input, renderer),
+            31 => init_output_backend::<31>(&ctx, params, buffer_size, device, input, renderer),
+            32 => init_output_backend::<32>(&ctx, params, buffer_size, device, input, renderer),
             _ => unreachable!(),
         };
 
+        if let Some(duplex_input) = duplex_input {
+            // The input is captured with the channel count of the output
+            let requested = duplex_input.options().number_of_channels;
+            let input_channels = requested.map_or(number_of_channels, |n| n as usize);
+            if input_channels > number_of_channels {
+                log::warn!(
+                    "Duplex input is limited to {} channels, the number of output channels",
+                    number_of_channels
+                );
+            }
+            duplex_input.start(
+                input_channels.min(number_of_channels),
+                sample_rate,
+                render_quantum_size,
+                stream.input_latency(sample_rate),
+            );
+        }
+
         let backend = CubebBackend {
             stream,
             number_of_channels,
             sample_rate,
+            render_quantum_size,
             sink_id: options.sink_id,
         };
 
@@ -272,8 +377,8 @@ impl AudioBackendManager for CubebBackend {
 
     fn build_input(
         options: AudioContextOptions,
-        _number_of_channels: Option<u32>,
-    ) -> (Self, Receiver<AudioBuffer>)
+        number_of_channels: Option<u32>,
+    ) -> Result<(Self, Receiver<AudioBuffer>), DomException>
     where
         Self: Sized,
     {
@@ -285,9 +390,16 @@ impl AudioBackendManager for CubebBackend {
          */
 
         // Set up cubeb context
-        let ctx = Context::init(None, None).unwrap();
+        let ctx = Context::init(None, None).map_err(|e| {
+            DomException::InvalidStateError(format!("Failed to create cubeb context: {:?}", e))
+        })?;
         log::info!("Audio Input Host: cubeb {:?}", ctx.backend_id());
 
+        if let Some(sample_rate) = options.sample_rate {
+            crate::check_valid_sample_rate(sample_rate)
+                .map_err(|e| DomException::OverconstrainedError(e.message().to_string()))?;
+        }
+
         // Use user requested sample rate, or else the device preferred one
         let device_sample_rate = ctx.preferred_sample_rate().map(|v| v as f32).ok();
         let sample_rate = options.sample_rate.or(device_sample_rate).unwrap_or(48000.);
@@ -295,6 +407,14 @@ impl AudioBackendManager for CubebBackend {
         // TODO support all channel configs
         let _max_channel_count = ctx.max_channel_count().map(|v| v as usize).ok();
         const NUMBER_OF_INPUT_CHANNELS: usize = 2;
+        if let Some(number_of_channels) = number_of_channels {
+            if number_of_channels as usize != NUMBER_OF_INPUT_CHANNELS {
+                return Err(DomException::OverconstrainedError(format!(
+                    "unsupported channel count {}, only stereo input is available",
+                    number_of_channels
+                )));
+            }
+        }
         let layout = cubeb::ChannelLayout::STEREO;
 
         let params = cubeb::StreamParamsBuilder::new()
@@ -316,10 +436,15 @@ impl AudioBackendManager for CubebBackend {
         let device = if options.sink_id.is_empty() {
             None
         } else {
-            Self::enumerate_devices_sync()
+            let device = Self::enumerate_devices_sync()
                 .into_iter()
                 .find(|e| e.device_id() == options.sink_id)
-                .map(|e| *e.device().downcast::<DeviceId>().unwrap())
+                .and_then(MediaDeviceInfo::device)
+                .map(|d| *d.downcast::<DeviceId>().unwrap())
+                .ok_or_else(|| {
+                    DomException::NotFoundError(format!("invalid deviceId {:?}", options.sink_id))
+                })?;
+            Some(device)
         };
 
         let smoothing = 3; // todo, use buffering to smooth frame drops
@@ -334,38 +459,55 @@ impl AudioBackendManager for CubebBackend {
             Some(devid) => builder.input(devid, &params),
         };
 
+        // Preallocate for the largest callbacks, so the audio thread never allocates. A host
+        // that delivers even more frames is handled in chunks.
+        let max_frames = (buffer_size as usize).max(MAX_RENDER_QUANTUM_SIZE);
+        let mut tmp = vec![0.; max_frames * NUMBER_OF_INPUT_CHANNELS];
+
         builder
             .name("Cubeb web_audio_api (mono)")
             .latency(buffer_size)
             .data_callback(move |input, _output| {
-                let mut tmp = [0.; RENDER_QUANTUM_SIZE * NUMBER_OF_INPUT_CHANNELS];
-                tmp.chunks_mut(NUMBER_OF_INPUT_CHANNELS)
-                    .zip(input)
-                    .for_each(|(t, i)| {
-                        t[0] = i.l;
-                        t[1] = i.r;
-                    });
-                renderer.render(&tmp);
+                for frames in input.chunks(max_frames) {
+                    let len = frames.len() * NUMBER_OF_INPUT_CHANNELS;
+                    tmp[..len]
+                        .chunks_mut(NUMBER_OF_INPUT_CHANNELS)
+                        .zip(frames)
+                        .for_each(|(t, i)| {
+                            t[0] = i.l;
+                            t[1] = i.r;
+                        });
+                    renderer.render(&tmp[..len]);
+                }
                 input.len() as isize
             })
             .state_callback(|state| {
                 println!("stream state changed: {state:?}");
             });
 
-        let stream = builder
-            .init(&ctx)
-            .expect("InvalidStateError - Failed to create cubeb stream");
+        let stream = builder.init(&ctx).map_err(|e| {
+            let message = format!("Failed to create cubeb stream: {:?}", e);
+            // the device may not support the requested sample rate
+            if options.sample_rate.is_some() {
+                DomException::OverconstrainedError(message)
+            } else {
+                DomException::InvalidStateError(message)
+            }
+        })?;
 
-        stream.start().unwrap();
+        stream.start().map_err(|e| {
+            DomException::InvalidStateError(format!("Failed to start cubeb stream: {:?}", e))
+        })?;
 
         let backend = CubebBackend {
             stream: ThreadSafeClosableStream::new(stream),
             number_of_channels: NUMBER_OF_INPUT_CHANNELS,
             sample_rate,
+            render_quantum_size: RENDER_QUANTUM_SIZE,
             sink_id: options.sink_id,
         };
 
-        (backend, receiver)
+        Ok((backend, receiver))
     }
 
     fn resume(&self) -> bool {
@@ -388,6 +530,10 @@ impl AudioBackendManager for CubebBackend {
         self.number_of_channels
     }
 
+    fn render_quantum_size(&self) -> usize {
+        self.render_quantum_size
+    }
+
     fn output_latency(&self) -> f64 {
         self.stream.output_latency(self.sample_rate)
     }
@@ -0,0 +1,313 @@
+use std::collections::VecDeque;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, RwLock};
+
+use crate::context::AudioContextInputOptions;
+use crate::render::AudioRenderQuantum;
+use crate::{AtomicF64, RENDER_QUANTUM_SIZE};
+
+/// Audio input captured in the same stream as the audio output
+///
+/// The audio backend pushes the input frames of every callback before rendering the output of
+/// that callback. The render thread then hands out exactly one render quantum of input per
+/// rendered quantum, so the input of a duplex stream is sample-aligned with the audio graph. An
+/// input captured by a separate stream (cpal) is only aligned with the callbacks of the output.
+///
+/// When the callback buffer size is not a multiple of the render quantum size, the render thread
+/// renders ahead of the input. The missing frames are filled with silence once, which delays the
+/// input by a constant number of frames (less than one render quantum) from then on. The input is
+/// discarded while the audio graph is not rendering, so the alignment is restored on resume.
+#[derive(Clone, Debug)]
+pub(crate) struct DuplexInput {
+    inner: Arc<DuplexInputInner>,
+}
+
+#[derive(Debug)]
+struct DuplexInputInner {
+    options: AudioContextInputOptions,
+    /// The render thread never blocks on this lock, the control thread only takes it when an
+    /// audio backend is set up
+    buffer: RwLock<DuplexInputBuffer>,
+    /// Latency of the input device in seconds
+    device_latency: AtomicF64,
+    /// Input latency in seconds, including the alignment with the render quantum
+    latency: AtomicF64,
+}
+
+#[derive(Debug)]
+struct DuplexInputBuffer {
+    number_of_channels: usize,
+    sample_rate: f32,
+    render_quantum_size: usize,
+    /// Maximum number of pending frames
+    capacity: usize,
+    /// Captured interleaved frames that have not been rendered yet
+    pending: VecDeque<f32>,
+    /// Captured frames of the current render quantum
+    quantum: Vec<Vec<f32>>,
+}
+
+impl DuplexInputBuffer {
+    fn new(number_of_channels: usize, sample_rate: f32, render_quantum_size: usize) -> Self {
+        // Buffer up to one second of input, which is way more than the audio backends deliver
+        // in a single callback
+        let capacity = (sample_rate as usize).max(render_quantum_size);
+
+        Self {
+            number_of_channels,
+            sample_rate,
+            render_quantum_size,
+            capacity,
+            pending: VecDeque::with_capacity(capacity * number_of_channels),
+            quantum: vec![vec![0.; render_quantum_size]; number_of_channels],
+        }
+    }
+
+    fn pending_frames(&self) -> usize {
+        self.pending.len() / self.number_of_channels
+    }
+}
+
+impl DuplexInput {
+    pub fn new(options: AudioContextInputOptions) -> Self {
+        let inner = DuplexInputInner {
+            options,
+            buffer: RwLock::new(DuplexInputBuffer::new(1, 48000., RENDER_QUANTUM_SIZE)),
+            device_latency: AtomicF64::new(0.),
+            latency: AtomicF64::new(0.),
+        };
+
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+
+    /// The input device and channel count requested by the user
+    pub fn options(&self) -> &AudioContextInputOptions {
+        &self.inner.options
+    }
+
+    /// Prepare for the input of a new audio backend
+    ///
+    /// This allocates, so it must be called before the render thread is started.
+    pub fn start(
+        &self,
+        number_of_channels: usize,
+        sample_rate: f32,
+        render_quantum_size: usize,
+        device_latency: f64,
+    ) {
+        let buffer = DuplexInputBuffer::new(number_of_channels, sample_rate, render_quantum_size);
+        *self.inner.buffer.write().unwrap() = buffer;
+        self.set_device_latency(device_latency);
+        self.inner.latency.store(device_latency, Ordering::Relaxed);
+    }
+
+    /// Update the latency of the input device, in seconds
+    pub fn set_device_latency(&self, latency: f64) {
+        self.inner.device_latency.store(latency, Ordering::Relaxed);
+    }
+
+    /// Input latency in seconds, including the alignment with the render quantum
+    pub fn latency(&self) -> f64 {
+        self.inner.latency.load(Ordering::Relaxed)
+    }
+
+    /// Append the input frames of the current callback
+    ///
+    /// The `input` is interleaved with `stride` samples per frame, of which the first
+    /// `number_of_channels` are used. Missing channels are filled with silence.
+    pub fn push(&self, input: &[f32], stride: usize) {
+        // never block the render thread
+        let Ok(mut buffer) = self.inner.buffer.try_write() else {
+            return;
+        };
+
+        let number_of_channels = buffer.number_of_channels;
+        let capacity = buffer.capacity;
+        let frames = input.chunks_exact(stride);
+
+        // drop the oldest frames before appending rather than allocating
+        let skip = frames.len().saturating_sub(capacity);
+        let excess = (buffer.pending_frames() + frames.len() - skip).saturating_sub(capacity);
+        buffer.pending.drain(..excess * number_of_channels);
+
+        for frame in frames.skip(skip) {
+            for channel in 0..number_of_channels {
+                buffer
+                    .pending
+                    .push_back(frame.get(channel).copied().unwrap_or(0.));
+            }
+        }
+    }
+
+    /// Move the input frames of the next render quantum out of the pending input
+    pub fn advance(&self) {
+        let Ok(mut buffer) = self.inner.buffer.try_write() else {
+            return;
+        };
+        let DuplexInputBuffer {
+            number_of_channels,
+            render_quantum_size,
+            pending,
+            quantum,
+            ..
+        } = &mut *buffer;
+
+        // Rendering is ahead of the input, delay the input by the missing frames
+        let available = pending.len() / *number_of_channels;
+        let missing = render_quantum_size.saturating_sub(available);
+        quantum
+            .iter_mut()
+            .for_each(|channel| channel[..missing].fill(0.));
+
+        for i in missing..*render_quantum_size {
+            for channel in quantum.iter_mut() {
+                channel[i] = pending.pop_front().unwrap();
+            }
+        }
+    }
+
+    /// Discard the pending input, because the audio graph is not rendering
+    pub fn reset(&self) {
+        let Ok(mut buffer) = self.inner.buffer.try_write() else {
+            return;
+        };
+        buffer.pending.clear();
+        buffer
+            .quantum
+            .iter_mut()
+            .for_each(|channel| channel.fill(0.));
+
+        let device_latency = self.inner.device_latency.load(Ordering::Relaxed);
+        self.inner.latency.store(device_latency, Ordering::Relaxed);
+    }
+
+    /// Update the input latency after rendering a callback
+    ///
+    /// `leftover_frames` is the number of frames that have been rendered but not been played yet.
+    /// An input frame is rendered into the output frame that is played `pending + leftover`
+    /// frames after the output frame of the callback that captured it.
+    pub fn update_latency(&self, leftover_frames: usize) {
+        let Ok(buffer) = self.inner.buffer.try_read() else {
+            return;
+        };
+        let alignment = (buffer.pending_frames() + leftover_frames) as f64;
+        let device_latency = self.inner.device_latency.load(Ordering::Relaxed);
+        let latency = device_latency + alignment / buffer.sample_rate as f64;
+        self.inner.latency.store(latency, Ordering::Relaxed);
+    }
+
+    /// Copy the input of the current render quantum into `output`
+    pub fn copy_quantum(&self, output: &mut AudioRenderQuantum) {
+        // The render thread only writes in between rendering the audio graph, so this fails
+        // only when the audio backend is being set up
+        let Ok(buffer) = self.inner.buffer.try_read() else {
+            output.make_silent();
+            return;
+        };
+
+        output.set_number_of_channels(buffer.number_of_channels);
+        output
+            .channels_mut()
+            .iter_mut()
+            .zip(&buffer.quantum)
+            .for_each(|(o, i)| o.copy_from_slice(i));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quantum(input: &DuplexInput) -> Vec<Vec<f32>> {
+        input.inner.buffer.read().unwrap().quantum.clone()
+    }
+
+    #[test]
+    fn test_aligned() {
+        let input = DuplexInput::new(AudioContextInputOptions::default());
+        input.start(1, 48000., 4, 0.01);
+
+        input.push(&[1., 2., 3., 4.], 1);
+        input.advance();
+        assert_eq!(quantum(&input), vec![vec![1., 2., 3., 4.]]);
+
+        input.update_latency(0);
+        assert_eq!(input.latency(), 0.01);
+    }
+
+    #[test]
+    fn test_misaligned() {
+        let input = DuplexInput::new(AudioContextInputOptions::default());
+        input.start(1, 8., 4, 0.);
+
+        // callbacks of 3 frames, the graph renders ahead of the input
+        input.push(&[1., 2., 3.], 1);
+        input.advance();
+        assert_eq!(quantum(&input), vec![vec![0., 1., 2., 3.]]);
+        input.update_latency(1);
+        assert_eq!(input.latency(), 0.125);
+
+        input.push(&[4., 5., 6.], 1);
+        input.advance();
+        assert_eq!(quantum(&input), vec![vec![0., 4., 5., 6.]]);
+        input.update_latency(2);
+
+        input.push(&[7., 8., 9.], 1);
+        input.advance();
+        assert_eq!(quantum(&input), vec![vec![0., 7., 8., 9.]]);
+        input.update_latency(3);
+        assert_eq!(input.latency(), 0.375);
+
+        // the leftover frames cover the next callback, the delay is constant from now on
+        input.push(&[10., 11., 12.], 1);
+        input.update_latency(0);
+        assert_eq!(input.latency(), 0.375);
+
+        input.push(&[13., 14., 15.], 1);
+        input.advance();
+        assert_eq!(quantum(&input), vec![vec![10., 11., 12., 13.]]);
+        input.update_latency(1);
+        assert_eq!(input.latency(), 0.375);
+    }
+
+    #[test]
+    fn test_channels() {
+        let input = DuplexInput::new(AudioContextInputOptions::default());
+        input.start(2, 48000., 2, 0.);
+
+        // use the first channels of the frames, fill missing channels with silence
+        input.push(&[1., 2., 3., 4., 5., 6.], 3);
+        input.advance();
+        assert_eq!(quantum(&input), vec![vec![1., 4.], vec![2., 5.]]);
+
+        input.push(&[1., 2.], 1);
+        input.advance();
+        assert_eq!(quantum(&input), vec![vec![1., 2.], vec![0., 0.]]);
+
+        input.push(&[1., 2., 3., 4.], 2);
+        input.reset();
+        input.advance();
+        assert_eq!(quantum(&input), vec![vec![0., 0.], vec![0., 0.]]);
+    }
+
+    #[test]
+    fn test_overflow_does_not_allocate() {
+        let input = DuplexInput::new(AudioContextInputOptions::default());
+        input.start(1, 4., 4, 0.);
+        let allocated = input.inner.buffer.read().unwrap().pending.capacity();
+
+        // keep the most recent frames
+        input.push(&[1., 2., 3.], 1);
+        input.push(&[4., 5., 6.], 1);
+        input.push(&[7., 8., 9., 10., 11., 12.], 1);
+        assert_eq!(
+            input.inner.buffer.read().unwrap().pending.capacity(),
+            allocated
+        );
+
+        input.advance();
+        assert_eq!(quantum(&input), vec![vec![9., 10., 11., 12.]]);
+    }
+}
@@ -0,0 +1,400 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::{AudioBackendManager, RenderThreadInit};
+
+use crate::buffer::AudioBuffer;
+use crate::context::{AudioContextOptions, FileSinkPacing};
+use crate::encoding::{AudioFileEncoder, AudioFileFormat};
+use crate::media_devices::MediaDeviceInfo;
+use crate::render::RenderThread;
+use crate::DomException;
+
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, TryRecvError};
+
+/// The file sink emulates a stereo output device
+const NUMBER_OF_CHANNELS: usize = 2;
+
+/// Prefix of the `sink_id` of the file sink
+pub(crate) const FILE_SINK_PREFIX: &str = "file:";
+
+/// Parse the path and audio file format of a `"file:<path>"` sink id
+///
+/// The format is derived from the file extension: `.wav` files contain 32-bit floating point
+/// samples and `.flac` files contain 24-bit samples. Returns `None` for other sink ids.
+pub(crate) fn parse_file_sink_id(sink_id: &str) -> Option<(&Path, AudioFileFormat)> {
+    let path = Path::new(sink_id.strip_prefix(FILE_SINK_PREFIX)?);
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    let format = match extension.as_str() {
+        "wav" => AudioFileFormat::WavFloat32,
+        "flac" => AudioFileFormat::Flac24,
+        _ => return None,
+    };
+
+    Some((path, format))
+}
+
+/// Create the output file of a `"file:<path>"` sink id, an existing file is truncated
+///
+/// This is part of the sink id validation, so a path that is not writable is reported to the
+/// caller rather than failing the stream.
+pub(crate) fn create_file_sink(sink_id: &str) -> Result<(File, AudioFileFormat), DomException> {
+    let (path, format) = parse_file_sink_id(sink_id)
+        .ok_or_else(|| DomException::NotFoundError(format!("invalid sinkId {:?}", sink_id)))?;
+    let file = File::create(path).map_err(|e| {
+        DomException::NotFoundError(format!("unable to create output file {:?}: {}", path, e))
+    })?;
+
+    Ok((file, format))
+}
+
+enum FileBackendMessage {
+    Resume,
+    Suspend,
+    Close,
+}
+
+#[derive(Clone)]
+pub(crate) struct FileBackend {
+    sender: Sender<FileBackendMessage>,
+    /// Disconnected when the render thread has finalized the file and terminated
+    done: Receiver<()>,
+    sink_id: String,
+    sample_rate: f32,
+    render_quantum_size: usize,
+}
+
+struct Callback {
+    receiver: Receiver<FileBackendMessage>,
+    render_thread: RenderThread,
+    encoder: Option<AudioFileEncoder<BufWriter<File>>>,
+    on_error: Box<dyn FnMut(String) + Send>,
+    pacing: FileSinkPacing,
+    sample_rate: f32,
+    buffer_size: usize,
+    running: bool,
+    _done: Sender<()>,
+}
+
+impl Callback {
+    fn run(mut self) {
+        let interval = Duration::from_secs_f32(self.buffer_size as f32 / self.sample_rate);
+        let mut buffer = vec![0.; self.buffer_size * NUMBER_OF_CHANNELS];
+
+        // For an isochronous callback we must calculate the deadline every render quantum
+        let mut deadline = Instant::now().checked_add(interval).unwrap();
+
+        loop {
+            match self.pacing {
+                FileSinkPacing::RealTime => {
+                    // poll the receiver as long as the deadline is in the future
+                    loop {
+                        match self.receiver.recv_deadline(deadline) {
+                            Ok(FileBackendMessage::Resume) => {
+                                self.running = true;
+                                deadline = Instant::now();
+                                break; // start processing right away
+                            }
+                            Ok(FileBackendMessage::Suspend) => self.running = false,
+                            Ok(FileBackendMessage::Close) | Err(RecvTimeoutError::Disconnected) => {
+                                return self.finalize()
+                            }
+                            Err(RecvTimeoutError::Timeout) => break,
+                        }
+                    }
+                }
+                FileSinkPacing::FreeRunning => {
+                    // render back to back, only block for messages when not rendering
+                    loop {
+                        let msg = if self.running && self.encoder.is_some() {
+                            match self.receiver.try_recv() {
+                                Ok(msg) => msg,
+                                Err(TryRecvError::Empty) => break,
+                                Err(TryRecvError::Disconnected) => return self.finalize(),
+                            }
+                        } else {
+                            match self.receiver.recv() {
+                                Ok(msg) => msg,
+                                Err(_) => return self.finalize(),
+                            }
+                        };
+
+                        match msg {
+                            FileBackendMessage::Resume => self.running = true,
+                            FileBackendMessage::Suspend => self.running = false,
+                            FileBackendMessage::Close => return self.finalize(),
+                        }
+                    }
+                    deadline = Instant::now();
+                }
+            }
+
+            if self.running && self.encoder.is_some() {
+                self.render(&mut buffer, deadline, interval);
+            }
+
+            deadline = deadline.checked_add(interval).unwrap();
+        }
+    }
+
+    fn render(&mut self, buffer: &mut [f32], playback_time: Instant, interval: Duration) {
+        let callback_start = Instant::now();
+
+        // There is no output latency, the buffer is played at the deadline
+        self.render_thread.render_timestamped(buffer, playback_time);
+
+        // A sound card plays silence when the buffer is not ready in time, record it as well.
+        // Rendering is never late when free running, the output is not played in real time.
+        let underrun_frames = match self.pacing {
+            FileSinkPacing::RealTime => {
+                let overrun = callback_start.elapsed().saturating_sub(interval);
+                ((overrun.as_secs_f64() * self.sample_rate as f64).ceil() as usize)
+                    .min(self.buffer_size)
+            }
+            FileSinkPacing::FreeRunning => 0,
+        };
+
+        let mut result = Ok(());
+        if underrun_frames > 0 {
+            let silence = vec![vec![0.; underrun_frames]; NUMBER_OF_CHANNELS];
+            result = self.write(AudioBuffer::from(silence, self.sample_rate));
+        }
+
+        if result.is_ok() {
+            let channels = (0..NUMBER_OF_CHANNELS)
+                .map(|i| {
+                    buffer
+                        .iter()
+                        .skip(i)
+                        .step_by(NUMBER_OF_CHANNELS)
+                        .copied()
+                        .collect()
+                })
+                .collect();
+            result = self.write(AudioBuffer::from(channels, self.sample_rate));
+        }
+
+        if let Err(e) = result {
+            // Stop writing, like an output device that has failed
+            log::error!("Error writing to the output file: {:?}", e);
+            self.encoder = None;
+            (self.on_error)(format!("Error writing to the output file: {e}"));
+        }
+    }
+
+    fn write(&mut self, buffer: AudioBuffer) -> std::io::Result<()> {
+        match self.encoder.as_mut() {
+            Some(encoder) => encoder.write(&buffer),
+            None => Ok(()),
+        }
+    }
+
+    fn finalize(&mut self) {
+        if let Some(encoder) = self.encoder.take() {
+            if let Err(e) = encoder.finalize() {
+                log::error!("Error finalizing the output file: {:?}", e);
+            }
+        }
+    }
+}
+
+impl AudioBackendManager for FileBackend {
+    /// Setup a new output stream (speakers)
+    fn build_output(options: AudioContextOptions, render_thread_init: RenderThreadInit) -> Self
+    where
+        Self: Sized,
+    {
+        let sample_rate = options.sample_rate.unwrap_or(48000.);
+
+        // There is no hardware, so emulate the buffer size of a sound card for the given latency
+        // category. Render in chunks of the render quantum size to prevent re-buffering.
+        let hardware_buffer_size =
+            super::buffer_size_for_latency_category(options.latency_hint, sample_rate);
+        let render_quantum_size =
+            super::render_quantum_size_for_category(options.render_size_hint, hardware_buffer_size);
+
+        // The file was created when validating the sink id. When it can not be created again,
+        // the stream starts as a failed output device.
+        let encoder = create_file_sink(&options.sink_id)
+            .map_err(|e| e.to_string())
+            .and_then(|(file, format)| {
+                let writer = BufWriter::new(file);
+                AudioFileEncoder::new(writer, format, NUMBER_OF_CHANNELS, sample_rate)
+                    .map_err(|e| format!("Error writing to the output file: {e}"))
+            });
+
+        let RenderThreadInit {
+            state,
+            frames_played,
+            ctrl_msg_recv,
+            load_value_send,
+            event_send,
+            output_timestamp,
+            playout_stats,
+            file_sink_pacing,
+            device_failure,
+            ..
+        } = render_thread_init;
+
+        let mut render_thread = RenderThread::new(
+            sample_rate,
+            render_quantum_size,
+            NUMBER_OF_CHANNELS,
+            ctrl_msg_recv,
+            state,
+            frames_played,
+            event_send,
+        );
+        render_thread.set_load_value_sender(load_value_send);
+        render_thread.set_output_timestamp(output_timestamp);
+        render_thread.set_playout_stats(playout_stats);
+        render_thread.set_device_failure(device_failure);
+        render_thread.spawn_garbage_collector_thread();
+
+        // Use a bounded channel for real-time safety. A maximum of 32 control messages (resume,
+        // suspend, ..) will be handled per render quantum. The control thread will block when the
+        // capacity is reached.
+        let (sender, receiver) = crossbeam_channel::bounded(32);
+        let (done_send, done_recv) = crossbeam_channel::bounded(0);
+
+        let mut on_error = Box::new(render_thread.device_error_handler());
+        let encoder = match encoder {
+            Ok(encoder) => Some(encoder),
+            Err(message) => {
+                log::error!("{}", message);
+                on_error(message);
+                None
+            }
+        };
+
+        let callback = Callback {
+            on_error,
+            render_thread,
+            receiver,
+            encoder,
+            pacing: file_sink_pacing,
+            sample_rate,
+            buffer_size: render_quantum_size,
+            running: true,
+            _done: done_send,
+        };
+
+        thread::spawn(move || callback.run());
+
+        Self {
+            sender,
+            done: done_recv,
+            sink_id: options.sink_id,
+            sample_rate,
+            render_quantum_size,
+        }
+    }
+
+    /// Setup a new input stream (microphone capture)
+    fn build_input(
+        _options: AudioContextOptions,
+        _number_of_channels: Option<u32>,
+    ) -> Result<(Self, Receiver<AudioBuffer>), DomException>
+    where
+        Self: Sized,
+    {
+        unimplemented!()
+    }
+
+    /// Resume or start the stream
+    fn resume(&self) -> bool {
+        self.sender.send(FileBackendMessage::Resume).unwrap();
+        true
+    }
+
+    /// Suspend the stream
+    fn suspend(&self) -> bool {
+        self.sender.send(FileBackendMessage::Suspend).unwrap();
+        true
+    }
+
+    /// Close the stream, freeing all resources. It cannot be started again after closing.
+    ///
+    /// This blocks until the file headers have been updated, so the file is complete when this
+    /// method returns.
+    fn close(&self) {
+        self.sender.send(FileBackendMessage::Close).unwrap();
+        let _ = self.done.recv();
+    }
+
+    /// Sample rate of the stream
+    fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Number of channels of the stream
+    fn number_of_channels(&self) -> usize {
+        NUMBER_OF_CHANNELS
+    }
+
+    /// Number of sample frames the render thread renders per render quantum
+    fn render_quantum_size(&self) -> usize {
+        self.render_quantum_size
+    }
+
+    /// Output latency of the stream in seconds
+    ///
+    /// This is the difference between the time the backend acquires the data in the callback and
+    /// the listener can hear the sound.
+    fn output_latency(&self) -> f64 {
+        0.
+    }
+
+    /// The audio output device
+    fn sink_id(&self) -> &str {
+        &self.sink_id
+    }
+
+    fn enumerate_devices_sync() -> Vec<MediaDeviceInfo>
+    where
+        Self: Sized,
+    {
+        unimplemented!()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file_sink_id() {
+        assert_eq!(
+            parse_file_sink_id("file:/tmp/out.wav"),
+            Some((Path::new("/tmp/out.wav"), AudioFileFormat::WavFloat32))
+        );
+        assert_eq!(
+            parse_file_sink_id("file:out.FLAC"),
+            Some((Path::new("out.FLAC"), AudioFileFormat::Flac24))
+        );
+        assert_eq!(parse_file_sink_id("file:/tmp/out.mp3"), None);
+        assert_eq!(parse_file_sink_id("file:/tmp/out"), None);
+        assert_eq!(parse_file_sink_id("/tmp/out.wav"), None);
+        assert_eq!(parse_file_sink_id("none"), None);
+    }
+
+    #[test]
+    fn test_create_file_sink() {
+        let path = std::env::temp_dir().join("web_audio_api_test_create_file_sink.wav");
+        let (_, format) = create_file_sink(&format!("file:{}", path.display())).unwrap();
+        assert_eq!(format, AudioFileFormat::WavFloat32);
+        assert!(path.exists());
+        std::fs::remove_file(&path).unwrap();
+
+        let path = std::env::temp_dir().join("web_audio_api_does_not_exist/out.wav");
+        let result = create_file_sink(&format!("file:{}", path.display()));
+        assert!(matches!(result, Err(DomException::NotFoundError(_))));
+        assert!(matches!(
+            create_file_sink("file:out.mp3"),
+            Err(DomException::NotFoundError(_))
+        ));
+    }
+}
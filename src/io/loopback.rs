@@ -0,0 +1,429 @@
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::{AudioBackendManager, DuplexInput, RenderThreadInit};
+
+use crate::buffer::AudioBuffer;
+use crate::context::AudioContextOptions;
+use crate::media_devices::MediaDeviceInfo;
+use crate::media_streams::{MediaStream, MediaStreamTrack};
+use crate::render::RenderThread;
+use crate::{AtomicF32, DomException, RENDER_QUANTUM_SIZE};
+
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, TryRecvError};
+
+/// The loopback sink emulates a stereo output device
+const NUMBER_OF_CHANNELS: usize = 2;
+
+/// Interval at which pending control messages are handled while the clock is not advancing
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+enum LoopbackMessage {
+    Advance { frames: usize, notify: Sender<()> },
+    Resume,
+    Suspend,
+    Close,
+}
+
+/// Handle to the `"loopback"` sink of an [`AudioContext`](crate::context::AudioContext)
+///
+/// The loopback sink does not play to an audio output device. Instead, every block rendered by
+/// the audio graph is delivered to this handle, so tests can inspect what a real-time context
+/// has actually played.
+///
+/// The sink runs on a virtual clock: time only progresses when [`advance`](Self::advance) is
+/// called, and rendering happens as fast as possible. Control operations such as
+/// [`suspend_sync`](crate::context::AudioContext::suspend_sync) and
+/// [`set_sink_id_sync`](crate::context::AudioContext::set_sink_id_sync) are handled in between
+/// calls to `advance`.
+///
+/// The handle remains valid when the sink of the context is changed. It only renders while the
+/// `"loopback"` sink is active.
+///
+/// ```
+/// use web_audio_api::context::{AudioContext, AudioContextOptions, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+///
+/// let context = AudioContext::new(AudioContextOptions {
+///     sink_id: "loopback".into(),
+///     ..AudioContextOptions::default()
+/// });
+/// let loopback = context.loopback();
+///
+/// let mut src = context.create_constant_source();
+/// src.connect(&context.destination());
+/// src.start();
+///
+/// // render one second of audio
+/// loopback.advance(context.sample_rate() as usize);
+///
+/// let played: Vec<_> = loopback.drain().collect();
+/// assert!(played.iter().all(|block| block.get_channel_data(0).iter().all(|&v| v == 1.)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Loopback {
+    inner: Arc<LoopbackInner>,
+}
+
+#[derive(Debug)]
+struct LoopbackInner {
+    /// Sender to the active loopback backend, if any
+    backend: Mutex<Option<Sender<LoopbackMessage>>>,
+    output_send: Sender<AudioBuffer>,
+    output_recv: Receiver<AudioBuffer>,
+    input_send: Sender<AudioBuffer>,
+    input_recv: Receiver<AudioBuffer>,
+    sample_rate: AtomicF32,
+}
+
+impl Default for Loopback {
+    fn default() -> Self {
+        let (output_send, output_recv) = crossbeam_channel::unbounded();
+        let (input_send, input_recv) = crossbeam_channel::unbounded();
+
+        let inner = LoopbackInner {
+            backend: Mutex::new(None),
+            output_send,
+            output_recv,
+            input_send,
+            input_recv,
+            sample_rate: AtomicF32::new(48000.),
+        };
+
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+}
+
+impl Loopback {
+    /// Advance the virtual clock by `number_of_frames`, rounded up to a whole number of render
+    /// quanta, and wait until the audio graph has been rendered
+    ///
+    /// Nothing is rendered when the `"loopback"` sink is not active or the context is suspended.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn advance(&self, number_of_frames: usize) {
+        let sender = match self.inner.backend.lock().unwrap().clone() {
+            Some(sender) => sender,
+            None => return,
+        };
+
+        let (notify, done) = crossbeam_channel::bounded(1);
+        let msg = LoopbackMessage::Advance {
+            frames: number_of_frames,
+            notify,
+        };
+        if sender.send(msg).is_ok() {
+            // the backend may be closed in the meantime, which drops the notifier
+            let _ = done.recv();
+        }
+    }
+
+    /// Returns the next rendered block, if any
+    ///
+    /// The blocks have the render quantum size of the context and two channels.
+    #[must_use]
+    pub fn try_recv(&self) -> Option<AudioBuffer> {
+        self.inner.output_recv.try_recv().ok()
+    }
+
+    /// Returns an iterator over the rendered blocks that have not been received yet
+    pub fn drain(&self) -> impl Iterator<Item = AudioBuffer> + '_ {
+        self.inner.output_recv.try_iter()
+    }
+
+    /// Returns a fake microphone stream that plays the buffers passed to
+    /// [`push_input`](Self::push_input)
+    ///
+    /// The stream plays silence when no input is pending. All streams returned by this method
+    /// share the same input, so each buffer is only played by one of them.
+    #[must_use]
+    pub fn microphone(&self) -> MediaStream {
+        let input = LoopbackInput {
+            loopback: Arc::clone(&self.inner),
+        };
+        let track = MediaStreamTrack::from_iter(input);
+        MediaStream::from_tracks(vec![track])
+    }
+
+    /// Queue a buffer to be played by the [`microphone`](Self::microphone) stream
+    ///
+    /// The buffer is resampled to the sample rate of the context when needed.
+    ///
+    /// When the context captures an input (see
+    /// [`AudioContextOptions::input`](crate::context::AudioContextOptions::input)), the buffers
+    /// are captured by the input source node instead. Their sample rate must match the context,
+    /// channels beyond the number of input channels are ignored and missing channels are silent.
+    /// The buffers are captured when the clock is advanced, so the input pushed before a call to
+    /// [`advance`](Self::advance) is rendered at the start of that call.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn push_input(&self, buffer: AudioBuffer) {
+        // the receiver is owned by this handle, so the channel is never disconnected
+        self.inner.input_send.send(buffer).unwrap();
+    }
+
+    fn set(&self, backend: Option<Sender<LoopbackMessage>>) {
+        *self.inner.backend.lock().unwrap() = backend;
+    }
+}
+
+/// Media stream of the fake microphone of the loopback sink
+struct LoopbackInput {
+    loopback: Arc<LoopbackInner>,
+}
+
+impl Iterator for LoopbackInput {
+    type Item = crate::FallibleBuffer;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = match self.loopback.input_recv.try_recv() {
+            Ok(buffer) => buffer,
+            Err(TryRecvError::Empty) => {
+                // no input pending, signal the live stream has no frames available
+                let sample_rate = self.loopback.sample_rate.load(Ordering::Relaxed);
+                AudioBuffer::from(vec![vec![]], sample_rate)
+            }
+            // the sender is owned by the `LoopbackInner` we hold
+            Err(TryRecvError::Disconnected) => unreachable!(),
+        };
+
+        Some(Ok(next))
+    }
+}
+
+struct Callback {
+    receiver: Receiver<LoopbackMessage>,
+    render_thread: RenderThread,
+    output: Sender<AudioBuffer>,
+    /// The fake microphone input, when captured in the same stream as the output
+    input: Option<(Receiver<AudioBuffer>, DuplexInput)>,
+    sample_rate: f32,
+    buffer_size: usize,
+    running: bool,
+}
+
+impl Callback {
+    fn run(mut self) {
+        let mut buffer = vec![0.; self.buffer_size * NUMBER_OF_CHANNELS];
+
+        loop {
+            match self.receiver.recv_timeout(POLL_INTERVAL) {
+                Ok(LoopbackMessage::Advance { frames, notify }) => {
+                    if self.running {
+                        self.capture_input();
+                        let count = (frames + self.buffer_size - 1) / self.buffer_size;
+                        for _ in 0..count {
+                            self.render(&mut buffer);
+                        }
+                    }
+                    let _ = notify.send(());
+                }
+                Ok(LoopbackMessage::Resume) => self.running = true,
+                Ok(LoopbackMessage::Suspend) => self.running = false,
+                Ok(LoopbackMessage::Close) | Err(RecvTimeoutError::Disconnected) => return,
+                Err(RecvTimeoutError::Timeout) => {
+                    // The clock is not advancing, but control messages should still take effect
+                    // as if the device is running
+                    if self.running {
+                        self.render_thread.handle_pending_control_messages();
+                    }
+                }
+            }
+        }
+    }
+
+    fn capture_input(&self) {
+        let Some((input, duplex_input)) = &self.input else {
+            return;
+        };
+
+        for buffer in input.try_iter() {
+            let number_of_channels = buffer.number_of_channels();
+            let channels: Vec<_> = (0..number_of_channels)
+                .map(|i| buffer.get_channel_data(i))
+                .collect();
+            let interleaved: Vec<f32> = (0..buffer.length())
+                .flat_map(|i| channels.iter().map(move |channel| channel[i]))
+                .collect();
+            duplex_input.push(&interleaved, number_of_channels);
+        }
+    }
+
+    fn render(&mut self, buffer: &mut [f32]) {
+        // There is no output latency, the buffer is played right away
+        self.render_thread
+            .render_timestamped(buffer, Instant::now());
+
+        let channels = (0..NUMBER_OF_CHANNELS)
+            .map(|i| {
+                buffer
+                    .iter()
+                    .skip(i)
+                    .step_by(NUMBER_OF_CHANNELS)
+                    .copied()
+                    .collect()
+            })
+            .collect();
+
+        // the receiver is owned by the `Loopback` handle, ignore when it is gone
+        let _ = self
+            .output
+            .send(AudioBuffer::from(channels, self.sample_rate));
+    }
+}
+
+/// Audio backend that delivers the rendered audio to the host application
+/// (`sink_id = "loopback"`)
+#[derive(Clone)]
+pub(crate) struct LoopbackBackend {
+    sender: Sender<LoopbackMessage>,
+    loopback: Loopback,
+    sample_rate: f32,
+    render_quantum_size: usize,
+}
+
+impl AudioBackendManager for LoopbackBackend {
+    /// Setup a new output stream (speakers)
+    fn build_output(options: AudioContextOptions, render_thread_init: RenderThreadInit) -> Self
+    where
+        Self: Sized,
+    {
+        let sample_rate = options.sample_rate.unwrap_or(48000.);
+
+        // There is no hardware, so the hardware size is the default size
+        let render_quantum_size =
+            super::render_quantum_size_for_category(options.render_size_hint, RENDER_QUANTUM_SIZE);
+
+        let RenderThreadInit {
+            state,
+            frames_played,
+            ctrl_msg_recv,
+            load_value_send,
+            event_send,
+            output_timestamp,
+            playout_stats,
+            loopback,
+            duplex_input,
+            ..
+        } = render_thread_init;
+
+        let mut render_thread = RenderThread::new(
+            sample_rate,
+            render_quantum_size,
+            NUMBER_OF_CHANNELS,
+            ctrl_msg_recv,
+            state,
+            frames_played,
+            event_send,
+        );
+        render_thread.set_load_value_sender(load_value_send);
+        render_thread.set_output_timestamp(output_timestamp);
+        render_thread.set_playout_stats(playout_stats);
+        render_thread.spawn_garbage_collector_thread();
+
+        // The fake microphone is captured without latency, in mono by default
+        let input = duplex_input.map(|duplex_input| {
+            let number_of_channels = duplex_input
+                .options()
+                .number_of_channels
+                .map_or(1, |n| n as usize);
+            duplex_input.start(number_of_channels, sample_rate, render_quantum_size, 0.);
+            render_thread.set_duplex_input(duplex_input.clone());
+            (loopback.inner.input_recv.clone(), duplex_input)
+        });
+
+        // Use a bounded channel, like the none backend
+        let (sender, receiver) = crossbeam_channel::bounded(32);
+
+        let callback = Callback {
+            render_thread,
+            receiver,
+            output: loopback.inner.output_send.clone(),
+            input,
+            sample_rate,
+            buffer_size: render_quantum_size,
+            running: true,
+        };
+
+        thread::spawn(move || callback.run());
+
+        loopback
+            .inner
+            .sample_rate
+            .store(sample_rate, Ordering::Relaxed);
+        loopback.set(Some(sender.clone()));
+
+        Self {
+            sender,
+            loopback,
+            sample_rate,
+            render_quantum_size,
+        }
+    }
+
+    /// Setup a new input stream (microphone capture)
+    fn build_input(
+        _options: AudioContextOptions,
+        _number_of_channels: Option<u32>,
+    ) -> Result<(Self, Receiver<AudioBuffer>), DomException>
+    where
+        Self: Sized,
+    {
+        unimplemented!()
+    }
+
+    /// Resume or start the stream
+    fn resume(&self) -> bool {
+        self.sender.send(LoopbackMessage::Resume).unwrap();
+        true
+    }
+
+    /// Suspend the stream
+    fn suspend(&self) -> bool {
+        self.sender.send(LoopbackMessage::Suspend).unwrap();
+        true
+    }
+
+    /// Close the stream, freeing all resources. It cannot be started again after closing.
+    fn close(&self) {
+        self.loopback.set(None);
+        self.sender.send(LoopbackMessage::Close).unwrap()
+    }
+
+    /// Sample rate of the stream
+    fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Number of channels of the stream
+    fn number_of_channels(&self) -> usize {
+        NUMBER_OF_CHANNELS
+    }
+
+    /// Number of sample frames the render thread renders per render quantum
+    fn render_quantum_size(&self) -> usize {
+        self.render_quantum_size
+    }
+
+    /// Output latency of the stream in seconds
+    ///
+    /// This is the difference between the time the backend acquires the data in the callback and
+    /// the listener can hear the sound.
+    fn output_latency(&self) -> f64 {
+        0.
+    }
+
+    /// The audio output device
+    fn sink_id(&self) -> &str {
+        "loopback"
+    }
+
+    fn enumerate_devices_sync() -> Vec<MediaDeviceInfo>
+    where
+        Self: Sized,
+    {
+        unimplemented!()
+    }
+}
@@ -1,8 +1,7 @@
 use std::error::Error;
 
-use crate::buffer::{AudioBuffer, AudioBufferOptions};
+use crate::buffer::AudioBuffer;
 use crate::io::AudioBackendManager;
-use crate::RENDER_QUANTUM_SIZE;
 
 use crossbeam_channel::{Receiver, Sender, TryRecvError};
 
@@ -44,16 +43,9 @@ impl Iterator for MicrophoneStream {
                 buffer
             }
             Err(TryRecvError::Empty) => {
-                // frame not received in time, emit silence
-                log::debug!("empty channel: input frame delayed");
-
-                let options = AudioBufferOptions {
-                    number_of_channels: self.number_of_channels,
-                    length: RENDER_QUANTUM_SIZE,
-                    sample_rate: self.sample_rate,
-                };
-
-                AudioBuffer::new(options)
+                // frame not received yet, signal the live stream has no frames available
+                let channels = vec![vec![]; self.number_of_channels];
+                AudioBuffer::from(channels, self.sample_rate)
             }
             Err(TryRecvError::Disconnected) => {
                 // MicrophoneRender has stopped, close stream
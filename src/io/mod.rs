@@ -1,21 +1,44 @@
 //! Audio input/output interfaces
 
 use std::sync::atomic::{AtomicU64, AtomicU8};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use crossbeam_channel::{Receiver, Sender};
 
 use crate::buffer::AudioBuffer;
-use crate::context::{AudioContextLatencyCategory, AudioContextOptions, AudioContextState};
+use crate::channel::{ChannelCounters, ControlSender, EventSender};
+use crate::context::{
+    AudioContextLatencyCategory, AudioContextOptions, AudioContextRenderSizeCategory,
+    AudioContextState, AudioTimestamp, FileSinkPacing,
+};
 use crate::events::EventDispatch;
 use crate::media_devices::MediaDeviceInfo;
 use crate::media_streams::{MediaStream, MediaStreamTrack};
 use crate::message::ControlMessage;
-use crate::{AudioRenderCapacityLoad, RENDER_QUANTUM_SIZE};
+use crate::{
+    AudioRenderCapacityLoad, DomException, PlayoutStats, MAX_RENDER_QUANTUM_SIZE,
+    RENDER_QUANTUM_SIZE,
+};
 
 mod none;
 pub(crate) use none::NoneBackend;
 
+mod pull;
+pub(crate) use pull::{PullBackend, PullRenderer};
+
+mod loopback;
+pub use loopback::Loopback;
+
+mod file;
+pub(crate) use file::{create_file_sink, parse_file_sink_id, FILE_SINK_PREFIX};
+
+mod duplex;
+pub(crate) use duplex::DuplexInput;
+
+#[cfg(any(feature = "cpal", test))]
+mod ring_buffer;
+
 #[cfg(feature = "cpal")]
 mod cpal;
 
@@ -29,9 +52,9 @@ mod microphone;
 pub(crate) struct ControlThreadInit {
     pub state: Arc<AtomicU8>,
     pub frames_played: Arc<AtomicU64>,
-    pub ctrl_msg_send: Sender<ControlMessage>,
+    pub ctrl_msg_send: ControlSender,
     pub load_value_recv: Receiver<AudioRenderCapacityLoad>,
-    pub event_send: Sender<EventDispatch>,
+    pub event_send: EventSender,
     pub event_recv: Receiver<EventDispatch>,
 }
 
@@ -41,21 +64,65 @@ pub(crate) struct RenderThreadInit {
     pub frames_played: Arc<AtomicU64>,
     pub ctrl_msg_recv: Receiver<ControlMessage>,
     pub load_value_send: Sender<AudioRenderCapacityLoad>,
-    pub event_send: Sender<EventDispatch>,
+    pub event_send: EventSender,
+    pub pull_renderer: PullRenderer,
+    pub loopback: Loopback,
+    pub file_sink_pacing: FileSinkPacing,
+    pub duplex_input: Option<DuplexInput>,
+    pub output_timestamp: Arc<Mutex<Option<AudioTimestamp>>>,
+    pub playout_stats: Arc<Mutex<PlayoutStats>>,
+    pub device_failure: DeviceFailure,
 }
 
-pub(crate) fn thread_init() -> (ControlThreadInit, RenderThreadInit) {
+/// Signal from the render thread that the output device has failed
+///
+/// The signal does not pass through the event loop, so the sink fallback of the `AudioContext`
+/// does not depend on the event dispatch.
+#[derive(Clone, Debug)]
+pub(crate) struct DeviceFailure {
+    send: Sender<()>,
+    recv: Receiver<()>,
+}
+
+impl Default for DeviceFailure {
+    fn default() -> Self {
+        let (send, recv) = crossbeam_channel::bounded(1);
+        Self { send, recv }
+    }
+}
+
+impl DeviceFailure {
+    /// Report the failure without blocking, failures that are not picked up yet are merged
+    pub fn notify(&self) {
+        let _ = self.send.try_send(());
+    }
+
+    /// Wait for a failure, returns `false` when none was reported within the timeout
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        self.recv.recv_timeout(timeout).is_ok()
+    }
+}
+
+pub(crate) fn thread_init(options: &AudioContextOptions) -> (ControlThreadInit, RenderThreadInit) {
     // Track audio context state - synced from render thread to control thread
     let state = Arc::new(AtomicU8::new(AudioContextState::Suspended as u8));
 
     // Track number of frames - synced from render thread to control thread
     let frames_played = Arc::new(AtomicU64::new(0));
 
+    // Number of messages sent and dropped - shared by the control and render thread
+    let counters = Arc::new(ChannelCounters::default());
+
     // Communication channel for ctrl msgs from the control thread to the render thread.
     // Use a bounded channel for real-time safety. A maximum of 256 control messages (add/remove
-    // node, settings, ..) will be handled per render quantum. The control thread will block when
-    // the capacity is reached.
+    // node, settings, ..) will be handled per render quantum. The overflow policy determines what
+    // happens on the control thread when the capacity is reached.
     let (ctrl_msg_send, ctrl_msg_recv) = crossbeam_channel::bounded(256);
+    let ctrl_msg_send = ControlSender::new(
+        ctrl_msg_send,
+        options.control_overflow_policy,
+        Arc::clone(&counters),
+    );
 
     // Communication channel for render load values.
     // A dedicated thread is consuming these messages so there is no need for buffering.
@@ -63,8 +130,17 @@ pub(crate) fn thread_init() -> (ControlThreadInit, RenderThreadInit) {
 
     // Communication channel for events from the render thread to the control thread.
     // Use a bounded channel for real-time safety. A maximum of 256 events (node ended, error, ..)
-    // will be sent per render quantum. Excess events are dropped when the capacity is reached.
-    let (event_send, event_recv) = crossbeam_channel::bounded(256);
+    // will be sent per render quantum. The overflow policy determines if excess events are dropped
+    // when the capacity is reached.
+    let (event_send, event_recv) =
+        crate::channel::event_channel(256, options.event_overflow_policy, counters);
+
+    // Track the output timestamp - synced from render thread to control thread. The render thread
+    // never blocks on this lock, it skips the update when the control thread holds it.
+    let output_timestamp = Arc::new(Mutex::new(None));
+
+    // Playout statistics - collected by the render thread, the same locking rules apply
+    let playout_stats = Arc::new(Mutex::new(PlayoutStats::default()));
 
     let control_thread_init = ControlThreadInit {
         state: Arc::clone(&state),
@@ -81,18 +157,46 @@ pub(crate) fn thread_init() -> (ControlThreadInit, RenderThreadInit) {
         ctrl_msg_recv,
         load_value_send,
         event_send,
+        pull_renderer: PullRenderer::default(),
+        loopback: Loopback::default(),
+        file_sink_pacing: options.file_sink_pacing,
+        duplex_input: options.input.clone().map(DuplexInput::new),
+        output_timestamp,
+        playout_stats,
+        device_failure: DeviceFailure::default(),
     };
 
     (control_thread_init, render_thread_init)
 }
 
-/// Set up an output stream (speakers) bases on the selected features (cubeb/cpal/none)
+/// Set up an output stream (speakers) bases on the selected features
+/// (cubeb/cpal/none/pull/loopback/file)
 pub(crate) fn build_output(
     options: AudioContextOptions,
     render_thread_init: RenderThreadInit,
 ) -> Box<dyn AudioBackendManager> {
+    let duplex_input = render_thread_init.duplex_input.clone();
+
     if options.sink_id == "none" {
         let backend = NoneBackend::build_output(options, render_thread_init);
+        capture_silence(duplex_input, &backend);
+        return Box::new(backend);
+    }
+
+    if options.sink_id == "pull" {
+        let backend = pull::PullBackend::build_output(options, render_thread_init);
+        capture_silence(duplex_input, &backend);
+        return Box::new(backend);
+    }
+
+    if options.sink_id == "loopback" {
+        let backend = loopback::LoopbackBackend::build_output(options, render_thread_init);
+        return Box::new(backend);
+    }
+
+    if parse_file_sink_id(&options.sink_id).is_some() {
+        let backend = file::FileBackend::build_output(options, render_thread_init);
+        capture_silence(duplex_input, &backend);
         return Box::new(backend);
     }
 
@@ -112,11 +216,30 @@ pub(crate) fn build_output(
     }
 }
 
+/// The sinks without an input device capture silence, see [`AudioContextOptions::input`]
+fn capture_silence(duplex_input: Option<DuplexInput>, backend: &dyn AudioBackendManager) {
+    if let Some(duplex_input) = duplex_input {
+        let number_of_channels = duplex_input
+            .options()
+            .number_of_channels
+            .map_or(1, |n| n as usize);
+        duplex_input.start(
+            number_of_channels,
+            backend.sample_rate(),
+            backend.render_quantum_size(),
+            0.,
+        );
+    }
+}
+
 /// Set up an input stream (microphone) bases on the selected features (cubeb/cpal/none)
+///
+/// The `sink_id` of the options must be `""` or a valid input device id, constraints that cannot
+/// be met by the device result in an `OverconstrainedError`.
 pub(crate) fn build_input(
     options: AudioContextOptions,
     number_of_channels: Option<u32>,
-) -> MediaStream {
+) -> Result<MediaStream, DomException> {
     #[cfg(all(not(feature = "cubeb"), not(feature = "cpal")))]
     {
         panic!("No audio backend available, enable the 'cpal' or 'cubeb' feature")
@@ -127,18 +250,18 @@ pub(crate) fn build_input(
         let (backend, receiver) = {
             #[cfg(feature = "cubeb")]
             {
-                cubeb::CubebBackend::build_input(options, number_of_channels)
+                cubeb::CubebBackend::build_input(options, number_of_channels)?
             }
 
             #[cfg(all(not(feature = "cubeb"), feature = "cpal"))]
             {
-                cpal::CpalBackend::build_input(options, number_of_channels)
+                cpal::CpalBackend::build_input(options, number_of_channels)?
             }
         };
 
         let media_iter = microphone::MicrophoneStream::new(receiver, Box::new(backend));
         let track = MediaStreamTrack::from_iter(media_iter);
-        MediaStream::from_tracks(vec![track])
+        Ok(MediaStream::from_tracks(vec![track]))
     }
 }
 
@@ -155,10 +278,13 @@ pub(crate) trait AudioBackendManager: Send + Sync + 'static {
         Self: Sized;
 
     /// Setup a new input stream (microphone capture)
+    ///
+    /// Returns a `NotFoundError` when the input device is not available and an
+    /// `OverconstrainedError` when the requested sample rate or channel count is not supported.
     fn build_input(
         options: AudioContextOptions,
         number_of_channels: Option<u32>,
-    ) -> (Self, Receiver<AudioBuffer>)
+    ) -> Result<(Self, Receiver<AudioBuffer>), DomException>
     where
         Self: Sized;
 
@@ -177,6 +303,9 @@ pub(crate) trait AudioBackendManager: Send + Sync + 'static {
     /// Number of channels of the stream
     fn number_of_channels(&self) -> usize;
 
+    /// Number of sample frames the render thread renders per render quantum
+    fn render_quantum_size(&self) -> usize;
+
     /// Output latency of the stream in seconds
     ///
     /// This is the difference between the time the backend acquires the data in the callback and
@@ -220,6 +349,26 @@ fn buffer_size_for_latency_category(
     }
 }
 
+/// Calculate the render quantum size in frames for a given render size category
+///
+/// The `hardware_buffer_size` is the (preferred) buffer size of the audio backend, it is used
+/// for the `Hardware` category.
+fn render_quantum_size_for_category(
+    render_size_cat: AudioContextRenderSizeCategory,
+    hardware_buffer_size: usize,
+) -> usize {
+    match render_size_cat {
+        AudioContextRenderSizeCategory::Default => RENDER_QUANTUM_SIZE,
+        AudioContextRenderSizeCategory::Hardware => {
+            hardware_buffer_size.clamp(1, MAX_RENDER_QUANTUM_SIZE)
+        }
+        AudioContextRenderSizeCategory::Custom(size) => {
+            crate::assert_valid_render_quantum_size(size);
+            size
+        }
+    }
+}
+
 pub(crate) fn enumerate_devices_sync() -> Vec<MediaDeviceInfo> {
     #[cfg(feature = "cubeb")]
     {
@@ -234,3 +383,27 @@ pub(crate) fn enumerate_devices_sync() -> Vec<MediaDeviceInfo> {
     #[cfg(all(not(feature = "cubeb"), not(feature = "cpal")))]
     panic!("No audio backend available, enable the 'cpal' or 'cubeb' feature")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_quantum_size_for_category() {
+        let size = render_quantum_size_for_category(AudioContextRenderSizeCategory::Default, 512);
+        assert_eq!(size, RENDER_QUANTUM_SIZE);
+
+        let size = render_quantum_size_for_category(AudioContextRenderSizeCategory::Hardware, 512);
+        assert_eq!(size, 512);
+
+        let size =
+            render_quantum_size_for_category(AudioContextRenderSizeCategory::Custom(64), 512);
+        assert_eq!(size, 64);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_render_quantum_size_for_invalid_category() {
+        render_quantum_size_for_category(AudioContextRenderSizeCategory::Custom(0), 512);
+    }
+}
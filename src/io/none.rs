@@ -7,7 +7,7 @@ use crate::buffer::AudioBuffer;
 use crate::context::AudioContextOptions;
 use crate::media_devices::MediaDeviceInfo;
 use crate::render::RenderThread;
-use crate::{MAX_CHANNELS, RENDER_QUANTUM_SIZE};
+use crate::{DomException, MAX_CHANNELS, RENDER_QUANTUM_SIZE};
 
 use crossbeam_channel::{Receiver, Sender};
 
@@ -21,6 +21,7 @@ enum NoneBackendMessage {
 pub(crate) struct NoneBackend {
     sender: Sender<NoneBackendMessage>,
     sample_rate: f32,
+    render_quantum_size: usize,
 }
 
 impl NoneBackend {
@@ -28,6 +29,7 @@ impl NoneBackend {
     pub(crate) fn void() -> Self {
         Self {
             sample_rate: 0.,
+            render_quantum_size: RENDER_QUANTUM_SIZE,
             sender: crossbeam_channel::bounded(0).0,
         }
     }
@@ -37,12 +39,13 @@ struct Callback {
     receiver: Receiver<NoneBackendMessage>,
     render_thread: RenderThread,
     sample_rate: f32,
+    buffer_size: usize,
     running: bool,
 }
 
 impl Callback {
     fn run(mut self) {
-        let buffer_size = RENDER_QUANTUM_SIZE; // TODO Latency Category
+        let buffer_size = self.buffer_size;
         let mut buffer = vec![0.; buffer_size * MAX_CHANNELS];
         let interval = Duration::from_secs_f32(buffer_size as f32 / self.sample_rate);
 
@@ -64,7 +67,10 @@ impl Callback {
             }
 
             if self.running {
-                self.render_thread.render(&mut buffer[..]);
+                // There is no output latency, the buffer is played at the deadline. Using the
+                // deadline instead of the current time makes the output timestamps deterministic.
+                self.render_thread
+                    .render_timestamped(&mut buffer[..], deadline);
             }
 
             deadline = deadline.checked_add(interval).unwrap();
@@ -80,16 +86,27 @@ impl AudioBackendManager for NoneBackend {
     {
         let sample_rate = options.sample_rate.unwrap_or(48000.);
 
+        // There is no hardware, so emulate the buffer size of a sound card for the given latency
+        // category. Render in chunks of the render quantum size to prevent re-buffering.
+        let hardware_buffer_size =
+            super::buffer_size_for_latency_category(options.latency_hint, sample_rate);
+        let render_quantum_size =
+            super::render_quantum_size_for_category(options.render_size_hint, hardware_buffer_size);
+
         let RenderThreadInit {
             state,
             frames_played,
             ctrl_msg_recv,
             load_value_send,
             event_send,
+            output_timestamp,
+            playout_stats,
+            ..
         } = render_thread_init;
 
         let mut render_thread = RenderThread::new(
             sample_rate,
+            render_quantum_size,
             MAX_CHANNELS,
             ctrl_msg_recv,
             state,
@@ -97,6 +114,8 @@ impl AudioBackendManager for NoneBackend {
             event_send,
         );
         render_thread.set_load_value_sender(load_value_send);
+        render_thread.set_output_timestamp(output_timestamp);
+        render_thread.set_playout_stats(playout_stats);
         render_thread.spawn_garbage_collector_thread();
 
         // Use a bounded channel for real-time safety. A maximum of 32 control messages (resume,
@@ -104,11 +123,11 @@ impl AudioBackendManager for NoneBackend {
         // capacity is reached.
         let (sender, receiver) = crossbeam_channel::bounded(32);
 
-        // todo: pass buffer size and sample rate
         let callback = Callback {
             render_thread,
             receiver,
             sample_rate,
+            buffer_size: render_quantum_size,
             running: true,
         };
 
@@ -117,6 +136,7 @@ impl AudioBackendManager for NoneBackend {
         Self {
             sender,
             sample_rate,
+            render_quantum_size,
         }
     }
 
@@ -124,7 +144,7 @@ impl AudioBackendManager for NoneBackend {
     fn build_input(
         _options: AudioContextOptions,
         _number_of_channels: Option<u32>,
-    ) -> (Self, Receiver<AudioBuffer>)
+    ) -> Result<(Self, Receiver<AudioBuffer>), DomException>
     where
         Self: Sized,
     {
@@ -158,6 +178,11 @@ impl AudioBackendManager for NoneBackend {
         MAX_CHANNELS
     }
 
+    /// Number of sample frames the render thread renders per render quantum
+    fn render_quantum_size(&self) -> usize {
+        self.render_quantum_size
+    }
+
     /// Output latency of the stream in seconds
     ///
     /// This is the difference between the time the backend acquires the data in the callback and
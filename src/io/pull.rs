@@ -0,0 +1,200 @@
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use super::{AudioBackendManager, RenderThreadInit};
+
+use crate::buffer::AudioBuffer;
+use crate::context::AudioContextOptions;
+use crate::media_devices::MediaDeviceInfo;
+use crate::render::RenderThread;
+use crate::{DomException, MAX_CHANNELS};
+
+use crossbeam_channel::Receiver;
+
+/// Handle to the render thread of the pull backend
+///
+/// The render thread is not driven by an audio backend but by the host application, which calls
+/// [`PullRenderer::render_into`] from its own audio callback.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PullRenderer {
+    render_thread: Arc<Mutex<Option<RenderThread>>>,
+}
+
+impl PullRenderer {
+    /// Render the audio graph into the interleaved `output` buffer
+    ///
+    /// The buffer is filled with silence when no pull backend is active, or when the render
+    /// thread is temporarily unavailable because the backend is being set up or torn down.
+    ///
+    /// An invalid number of channels or buffer length is logged and yields silence, as this runs
+    /// inside the audio callback of the host.
+    pub(crate) fn render_into(&self, output: &mut [f32], number_of_channels: usize) {
+        if number_of_channels == 0 || number_of_channels > MAX_CHANNELS {
+            log::warn!(
+                "NotSupportedError - Invalid number of channels: {:?} is outside range [1, {:?}]",
+                number_of_channels,
+                MAX_CHANNELS
+            );
+            output.fill(0.);
+            return;
+        }
+        if output.len() % number_of_channels != 0 {
+            log::warn!(
+                "IndexSizeError - output buffer length must be a multiple of the number of channels"
+            );
+            output.fill(0.);
+            return;
+        }
+
+        // Never block the audio callback of the host
+        let mut guard = match self.render_thread.try_lock() {
+            Ok(guard) => guard,
+            Err(_) => {
+                output.fill(0.);
+                return;
+            }
+        };
+
+        match guard.as_mut() {
+            Some(render_thread) => {
+                render_thread.set_number_of_channels(number_of_channels);
+                // The output latency of the host is unknown
+                render_thread.render_timestamped(output, Instant::now());
+            }
+            None => output.fill(0.),
+        }
+    }
+
+    /// Returns `true` if both handles refer to the same render thread slot
+    pub(crate) fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.render_thread, &other.render_thread)
+    }
+
+    fn set(&self, render_thread: Option<RenderThread>) {
+        let prev = std::mem::replace(&mut *self.render_thread.lock().unwrap(), render_thread);
+        // drop the previous render thread outside of the lock
+        drop(prev);
+    }
+}
+
+/// Audio backend that is driven by the host application (`sink_id = "pull"`)
+#[derive(Clone)]
+pub(crate) struct PullBackend {
+    renderer: PullRenderer,
+    sample_rate: f32,
+    render_quantum_size: usize,
+}
+
+impl AudioBackendManager for PullBackend {
+    /// Setup a new output stream (speakers)
+    fn build_output(options: AudioContextOptions, render_thread_init: RenderThreadInit) -> Self
+    where
+        Self: Sized,
+    {
+        let sample_rate = options.sample_rate.unwrap_or(48000.);
+
+        // The buffer size of the host is unknown, so the hardware size is the default size
+        let render_quantum_size = super::render_quantum_size_for_category(
+            options.render_size_hint,
+            crate::RENDER_QUANTUM_SIZE,
+        );
+
+        let RenderThreadInit {
+            state,
+            frames_played,
+            ctrl_msg_recv,
+            load_value_send,
+            event_send,
+            pull_renderer,
+            output_timestamp,
+            playout_stats,
+            ..
+        } = render_thread_init;
+
+        let mut render_thread = RenderThread::new(
+            sample_rate,
+            render_quantum_size,
+            MAX_CHANNELS,
+            ctrl_msg_recv,
+            state,
+            frames_played,
+            event_send,
+        );
+        render_thread.set_load_value_sender(load_value_send);
+        render_thread.set_output_timestamp(output_timestamp);
+        render_thread.set_playout_stats(playout_stats);
+        render_thread.spawn_garbage_collector_thread();
+
+        pull_renderer.set(Some(render_thread));
+
+        Self {
+            renderer: pull_renderer,
+            sample_rate,
+            render_quantum_size,
+        }
+    }
+
+    /// Setup a new input stream (microphone capture)
+    fn build_input(
+        _options: AudioContextOptions,
+        _number_of_channels: Option<u32>,
+    ) -> Result<(Self, Receiver<AudioBuffer>), DomException>
+    where
+        Self: Sized,
+    {
+        unimplemented!()
+    }
+
+    /// Resume or start the stream
+    fn resume(&self) -> bool {
+        // The host application drives the stream
+        true
+    }
+
+    /// Suspend the stream
+    fn suspend(&self) -> bool {
+        // The host application drives the stream
+        true
+    }
+
+    /// Close the stream, freeing all resources. It cannot be started again after closing.
+    fn close(&self) {
+        self.renderer.set(None);
+    }
+
+    /// Sample rate of the stream
+    fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Number of channels of the stream
+    fn number_of_channels(&self) -> usize {
+        MAX_CHANNELS
+    }
+
+    /// Number of sample frames the render thread renders per render quantum
+    fn render_quantum_size(&self) -> usize {
+        self.render_quantum_size
+    }
+
+    /// Output latency of the stream in seconds
+    ///
+    /// This is the difference between the time the backend acquires the data in the callback and
+    /// the listener can hear the sound.
+    fn output_latency(&self) -> f64 {
+        // unknown, managed by the host application
+        0.
+    }
+
+    /// The audio output device
+    fn sink_id(&self) -> &str {
+        "pull"
+    }
+
+    fn enumerate_devices_sync() -> Vec<MediaDeviceInfo>
+    where
+        Self: Sized,
+    {
+        unimplemented!()
+    }
+}
@@ -0,0 +1,115 @@
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+/// Lock-free single producer, single consumer queue of audio samples
+///
+/// All memory is allocated up front, so samples can be handed from one audio callback to another
+/// without allocating or locking. The samples are stored as the bits of atomics, which makes the
+/// queue safe to share without `unsafe` code.
+#[derive(Debug)]
+pub(crate) struct SampleRingBuffer {
+    samples: Box<[AtomicU32]>,
+    /// Total number of samples read, only updated by the consumer
+    read: AtomicUsize,
+    /// Total number of samples written, only updated by the producer
+    written: AtomicUsize,
+}
+
+impl SampleRingBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: (0..capacity).map(|_| AtomicU32::new(0)).collect(),
+            read: AtomicUsize::new(0),
+            written: AtomicUsize::new(0),
+        }
+    }
+
+    /// Append all `samples`, or none when they do not fit in the free space
+    ///
+    /// Returns whether the samples were written. Must only be called by the producer.
+    pub fn push<I: ExactSizeIterator<Item = f32>>(&self, samples: I) -> bool {
+        let capacity = self.samples.len();
+        let written = self.written.load(Ordering::Relaxed);
+        let read = self.read.load(Ordering::Acquire);
+        if capacity - written.wrapping_sub(read) < samples.len() {
+            return false;
+        }
+
+        let mut position = written;
+        for sample in samples {
+            self.samples[position % capacity].store(sample.to_bits(), Ordering::Relaxed);
+            position = position.wrapping_add(1);
+        }
+        self.written.store(position, Ordering::Release);
+
+        true
+    }
+
+    /// Move the oldest samples into `output`, returns the number of samples moved
+    ///
+    /// Must only be called by the consumer.
+    pub fn pop_into(&self, output: &mut [f32]) -> usize {
+        let capacity = self.samples.len();
+        let read = self.read.load(Ordering::Relaxed);
+        let written = self.written.load(Ordering::Acquire);
+        let count = written.wrapping_sub(read).min(output.len());
+
+        output[..count].iter_mut().enumerate().for_each(|(i, o)| {
+            let position = read.wrapping_add(i) % capacity;
+            *o = f32::from_bits(self.samples[position].load(Ordering::Relaxed));
+        });
+        self.read.store(read.wrapping_add(count), Ordering::Release);
+
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_pop() {
+        let ring = SampleRingBuffer::new(4);
+        let mut output = [0.; 4];
+
+        assert!(ring.push([1., 2., 3.].into_iter()));
+        // all or nothing
+        assert!(!ring.push([4., 5.].into_iter()));
+        assert_eq!(ring.pop_into(&mut output[..2]), 2);
+        assert_eq!(output[..2], [1., 2.]);
+
+        // wrap around
+        assert!(ring.push([4., 5., 6.].into_iter()));
+        assert_eq!(ring.pop_into(&mut output), 4);
+        assert_eq!(output, [3., 4., 5., 6.]);
+        assert_eq!(ring.pop_into(&mut output), 0);
+    }
+
+    #[test]
+    fn test_threads() {
+        let ring = SampleRingBuffer::new(64);
+        let total = 10_000;
+
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                let mut next = 0;
+                while next < total {
+                    let chunk = (next..(next + 3).min(total)).map(|v| v as f32);
+                    if ring.push(chunk) {
+                        next = (next + 3).min(total);
+                    }
+                }
+            });
+
+            let mut expected = 0;
+            let mut output = [0.; 16];
+            while expected < total {
+                let count = ring.pop_into(&mut output);
+                for &v in &output[..count] {
+                    assert_eq!(v, expected as f32);
+                    expected += 1;
+                }
+            }
+        });
+    }
+}
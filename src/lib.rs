@@ -13,6 +13,9 @@ use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 /// see. <https://webaudio.github.io/web-audio-api/#render-quantum>
 pub(crate) const RENDER_QUANTUM_SIZE: usize = 128;
 
+/// Maximum render quantum size that can be requested with a custom render size hint
+pub(crate) const MAX_RENDER_QUANTUM_SIZE: usize = 16384;
+
 /// Maximum number of channels for audio processing
 pub const MAX_CHANNELS: usize = 32;
 
@@ -22,8 +25,18 @@ pub use buffer::*;
 mod capacity;
 pub use capacity::*;
 
+mod channel;
+pub use channel::*;
+
 pub mod context;
 
+mod diagnostics;
+pub use diagnostics::*;
+
+mod error;
+pub(crate) use error::expect_valid;
+pub use error::DomException;
+
 pub mod media_devices;
 pub mod media_recorder;
 pub mod media_streams;
@@ -42,6 +55,9 @@ pub use param::*;
 mod periodic_wave;
 pub use periodic_wave::*;
 
+mod profiler;
+pub use profiler::*;
+
 mod render;
 
 mod spatial;
@@ -54,6 +70,9 @@ mod message;
 
 mod decoding;
 
+mod encoding;
+pub use encoding::AudioFileFormat;
+
 mod media_element;
 pub use media_element::MediaElement;
 
@@ -119,7 +138,7 @@ impl AtomicF64 {
     }
 }
 
-/// Assert that the given sample rate is valid.
+/// Check that the given sample rate is valid.
 ///
 /// Note that in practice sample rates should stand between 8000Hz (lower bound for
 /// voice based applications, e.g. see phone bandwidth) and 96000Hz (for very high
@@ -129,27 +148,57 @@ impl AtomicF64 {
 /// - see <https://webaudio.github.io/web-audio-api/#dom-baseaudiocontext-samplerate>
 /// > An implementation MUST support sample rates in at least the range 8000 to 96000.
 ///
+/// # Errors
+///
+/// Returns a `NotSupportedError` if the given sample rate is lower than 2000 or greater than
+/// 384000
+pub(crate) fn check_valid_sample_rate(sample_rate: f32) -> Result<(), DomException> {
+    // Arbitrary cutoffs defined as:
+    // min_sample_rate = min_required_in_spec / 4
+    // max_sample_rate = max_required_in_spec * 4
+    let min_sample_rate = 2_000.;
+    let max_sample_rate = 384_000.;
+
+    if sample_rate >= min_sample_rate && sample_rate <= max_sample_rate {
+        Ok(())
+    } else {
+        Err(DomException::NotSupportedError(format!(
+            "Invalid sample rate: {:?}, should be in the range [{:?}, {:?}]",
+            sample_rate, min_sample_rate, max_sample_rate,
+        )))
+    }
+}
+
+/// Assert that the given sample rate is valid, see [`check_valid_sample_rate`]
+///
 /// # Panics
 ///
 /// This function will panic if:
-/// - the given sample rate is lower than 4000 or greater than 192000
+/// - the given sample rate is lower than 2000 or greater than 384000
 ///
 #[track_caller]
 #[inline(always)]
 pub(crate) fn assert_valid_sample_rate(sample_rate: f32) {
-    // Arbitrary cutoffs defined as:
-    // min_sample_rate = min_required_in_spec / 4
-    // max_sample_rate = max_required_in_spec * 4
-    let min_sample_rate = 2_000.;
-    let max_sample_rate = 384_000.;
+    expect_valid(check_valid_sample_rate(sample_rate));
+}
 
-    assert!(
-        sample_rate >= min_sample_rate && sample_rate <= max_sample_rate,
-        "NotSupportedError - Invalid sample rate: {:?}, should be in the range [{:?}, {:?}]",
-        sample_rate,
-        min_sample_rate,
-        max_sample_rate,
-    );
+/// Check that the given number of channels is valid.
+///
+/// # Errors
+///
+/// Returns a `NotSupportedError` if the given number of channels is outside the [1, 32] range,
+/// 32 being defined by the MAX_CHANNELS constant.
+pub(crate) fn check_valid_number_of_channels(
+    number_of_channels: usize,
+) -> Result<(), DomException> {
+    if number_of_channels > 0 && number_of_channels <= MAX_CHANNELS {
+        Ok(())
+    } else {
+        Err(DomException::NotSupportedError(format!(
+            "Invalid number of channels: {:?} is outside range [1, {:?}]",
+            number_of_channels, MAX_CHANNELS
+        )))
+    }
 }
 
 /// Assert that the given number of channels is valid.
@@ -158,53 +207,106 @@ pub(crate) fn assert_valid_sample_rate(sample_rate: f32) {
 ///
 /// This function will panic if:
 /// - the given number of channels is outside the [1, 32] range,
-/// 32 being defined by the MAX_CHANNELS constant.
+///   32 being defined by the MAX_CHANNELS constant.
 ///
 #[track_caller]
 #[inline(always)]
 pub(crate) fn assert_valid_number_of_channels(number_of_channels: usize) {
-    assert!(
-        number_of_channels > 0 && number_of_channels <= MAX_CHANNELS,
-        "NotSupportedError - Invalid number of channels: {:?} is outside range [1, {:?}]",
-        number_of_channels,
-        MAX_CHANNELS
-    );
+    expect_valid(check_valid_number_of_channels(number_of_channels));
 }
 
-/// Assert that the given channel number is valid according to the number of channels
+/// Check that the given channel number is valid according to the number of channels
 /// of an Audio asset (e.g. [`AudioBuffer`]).
 ///
-/// # Panics
+/// # Errors
 ///
-/// This function will panic if:
-/// - the given channel number is greater than or equal to the given number of channels.
+/// Returns an `IndexSizeError` if the given channel number is greater than or equal to the given
+/// number of channels.
+pub(crate) fn check_valid_channel_number(
+    channel_number: usize,
+    number_of_channels: usize,
+) -> Result<(), DomException> {
+    if channel_number < number_of_channels {
+        Ok(())
+    } else {
+        Err(DomException::IndexSizeError(format!(
+            "Invalid channel number {:?} (number of channels: {:?})",
+            channel_number, number_of_channels
+        )))
+    }
+}
+
+/// Check that the given value number is a valid buffer length, i.e. greater than zero
 ///
-#[track_caller]
-#[inline(always)]
-pub(crate) fn assert_valid_channel_number(channel_number: usize, number_of_channels: usize) {
-    assert!(
-        channel_number < number_of_channels,
-        "IndexSizeError - Invalid channel number {:?} (number of channels: {:?})",
-        channel_number,
-        number_of_channels
-    );
+/// # Errors
+///
+/// Returns a `NotSupportedError` if the given value is zero
+pub(crate) fn check_valid_buffer_length(length: usize) -> Result<(), DomException> {
+    if length > 0 {
+        Ok(())
+    } else {
+        Err(DomException::NotSupportedError(format!(
+            "Invalid length: {:?} is less than or equal to minimum bound (0)",
+            length,
+        )))
+    }
+}
+
+/// Check that the given render quantum size is valid, i.e. strictly positive and
+/// lower than or equal to `MAX_RENDER_QUANTUM_SIZE`
+///
+/// # Errors
+///
+/// Returns a `NotSupportedError` if the given render quantum size is zero or greater than 16384
+pub(crate) fn check_valid_render_quantum_size(
+    render_quantum_size: usize,
+) -> Result<(), DomException> {
+    if render_quantum_size > 0 && render_quantum_size <= MAX_RENDER_QUANTUM_SIZE {
+        Ok(())
+    } else {
+        Err(DomException::NotSupportedError(format!(
+            "Invalid render quantum size: {:?} is outside range [1, {:?}]",
+            render_quantum_size, MAX_RENDER_QUANTUM_SIZE
+        )))
+    }
 }
 
-/// Assert that the given value number is a valid buffer length, i.e. greater than zero
+/// Assert that the given render quantum size is valid, i.e. strictly positive and
+/// lower than or equal to `MAX_RENDER_QUANTUM_SIZE`
 ///
 /// # Panics
 ///
 /// This function will panic if:
-/// - the given value is not lower than or equal to zero
+/// - the given render quantum size is zero or greater than 16384
 ///
 #[track_caller]
 #[inline(always)]
-pub(crate) fn assert_valid_buffer_length(length: usize) {
-    assert!(
-        length > 0,
-        "NotSupportedError - Invalid length: {:?} is less than or equal to minimum bound (0)",
-        length,
-    );
+pub(crate) fn assert_valid_render_quantum_size(render_quantum_size: usize) {
+    expect_valid(check_valid_render_quantum_size(render_quantum_size));
+}
+
+/// Check that the given value number is a valid time information, i.e. greater
+/// than or equal to zero and finite.
+///
+/// # Errors
+///
+/// Returns a `TypeError` if the given value is not finite, a `RangeError` if it is lower than
+/// zero
+pub(crate) fn check_valid_time_value(value: f64) -> Result<(), DomException> {
+    if !value.is_finite() {
+        return Err(DomException::TypeError(String::from(
+            "The provided time value is non-finite.",
+        )));
+    }
+
+    if value < 0. {
+        return Err(DomException::RangeError(format!(
+            "The provided time value ({:?}) cannot be negative",
+            value
+        )));
+    }
+
+    Ok(())
 }
 
 /// Assert that the given value number is a valid time information, i.e. greater
@@ -218,18 +320,13 @@ pub(crate) fn assert_valid_buffer_length(length: usize) {
 #[track_caller]
 #[inline(always)]
 pub(crate) fn assert_valid_time_value(value: f64) {
-    assert!(
-        value.is_finite(),
-        "TypeError - The provided time value is non-finite.",
-    );
-
-    assert!(
-        value >= 0.,
-        "RangeError - The provided time value ({:?}) cannot be negative",
-        value
-    );
+    expect_valid(check_valid_time_value(value));
 }
 
+/// Stream of audio buffers
+///
+/// A live stream yields an empty buffer when no frames are available yet, as opposed to `None`
+/// which ends the stream.
 pub(crate) trait AudioBufferIter: Iterator<Item = FallibleBuffer> + Send + 'static {}
 
 impl<M: Iterator<Item = FallibleBuffer> + Send + 'static> AudioBufferIter for M {}
@@ -290,6 +387,25 @@ mod tests {
         assert_valid_number_of_channels(32);
     }
 
+    #[test]
+    #[should_panic]
+    fn test_invalid_render_quantum_size_min() {
+        assert_valid_render_quantum_size(0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_render_quantum_size_max() {
+        assert_valid_render_quantum_size(16385);
+    }
+
+    #[test]
+    fn test_valid_render_quantum_size() {
+        assert_valid_render_quantum_size(1);
+        assert_valid_render_quantum_size(128);
+        assert_valid_render_quantum_size(16384);
+    }
+
     #[test]
     #[should_panic]
     fn test_invalid_time_value_non_finite() {
@@ -0,0 +1,303 @@
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crossbeam_channel::{RecvTimeoutError, Sender};
+
+use super::{enumerate_devices_sync, MediaDeviceInfo};
+use crate::Event;
+
+type DeviceChangeCallback = Box<dyn FnMut(DeviceChangeEvent) + Send + 'static>;
+
+/// The `devicechange` handler, taken out of the slot while it runs
+#[derive(Default)]
+struct CallbackSlot {
+    callback: Option<DeviceChangeCallback>,
+    /// Incremented when the handler is set or cleared, to detect changes while it runs
+    version: u64,
+}
+
+/// Default interval between two queries of the device list
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Interface for the `devicechange` event, containing the added and removed devices
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct DeviceChangeEvent {
+    /// Devices that were not present in the previous device list
+    pub added: Vec<MediaDeviceInfo>,
+    /// Devices of the previous device list that are no longer present
+    pub removed: Vec<MediaDeviceInfo>,
+    /// Inherits from this base Event
+    pub event: Event,
+}
+
+/// Watch the available media devices for changes, e.g. a headset being plugged or unplugged
+///
+/// The device list is queried periodically on a dedicated thread and compared to the previous
+/// list by `device_id`. The `devicechange` callback runs on that thread. Watching stops when the
+/// monitor is dropped.
+///
+/// ```no_run
+/// use web_audio_api::media_devices::DeviceChangeMonitor;
+///
+/// let monitor = DeviceChangeMonitor::new();
+/// monitor.set_ondevicechange(|event| {
+///     for device in &event.added {
+///         println!("connected: {}", device.label());
+///     }
+///     for device in &event.removed {
+///         println!("disconnected: {}", device.label());
+///     }
+/// });
+/// ```
+pub struct DeviceChangeMonitor {
+    callback: Arc<Mutex<CallbackSlot>>,
+    // dropping the sender stops the polling thread
+    _stop: Sender<()>,
+}
+
+impl std::fmt::Debug for DeviceChangeMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeviceChangeMonitor")
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for DeviceChangeMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeviceChangeMonitor {
+    /// Start watching the devices of the audio backend, see [`enumerate_devices_sync`]
+    pub fn new() -> Self {
+        Self::with_provider(enumerate_devices_sync, DEFAULT_POLL_INTERVAL)
+    }
+
+    /// Start watching the device list returned by `provider`, queried every `interval`
+    ///
+    /// This allows to inject another source of devices than the audio backend, e.g. for testing.
+    /// The initial device list is queried before this function returns, subsequent changes are
+    /// reported to the `devicechange` callback.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the polling thread cannot be started.
+    pub fn with_provider<P>(mut provider: P, interval: Duration) -> Self
+    where
+        P: FnMut() -> Vec<MediaDeviceInfo> + Send + 'static,
+    {
+        let callback: Arc<Mutex<CallbackSlot>> = Arc::default();
+        let (stop_send, stop_recv) = crossbeam_channel::bounded::<()>(0);
+        let (ready_send, ready_recv) = crossbeam_channel::bounded(0);
+
+        let callback_clone = Arc::clone(&callback);
+        std::thread::spawn(move || {
+            // the device handles are not `Send`, so the device list lives on this thread
+            let mut devices = provider();
+            let _ = ready_send.send(());
+
+            while let Err(RecvTimeoutError::Timeout) = stop_recv.recv_timeout(interval) {
+                let current = provider();
+                let (added, removed) = diff_devices(&mut devices, current);
+
+                if added.is_empty() && removed.is_empty() {
+                    continue;
+                }
+
+                // Run the handler without holding the lock, so it can replace itself
+                let mut slot = callback_clone.lock().unwrap();
+                let (Some(mut f), version) = (slot.callback.take(), slot.version) else {
+                    continue;
+                };
+                drop(slot);
+
+                let event = DeviceChangeEvent {
+                    added,
+                    removed,
+                    event: Event {
+                        type_: "devicechange",
+                    },
+                };
+                if std::panic::catch_unwind(AssertUnwindSafe(|| (f)(event))).is_err() {
+                    log::error!("devicechange handler panicked, it is removed");
+                    continue;
+                }
+
+                // put the handler back, unless it was set or cleared while running
+                let mut slot = callback_clone.lock().unwrap();
+                if slot.version == version {
+                    slot.callback = Some(f);
+                }
+            }
+
+            log::debug!("Device change monitor has terminated");
+        });
+
+        ready_recv
+            .recv()
+            .expect("InvalidStateError - device change monitor failed to start");
+
+        Self {
+            callback,
+            _stop: stop_send,
+        }
+    }
+
+    /// Register callback to run when the list of available devices changes
+    ///
+    /// Only a single event handler is active at any time. Calling this method multiple times will
+    /// override the previous event handler. The handler may replace or clear itself, it is
+    /// removed when it panics.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn set_ondevicechange<F: FnMut(DeviceChangeEvent) + Send + 'static>(&self, callback: F) {
+        let mut slot = self.callback.lock().unwrap();
+        slot.callback = Some(Box::new(callback));
+        slot.version += 1;
+    }
+
+    /// Unset the callback to run when the list of available devices changes
+    #[allow(clippy::missing_panics_doc)]
+    pub fn clear_ondevicechange(&self) {
+        let mut slot = self.callback.lock().unwrap();
+        slot.callback = None;
+        slot.version += 1;
+    }
+}
+
+/// Replace `devices` with the `current` list, returning the added and removed devices
+fn diff_devices(
+    devices: &mut Vec<MediaDeviceInfo>,
+    current: Vec<MediaDeviceInfo>,
+) -> (Vec<MediaDeviceInfo>, Vec<MediaDeviceInfo>) {
+    fn is_listed(list: &[MediaDeviceInfo], device: &MediaDeviceInfo) -> bool {
+        list.iter().any(|d| d.device_id() == device.device_id())
+    }
+
+    let previous = std::mem::replace(
+        devices,
+        current.iter().map(MediaDeviceInfo::detached).collect(),
+    );
+    let added = current
+        .into_iter()
+        .filter(|d| !is_listed(&previous, d))
+        .collect();
+    let removed = previous
+        .into_iter()
+        .filter(|d| !is_listed(devices, d))
+        .collect();
+
+    (added, removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media_devices::MediaDeviceInfoKind;
+
+    fn device(device_id: &str) -> MediaDeviceInfo {
+        MediaDeviceInfo::without_device(
+            device_id.to_string(),
+            None,
+            MediaDeviceInfoKind::AudioOutput,
+            format!("Speakers {}", device_id),
+        )
+    }
+
+    #[test]
+    fn test_device_change() {
+        let ids = Arc::new(Mutex::new(vec!["1", "2"]));
+        let ids_clone = Arc::clone(&ids);
+        let provider = move || {
+            ids_clone
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|id| device(id))
+                .collect()
+        };
+
+        let monitor = DeviceChangeMonitor::with_provider(provider, Duration::from_millis(1));
+        let (send, recv) = crossbeam_channel::unbounded();
+        monitor.set_ondevicechange(move |event| {
+            let ids = |list: &[MediaDeviceInfo]| {
+                list.iter()
+                    .map(|d| d.device_id().to_string())
+                    .collect::<Vec<_>>()
+            };
+            let _ = send.send((ids(&event.added), ids(&event.removed)));
+        });
+
+        *ids.lock().unwrap() = vec!["2", "3"];
+        let (added, removed) = recv.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(added, ["3"]);
+        assert_eq!(removed, ["1"]);
+
+        *ids.lock().unwrap() = vec!["2"];
+        let (added, removed) = recv.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert!(added.is_empty());
+        assert_eq!(removed, ["3"]);
+
+        // no events when nothing changes
+        assert!(recv.recv_timeout(Duration::from_millis(20)).is_err());
+
+        // no events after the handler is cleared, which drops the callback
+        monitor.clear_ondevicechange();
+        *ids.lock().unwrap() = vec![];
+        assert_eq!(
+            recv.recv_timeout(Duration::from_millis(20)),
+            Err(RecvTimeoutError::Disconnected)
+        );
+    }
+
+    #[test]
+    fn test_clear_from_handler() {
+        let ids = Arc::new(Mutex::new(vec!["1"]));
+        let ids_clone = Arc::clone(&ids);
+        let provider = move || {
+            ids_clone
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|id| device(id))
+                .collect()
+        };
+
+        let monitor = Arc::new(DeviceChangeMonitor::with_provider(
+            provider,
+            Duration::from_millis(1),
+        ));
+        let monitor_clone = Arc::downgrade(&monitor);
+        let (send, recv) = crossbeam_channel::unbounded();
+        monitor.set_ondevicechange(move |_| {
+            monitor_clone.upgrade().unwrap().clear_ondevicechange();
+            let _ = send.send(());
+        });
+
+        // the handler runs once and is dropped after clearing itself
+        *ids.lock().unwrap() = vec!["2"];
+        assert_eq!(recv.recv_timeout(Duration::from_secs(1)), Ok(()));
+        assert_eq!(
+            recv.recv_timeout(Duration::from_secs(1)),
+            Err(RecvTimeoutError::Disconnected)
+        );
+    }
+
+    #[test]
+    fn test_drop_stops_polling() {
+        let monitor = DeviceChangeMonitor::with_provider(Vec::new, Duration::from_millis(1));
+        let (send, recv) = crossbeam_channel::unbounded::<()>();
+        monitor.set_ondevicechange(move |_| {
+            let _ = send.send(());
+        });
+
+        // the callback is dropped when the polling thread has terminated
+        drop(monitor);
+        assert_eq!(
+            recv.recv_timeout(Duration::from_secs(1)),
+            Err(RecvTimeoutError::Disconnected)
+        );
+    }
+}
@@ -6,14 +6,22 @@
 
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
+use std::panic::AssertUnwindSafe;
+use std::sync::OnceLock;
 
 use crate::context::{AudioContextLatencyCategory, AudioContextOptions};
 use crate::media_streams::MediaStream;
+use crate::{expect_valid, DomException};
+
+mod device_change;
+pub use device_change::*;
 
 /// List the available media output devices, such as speakers, headsets, loopbacks, etc
 ///
 /// The media device_id can be used to specify the [`sink_id` of the `AudioContext`](crate::context::AudioContextOptions::sink_id)
 ///
+/// Use a [`DeviceChangeMonitor`] to get notified when devices are added or removed.
+///
 /// ```no_run
 /// use web_audio_api::media_devices::{enumerate_devices_sync, MediaDeviceInfoKind};
 ///
@@ -27,6 +35,74 @@ pub fn enumerate_devices_sync() -> Vec<MediaDeviceInfo> {
     crate::io::enumerate_devices_sync()
 }
 
+/// List the available media output devices, such as speakers, headsets, loopbacks, etc
+///
+/// This is the async version of [`enumerate_devices_sync`]. The audio backend is queried on a
+/// separate thread so the caller is not blocked.
+///
+/// The backend device handles can not be sent across threads, so the returned devices only carry
+/// the descriptive fields. They can be used as the `sink_id` or `device_id` like any other device.
+///
+/// ```no_run
+/// use web_audio_api::media_devices::{enumerate_devices, MediaDeviceInfoKind};
+///
+/// let devices = futures::executor::block_on(enumerate_devices());
+/// for device in devices.iter().filter(|d| d.kind() == MediaDeviceInfoKind::AudioInput) {
+///     println!("{} - {}", device.device_id(), device.label());
+/// }
+/// ```
+pub async fn enumerate_devices() -> Vec<MediaDeviceInfo> {
+    run_off_thread(|| {
+        // The backend device handles are not `Send`, only ship the descriptive fields
+        enumerate_devices_sync()
+            .into_iter()
+            .map(|d| (d.device_id, d.group_id, d.kind, d.label))
+            .collect::<Vec<_>>()
+    })
+    .await
+    .into_iter()
+    .map(|(device_id, group_id, kind, label)| {
+        MediaDeviceInfo::without_device(device_id, group_id, kind, label)
+    })
+    .collect()
+}
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Run a blocking backend operation on the media devices thread and await the result
+///
+/// The thread is spawned on first use and shared by all operations, which run one at a time.
+async fn run_off_thread<T, F>(f: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    static WORKER: OnceLock<crossbeam_channel::Sender<Job>> = OnceLock::new();
+    let worker = WORKER.get_or_init(|| {
+        let (send, recv) = crossbeam_channel::unbounded::<Job>();
+        std::thread::Builder::new()
+            .name("media devices".into())
+            .spawn(move || {
+                for job in recv {
+                    // a panic drops the result sender of the job, the caller propagates it
+                    let _ = std::panic::catch_unwind(AssertUnwindSafe(job));
+                }
+            })
+            .expect("failed to spawn the media devices thread");
+        send
+    });
+
+    let (send, recv) = futures_channel::oneshot::channel();
+    let job = Box::new(move || {
+        // ignore the error, the receiving future was dropped
+        let _ = send.send(f());
+    });
+    // the worker is never dropped, it outlives the sender
+    worker.send(job).unwrap();
+    // the sender is only dropped without sending when the operation panics, propagate it
+    recv.await.expect("media devices operation panicked")
+}
+
 // Internal struct to derive a stable id for a given input / output device
 // cf. https://github.com/orottier/web-audio-api-rs/issues/356
 #[derive(Hash)]
@@ -77,7 +153,8 @@ pub struct MediaDeviceInfo {
     group_id: Option<String>,
     kind: MediaDeviceInfoKind,
     label: String,
-    device: Box<dyn std::any::Any>,
+    /// Backend device handle, `None` when the info was sent across threads
+    device: Option<Box<dyn std::any::Any>>,
 }
 
 impl MediaDeviceInfo {
@@ -93,7 +170,23 @@ impl MediaDeviceInfo {
             group_id,
             kind,
             label,
-            device,
+            device: Some(device),
+        }
+    }
+
+    /// Device info that only carries the descriptive fields, without backend device handle
+    pub(crate) fn without_device(
+        device_id: String,
+        group_id: Option<String>,
+        kind: MediaDeviceInfoKind,
+        label: String,
+    ) -> Self {
+        Self {
+            device_id,
+            group_id,
+            kind,
+            label,
+            device: None,
         }
     }
 
@@ -120,9 +213,21 @@ impl MediaDeviceInfo {
         &self.label
     }
 
-    pub(crate) fn device(self) -> Box<dyn std::any::Any> {
+    /// Backend device handle, only available for the devices listed by the backend on the
+    /// current thread
+    pub(crate) fn device(self) -> Option<Box<dyn std::any::Any>> {
         self.device
     }
+
+    /// Copy of the device info without the backend device handle
+    pub(crate) fn detached(&self) -> Self {
+        Self::without_device(
+            self.device_id.clone(),
+            self.group_id.clone(),
+            self.kind,
+            self.label.clone(),
+        )
+    }
 }
 
 /// Dictionary used to instruct what sort of tracks to include in the [`MediaStream`] returned by
@@ -167,11 +272,18 @@ impl From<MediaTrackConstraints> for AudioContextOptions {
             sample_rate: value.sample_rate,
             sink_id,
             render_size_hint: Default::default(),
+            render_worker_threads: 0,
+            control_overflow_policy: Default::default(),
+            event_overflow_policy: Default::default(),
+            event_dispatch_mode: Default::default(),
+            fallback_to_default_sink: false,
+            file_sink_pacing: Default::default(),
+            input: None,
         }
     }
 }
 
-/// Check if the provided device_id is available for playback
+/// Check if the provided device_id is available for capturing
 ///
 /// It should be "" or a valid input `deviceId` returned from [`enumerate_devices_sync`]
 fn is_valid_device_id(device_id: &str) -> bool {
@@ -194,8 +306,16 @@ fn is_valid_device_id(device_id: &str) -> bool {
 /// kept alive and emit audio buffers. Call the `close()` method if you want to stop the media
 /// input and release all system resources.
 ///
-/// This function operates synchronously, which may be undesirable on the control thread. An async
-/// version is currently not implemented.
+/// This function operates synchronously, which may be undesirable on the control thread. Use
+/// [`get_user_media`] for the async version.
+///
+/// An invalid `device_id` falls back to the default input device and unsupported constraints fall
+/// back to the device defaults, both are logged.
+///
+/// # Panics
+///
+/// This function panics when no input device is available or the input stream fails to start,
+/// see [`try_get_user_media_sync`] for the fallible version.
 ///
 /// # Example
 ///
@@ -218,7 +338,22 @@ fn is_valid_device_id(device_id: &str) -> bool {
 /// // enjoy listening
 /// std::thread::sleep(std::time::Duration::from_secs(4));
 /// ```
+#[track_caller]
 pub fn get_user_media_sync(constraints: MediaStreamConstraints) -> MediaStream {
+    expect_valid(try_get_user_media_sync(constraints))
+}
+
+/// Prompt for permission to use a media input (audio only), without panicking
+///
+/// See [`get_user_media_sync`] for the fallback behavior of invalid constraints.
+///
+/// # Errors
+///
+/// Returns a [`DomException::NotFoundError`] when no input device is available, and a
+/// [`DomException::InvalidStateError`] when the input stream fails to start.
+pub fn try_get_user_media_sync(
+    constraints: MediaStreamConstraints,
+) -> Result<MediaStream, DomException> {
     let (channel_count, mut options) = match constraints {
         MediaStreamConstraints::Audio => (None, AudioContextOptions::default()),
         MediaStreamConstraints::AudioWithConstraints(cs) => (cs.channel_count, cs.into()),
@@ -229,5 +364,78 @@ pub fn get_user_media_sync(constraints: MediaStreamConstraints) -> MediaStream {
         options.sink_id = String::from("");
     }
 
-    crate::io::build_input(options, channel_count)
+    match crate::io::build_input(options.clone(), channel_count) {
+        Err(DomException::OverconstrainedError(message)) => {
+            log::error!("OverconstrainedError: {}", message);
+            options.sample_rate = None;
+            crate::io::build_input(options, None)
+        }
+        result => result,
+    }
+}
+
+/// Prompt for permission to use a media input (audio only)
+///
+/// This is the async version of [`get_user_media_sync`]. The input device is opened on a separate
+/// thread so the caller is not blocked.
+///
+/// # Errors
+///
+/// Returns a [`DomException::NotFoundError`] when the requested `device_id` is not a valid audio
+/// input device or no input device is available, and a [`DomException::OverconstrainedError`]
+/// when the device cannot satisfy the requested sample rate or channel count.
+///
+/// # Example
+///
+/// ```no_run
+/// use web_audio_api::context::{BaseAudioContext, AudioContext};
+/// use web_audio_api::media_devices;
+/// use web_audio_api::media_devices::MediaStreamConstraints;
+/// use web_audio_api::node::AudioNode;
+///
+/// let context = AudioContext::default();
+/// let mic = futures::executor::block_on(media_devices::get_user_media(MediaStreamConstraints::Audio))
+///     .expect("no microphone available");
+///
+/// let background = context.create_media_stream_source(&mic);
+/// background.connect(&context.destination());
+/// ```
+pub async fn get_user_media(
+    constraints: MediaStreamConstraints,
+) -> Result<MediaStream, DomException> {
+    let (channel_count, options) = match constraints {
+        MediaStreamConstraints::Audio => (None, AudioContextOptions::default()),
+        MediaStreamConstraints::AudioWithConstraints(cs) => (cs.channel_count, cs.into()),
+    };
+
+    run_off_thread(move || {
+        if !is_valid_device_id(&options.sink_id) {
+            return Err(DomException::NotFoundError(format!(
+                "invalid deviceId {:?}",
+                options.sink_id
+            )));
+        }
+
+        crate::io::build_input(options, channel_count)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    #[test]
+    fn test_run_off_thread_reuses_worker() {
+        let first = block_on(run_off_thread(|| std::thread::current().id()));
+        assert_ne!(first, std::thread::current().id());
+
+        // a panicking operation does not take down the worker
+        let result = std::panic::catch_unwind(|| block_on(run_off_thread(|| panic!("boom"))));
+        assert!(result.is_err());
+
+        let second = block_on(run_off_thread(|| std::thread::current().id()));
+        assert_eq!(first, second);
+    }
 }
@@ -12,6 +12,7 @@ use crate::{AtomicF64, AudioBuffer, RENDER_QUANTUM_SIZE};
 pub(crate) struct RTSStream {
     stream: ReadDiskStream<SymphoniaDecoder>,
     number_of_channels: usize,
+    render_quantum_size: usize,
     current_time: Arc<AtomicF64>,
     receiver: Receiver<MediaElementAction>,
     loop_: Arc<AtomicBool>,
@@ -102,6 +103,7 @@ impl MediaElement {
         let rts_stream = RTSStream {
             stream: read_disk_stream,
             number_of_channels,
+            render_quantum_size: RENDER_QUANTUM_SIZE,
             current_time: Arc::clone(&current_time),
             receiver,
             loop_: Arc::clone(&loop_),
@@ -160,6 +162,13 @@ impl MediaElement {
     }
 }
 
+impl RTSStream {
+    /// Yield buffers of the render quantum size of the context playing the stream
+    pub(crate) fn set_render_quantum_size(&mut self, render_quantum_size: usize) {
+        self.render_quantum_size = render_quantum_size;
+    }
+}
+
 impl Iterator for RTSStream {
     type Item = Result<AudioBuffer, Box<dyn Error + Send + Sync>>;
 
@@ -185,7 +194,7 @@ impl Iterator for RTSStream {
 
         if self.paused.load(Ordering::SeqCst) {
             let silence = AudioBuffer::from(
-                vec![vec![0.; RENDER_QUANTUM_SIZE]; self.number_of_channels],
+                vec![vec![0.; self.render_quantum_size]; self.number_of_channels],
                 sample_rate,
             );
             return Some(Ok(silence));
@@ -193,7 +202,7 @@ impl Iterator for RTSStream {
 
         let playback_rate = self.playback_rate.load(Ordering::SeqCst).abs();
         let _reverse = playback_rate < 0.; // TODO
-        let samples = (RENDER_QUANTUM_SIZE as f64 * playback_rate) as usize;
+        let samples = (self.render_quantum_size as f64 * playback_rate) as usize;
 
         let next = match self.stream.read(samples) {
             Ok(data) => {
@@ -208,7 +217,7 @@ impl Iterator for RTSStream {
                 } else {
                     let current_time = self.current_time.load(Ordering::SeqCst);
                     self.current_time.store(
-                        current_time + (RENDER_QUANTUM_SIZE as f64 / sample_rate as f64),
+                        current_time + (self.render_quantum_size as f64 / sample_rate as f64),
                         Ordering::SeqCst,
                     );
                 }
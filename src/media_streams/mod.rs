@@ -27,6 +27,11 @@ pub enum MediaStreamTrackState {
 }
 
 /// Single media track within a [`MediaStream`]
+///
+/// The track yields the buffers of its source. A live source, such as a microphone, yields an
+/// empty buffer (without any frames) when no new frames are available yet. Consumers of
+/// [`MediaStreamTrack::iter`] should poll again later instead of treating it as silence or as the
+/// end of the track. The media stream source nodes fill these gaps with silence.
 #[derive(Clone)]
 pub struct MediaStreamTrack {
     inner: Arc<MediaStreamTrackInner>,
@@ -4,8 +4,9 @@ use std::any::Any;
 
 use crate::context::AudioNodeId;
 use crate::node::{ChannelConfigInner, ChannelCountMode, ChannelInterpretation};
-use crate::render::graph::Graph;
+use crate::render::graph::{EdgeFade, Graph};
 use crate::render::AudioProcessor;
+use crate::NodeProfile;
 
 /// Commands from the control thread to the render thread
 pub(crate) enum ControlMessage {
@@ -35,6 +36,24 @@ pub(crate) enum ControlMessage {
         output: usize,
     },
 
+    /// Connect a node to another in the audio graph, starting at a given frame
+    ConnectNodeAt {
+        from: AudioNodeId,
+        to: AudioNodeId,
+        input: usize,
+        output: usize,
+        fade: EdgeFade,
+    },
+
+    /// Clear the connection between two given nodes in the audio graph, starting at a given frame
+    DisconnectNodeAt {
+        from: AudioNodeId,
+        to: AudioNodeId,
+        input: usize,
+        output: usize,
+        fade: EdgeFade,
+    },
+
     /// Notify the render thread this node is dropped in the control thread
     ControlHandleDropped { id: AudioNodeId },
 
@@ -67,6 +86,15 @@ pub(crate) enum ControlMessage {
     /// Request a diagnostic report of the audio graph
     RunDiagnostics { buffer: Vec<u8> },
 
+    /// Request a typed snapshot of the audio graph
+    RunGraphDiagnostics,
+
+    /// Enable or disable measuring the processing time of each node
+    SetProfiling { enabled: bool },
+
+    /// Request the processing times since the last request, the buffer is reused for the next ones
+    CollectProfile { buffer: Vec<NodeProfile> },
+
     /// Update the channel count of a node
     SetChannelCount { id: AudioNodeId, count: usize },
 
@@ -81,6 +109,63 @@ pub(crate) enum ControlMessage {
         id: AudioNodeId,
         interpretation: ChannelInterpretation,
     },
+
+    /// Apply a batch of graph edits at once, at the first render quantum starting at or after
+    /// `when` (or immediately when `None`)
+    Transaction {
+        messages: Vec<ControlMessage>,
+        when: Option<f64>,
+    },
+}
+
+impl ControlMessage {
+    /// Indicates if this message changes the audio graph, these are applied in order
+    pub fn edits_graph(&self) -> bool {
+        use ControlMessage::*;
+
+        matches!(
+            self,
+            RegisterNode { .. }
+                | ConnectNode { .. }
+                | DisconnectNode { .. }
+                | ConnectNodeAt { .. }
+                | DisconnectNodeAt { .. }
+                | ControlHandleDropped { .. }
+                | MarkCycleBreaker { .. }
+                | NodeMessage { .. }
+                | SetChannelCount { .. }
+                | SetChannelCountMode { .. }
+                | SetChannelInterpretation { .. }
+                | Transaction { .. }
+        )
+    }
+
+    /// Indicates if this message changes the topology of the audio graph, later messages may
+    /// rely on it so it must never be discarded
+    pub fn edits_topology(&self) -> bool {
+        self.edits_graph() && !matches!(self, ControlMessage::NodeMessage { .. })
+    }
+
+    /// The nodes this graph edit refers to, these must be alive when it is applied
+    pub fn node_ids(&self) -> impl Iterator<Item = AudioNodeId> {
+        use ControlMessage::*;
+
+        let ids = match *self {
+            RegisterNode { id, .. }
+            | ControlHandleDropped { id }
+            | MarkCycleBreaker { id }
+            | NodeMessage { id, .. }
+            | SetChannelCount { id, .. }
+            | SetChannelCountMode { id, .. }
+            | SetChannelInterpretation { id, .. } => [Some(id), None],
+            ConnectNode { from, to, .. }
+            | DisconnectNode { from, to, .. }
+            | ConnectNodeAt { from, to, .. }
+            | DisconnectNodeAt { from, to, .. } => [Some(from), Some(to)],
+            _ => [None, None],
+        };
+        ids.into_iter().flatten()
+    }
 }
 
 /// Helper object to emit single notification
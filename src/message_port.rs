@@ -1,9 +1,10 @@
 use std::any::Any;
+use std::ops::ControlFlow;
 
 use crate::context::AudioContextRegistration;
 use crate::node::AudioNode;
 
-use crate::events::{EventHandler, EventPayload, EventType};
+use crate::events::{EventHandler, EventPayload, EventStream, EventType};
 
 /// One of the two ports of a message channel
 ///
@@ -51,4 +52,21 @@ impl<'a> MessagePort<'a> {
             .context()
             .clear_event_handler(EventType::Message(self.0.id()));
     }
+
+    /// Stream of the messages arriving on the channel.
+    ///
+    /// Messages can not be copied, so the stream takes them exclusively: while it is alive, the
+    /// `onmessage` handler does not run. When multiple streams are alive, the most recently
+    /// created one receives the messages. The handler receives messages again once the streams
+    /// are dropped.
+    pub fn message_stream(&self) -> EventStream<Box<dyn Any + Send + 'static>> {
+        EventStream::new_exclusive(
+            self.0.context(),
+            EventType::Message(self.0.id()),
+            |v| match v {
+                EventPayload::Message(v) => ControlFlow::Continue(Some(v)),
+                _ => unreachable!(),
+            },
+        )
+    }
 }
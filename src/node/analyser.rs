@@ -112,7 +112,7 @@ impl AnalyserNode {
             let min_decibels = options.min_decibels;
             let max_decibels = options.max_decibels;
 
-            let mut analyser = Analyser::new();
+            let mut analyser = Analyser::new(context.render_quantum_size());
             analyser.set_fft_size(fft_size);
             analyser.set_smoothing_time_constant(smoothing_time_constant);
             analyser.set_decibels(min_decibels, max_decibels);
@@ -8,9 +8,9 @@ use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
 use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
-use crate::{assert_valid_time_value, AtomicF64, RENDER_QUANTUM_SIZE};
+use crate::{check_valid_time_value, expect_valid, AtomicF64, DomException};
 
-use super::{AudioNode, AudioScheduledSourceNode, ChannelConfig};
+use super::{check_can_start, check_can_stop, AudioNode, AudioScheduledSourceNode, ChannelConfig};
 
 /// Options for constructing an [`AudioBufferSourceNode`]
 // dictionary AudioBufferSourceOptions {
@@ -135,29 +135,17 @@ impl AudioNode for AudioBufferSourceNode {
 }
 
 impl AudioScheduledSourceNode for AudioBufferSourceNode {
-    fn start(&mut self) {
-        let start = self.registration.context().current_time();
-        self.start_at_with_offset_and_duration(start, 0., f64::MAX);
+    fn try_start_at(&mut self, when: f64) -> Result<(), DomException> {
+        self.try_start_at_with_offset_and_duration(when, 0., f64::MAX)
     }
 
-    fn start_at(&mut self, when: f64) {
-        self.start_at_with_offset_and_duration(when, 0., f64::MAX);
-    }
-
-    fn stop(&mut self) {
-        let stop = self.registration.context().current_time();
-        self.stop_at(stop);
-    }
-
-    fn stop_at(&mut self, when: f64) {
-        assert_valid_time_value(when);
-        assert_eq!(
-            self.start_stop_count, 1,
-            "InvalidStateError cannot stop before start"
-        );
+    fn try_stop_at(&mut self, when: f64) -> Result<(), DomException> {
+        check_valid_time_value(when)?;
+        check_can_stop(self.start_stop_count)?;
 
         self.start_stop_count += 1;
         self.registration.post_message(ControlMessage::Stop(when));
+        Ok(())
     }
 }
 
@@ -217,6 +205,7 @@ impl AudioBufferSourceNode {
                 loop_state,
                 render_state: AudioBufferRendererState::default(),
                 ended_triggered: false,
+                playback_infos: vec![None; context.render_quantum_size()],
             };
 
             let node = Self {
@@ -250,23 +239,52 @@ impl AudioBufferSourceNode {
         self.start_at_with_offset_and_duration(start, offset, f64::MAX);
     }
 
+    /// Start the playback at the given time and with a given offset, see
+    /// [`Self::start_at_with_offset`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidStateError` if the source was already started, a `TypeError` or
+    /// `RangeError` if a time value is not finite or negative
+    pub fn try_start_at_with_offset(
+        &mut self,
+        start: f64,
+        offset: f64,
+    ) -> Result<(), DomException> {
+        self.try_start_at_with_offset_and_duration(start, offset, f64::MAX)
+    }
+
     /// Start the playback at the given time, with a given offset, for a given duration
     ///
     /// # Panics
     ///
     /// Panics if the source was already started
     pub fn start_at_with_offset_and_duration(&mut self, start: f64, offset: f64, duration: f64) {
-        assert_valid_time_value(start);
-        assert_valid_time_value(offset);
-        assert_valid_time_value(duration);
-        assert_eq!(
-            self.start_stop_count, 0,
-            "InvalidStateError - Cannot call `start` twice"
-        );
+        expect_valid(self.try_start_at_with_offset_and_duration(start, offset, duration));
+    }
+
+    /// Start the playback at the given time, with a given offset, for a given duration, see
+    /// [`Self::start_at_with_offset_and_duration`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidStateError` if the source was already started, a `TypeError` or
+    /// `RangeError` if a time value is not finite or negative
+    pub fn try_start_at_with_offset_and_duration(
+        &mut self,
+        start: f64,
+        offset: f64,
+        duration: f64,
+    ) -> Result<(), DomException> {
+        check_valid_time_value(start)?;
+        check_valid_time_value(offset)?;
+        check_valid_time_value(duration)?;
+        check_can_start(self.start_stop_count)?;
 
         self.start_stop_count += 1;
         let control = ControlMessage::StartWithOffsetAndDuration(start, offset, duration);
         self.registration.post_message(control);
+        Ok(())
     }
 
     /// Current buffer value (nullable)
@@ -281,15 +299,27 @@ impl AudioBufferSourceNode {
     /// Panics if a buffer has already been given to the source (though `new` or through
     /// `set_buffer`)
     pub fn set_buffer(&mut self, audio_buffer: AudioBuffer) {
-        let clone = audio_buffer.clone();
+        expect_valid(self.try_set_buffer(audio_buffer));
+    }
 
-        assert!(
-            self.buffer.is_none(),
-            "InvalidStateError - cannot assign buffer twice",
-        );
+    /// Provide an [`AudioBuffer`] as the source of data to be played bask, see
+    /// [`Self::set_buffer`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidStateError` if a buffer has already been given to the source
+    pub fn try_set_buffer(&mut self, audio_buffer: AudioBuffer) -> Result<(), DomException> {
+        if self.buffer.is_some() {
+            return Err(DomException::InvalidStateError(String::from(
+                "cannot assign buffer twice",
+            )));
+        }
+
+        let clone = audio_buffer.clone();
         self.buffer = Some(audio_buffer);
 
         self.registration.post_message(clone);
+        Ok(())
     }
 
     /// K-rate [`AudioParam`] that defines the speed at which the [`AudioBuffer`]
@@ -385,6 +415,9 @@ struct AudioBufferSourceRenderer {
     loop_state: LoopState,
     render_state: AudioBufferRendererState,
     ended_triggered: bool,
+    // internal buffer used to store playback infos to compute the samples
+    // according to the source buffer, preallocated to the render quantum size
+    playback_infos: Vec<Option<PlaybackInfo>>,
 }
 
 impl AudioBufferSourceRenderer {
@@ -416,7 +449,7 @@ impl AudioProcessor for AudioBufferSourceRenderer {
 
         let sample_rate = scope.sample_rate as f64;
         let dt = 1. / sample_rate;
-        let block_duration = dt * RENDER_QUANTUM_SIZE as f64;
+        let block_duration = dt * scope.render_quantum_size as f64;
         let next_block_time = scope.current_time + block_duration;
 
         // Return early if start_time is beyond this block
@@ -588,14 +621,15 @@ impl AudioProcessor for AudioBufferSourceRenderer {
                     });
 
                 if let Some(loop_point_index) = loop_point_index {
-                    buffer_time = ((RENDER_QUANTUM_SIZE - loop_point_index) as f64 / sample_rate)
+                    buffer_time = ((scope.render_quantum_size - loop_point_index) as f64
+                        / sample_rate)
                         % buffer_duration;
                 } else {
                     buffer_time += block_duration;
                 }
             } else {
                 let start_index = (buffer_time * sample_rate).round() as usize;
-                let end_index = start_index + RENDER_QUANTUM_SIZE;
+                let end_index = start_index + scope.render_quantum_size;
                 // we can do memcopy
                 buffer
                     .channels()
@@ -633,9 +667,9 @@ impl AudioProcessor for AudioBufferSourceRenderer {
             self.render_state.entered_loop = false;
         }
 
-        // internal buffer used to store playback infos to compute the samples
-        // according to the source buffer. (prev_sample_index, k)
-        let mut playback_infos = [None; RENDER_QUANTUM_SIZE];
+        // reset the playback infos (prev_sample_index, k) of the previous block
+        let playback_infos = &mut self.playback_infos[..];
+        playback_infos.fill(None);
 
         // compute position for each sample and store into `self.positions`
         for (i, playback_info) in playback_infos.iter_mut().enumerate() {
@@ -813,6 +847,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_try_start_stop_set_buffer() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let mut src = context.create_buffer_source();
+
+        let result = src.try_stop();
+        assert!(matches!(result, Err(DomException::InvalidStateError(_))));
+        let result = src.try_start_at(f64::NAN);
+        assert!(matches!(result, Err(DomException::TypeError(_))));
+
+        assert!(src.try_start().is_ok());
+        let result = src.try_start_at_with_offset(0., 1.);
+        assert!(matches!(result, Err(DomException::InvalidStateError(_))));
+
+        assert!(src.try_stop_at(1.).is_ok());
+        let result = src.try_stop();
+        assert!(matches!(result, Err(DomException::InvalidStateError(_))));
+
+        let buffer = context.create_buffer(1, 1, 44_100.);
+        assert!(src.try_set_buffer(buffer.clone()).is_ok());
+        let result = src.try_set_buffer(buffer);
+        assert!(matches!(result, Err(DomException::InvalidStateError(_))));
+    }
+
     #[test]
     fn test_playing_some_file() {
         let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44_100.);
@@ -1,8 +1,10 @@
+use std::ops::ControlFlow;
 use std::sync::{Arc, Mutex};
 
 use crate::context::{AudioContextRegistration, ConcreteBaseAudioContext};
-use crate::events::{ErrorEvent, EventHandler, EventPayload, EventType};
+use crate::events::{ErrorEvent, EventHandler, EventPayload, EventStream, EventType};
 use crate::message::ControlMessage;
+use crate::{expect_valid, DomException};
 
 /// How channels must be matched between the node's inputs and outputs.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -213,6 +215,46 @@ impl From<AudioNodeOptions> for ChannelConfig {
     }
 }
 
+/// Check that the source and destination node of a (dis)connection belong to the same context
+///
+/// # Errors
+///
+/// Returns an `InvalidAccessError` if the contexts do not match
+pub(crate) fn check_same_context(
+    source: &ConcreteBaseAudioContext,
+    dest: &ConcreteBaseAudioContext,
+    action: &str,
+) -> Result<(), DomException> {
+    if source == dest {
+        Ok(())
+    } else {
+        Err(DomException::InvalidAccessError(format!(
+            "Attempting to {} nodes from different contexts",
+            action
+        )))
+    }
+}
+
+/// Check that the given input or output port exists
+///
+/// # Errors
+///
+/// Returns an `IndexSizeError` if the port is out of bounds
+pub(crate) fn check_valid_port(
+    kind: &str,
+    port: usize,
+    number_of_ports: usize,
+) -> Result<(), DomException> {
+    if port < number_of_ports {
+        Ok(())
+    } else {
+        Err(DomException::IndexSizeError(format!(
+            "{} port {} is out of bounds",
+            kind, port
+        )))
+    }
+}
+
 /// This interface represents audio sources, the audio destination, and intermediate processing
 /// modules.
 ///
@@ -248,6 +290,16 @@ pub trait AudioNode {
         self.connect_from_output_to_input(dest, 0, 0)
     }
 
+    /// Connect the output of this AudioNode to the input of another node, see [`Self::connect`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidAccessError` if the AudioContext of the source and destination does not
+    /// match
+    fn try_connect<'a>(&self, dest: &'a dyn AudioNode) -> Result<&'a dyn AudioNode, DomException> {
+        self.try_connect_from_output_to_input(dest, 0, 0)
+    }
+
     /// Connect a specific output of this AudioNode to a specific input of another node.
     ///
     /// # Panics
@@ -262,22 +314,25 @@ pub trait AudioNode {
         output: usize,
         input: usize,
     ) -> &'a dyn AudioNode {
-        assert!(
-            self.context() == dest.context(),
-            "InvalidAccessError - Attempting to connect nodes from different contexts",
-        );
-
-        assert!(
-            self.number_of_outputs() > output,
-            "IndexSizeError - output port {} is out of bounds",
-            output
-        );
+        expect_valid(self.try_connect_from_output_to_input(dest, output, input))
+    }
 
-        assert!(
-            dest.number_of_inputs() > input,
-            "IndexSizeError - input port {} is out of bounds",
-            input
-        );
+    /// Connect a specific output of this AudioNode to a specific input of another node, see
+    /// [`Self::connect_from_output_to_input`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidAccessError` if the AudioContext of the source and destination does not
+    /// match, an `IndexSizeError` if the input or output port is out of bounds
+    fn try_connect_from_output_to_input<'a>(
+        &self,
+        dest: &'a dyn AudioNode,
+        output: usize,
+        input: usize,
+    ) -> Result<&'a dyn AudioNode, DomException> {
+        check_same_context(self.context(), dest.context(), "connect")?;
+        check_valid_port("output", output, self.number_of_outputs())?;
+        check_valid_port("input", input, dest.number_of_inputs())?;
 
         self.context().connect(
             self.registration().id(),
@@ -285,13 +340,15 @@ pub trait AudioNode {
             output,
             input,
         );
-        dest
+        Ok(dest)
     }
 
     /// Disconnects all outgoing connections from the AudioNode.
     fn disconnect(&self) {
-        self.context()
-            .disconnect(self.registration().id(), None, None, None);
+        expect_valid(
+            self.context()
+                .disconnect(self.registration().id(), None, None, None),
+        );
     }
 
     /// Disconnects all outputs of the AudioNode that go to a specific destination AudioNode.
@@ -302,17 +359,25 @@ pub trait AudioNode {
     /// - the AudioContext of the source and destination does not match
     /// - the source node was not connected to the destination node
     fn disconnect_dest(&self, dest: &dyn AudioNode) {
-        assert!(
-            self.context() == dest.context(),
-            "InvalidAccessError - Attempting to disconnect nodes from different contexts"
-        );
+        expect_valid(self.try_disconnect_dest(dest));
+    }
+
+    /// Disconnects all outputs of the AudioNode that go to a specific destination AudioNode, see
+    /// [`Self::disconnect_dest`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidAccessError` if the AudioContext of the source and destination does not
+    /// match, or if the source node was not connected to the destination node
+    fn try_disconnect_dest(&self, dest: &dyn AudioNode) -> Result<(), DomException> {
+        check_same_context(self.context(), dest.context(), "disconnect")?;
 
         self.context().disconnect(
             self.registration().id(),
             None,
             Some(dest.registration().id()),
             None,
-        );
+        )
     }
 
     /// Disconnects all outgoing connections at the given output port from the AudioNode.
@@ -322,14 +387,20 @@ pub trait AudioNode {
     /// This function will panic when
     /// - if the output port is out of bounds for this node
     fn disconnect_output(&self, output: usize) {
-        assert!(
-            self.number_of_outputs() > output,
-            "IndexSizeError - output port {} is out of bounds",
-            output
-        );
+        expect_valid(self.try_disconnect_output(output));
+    }
+
+    /// Disconnects all outgoing connections at the given output port from the AudioNode, see
+    /// [`Self::disconnect_output`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `IndexSizeError` if the output port is out of bounds for this node
+    fn try_disconnect_output(&self, output: usize) -> Result<(), DomException> {
+        check_valid_port("output", output, self.number_of_outputs())?;
 
         self.context()
-            .disconnect(self.registration().id(), Some(output), None, None);
+            .disconnect(self.registration().id(), Some(output), None, None)
     }
 
     /// Disconnects a specific output of the AudioNode to a specific destination AudioNode
@@ -341,23 +412,31 @@ pub trait AudioNode {
     /// - if the output port is out of bounds for the source node
     /// - the source node was not connected to the destination node
     fn disconnect_dest_from_output(&self, dest: &dyn AudioNode, output: usize) {
-        assert!(
-            self.context() == dest.context(),
-            "InvalidAccessError - Attempting to disconnect nodes from different contexts"
-        );
+        expect_valid(self.try_disconnect_dest_from_output(dest, output));
+    }
 
-        assert!(
-            self.number_of_outputs() > output,
-            "IndexSizeError - output port {} is out of bounds",
-            output
-        );
+    /// Disconnects a specific output of the AudioNode to a specific destination AudioNode, see
+    /// [`Self::disconnect_dest_from_output`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidAccessError` if the AudioContext of the source and destination does not
+    /// match or if the source node was not connected to the destination node, an
+    /// `IndexSizeError` if the output port is out of bounds
+    fn try_disconnect_dest_from_output(
+        &self,
+        dest: &dyn AudioNode,
+        output: usize,
+    ) -> Result<(), DomException> {
+        check_same_context(self.context(), dest.context(), "disconnect")?;
+        check_valid_port("output", output, self.number_of_outputs())?;
 
         self.context().disconnect(
             self.registration().id(),
             Some(output),
             Some(dest.registration().id()),
             None,
-        );
+        )
     }
 
     /// Disconnects a specific output of the AudioNode to a specific input of some destination
@@ -376,29 +455,340 @@ pub trait AudioNode {
         output: usize,
         input: usize,
     ) {
-        assert!(
-            self.context() == dest.context(),
-            "InvalidAccessError - Attempting to disconnect nodes from different contexts"
-        );
+        expect_valid(self.try_disconnect_dest_from_output_to_input(dest, output, input));
+    }
 
-        assert!(
-            self.number_of_outputs() > output,
-            "IndexSizeError - output port {} is out of bounds",
-            output
+    /// Disconnects a specific output of the AudioNode to a specific input of some destination
+    /// AudioNode, see [`Self::disconnect_dest_from_output_to_input`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidAccessError` if the AudioContext of the source and destination does not
+    /// match or if the source node was not connected to the destination node, an
+    /// `IndexSizeError` if the input or output port is out of bounds
+    fn try_disconnect_dest_from_output_to_input(
+        &self,
+        dest: &dyn AudioNode,
+        output: usize,
+        input: usize,
+    ) -> Result<(), DomException> {
+        check_same_context(self.context(), dest.context(), "disconnect")?;
+        check_valid_port("output", output, self.number_of_outputs())?;
+        check_valid_port("input", input, dest.number_of_inputs())?;
+
+        self.context().disconnect(
+            self.registration().id(),
+            Some(output),
+            Some(dest.registration().id()),
+            Some(input),
+        )
+    }
+
+    /// Connect the output of this AudioNode to the input of another node at the given time.
+    ///
+    /// The connection is made at the exact frame of `when` (in the same time coordinate system as
+    /// the AudioContext's `current_time`) and its gain ramps linearly from zero to one over
+    /// `fade_time` seconds. Use a `fade_time` of zero for an instantaneous connection.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic when
+    /// - the AudioContext of the source and destination does not match
+    /// - `when` or `fade_time` is negative or not finite
+    fn connect_at<'a>(
+        &self,
+        dest: &'a dyn AudioNode,
+        when: f64,
+        fade_time: f64,
+    ) -> &'a dyn AudioNode {
+        self.connect_from_output_to_input_at(dest, 0, 0, when, fade_time)
+    }
+
+    /// Connect the output of this AudioNode to the input of another node at the given time, see
+    /// [`Self::connect_at`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidAccessError` if the AudioContext of the source and destination does not
+    /// match, a `TypeError` or `RangeError` if `when` or `fade_time` is not finite or negative
+    fn try_connect_at<'a>(
+        &self,
+        dest: &'a dyn AudioNode,
+        when: f64,
+        fade_time: f64,
+    ) -> Result<&'a dyn AudioNode, DomException> {
+        self.try_connect_from_output_to_input_at(dest, 0, 0, when, fade_time)
+    }
+
+    /// Connect a specific output of this AudioNode to a specific input of another node at the
+    /// given time, see [`Self::connect_at`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic when
+    /// - the AudioContext of the source and destination does not match
+    /// - if the input port is out of bounds for the destination node
+    /// - if the output port is out of bounds for the source node
+    /// - `when` or `fade_time` is negative or not finite
+    fn connect_from_output_to_input_at<'a>(
+        &self,
+        dest: &'a dyn AudioNode,
+        output: usize,
+        input: usize,
+        when: f64,
+        fade_time: f64,
+    ) -> &'a dyn AudioNode {
+        expect_valid(self.try_connect_from_output_to_input_at(dest, output, input, when, fade_time))
+    }
+
+    /// Connect a specific output of this AudioNode to a specific input of another node at the
+    /// given time, see [`Self::connect_from_output_to_input_at`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidAccessError` if the AudioContext of the source and destination does not
+    /// match, an `IndexSizeError` if the input or output port is out of bounds, a `TypeError` or
+    /// `RangeError` if `when` or `fade_time` is not finite or negative
+    fn try_connect_from_output_to_input_at<'a>(
+        &self,
+        dest: &'a dyn AudioNode,
+        output: usize,
+        input: usize,
+        when: f64,
+        fade_time: f64,
+    ) -> Result<&'a dyn AudioNode, DomException> {
+        check_same_context(self.context(), dest.context(), "connect")?;
+        check_valid_port("output", output, self.number_of_outputs())?;
+        check_valid_port("input", input, dest.number_of_inputs())?;
+        crate::check_valid_time_value(when)?;
+        crate::check_valid_time_value(fade_time)?;
+
+        self.context().connect_at(
+            self.registration().id(),
+            dest.registration().id(),
+            output,
+            input,
+            when,
+            fade_time,
         );
+        Ok(dest)
+    }
+
+    /// Disconnects all outgoing connections from the AudioNode at the given time.
+    ///
+    /// The connections are broken at the exact frame of `when` (in the same time coordinate
+    /// system as the AudioContext's `current_time`) after their gain ramped linearly from one to
+    /// zero over `fade_time` seconds, starting at `when`. Use a `fade_time` of zero for an
+    /// instantaneous disconnection.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic when
+    /// - `when` or `fade_time` is negative or not finite
+    fn disconnect_at(&self, when: f64, fade_time: f64) {
+        expect_valid(self.try_disconnect_at(when, fade_time));
+    }
+
+    /// Disconnects all outgoing connections from the AudioNode at the given time, see
+    /// [`Self::disconnect_at`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a `TypeError` or `RangeError` if `when` or `fade_time` is not finite or negative
+    fn try_disconnect_at(&self, when: f64, fade_time: f64) -> Result<(), DomException> {
+        crate::check_valid_time_value(when)?;
+        crate::check_valid_time_value(fade_time)?;
+
+        self.context()
+            .disconnect_at(self.registration().id(), None, None, None, when, fade_time)
+    }
+
+    /// Disconnects all outputs of the AudioNode that go to a specific destination AudioNode at the
+    /// given time, see [`Self::disconnect_at`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic when
+    /// - the AudioContext of the source and destination does not match
+    /// - the source node was not connected to the destination node
+    /// - `when` or `fade_time` is negative or not finite
+    fn disconnect_dest_at(&self, dest: &dyn AudioNode, when: f64, fade_time: f64) {
+        expect_valid(self.try_disconnect_dest_at(dest, when, fade_time));
+    }
+
+    /// Disconnects all outputs of the AudioNode that go to a specific destination AudioNode at the
+    /// given time, see [`Self::disconnect_dest_at`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidAccessError` if the AudioContext of the source and destination does not
+    /// match or if the source node was not connected to the destination node, a `TypeError` or
+    /// `RangeError` if `when` or `fade_time` is not finite or negative
+    fn try_disconnect_dest_at(
+        &self,
+        dest: &dyn AudioNode,
+        when: f64,
+        fade_time: f64,
+    ) -> Result<(), DomException> {
+        check_same_context(self.context(), dest.context(), "disconnect")?;
+        crate::check_valid_time_value(when)?;
+        crate::check_valid_time_value(fade_time)?;
+
+        self.context().disconnect_at(
+            self.registration().id(),
+            None,
+            Some(dest.registration().id()),
+            None,
+            when,
+            fade_time,
+        )
+    }
+
+    /// Disconnects all outgoing connections at the given output port from the AudioNode at the
+    /// given time, see [`Self::disconnect_at`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic when
+    /// - if the output port is out of bounds for this node
+    /// - `when` or `fade_time` is negative or not finite
+    fn disconnect_output_at(&self, output: usize, when: f64, fade_time: f64) {
+        expect_valid(self.try_disconnect_output_at(output, when, fade_time));
+    }
 
-        assert!(
-            dest.number_of_inputs() > input,
-            "IndexSizeError - input port {} is out of bounds",
-            input
+    /// Disconnects all outgoing connections at the given output port from the AudioNode at the
+    /// given time, see [`Self::disconnect_output_at`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `IndexSizeError` if the output port is out of bounds, a `TypeError` or
+    /// `RangeError` if `when` or `fade_time` is not finite or negative
+    fn try_disconnect_output_at(
+        &self,
+        output: usize,
+        when: f64,
+        fade_time: f64,
+    ) -> Result<(), DomException> {
+        check_valid_port("output", output, self.number_of_outputs())?;
+        crate::check_valid_time_value(when)?;
+        crate::check_valid_time_value(fade_time)?;
+
+        self.context().disconnect_at(
+            self.registration().id(),
+            Some(output),
+            None,
+            None,
+            when,
+            fade_time,
+        )
+    }
+
+    /// Disconnects a specific output of the AudioNode to a specific destination AudioNode at the
+    /// given time, see [`Self::disconnect_at`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic when
+    /// - the AudioContext of the source and destination does not match
+    /// - if the output port is out of bounds for the source node
+    /// - the source node was not connected to the destination node
+    /// - `when` or `fade_time` is negative or not finite
+    fn disconnect_dest_from_output_at(
+        &self,
+        dest: &dyn AudioNode,
+        output: usize,
+        when: f64,
+        fade_time: f64,
+    ) {
+        expect_valid(self.try_disconnect_dest_from_output_at(dest, output, when, fade_time));
+    }
+
+    /// Disconnects a specific output of the AudioNode to a specific destination AudioNode at the
+    /// given time, see [`Self::disconnect_dest_from_output_at`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidAccessError` if the AudioContext of the source and destination does not
+    /// match or if the source node was not connected to the destination node, an
+    /// `IndexSizeError` if the output port is out of bounds, a `TypeError` or `RangeError` if
+    /// `when` or `fade_time` is not finite or negative
+    fn try_disconnect_dest_from_output_at(
+        &self,
+        dest: &dyn AudioNode,
+        output: usize,
+        when: f64,
+        fade_time: f64,
+    ) -> Result<(), DomException> {
+        check_same_context(self.context(), dest.context(), "disconnect")?;
+        check_valid_port("output", output, self.number_of_outputs())?;
+        crate::check_valid_time_value(when)?;
+        crate::check_valid_time_value(fade_time)?;
+
+        self.context().disconnect_at(
+            self.registration().id(),
+            Some(output),
+            Some(dest.registration().id()),
+            None,
+            when,
+            fade_time,
+        )
+    }
+
+    /// Disconnects a specific output of the AudioNode to a specific input of some destination
+    /// AudioNode at the given time, see [`Self::disconnect_at`].
+    ///
+    /// # Panics
+    ///
+    /// This function will panic when
+    /// - the AudioContext of the source and destination does not match
+    /// - if the input port is out of bounds for the destination node
+    /// - if the output port is out of bounds for the source node
+    /// - the source node was not connected to the destination node
+    /// - `when` or `fade_time` is negative or not finite
+    fn disconnect_dest_from_output_to_input_at(
+        &self,
+        dest: &dyn AudioNode,
+        output: usize,
+        input: usize,
+        when: f64,
+        fade_time: f64,
+    ) {
+        expect_valid(
+            self.try_disconnect_dest_from_output_to_input_at(dest, output, input, when, fade_time),
         );
+    }
 
-        self.context().disconnect(
+    /// Disconnects a specific output of the AudioNode to a specific input of some destination
+    /// AudioNode at the given time, see [`Self::disconnect_dest_from_output_to_input_at`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidAccessError` if the AudioContext of the source and destination does not
+    /// match or if the source node was not connected to the destination node, an
+    /// `IndexSizeError` if the input or output port is out of bounds, a `TypeError` or
+    /// `RangeError` if `when` or `fade_time` is not finite or negative
+    fn try_disconnect_dest_from_output_to_input_at(
+        &self,
+        dest: &dyn AudioNode,
+        output: usize,
+        input: usize,
+        when: f64,
+        fade_time: f64,
+    ) -> Result<(), DomException> {
+        check_same_context(self.context(), dest.context(), "disconnect")?;
+        check_valid_port("output", output, self.number_of_outputs())?;
+        check_valid_port("input", input, dest.number_of_inputs())?;
+        crate::check_valid_time_value(when)?;
+        crate::check_valid_time_value(fade_time)?;
+
+        self.context().disconnect_at(
             self.registration().id(),
             Some(output),
             Some(dest.registration().id()),
             Some(input),
-        );
+            when,
+            fade_time,
+        )
     }
 
     /// The number of inputs feeding into the AudioNode. For source nodes, this will be 0.
@@ -463,4 +853,21 @@ pub trait AudioNode {
         self.context()
             .clear_event_handler(EventType::ProcessorError(self.registration().id()));
     }
+
+    /// Stream that yields a single item when an unhandled exception occurs in the audio
+    /// processor.
+    ///
+    /// The error can not be copied, so the stream takes it exclusively: while it is alive, the
+    /// `onprocessorerror` handler does not run. When multiple streams are alive, the most
+    /// recently created one receives the error.
+    fn processor_error_stream(&self) -> EventStream<ErrorEvent> {
+        EventStream::new_exclusive(
+            self.context(),
+            EventType::ProcessorError(self.registration().id()),
+            |v| match v {
+                EventPayload::ProcessorError(v) => ControlFlow::Break(v),
+                _ => unreachable!(),
+            },
+        )
+    }
 }
@@ -10,7 +10,7 @@ use crate::param::{AudioParam, AudioParamDescriptor};
 use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
-use crate::{MAX_CHANNELS, RENDER_QUANTUM_SIZE};
+use crate::MAX_CHANNELS;
 
 use super::{AudioNode, AudioNodeOptions, ChannelConfig};
 
@@ -401,6 +401,7 @@ impl BiquadFilterNode {
                 q: q_proc,
                 type_,
                 xy: ArrayVec::new(),
+                coefs_list: vec![Coefficients::default(); context.render_quantum_size()],
             };
 
             let node = Self {
@@ -561,6 +562,8 @@ struct BiquadFilterRenderer {
     type_: BiquadFilterType,
     // keep filter state for each channel
     xy: ArrayVec<[f64; 4], MAX_CHANNELS>,
+    // coefs for each frame of the render quantum, preallocated to the render quantum size
+    coefs_list: Vec<Coefficients>,
 }
 
 impl AudioProcessor for BiquadFilterRenderer {
@@ -633,8 +636,9 @@ impl AudioProcessor for BiquadFilterRenderer {
             f64::from(q[0]),
         );
 
-        let mut coefs_list = [coef; RENDER_QUANTUM_SIZE];
-        // if one of the params has a length of RENDER_QUANTUM_SIZE, we need
+        let coefs_list = &mut self.coefs_list[..];
+        coefs_list.fill(coef);
+        // if one of the params has a length of render quantum size, we need
         // to compute the coefs for each frame
         if frequency.len() != 1 || detune.len() != 1 || q.len() != 1 || gain.len() != 1 {
             coefs_list
@@ -4,27 +4,44 @@ use crate::context::{AudioContextRegistration, BaseAudioContext};
 use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
-use crate::MAX_CHANNELS;
+use crate::{expect_valid, DomException, MAX_CHANNELS};
 
 use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation};
 
-/// Assert that the given number of channels is valid for a ChannelMergerNode
+/// Check that the given number of channels is valid for a ChannelMergerNode
 ///
-/// # Panics
+/// # Errors
 ///
-/// This function will panic if:
+/// Returns an error if:
 /// - the given number of channels is outside the [1, 32] range,
 /// 32 being defined by the MAX_CHANNELS constant.
 ///
-#[track_caller]
-#[inline(always)]
-pub(crate) fn assert_valid_number_of_channels(number_of_channels: usize) {
-    assert!(
-        number_of_channels > 0 && number_of_channels <= MAX_CHANNELS,
-        "IndexSizeError - Invalid number of channels: {:?} is outside range [1, {:?}]",
-        number_of_channels,
-        MAX_CHANNELS
-    );
+fn check_valid_number_of_channels(number_of_channels: usize) -> Result<(), DomException> {
+    if number_of_channels > 0 && number_of_channels <= MAX_CHANNELS {
+        Ok(())
+    } else {
+        Err(DomException::IndexSizeError(format!(
+            "Invalid number of channels: {:?} is outside range [1, {:?}]",
+            number_of_channels, MAX_CHANNELS
+        )))
+    }
+}
+
+/// Check that the channel count is valid for the ChannelMergerNode
+/// see <https://webaudio.github.io/web-audio-api/#audionode-channelcount-constraints>
+///
+/// # Errors
+///
+/// Returns an error if given count is greater than 2
+///
+fn check_valid_channel_count(count: usize) -> Result<(), DomException> {
+    if count == 1 {
+        Ok(())
+    } else {
+        Err(DomException::InvalidStateError(String::from(
+            "channel count of ChannelMergerNode must be equal to 1",
+        )))
+    }
 }
 
 /// Assert that the channel count is valid for the ChannelMergerNode
@@ -37,10 +54,24 @@ pub(crate) fn assert_valid_number_of_channels(number_of_channels: usize) {
 #[track_caller]
 #[inline(always)]
 fn assert_valid_channel_count(count: usize) {
-    assert!(
-        count == 1,
-        "InvalidStateError - channel count of ChannelMergerNode must be equal to 1"
-    );
+    expect_valid(check_valid_channel_count(count));
+}
+
+/// Check that the channel count mode is valid for the ChannelMergerNode
+/// see <https://webaudio.github.io/web-audio-api/#audionode-channelcountmode-constraints>
+///
+/// # Errors
+///
+/// Returns an error if the mode is not equal to Explicit
+///
+fn check_valid_channel_count_mode(mode: ChannelCountMode) -> Result<(), DomException> {
+    if mode == ChannelCountMode::Explicit {
+        Ok(())
+    } else {
+        Err(DomException::InvalidStateError(String::from(
+            "channel count of ChannelMergerNode must be set to Explicit",
+        )))
+    }
 }
 
 /// Assert that the channel count mode is valid for the ChannelMergerNode
@@ -53,10 +84,7 @@ fn assert_valid_channel_count(count: usize) {
 #[track_caller]
 #[inline(always)]
 fn assert_valid_channel_count_mode(mode: ChannelCountMode) {
-    assert!(
-        mode == ChannelCountMode::Explicit,
-        "InvalidStateError - channel count of ChannelMergerNode must be set to Explicit"
-    );
+    expect_valid(check_valid_channel_count_mode(mode));
 }
 
 /// Options for constructing a [`ChannelMergerNode`]
@@ -119,13 +147,32 @@ impl AudioNode for ChannelMergerNode {
 }
 
 impl ChannelMergerNode {
+    /// Create a new ChannelMergerNode
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the options are invalid, see [`Self::try_new`]
     pub fn new<C: BaseAudioContext>(context: &C, options: ChannelMergerOptions) -> Self {
-        context.base().register(move |registration| {
-            assert_valid_number_of_channels(options.number_of_inputs);
-
-            assert_valid_channel_count(options.audio_node_options.channel_count);
-            assert_valid_channel_count_mode(options.audio_node_options.channel_count_mode);
+        expect_valid(Self::try_new(context, options))
+    }
 
+    /// Create a new ChannelMergerNode
+    ///
+    /// # Errors
+    ///
+    /// Returns an `IndexSizeError` if the number of inputs is outside the [1, 32] range, and an
+    /// `InvalidStateError` if the channel count is not 1 or the channel count mode is not
+    /// `Explicit`
+    pub fn try_new<C: BaseAudioContext>(
+        context: &C,
+        options: ChannelMergerOptions,
+    ) -> Result<Self, DomException> {
+        check_valid_number_of_channels(options.number_of_inputs)?;
+
+        check_valid_channel_count(options.audio_node_options.channel_count)?;
+        check_valid_channel_count_mode(options.audio_node_options.channel_count_mode)?;
+
+        let node = context.base().register(move |registration| {
             let node = ChannelMergerNode {
                 registration,
                 channel_config: options.audio_node_options.into(),
@@ -135,7 +182,9 @@ impl ChannelMergerNode {
             let render = ChannelMergerRenderer {};
 
             (node, Box::new(render))
-        })
+        });
+
+        Ok(node)
     }
 }
 
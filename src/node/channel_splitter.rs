@@ -4,29 +4,46 @@ use crate::context::{AudioContextRegistration, BaseAudioContext};
 use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
-use crate::MAX_CHANNELS;
+use crate::{expect_valid, DomException, MAX_CHANNELS};
 
 use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation};
 
 const DEFAULT_NUMBER_OF_OUTPUTS: usize = 6;
 
-/// Assert that the given number of channels is valid for a ChannelMergerNode
+/// Check that the given number of channels is valid for a ChannelMergerNode
 ///
-/// # Panics
+/// # Errors
 ///
-/// This function will panic if:
+/// Returns an error if:
 /// - the given number of channels is outside the [1, 32] range,
 /// 32 being defined by the MAX_CHANNELS constant.
 ///
-#[track_caller]
-#[inline(always)]
-pub(crate) fn assert_valid_number_of_channels(number_of_channels: usize) {
-    assert!(
-        number_of_channels > 0 && number_of_channels <= MAX_CHANNELS,
-        "IndexSizeError - Invalid number of channels: {:?} is outside range [1, {:?}]",
-        number_of_channels,
-        MAX_CHANNELS
-    );
+fn check_valid_number_of_channels(number_of_channels: usize) -> Result<(), DomException> {
+    if number_of_channels > 0 && number_of_channels <= MAX_CHANNELS {
+        Ok(())
+    } else {
+        Err(DomException::IndexSizeError(format!(
+            "Invalid number of channels: {:?} is outside range [1, {:?}]",
+            number_of_channels, MAX_CHANNELS
+        )))
+    }
+}
+
+/// Check that the channel count is valid for the ChannelSplitterNode
+/// see <https://webaudio.github.io/web-audio-api/#audionode-channelcount-constraints>
+///
+/// # Errors
+///
+/// Returns an error if given count is not equal to number of outputs
+///
+fn check_valid_channel_count(count: usize, number_of_outputs: usize) -> Result<(), DomException> {
+    if count == number_of_outputs {
+        Ok(())
+    } else {
+        Err(DomException::InvalidStateError(String::from(
+            "channel count of ChannelSplitterNode must be equal to number of outputs",
+        )))
+    }
 }
 
 /// Assert that the channel count is valid for the ChannelSplitterNode
@@ -39,10 +56,24 @@ pub(crate) fn assert_valid_number_of_channels(number_of_channels: usize) {
 #[track_caller]
 #[inline(always)]
 fn assert_valid_channel_count(count: usize, number_of_outputs: usize) {
-    assert!(
-        count == number_of_outputs,
-        "InvalidStateError - channel count of ChannelSplitterNode must be equal to number of outputs"
-    );
+    expect_valid(check_valid_channel_count(count, number_of_outputs));
+}
+
+/// Check that the channel count mode is valid for the ChannelSplitterNode
+/// see <https://webaudio.github.io/web-audio-api/#audionode-channelcountmode-constraints>
+///
+/// # Errors
+///
+/// Returns an error if the mode is not equal to Explicit
+///
+fn check_valid_channel_count_mode(mode: ChannelCountMode) -> Result<(), DomException> {
+    if mode == ChannelCountMode::Explicit {
+        Ok(())
+    } else {
+        Err(DomException::InvalidStateError(String::from(
+            "channel count of ChannelSplitterNode must be set to Explicit",
+        )))
+    }
 }
 
 /// Assert that the channel count mode is valid for the ChannelSplitterNode
@@ -55,10 +86,26 @@ fn assert_valid_channel_count(count: usize, number_of_outputs: usize) {
 #[track_caller]
 #[inline(always)]
 fn assert_valid_channel_count_mode(mode: ChannelCountMode) {
-    assert!(
-        mode == ChannelCountMode::Explicit,
-        "InvalidStateError - channel count of ChannelSplitterNode must be set to Explicit"
-    );
+    expect_valid(check_valid_channel_count_mode(mode));
+}
+
+/// Check that the channel interpretation is valid for the ChannelSplitterNode
+/// see <https://webaudio.github.io/web-audio-api/#audionode-channelinterpretation-constraints>
+///
+/// # Errors
+///
+/// Returns an error if the mode is not equal to Explicit
+///
+fn check_valid_channel_interpretation(
+    interpretation: ChannelInterpretation,
+) -> Result<(), DomException> {
+    if interpretation == ChannelInterpretation::Discrete {
+        Ok(())
+    } else {
+        Err(DomException::InvalidStateError(String::from(
+            "channel interpretation of ChannelSplitterNode must be set to Discrete",
+        )))
+    }
 }
 
 /// Assert that the channel interpretation is valid for the ChannelSplitterNode
@@ -71,10 +118,7 @@ fn assert_valid_channel_count_mode(mode: ChannelCountMode) {
 #[track_caller]
 #[inline(always)]
 fn assert_valid_channel_interpretation(interpretation: ChannelInterpretation) {
-    assert!(
-        interpretation == ChannelInterpretation::Discrete,
-        "InvalidStateError - channel interpretation of ChannelSplitterNode must be set to Discrete"
-    );
+    expect_valid(check_valid_channel_interpretation(interpretation));
 }
 
 /// Options for constructing a [`ChannelSplitterNode`]
@@ -143,23 +187,42 @@ impl AudioNode for ChannelSplitterNode {
 }
 
 impl ChannelSplitterNode {
-    pub fn new<C: BaseAudioContext>(context: &C, mut options: ChannelSplitterOptions) -> Self {
-        context.base().register(move |registration| {
-            assert_valid_number_of_channels(options.number_of_outputs);
-
-            // if channel count has been explicitly set, we need to check
-            // its value against number of outputs
-            if options.audio_node_options.channel_count != DEFAULT_NUMBER_OF_OUTPUTS {
-                assert_valid_channel_count(
-                    options.audio_node_options.channel_count,
-                    options.number_of_outputs,
-                );
-            }
-            options.audio_node_options.channel_count = options.number_of_outputs;
+    /// Create a new ChannelSplitterNode
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the options are invalid, see [`Self::try_new`]
+    pub fn new<C: BaseAudioContext>(context: &C, options: ChannelSplitterOptions) -> Self {
+        expect_valid(Self::try_new(context, options))
+    }
 
-            assert_valid_channel_count_mode(options.audio_node_options.channel_count_mode);
-            assert_valid_channel_interpretation(options.audio_node_options.channel_interpretation);
+    /// Create a new ChannelSplitterNode
+    ///
+    /// # Errors
+    ///
+    /// Returns an `IndexSizeError` if the number of outputs is outside the [1, 32] range, and an
+    /// `InvalidStateError` if the channel count, channel count mode or channel interpretation
+    /// is invalid
+    pub fn try_new<C: BaseAudioContext>(
+        context: &C,
+        mut options: ChannelSplitterOptions,
+    ) -> Result<Self, DomException> {
+        check_valid_number_of_channels(options.number_of_outputs)?;
+
+        // if channel count has been explicitly set, we need to check
+        // its value against number of outputs
+        if options.audio_node_options.channel_count != DEFAULT_NUMBER_OF_OUTPUTS {
+            check_valid_channel_count(
+                options.audio_node_options.channel_count,
+                options.number_of_outputs,
+            )?;
+        }
+        options.audio_node_options.channel_count = options.number_of_outputs;
+
+        check_valid_channel_count_mode(options.audio_node_options.channel_count_mode)?;
+        check_valid_channel_interpretation(options.audio_node_options.channel_interpretation)?;
 
+        let node = context.base().register(move |registration| {
             let node = ChannelSplitterNode {
                 registration,
                 channel_config: options.audio_node_options.into(),
@@ -171,7 +234,9 @@ impl ChannelSplitterNode {
             };
 
             (node, Box::new(render))
-        })
+        });
+
+        Ok(node)
     }
 }
 
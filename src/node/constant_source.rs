@@ -5,9 +5,9 @@ use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
 use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
-use crate::{assert_valid_time_value, RENDER_QUANTUM_SIZE};
+use crate::{check_valid_time_value, DomException};
 
-use super::{AudioNode, AudioScheduledSourceNode, ChannelConfig};
+use super::{check_can_start, check_can_stop, AudioNode, AudioScheduledSourceNode, ChannelConfig};
 
 /// Options for constructing an [`ConstantSourceNode`]
 // dictionary ConstantSourceOptions {
@@ -98,36 +98,22 @@ impl AudioNode for ConstantSourceNode {
 }
 
 impl AudioScheduledSourceNode for ConstantSourceNode {
-    fn start(&mut self) {
-        let when = self.registration.context().current_time();
-        self.start_at(when);
-    }
-
-    fn start_at(&mut self, when: f64) {
-        assert_valid_time_value(when);
-        assert_eq!(
-            self.start_stop_count, 0,
-            "InvalidStateError - Cannot call `start` twice"
-        );
+    fn try_start_at(&mut self, when: f64) -> Result<(), DomException> {
+        check_valid_time_value(when)?;
+        check_can_start(self.start_stop_count)?;
 
         self.start_stop_count += 1;
         self.registration.post_message(Schedule::Start(when));
+        Ok(())
     }
 
-    fn stop(&mut self) {
-        let when = self.registration.context().current_time();
-        self.stop_at(when);
-    }
-
-    fn stop_at(&mut self, when: f64) {
-        assert_valid_time_value(when);
-        assert_eq!(
-            self.start_stop_count, 1,
-            "InvalidStateError cannot stop before start"
-        );
+    fn try_stop_at(&mut self, when: f64) -> Result<(), DomException> {
+        check_valid_time_value(when)?;
+        check_can_stop(self.start_stop_count)?;
 
         self.start_stop_count += 1;
         self.registration.post_message(Schedule::Stop(when));
+        Ok(())
     }
 }
 
@@ -188,7 +174,7 @@ impl AudioProcessor for ConstantSourceRenderer {
         let output = &mut outputs[0];
 
         let dt = 1. / scope.sample_rate as f64;
-        let next_block_time = scope.current_time + dt * RENDER_QUANTUM_SIZE as f64;
+        let next_block_time = scope.current_time + dt * scope.render_quantum_size as f64;
 
         if self.start_time >= next_block_time {
             output.make_silent();
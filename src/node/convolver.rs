@@ -8,10 +8,36 @@ use crate::context::{AudioContextRegistration, BaseAudioContext};
 use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
-use crate::RENDER_QUANTUM_SIZE;
+
+use crate::{expect_valid, DomException};
 
 use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelInterpretation};
 
+/// Check that the impulse response buffer is valid for the given context sample rate
+///
+/// # Errors
+///
+/// Returns a `NotSupportedError` if the sample rate of the buffer differs from the context
+/// sample rate, or if the buffer does not consist of 1, 2 or 4 channels.
+fn check_valid_buffer(buffer: &AudioBuffer, sample_rate: f32) -> Result<(), DomException> {
+    // If the buffer number of channels is not 1, 2, 4, or if the sample-rate of the buffer is
+    // not the same as the sample-rate of its associated BaseAudioContext, a NotSupportedError
+    // MUST be thrown.
+    if buffer.sample_rate() != sample_rate {
+        return Err(DomException::NotSupportedError(String::from(
+            "sample rate of the convolution buffer must match the audio context",
+        )));
+    }
+
+    if ![1, 2, 4].contains(&buffer.number_of_channels()) {
+        return Err(DomException::NotSupportedError(String::from(
+            "the convolution buffer must consist of 1, 2 or 4 channels",
+        )));
+    }
+
+    Ok(())
+}
+
 /// Scale buffer by an equal-power normalization
 // see - <https://webaudio.github.io/web-audio-api/#dom-convolvernode-normalize>
 fn normalize_buffer(buffer: &AudioBuffer) -> f32 {
@@ -151,12 +177,30 @@ impl ConvolverNode {
     /// Panics when an AudioBuffer is provided via the `ConvolverOptions` with a sample rate
     /// different from the audio context sample rate.
     pub fn new<C: BaseAudioContext>(context: &C, options: ConvolverOptions) -> Self {
+        expect_valid(Self::try_new(context, options))
+    }
+
+    /// returns a `ConvolverNode` instance, see [`Self::new`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotSupportedError` when an AudioBuffer is provided via the `ConvolverOptions`
+    /// with a sample rate different from the audio context sample rate, or with a number of
+    /// channels other than 1, 2 or 4.
+    pub fn try_new<C: BaseAudioContext>(
+        context: &C,
+        options: ConvolverOptions,
+    ) -> Result<Self, DomException> {
         let ConvolverOptions {
             buffer,
             disable_normalization,
             audio_node_options: channel_config,
         } = options;
 
+        if let Some(buffer) = &buffer {
+            check_valid_buffer(buffer, context.sample_rate())?;
+        }
+
         let mut node = context.base().register(move |registration| {
             let renderer = ConvolverRenderer { inner: None };
 
@@ -172,10 +216,10 @@ impl ConvolverNode {
 
         // renderer has been sent to render thread, we can send it messages
         if let Some(buffer) = buffer {
-            node.set_buffer(buffer);
+            node.try_set_buffer(buffer)?;
         }
 
-        node
+        Ok(node)
     }
 
     /// Get the current impulse response buffer
@@ -190,22 +234,18 @@ impl ConvolverNode {
     /// Panics when the sample rate of the provided AudioBuffer differs from the audio context
     /// sample rate.
     pub fn set_buffer(&mut self, buffer: AudioBuffer) {
-        // If the buffer number of channels is not 1, 2, 4, or if the sample-rate of the buffer is
-        // not the same as the sample-rate of its associated BaseAudioContext, a NotSupportedError
-        // MUST be thrown.
-
-        let sample_rate = buffer.sample_rate();
-        assert_eq!(
-            sample_rate,
-            self.context().sample_rate(),
-            "NotSupportedError - sample rate of the convolution buffer must match the audio context"
-        );
+        expect_valid(self.try_set_buffer(buffer));
+    }
 
+    /// Set or update the impulse response buffer, see [`Self::set_buffer`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotSupportedError` when the sample rate of the provided AudioBuffer differs
+    /// from the audio context sample rate, or when it does not consist of 1, 2 or 4 channels.
+    pub fn try_set_buffer(&mut self, buffer: AudioBuffer) -> Result<(), DomException> {
+        check_valid_buffer(&buffer, self.context().sample_rate())?;
         let number_of_channels = buffer.number_of_channels();
-        assert!(
-            [1, 2, 4].contains(&number_of_channels),
-            "NotSupportedError - the convolution buffer must consist of 1, 2 or 4 channels"
-        );
 
         // normalize before padding because the length of the buffer affects the scale
         let scale = if self.normalize {
@@ -214,9 +254,13 @@ impl ConvolverNode {
             1.
         };
 
-        // Pad the response buffer with zeroes so its size is a power of 2, with 2 * 128 as min size
+        // Pad the response buffer with zeroes so its size is a power of 2, with 2 render quanta as
+        // min size, and so it consists of whole render quanta
+        let render_quantum_size = self.context().render_quantum_size();
         let length = buffer.length();
-        let padded_length = length.next_power_of_two().max(2 * RENDER_QUANTUM_SIZE);
+        let padded_length = length.next_power_of_two().max(2 * render_quantum_size);
+        let padded_length =
+            (padded_length + render_quantum_size - 1) / render_quantum_size * render_quantum_size;
         let samples: Vec<_> = (0..number_of_channels)
             .map(|_| {
                 let mut samples = vec![0.; padded_length];
@@ -228,11 +272,13 @@ impl ConvolverNode {
             })
             .collect();
 
-        let padded_buffer = AudioBuffer::from(samples, sample_rate);
-        let convolve = ConvolverRendererInner::new(padded_buffer);
+        let padded_buffer = AudioBuffer::from(samples, buffer.sample_rate());
+        let convolve = ConvolverRendererInner::new(padded_buffer, render_quantum_size);
 
         self.registration.post_message(Some(convolve));
         self.buffer = Some(buffer);
+
+        Ok(())
     }
 
     /// Denotes if the response buffer will be scaled with an equal-power normalization
@@ -314,6 +360,7 @@ impl Fft {
 }
 
 struct ConvolverRendererInner {
+    render_quantum_size: usize,
     num_ir_blocks: usize,
     h: Vec<Complex<f32>>,
     fdl: Vec<Complex<f32>>,
@@ -322,30 +369,31 @@ struct ConvolverRendererInner {
 }
 
 impl ConvolverRendererInner {
-    fn new(response: AudioBuffer) -> Self {
+    fn new(response: AudioBuffer, render_quantum_size: usize) -> Self {
         // mono processing only for now
         let response = response.channel_data(0).as_slice();
 
-        let mut fft2 = Fft::new(2 * RENDER_QUANTUM_SIZE);
+        let mut fft2 = Fft::new(2 * render_quantum_size);
         let p = response.len();
 
-        let num_ir_blocks = p / RENDER_QUANTUM_SIZE;
+        let num_ir_blocks = p / render_quantum_size;
 
-        let mut h = vec![Complex::default(); num_ir_blocks * 2 * RENDER_QUANTUM_SIZE];
+        let mut h = vec![Complex::default(); num_ir_blocks * 2 * render_quantum_size];
         for (resp_fft, resp) in h
-            .chunks_mut(2 * RENDER_QUANTUM_SIZE)
-            .zip(response.chunks(RENDER_QUANTUM_SIZE))
+            .chunks_mut(2 * render_quantum_size)
+            .zip(response.chunks(render_quantum_size))
         {
-            // fill resp_fft with FFT of resp.zero_pad(RENDER_QUANTUM_SIZE)
-            fft2.real()[..RENDER_QUANTUM_SIZE].copy_from_slice(resp);
-            fft2.real()[RENDER_QUANTUM_SIZE..].fill(0.);
+            // fill resp_fft with FFT of resp.zero_pad(render_quantum_size)
+            fft2.real()[..render_quantum_size].copy_from_slice(resp);
+            fft2.real()[render_quantum_size..].fill(0.);
             resp_fft[..fft2.complex().len()].copy_from_slice(fft2.process());
         }
 
-        let fdl = vec![Complex::default(); 2 * RENDER_QUANTUM_SIZE * num_ir_blocks];
-        let out = vec![0.; 2 * RENDER_QUANTUM_SIZE - 1];
+        let fdl = vec![Complex::default(); 2 * render_quantum_size * num_ir_blocks];
+        let out = vec![0.; 2 * render_quantum_size - 1];
 
         Self {
+            render_quantum_size,
             num_ir_blocks,
             h,
             fdl,
@@ -355,13 +403,14 @@ impl ConvolverRendererInner {
     }
 
     fn process(&mut self, input: &[f32], output: &mut [f32]) {
-        self.fft2.real()[..RENDER_QUANTUM_SIZE].copy_from_slice(input);
-        self.fft2.real()[RENDER_QUANTUM_SIZE..].fill(0.);
+        let render_quantum_size = self.render_quantum_size;
+        self.fft2.real()[..render_quantum_size].copy_from_slice(input);
+        self.fft2.real()[render_quantum_size..].fill(0.);
         let spectrum = self.fft2.process();
 
         self.fdl
-            .chunks_mut(2 * RENDER_QUANTUM_SIZE)
-            .zip(self.h.chunks(2 * RENDER_QUANTUM_SIZE))
+            .chunks_mut(2 * render_quantum_size)
+            .zip(self.h.chunks(2 * render_quantum_size))
             .for_each(|(fdl_c, h_c)| {
                 fdl_c
                     .iter_mut()
@@ -374,13 +423,13 @@ impl ConvolverRendererInner {
         self.fft2.complex().copy_from_slice(&self.fdl[..c_len]);
         let inverse = self.fft2.inverse();
         self.out.iter_mut().zip(inverse).for_each(|(o, i)| {
-            *o += i / (2 * RENDER_QUANTUM_SIZE) as f32;
+            *o += i / (2 * render_quantum_size) as f32;
         });
 
-        output.copy_from_slice(&self.out[..RENDER_QUANTUM_SIZE]);
+        output.copy_from_slice(&self.out[..render_quantum_size]);
 
-        roll_zero(&mut self.fdl[..], 2 * RENDER_QUANTUM_SIZE);
-        roll_zero(&mut self.out[..], RENDER_QUANTUM_SIZE);
+        roll_zero(&mut self.fdl[..], 2 * render_quantum_size);
+        roll_zero(&mut self.out[..], render_quantum_size);
     }
 
     fn tail(&mut self, output: &mut AudioRenderQuantum) -> bool {
@@ -390,20 +439,21 @@ impl ConvolverRendererInner {
         }
 
         self.num_ir_blocks -= 1;
+        let render_quantum_size = self.render_quantum_size;
 
         let c_len = self.fft2.complex().len();
         self.fft2.complex().copy_from_slice(&self.fdl[..c_len]);
         let inverse = self.fft2.inverse();
         self.out.iter_mut().zip(inverse).for_each(|(o, i)| {
-            *o += i / (2 * RENDER_QUANTUM_SIZE) as f32;
+            *o += i / (2 * render_quantum_size) as f32;
         });
 
         output
             .channel_data_mut(0)
-            .copy_from_slice(&self.out[..RENDER_QUANTUM_SIZE]);
+            .copy_from_slice(&self.out[..render_quantum_size]);
 
-        roll_zero(&mut self.fdl[..], 2 * RENDER_QUANTUM_SIZE);
-        roll_zero(&mut self.out[..], RENDER_QUANTUM_SIZE);
+        roll_zero(&mut self.fdl[..], 2 * render_quantum_size);
+        roll_zero(&mut self.out[..], render_quantum_size);
 
         self.num_ir_blocks > 0
     }
@@ -507,6 +557,21 @@ mod tests {
         let _ = ConvolverNode::new(&context, options);
     }
 
+    #[test]
+    fn test_try_set_buffer() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let mut conv = ConvolverNode::try_new(&context, ConvolverOptions::default()).unwrap();
+
+        let ir = AudioBuffer::from(vec![vec![1.]; 1], 44100.); // sample_rate differs
+        let error = conv.try_set_buffer(ir).unwrap_err();
+        assert!(matches!(error, DomException::NotSupportedError(_)));
+        assert!(conv.buffer().is_none());
+
+        let ir = AudioBuffer::from(vec![vec![1.]; 2], 48000.);
+        assert!(conv.try_set_buffer(ir).is_ok());
+        assert!(conv.buffer().is_some());
+    }
+
     #[test]
     fn test_constructor_options_buffer() {
         let sample_rate = 44100.;
@@ -3,9 +3,13 @@ use crate::param::{AudioParam, AudioParamDescriptor};
 use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
-use crate::RENDER_QUANTUM_SIZE;
 
-use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelInterpretation};
+use crate::{expect_valid, DomException};
+
+use super::{
+    check_same_context, check_valid_port, AudioNode, AudioNodeOptions, ChannelConfig,
+    ChannelInterpretation,
+};
 
 use std::cell::{Cell, RefCell, RefMut};
 use std::rc::Rc;
@@ -127,28 +131,15 @@ impl AudioNode for DelayNode {
     }
 
     /// Connect a specific output of this AudioNode to a specific input of another node.
-    fn connect_from_output_to_input<'a>(
+    fn try_connect_from_output_to_input<'a>(
         &self,
         dest: &'a dyn AudioNode,
         output: usize,
         input: usize,
-    ) -> &'a dyn AudioNode {
-        assert!(
-            self.context() == dest.context(),
-            "InvalidAccessError - Attempting to connect nodes from different contexts",
-        );
-
-        assert!(
-            self.number_of_outputs() > output,
-            "IndexSizeError - output port {} is out of bounds",
-            output
-        );
-
-        assert!(
-            dest.number_of_inputs() > input,
-            "IndexSizeError - input port {} is out of bounds",
-            input
-        );
+    ) -> Result<&'a dyn AudioNode, DomException> {
+        check_same_context(self.context(), dest.context(), "connect")?;
+        check_valid_port("output", output, self.number_of_outputs())?;
+        check_valid_port("input", input, dest.number_of_inputs())?;
 
         self.context().connect(
             self.reader_registration.id(),
@@ -157,120 +148,72 @@ impl AudioNode for DelayNode {
             input,
         );
 
-        dest
+        Ok(dest)
     }
 
     /// Disconnects all outgoing connections from the AudioNode.
     fn disconnect(&self) {
-        self.context()
-            .disconnect(self.reader_registration.id(), None, None, None);
+        expect_valid(
+            self.context()
+                .disconnect(self.reader_registration.id(), None, None, None),
+        );
     }
 
     /// Disconnects all outputs of the AudioNode that go to a specific destination AudioNode.
-    ///
-    /// # Panics
-    ///
-    /// This function will panic when
-    /// - the AudioContext of the source and destination does not match
-    /// - the source node was not connected to the destination node
-    fn disconnect_dest(&self, dest: &dyn AudioNode) {
-        assert!(
-            self.context() == dest.context(),
-            "InvalidAccessError - Attempting to disconnect nodes from different contexts"
-        );
+    fn try_disconnect_dest(&self, dest: &dyn AudioNode) -> Result<(), DomException> {
+        check_same_context(self.context(), dest.context(), "disconnect")?;
 
         self.context().disconnect(
             self.reader_registration.id(),
             None,
             Some(dest.registration().id()),
             None,
-        );
+        )
     }
 
     /// Disconnects all outgoing connections at the given output port from the AudioNode.
-    ///
-    /// # Panics
-    ///
-    /// This function will panic when
-    /// - if the output port is out of bounds for this node
-    fn disconnect_output(&self, output: usize) {
-        assert!(
-            self.number_of_outputs() > output,
-            "IndexSizeError - output port {} is out of bounds",
-            output
-        );
+    fn try_disconnect_output(&self, output: usize) -> Result<(), DomException> {
+        check_valid_port("output", output, self.number_of_outputs())?;
 
         self.context()
-            .disconnect(self.reader_registration.id(), Some(output), None, None);
+            .disconnect(self.reader_registration.id(), Some(output), None, None)
     }
 
     /// Disconnects a specific output of the AudioNode to a specific destination AudioNode
-    ///
-    /// # Panics
-    ///
-    /// This function will panic when
-    /// - the AudioContext of the source and destination does not match
-    /// - if the output port is out of bounds for the source node
-    /// - the source node was not connected to the destination node
-    fn disconnect_dest_from_output(&self, dest: &dyn AudioNode, output: usize) {
-        assert!(
-            self.context() == dest.context(),
-            "InvalidAccessError - Attempting to disconnect nodes from different contexts"
-        );
-
-        assert!(
-            self.number_of_outputs() > output,
-            "IndexSizeError - output port {} is out of bounds",
-            output
-        );
+    fn try_disconnect_dest_from_output(
+        &self,
+        dest: &dyn AudioNode,
+        output: usize,
+    ) -> Result<(), DomException> {
+        check_same_context(self.context(), dest.context(), "disconnect")?;
+        check_valid_port("output", output, self.number_of_outputs())?;
 
         self.context().disconnect(
             self.reader_registration.id(),
             Some(output),
             Some(dest.registration().id()),
             None,
-        );
+        )
     }
 
     /// Disconnects a specific output of the AudioNode to a specific input of some destination
     /// AudioNode
-    ///
-    /// # Panics
-    ///
-    /// This function will panic when
-    /// - the AudioContext of the source and destination does not match
-    /// - if the input port is out of bounds for the destination node
-    /// - if the output port is out of bounds for the source node
-    /// - the source node was not connected to the destination node
-    fn disconnect_dest_from_output_to_input(
+    fn try_disconnect_dest_from_output_to_input(
         &self,
         dest: &dyn AudioNode,
         output: usize,
         input: usize,
-    ) {
-        assert!(
-            self.context() == dest.context(),
-            "InvalidAccessError - Attempting to disconnect nodes from different contexts"
-        );
-
-        assert!(
-            self.number_of_outputs() > output,
-            "IndexSizeError - output port {} is out of bounds",
-            output
-        );
-
-        assert!(
-            dest.number_of_inputs() > input,
-            "IndexSizeError - input port {} is out of bounds",
-            input
-        );
+    ) -> Result<(), DomException> {
+        check_same_context(self.context(), dest.context(), "disconnect")?;
+        check_valid_port("output", output, self.number_of_outputs())?;
+        check_valid_port("input", input, dest.number_of_inputs())?;
 
         self.context().disconnect(
             self.reader_registration.id(),
             Some(output),
             Some(dest.registration().id()),
             Some(input),
-        );
+        )
     }
 }
 
@@ -281,24 +224,45 @@ impl DelayNode {
     ///
     /// Panics when the max delay value is smaller than zero or langer than three minutes.
     pub fn new<C: BaseAudioContext>(context: &C, options: DelayOptions) -> Self {
+        expect_valid(Self::try_new(context, options))
+    }
+
+    /// Create a new DelayNode, see [`Self::new`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotSupportedError` when the max delay value is smaller than zero or langer
+    /// than three minutes, and a `TypeError` when the delay time is not finite.
+    pub fn try_new<C: BaseAudioContext>(
+        context: &C,
+        options: DelayOptions,
+    ) -> Result<Self, DomException> {
         let sample_rate = context.sample_rate() as f64;
 
         // Specifies the maximum delay time in seconds allowed for the delay line.
         // If specified, this value MUST be greater than zero and less than three
         // minutes or a NotSupportedError exception MUST be thrown. If not specified,
         // then 1 will be used.
-        assert!(
-            options.max_delay_time > 0. && options.max_delay_time < 180.,
-            "NotSupportedError - maxDelayTime MUST be greater than zero and less than three minutes",
-        );
+        if !(options.max_delay_time > 0. && options.max_delay_time < 180.) {
+            return Err(DomException::NotSupportedError(String::from(
+                "maxDelayTime MUST be greater than zero and less than three minutes",
+            )));
+        }
+
+        if !options.delay_time.is_finite() {
+            return Err(DomException::TypeError(String::from(
+                "The provided delay time is non-finite.",
+            )));
+        }
 
         // Allocate large enough ring buffer to store all delayed samples.
         // We add one extra slot in the ring buffer so that reader never reads the
         // same entry in history as the writer, even if `delay_time == max_delay_time`
         // of if `max_delay_time < quantum duration`
         let max_delay_time = options.max_delay_time;
+        let render_quantum_size = context.render_quantum_size();
         let num_quanta =
-            (max_delay_time * sample_rate / RENDER_QUANTUM_SIZE as f64).ceil() as usize;
+            (max_delay_time * sample_rate / render_quantum_size as f64).ceil() as usize;
         let ring_buffer = Vec::with_capacity(num_quanta + 1);
 
         let shared_ring_buffer = Rc::new(RefCell::new(ring_buffer));
@@ -334,6 +298,7 @@ impl DelayNode {
                     in_cycle: false,
                     last_written_index_checked: None,
                     latest_frame_written: latest_frame_written_clone,
+                    playback_infos: vec![PlaybackInfo::default(); render_quantum_size],
                 };
 
                 let node = DelayNode {
@@ -364,7 +329,7 @@ impl DelayNode {
         context.base().mark_cycle_breaker(&node.writer_registration);
         context.base().connect(writer_id, reader_id, 0, 0);
 
-        node
+        Ok(node)
     }
 
     /// A-rate [`AudioParam`] representing the amount of delay (in seconds) to apply.
@@ -463,6 +428,10 @@ impl AudioProcessor for DelayWriter {
     fn has_side_effects(&self) -> bool {
         true // message passing
     }
+
+    fn requires_render_thread(&self) -> bool {
+        true // shares the ring buffer with the DelayReader
+    }
 }
 
 impl DelayWriter {
@@ -497,6 +466,8 @@ struct DelayReader {
     last_written_index: Rc<Cell<Option<usize>>>,
     // local copy of shared `last_written_index` so as to avoid render ordering issues
     last_written_index_checked: Option<usize>,
+    // playback infos of the current block, preallocated to the render quantum size
+    playback_infos: Vec<PlaybackInfo>,
 }
 
 // SAFETY:
@@ -545,10 +516,11 @@ impl AudioProcessor for DelayReader {
         let delay = params.get(&self.delay_time);
         let sample_rate = scope.sample_rate as f64;
         let dt = 1. / sample_rate;
-        let quantum_duration = RENDER_QUANTUM_SIZE as f64 * dt;
+        let render_quantum_size = scope.render_quantum_size;
+        let quantum_duration = render_quantum_size as f64 * dt;
         let ring_size = ring_buffer.len() as i32;
         let ring_index = self.index as i32;
-        let mut playback_infos = [PlaybackInfo::default(); RENDER_QUANTUM_SIZE];
+        let playback_infos = &mut self.playback_infos[..];
 
         if delay.len() == 1 {
             playback_infos[0] = Self::get_playback_infos(
@@ -561,7 +533,7 @@ impl AudioProcessor for DelayReader {
                 ring_index,
             );
 
-            for i in 1..RENDER_QUANTUM_SIZE {
+            for i in 1..render_quantum_size {
                 let PlaybackInfo {
                     prev_block_index,
                     prev_frame_index,
@@ -571,7 +543,7 @@ impl AudioProcessor for DelayReader {
                 let mut prev_block_index = prev_block_index;
                 let mut prev_frame_index = prev_frame_index + 1;
 
-                if prev_frame_index >= RENDER_QUANTUM_SIZE {
+                if prev_frame_index >= render_quantum_size {
                     prev_block_index = (prev_block_index + 1) % ring_buffer.len();
                     prev_frame_index = 0;
                 }
@@ -626,7 +598,7 @@ impl AudioProcessor for DelayReader {
                     let mut next_block_index = prev_block_index;
                     let mut next_frame_index = prev_frame_index + 1;
 
-                    if next_frame_index >= RENDER_QUANTUM_SIZE {
+                    if next_frame_index >= render_quantum_size {
                         next_block_index = (next_block_index + 1) % ring_buffer.len();
                         next_frame_index = 0;
                     }
@@ -681,6 +653,10 @@ impl AudioProcessor for DelayReader {
 
         true
     }
+
+    fn requires_render_thread(&self) -> bool {
+        true // shares the ring buffer with the DelayWriter
+    }
 }
 
 impl DelayReader {
@@ -706,7 +682,8 @@ impl DelayReader {
         let position = sample_index - num_samples;
         let position_floored = position.floor();
         // find address of the frame in the ring buffer just before `position`
-        let num_frames = RENDER_QUANTUM_SIZE as i32;
+        // (the render quantum size is recovered from the quantum duration)
+        let num_frames = (quantum_duration * sample_rate).round() as i32;
 
         // offset of the block in which the target sample is recorded
         // we need to be `float` here so that `floor()` behaves as expected
@@ -752,6 +729,22 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_try_new_invalid_max_delay_time() {
+        let context = OfflineAudioContext::new(1, 128, 48_000.);
+
+        for max_delay_time in [0., 180., f64::NAN] {
+            let options = DelayOptions {
+                max_delay_time,
+                ..DelayOptions::default()
+            };
+            let error = DelayNode::try_new(&context, options).unwrap_err();
+            assert!(matches!(error, DomException::NotSupportedError(_)));
+        }
+
+        assert!(context.try_create_delay(2.).is_ok());
+    }
+
     #[test]
     fn test_audioparam_value_applies_immediately() {
         let context = OfflineAudioContext::new(1, 128, 48_000.);
@@ -6,7 +6,7 @@ use crate::param::{AudioParam, AudioParamDescriptor};
 use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
-use crate::{AtomicF32, RENDER_QUANTUM_SIZE};
+use crate::AtomicF32;
 
 use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation};
 
@@ -250,8 +250,9 @@ impl DynamicsCompressorNode {
 
             // define the number of buffers we need to have a delay line of ~6ms
             // const delay = new DelayNode(context, {delayTime: 0.006});
+            let render_quantum_size = context.render_quantum_size();
             let ring_buffer_size =
-                (context.sample_rate() * 0.006 / RENDER_QUANTUM_SIZE as f32).ceil() as usize + 1;
+                (context.sample_rate() * 0.006 / render_quantum_size as f32).ceil() as usize + 1;
             let ring_buffer = Vec::<AudioRenderQuantum>::with_capacity(ring_buffer_size);
 
             let render = DynamicsCompressorRenderer {
@@ -264,6 +265,7 @@ impl DynamicsCompressorNode {
                 ring_buffer,
                 ring_index: 0,
                 prev_detector_value: 0.,
+                reduction_gains: vec![0.; render_quantum_size],
             };
 
             let node = DynamicsCompressorNode {
@@ -316,6 +318,8 @@ struct DynamicsCompressorRenderer {
     ring_buffer: Vec<AudioRenderQuantum>,
     ring_index: usize,
     prev_detector_value: f32,
+    // reduction gains (lin) of the current block, preallocated to the render quantum size
+    reduction_gains: Vec<f32>,
 }
 
 // SAFETY:
@@ -391,10 +395,9 @@ impl AudioProcessor for DynamicsCompressorRenderer {
         let mut prev_detector_value = self.prev_detector_value;
 
         let mut reduction_gain = 0.; // dB
-        let mut reduction_gains = [0.; 128]; // lin
-        let mut detector_values = [0.; 128]; // lin
+        let reduction_gains = &mut self.reduction_gains[..]; // lin
 
-        for i in 0..RENDER_QUANTUM_SIZE {
+        for (i, gain) in reduction_gains.iter_mut().enumerate() {
             // pick highest value for this index across all input channels
             // @tbc - this seems to be what is done in chrome
             let mut max = f32::MIN;
@@ -435,11 +438,10 @@ impl AudioProcessor for DynamicsCompressorRenderer {
                 release_tau * prev_detector_value + (1. - release_tau) * sample_attenuation
             };
 
-            detector_values[i] = detector_value;
             // cdB = -yL + make up gain
             reduction_gain = -1. * detector_value + makeup_gain;
             // convert to lin now, so we just to multiply samples later
-            reduction_gains[i] = db_to_lin(reduction_gain);
+            *gain = db_to_lin(reduction_gain);
             // update prev_detector_value for next sample
             prev_detector_value = detector_value;
         }
@@ -470,7 +472,7 @@ impl AudioProcessor for DynamicsCompressorRenderer {
         output.channels_mut().iter_mut().for_each(|channel| {
             channel
                 .iter_mut()
-                .zip(reduction_gains.iter())
+                .zip(self.reduction_gains.iter())
                 .for_each(|(o, g)| *o *= g);
         });
 
@@ -484,6 +486,7 @@ mod tests {
 
     use crate::context::OfflineAudioContext;
     use crate::node::AudioScheduledSourceNode;
+    use crate::RENDER_QUANTUM_SIZE;
 
     use super::*;
 
@@ -8,59 +8,86 @@ use crate::context::{AudioContextRegistration, BaseAudioContext};
 use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
-use crate::MAX_CHANNELS;
+use crate::{expect_valid, DomException, MAX_CHANNELS};
 
 use super::{AudioNode, AudioNodeOptions, ChannelConfig};
 
 /// Filter order is limited to 20
 const MAX_IIR_COEFFS_LEN: usize = 20;
 
-/// Assert that the feedforward coefficients are valid
+/// Check that the feedforward coefficients are valid
 /// see <https://webaudio.github.io/web-audio-api/#dom-baseaudiocontext-createiirfilter-feedforward>
 ///
-/// # Panics
+/// # Errors
 ///
-/// This function panics if:
+/// Returns an error if:
 /// - coefs length is 0 and greater than 20
 /// - all coefs are zeros
 ///
+fn check_valid_feedforward_coefs(coefs: &[f64]) -> Result<(), DomException> {
+    if coefs.is_empty() || coefs.len() > MAX_IIR_COEFFS_LEN {
+        return Err(DomException::NotSupportedError(format!(
+            "IIR Filter feedforward coefficients should have length >= 0 and <= {}",
+            MAX_IIR_COEFFS_LEN,
+        )));
+    }
+
+    if coefs.iter().all(|&f| f == 0.) {
+        return Err(DomException::InvalidStateError(String::from(
+            "IIR Filter feedforward coefficients cannot be all zeros",
+        )));
+    }
+
+    Ok(())
+}
+
+/// Assert that the feedforward coefficients are valid
+///
+/// # Panics
+///
+/// This function panics if the coefficients are not valid, see
+/// [`check_valid_feedforward_coefs`]
 #[track_caller]
 #[inline(always)]
 fn assert_valid_feedforward_coefs(coefs: &[f64]) {
-    assert!(
-        !coefs.is_empty() && coefs.len() <= MAX_IIR_COEFFS_LEN,
-        "NotSupportedError - IIR Filter feedforward coefficients should have length >= 0 and <= {}",
-        MAX_IIR_COEFFS_LEN,
-    );
-
-    assert!(
-        !coefs.iter().all(|&f| f == 0.),
-        "InvalidStateError - IIR Filter feedforward coefficients cannot be all zeros"
-    );
+    expect_valid(check_valid_feedforward_coefs(coefs));
 }
 
-/// Assert that the feedforward coefficients are valid
-/// see <https://webaudio.github.io/web-audio-api/#dom-baseaudiocontext-createiirfilter-feedforward>
+/// Check that the feedback coefficients are valid
+/// see <https://webaudio.github.io/web-audio-api/#dom-baseaudiocontext-createiirfilter-feedback>
 ///
-/// # Panics
+/// # Errors
 ///
-/// This function panics if:
+/// Returns an error if:
 /// - coefs length is 0 and greater than 20
 /// - first coef is zero
 ///
+fn check_valid_feedback_coefs(coefs: &[f64]) -> Result<(), DomException> {
+    if coefs.is_empty() || coefs.len() > MAX_IIR_COEFFS_LEN {
+        return Err(DomException::NotSupportedError(format!(
+            "IIR Filter feedback coefficients should have length >= 0 and <= {}",
+            MAX_IIR_COEFFS_LEN,
+        )));
+    }
+
+    if coefs[0] == 0. {
+        return Err(DomException::InvalidStateError(String::from(
+            "IIR Filter feedback first coefficient cannot be zero",
+        )));
+    }
+
+    Ok(())
+}
+
+/// Assert that the feedback coefficients are valid
+///
+/// # Panics
+///
+/// This function panics if the coefficients are not valid, see [`check_valid_feedback_coefs`]
 #[track_caller]
 #[inline(always)]
 fn assert_valid_feedback_coefs(coefs: &[f64]) {
-    assert!(
-        !coefs.is_empty() && coefs.len() <= MAX_IIR_COEFFS_LEN,
-        "NotSupportedError - IIR Filter feedback coefficients should have length >= 0 and <= {}",
-        MAX_IIR_COEFFS_LEN,
-    );
-
-    assert_ne!(
-        coefs[0], 0.,
-        "InvalidStateError - IIR Filter feedback first coefficient cannot be zero"
-    );
+    expect_valid(check_valid_feedback_coefs(coefs));
 }
 
 /// Options for constructing a [`IIRFilterNode`]
@@ -183,6 +210,29 @@ impl IIRFilterNode {
     /// - feedback first coef is zero
     ///
     pub fn new<C: BaseAudioContext>(context: &C, options: IIRFilterOptions) -> Self {
+        assert_valid_feedforward_coefs(&options.feedforward);
+        assert_valid_feedback_coefs(&options.feedback);
+        Self::new_unchecked(context, options)
+    }
+
+    /// Creates an `IirFilterNode`, see [`Self::new`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotSupportedError` if a coefs length is 0 or greater than 20, and an
+    /// `InvalidStateError` if the feedforward coefs are all zeros or the first feedback coef
+    /// is zero
+    pub fn try_new<C: BaseAudioContext>(
+        context: &C,
+        options: IIRFilterOptions,
+    ) -> Result<Self, DomException> {
+        check_valid_feedforward_coefs(&options.feedforward)?;
+        check_valid_feedback_coefs(&options.feedback)?;
+        Ok(Self::new_unchecked(context, options))
+    }
+
+    /// Creates an `IirFilterNode` from options that have been validated
+    fn new_unchecked<C: BaseAudioContext>(context: &C, options: IIRFilterOptions) -> Self {
         context.base().register(move |registration| {
             let IIRFilterOptions {
                 feedforward,
@@ -190,9 +240,6 @@ impl IIRFilterNode {
                 audio_node_options: channel_config,
             } = options;
 
-            assert_valid_feedforward_coefs(&feedforward);
-            assert_valid_feedback_coefs(&feedback);
-
             let render = IirFilterRenderer::new(feedforward.clone(), feedback.clone());
 
             let node = Self {
@@ -484,6 +531,25 @@ mod tests {
         assert_valid_feedback_coefs(&feedback);
     }
 
+    #[test]
+    #[should_panic(expected = "InvalidStateError - IIR Filter feedback first coefficient")]
+    fn test_new_invalid_coefs_panics() {
+        let context = OfflineAudioContext::new(1, 1, 44_100.);
+        let _iir = context.create_iir_filter(vec![1.], vec![0.]);
+    }
+
+    #[test]
+    fn test_try_new_invalid_coefs() {
+        let context = OfflineAudioContext::new(1, 1, 44_100.);
+        let options = IIRFilterOptions {
+            feedback: vec![1.],
+            feedforward: vec![0.; 3],
+            audio_node_options: AudioNodeOptions::default(),
+        };
+        let error = IIRFilterNode::try_new(&context, options).unwrap_err();
+        assert!(matches!(error, DomException::InvalidStateError(_)));
+    }
+
     #[test]
     #[should_panic]
     fn test_frequency_response_arguments() {
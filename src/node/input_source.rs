@@ -0,0 +1,99 @@
+use crate::context::{AudioContext, AudioContextRegistration, BaseAudioContext};
+use crate::io::DuplexInput;
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+
+use super::{AudioNode, ChannelConfig};
+
+/// An audio source from the input device that is captured in the same stream as the audio
+/// output of an [`AudioContext`]
+///
+/// The input device is configured with
+/// [`AudioContextOptions::input`](crate::context::AudioContextOptions::input). Unlike a
+/// [`MediaStreamAudioSourceNode`](crate::node::MediaStreamAudioSourceNode) playing the stream of
+/// [`get_user_media_sync`](crate::media_devices::get_user_media_sync), the input is handed to the
+/// audio graph in the callbacks of the output stream. A sound that is captured at a given moment
+/// is played back [`input_latency`](AudioContext::input_latency) +
+/// [`output_latency`](AudioContext::output_latency) seconds later.
+///
+/// With the `cubeb` backend and the `"loopback"` sink the input and output share a single duplex
+/// stream, so the input is sample-aligned with the render quanta of the audio graph. The `cpal`
+/// backend captures the input with a separate stream, which runs on the clock of the input
+/// device. Its frames are buffered until the next output callback, so the input may drift or
+/// drop frames relative to the output.
+///
+/// All the nodes created for a context play the same input.
+///
+/// - see also: [`AudioContext::create_input_source`]
+#[derive(Debug)]
+pub struct InputAudioSourceNode {
+    registration: AudioContextRegistration,
+    channel_config: ChannelConfig,
+}
+
+impl AudioNode for InputAudioSourceNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        0
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl InputAudioSourceNode {
+    /// Create a new `InputAudioSourceNode`
+    ///
+    /// # Panics
+    ///
+    /// This method will panic when the context does not capture an input.
+    pub fn new(context: &AudioContext) -> Self {
+        let input = context
+            .duplex_input()
+            .expect("InvalidStateError - the AudioContext does not capture an input")
+            .clone();
+
+        context.base().register(move |registration| {
+            let node = InputAudioSourceNode {
+                registration,
+                channel_config: ChannelConfig::default(),
+            };
+
+            let render = InputAudioSourceRenderer { input };
+
+            (node, Box::new(render))
+        })
+    }
+}
+
+struct InputAudioSourceRenderer {
+    input: DuplexInput,
+}
+
+impl AudioProcessor for InputAudioSourceRenderer {
+    fn process(
+        &mut self,
+        _inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single output node
+        let output = &mut outputs[0];
+
+        // the render thread has provided the input of this render quantum
+        self.input.copy_quantum(output);
+
+        // source node
+        true
+    }
+}
@@ -1,7 +1,6 @@
 use crate::context::{AudioContextRegistration, BaseAudioContext};
 use crate::resampling::Resampler;
 use crate::MediaElement;
-use crate::RENDER_QUANTUM_SIZE;
 
 use super::{AudioNode, ChannelConfig, MediaStreamRenderer};
 
@@ -90,12 +89,14 @@ impl MediaElementAudioSourceNode {
                 channel_config: ChannelConfig::default(),
             };
 
-            let stream = options
+            let mut stream = options
                 .media_element
                 .take_stream()
                 .expect("InvalidStateError - stream already taken");
+            stream.set_render_quantum_size(context.render_quantum_size());
 
-            let resampler = Resampler::new(context.sample_rate(), RENDER_QUANTUM_SIZE, stream);
+            let resampler =
+                Resampler::new(context.sample_rate(), context.render_quantum_size(), stream);
 
             let render = MediaStreamRenderer::new(resampler);
 
@@ -1,7 +1,6 @@
 use crate::context::{AudioContextRegistration, BaseAudioContext};
 use crate::media_streams::MediaStream;
-use crate::resampling::Resampler;
-use crate::RENDER_QUANTUM_SIZE;
+use crate::resampling::{JitterBuffer, JitterBufferStats};
 
 use super::{AudioNode, ChannelConfig, MediaStreamRenderer};
 
@@ -18,16 +17,42 @@ pub struct MediaStreamAudioSourceOptions<'a> {
     pub media_stream: &'a MediaStream,
 }
 
+/// Statistics of the jitter buffer of a [`MediaStreamAudioSourceNode`] or
+/// [`MediaStreamTrackAudioSourceNode`](crate::node::MediaStreamTrackAudioSourceNode)
+///
+/// A live stream, such as the microphone input, is produced on the clock of another device. The
+/// source node buffers the stream and compensates the drift between both clocks by fine-tuning
+/// the resampling ratio. The frames are counted at the sample rate of the stream.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct MediaStreamSourceStats {
+    /// Number of frames buffered ahead of playback
+    pub buffered_frames: usize,
+    /// Number of buffered frames the jitter buffer aims for, it adapts to the size of the buffers
+    /// yielded by the stream
+    pub target_frames: usize,
+    /// Estimated drift of the clock of the stream relative to the clock of the context, e.g.
+    /// `0.0001` when the stream runs 100 ppm fast
+    pub estimated_drift: f64,
+    /// Total number of silent frames inserted when the buffer ran dry
+    pub inserted_frames: u64,
+    /// Total number of frames dropped when the buffer overflowed
+    pub dropped_frames: u64,
+}
+
 /// An audio source from a [`MediaStream`] (e.g. microphone input)
 ///
 /// IMPORTANT: the media stream is polled on the render thread so you must ensure the media stream
 /// iterator never blocks. Use a
 /// [`MediaElementAudioSourceNode`](crate::node::MediaElementAudioSourceNode) for real time safe
 /// media playback.
+///
+/// A live stream yields an empty buffer when no frames are available yet. Its clock drift is
+/// compensated by an adaptive jitter buffer, see [`stats`](Self::stats).
 #[derive(Debug)]
 pub struct MediaStreamAudioSourceNode {
     registration: AudioContextRegistration,
     channel_config: ChannelConfig,
+    stats: JitterBufferStats,
 }
 
 impl AudioNode for MediaStreamAudioSourceNode {
@@ -59,20 +84,31 @@ impl MediaStreamAudioSourceNode {
         options: MediaStreamAudioSourceOptions<'_>,
     ) -> Self {
         context.base().register(move |registration| {
-            let node = MediaStreamAudioSourceNode {
-                registration,
-                channel_config: ChannelConfig::default(),
-            };
+            let stats = JitterBufferStats::new();
 
-            let resampler = Resampler::new(
+            let jitter_buffer = JitterBuffer::new(
                 context.sample_rate(),
-                RENDER_QUANTUM_SIZE,
+                context.render_quantum_size(),
                 options.media_stream.get_tracks()[0].iter(),
+                stats.clone(),
             );
 
-            let render = MediaStreamRenderer::new(resampler);
+            let node = MediaStreamAudioSourceNode {
+                registration,
+                channel_config: ChannelConfig::default(),
+                stats,
+            };
+
+            let render = MediaStreamRenderer::new(jitter_buffer);
 
             (node, Box::new(render))
         })
     }
+
+    /// Statistics of the jitter buffer of the media stream
+    ///
+    /// The statistics are updated by the render thread for every render quantum.
+    pub fn stats(&self) -> MediaStreamSourceStats {
+        self.stats.load()
+    }
 }
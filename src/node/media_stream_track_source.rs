@@ -1,9 +1,8 @@
 use crate::context::{AudioContextRegistration, BaseAudioContext};
 use crate::media_streams::MediaStreamTrack;
-use crate::resampling::Resampler;
-use crate::RENDER_QUANTUM_SIZE;
+use crate::resampling::{JitterBuffer, JitterBufferStats};
 
-use super::{AudioNode, ChannelConfig, MediaStreamRenderer};
+use super::{AudioNode, ChannelConfig, MediaStreamRenderer, MediaStreamSourceStats};
 
 /// Options for constructing a [`MediaStreamTrackAudioSourceNode`]
 // dictionary MediaStreamTrackAudioSourceOptions {
@@ -26,6 +25,9 @@ pub struct MediaStreamTrackAudioSourceOptions<'a> {
 /// frame.  Use a [`MediaElementAudioSourceNode`](crate::node::MediaElementAudioSourceNode) for
 /// real time safe media playback.
 ///
+/// A live track yields an empty buffer when no frames are available yet. Its clock drift is
+/// compensated by an adaptive jitter buffer, see [`stats`](Self::stats).
+///
 /// # Example
 ///
 /// ```no_run
@@ -62,6 +64,7 @@ pub struct MediaStreamTrackAudioSourceOptions<'a> {
 pub struct MediaStreamTrackAudioSourceNode {
     registration: AudioContextRegistration,
     channel_config: ChannelConfig,
+    stats: JitterBufferStats,
 }
 
 impl AudioNode for MediaStreamTrackAudioSourceNode {
@@ -88,20 +91,31 @@ impl MediaStreamTrackAudioSourceNode {
         options: MediaStreamTrackAudioSourceOptions<'_>,
     ) -> Self {
         context.base().register(move |registration| {
-            let node = MediaStreamTrackAudioSourceNode {
-                registration,
-                channel_config: ChannelConfig::default(),
-            };
+            let stats = JitterBufferStats::new();
 
-            let resampler = Resampler::new(
+            let jitter_buffer = JitterBuffer::new(
                 context.sample_rate(),
-                RENDER_QUANTUM_SIZE,
+                context.render_quantum_size(),
                 options.media_stream_track.iter(),
+                stats.clone(),
             );
 
-            let render = MediaStreamRenderer::new(resampler);
+            let node = MediaStreamTrackAudioSourceNode {
+                registration,
+                channel_config: ChannelConfig::default(),
+                stats,
+            };
+
+            let render = MediaStreamRenderer::new(jitter_buffer);
 
             (node, Box::new(render))
         })
     }
+
+    /// Statistics of the jitter buffer of the media stream track
+    ///
+    /// The statistics are updated by the render thread for every render quantum.
+    pub fn stats(&self) -> MediaStreamSourceStats {
+        self.stats.load()
+    }
 }
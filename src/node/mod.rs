@@ -39,6 +39,8 @@ mod gain;
 pub use gain::*;
 mod iir_filter;
 pub use iir_filter::*;
+mod input_source;
+pub use input_source::*;
 mod media_element_source;
 pub use media_element_source::*;
 mod media_stream_destination;
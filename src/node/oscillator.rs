@@ -7,11 +7,11 @@ use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
 use crate::PeriodicWave;
-use crate::{assert_valid_time_value, RENDER_QUANTUM_SIZE};
+use crate::{check_valid_time_value, DomException};
 
 use super::{
-    precomputed_sine_table, AudioNode, AudioNodeOptions, AudioScheduledSourceNode, ChannelConfig,
-    TABLE_LENGTH_USIZE,
+    check_can_start, check_can_stop, precomputed_sine_table, AudioNode, AudioNodeOptions,
+    AudioScheduledSourceNode, ChannelConfig, TABLE_LENGTH_USIZE,
 };
 
 fn get_phase_incr(freq: f32, detune: f32, sample_rate: f64) -> f64 {
@@ -165,36 +165,22 @@ impl AudioNode for OscillatorNode {
 }
 
 impl AudioScheduledSourceNode for OscillatorNode {
-    fn start(&mut self) {
-        let when = self.registration.context().current_time();
-        self.start_at(when);
-    }
-
-    fn start_at(&mut self, when: f64) {
-        assert_valid_time_value(when);
-        assert_eq!(
-            self.start_stop_count, 0,
-            "InvalidStateError - Cannot call `start` twice"
-        );
+    fn try_start_at(&mut self, when: f64) -> Result<(), DomException> {
+        check_valid_time_value(when)?;
+        check_can_start(self.start_stop_count)?;
 
         self.start_stop_count += 1;
         self.registration.post_message(Schedule::Start(when));
+        Ok(())
     }
 
-    fn stop(&mut self) {
-        let when = self.registration.context().current_time();
-        self.stop_at(when);
-    }
-
-    fn stop_at(&mut self, when: f64) {
-        assert_valid_time_value(when);
-        assert_eq!(
-            self.start_stop_count, 1,
-            "InvalidStateError cannot stop before start"
-        );
+    fn try_stop_at(&mut self, when: f64) -> Result<(), DomException> {
+        check_valid_time_value(when)?;
+        check_can_stop(self.start_stop_count)?;
 
         self.start_stop_count += 1;
         self.registration.post_message(Schedule::Stop(when));
+        Ok(())
     }
 }
 
@@ -374,7 +360,7 @@ impl AudioProcessor for OscillatorRenderer {
 
         let sample_rate = scope.sample_rate as f64;
         let dt = 1. / sample_rate;
-        let num_frames = RENDER_QUANTUM_SIZE;
+        let num_frames = scope.render_quantum_size;
         let next_block_time = scope.current_time + dt * num_frames as f64;
 
         if self.start_time >= next_block_time {
@@ -11,7 +11,6 @@ use crate::param::{AudioParam, AudioParamDescriptor};
 use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
-use crate::RENDER_QUANTUM_SIZE;
 
 use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation};
 
@@ -31,13 +30,18 @@ pub(crate) fn assert_valid_cone_outer_gain(value: f64) {
     );
 }
 
-/// Load the HRTF processor for the given sample_rate
+/// Load the HRTF processor for the given sample_rate and render quantum size
 ///
 /// The included data contains the impulse responses at 44100 Hertz, so it needs to be resampled
 /// for other values (which can easily take 100s of milliseconds). Therefore cache the result (per
-/// sample rate) in a global variable and clone it every time a new panner is created.
-pub(crate) fn load_hrtf_processor(sample_rate: u32) -> (HrtfProcessor, usize) {
-    static INSTANCE: OnceLock<Mutex<HashMap<u32, (HrtfProcessor, usize)>>> = OnceLock::new();
+/// sample rate and render quantum size) in a global variable and clone it every time a new panner
+/// is created.
+pub(crate) fn load_hrtf_processor(
+    sample_rate: u32,
+    render_quantum_size: usize,
+) -> (HrtfProcessor, usize) {
+    type HrtfCache = HashMap<(u32, usize), (HrtfProcessor, usize)>;
+    static INSTANCE: OnceLock<Mutex<HrtfCache>> = OnceLock::new();
     let cache = INSTANCE.get_or_init(|| Mutex::new(HashMap::new()));
 
     // There's an upstream bug for low sample rates, so work around it by forcing sample_rate to be
@@ -47,7 +51,11 @@ pub(crate) fn load_hrtf_processor(sample_rate: u32) -> (HrtfProcessor, usize) {
 
     // To avoid poisening the cache mutex, don't use the `entry()` API on HashMap
     {
-        if let Some(value) = cache.lock().unwrap().get(&sample_rate) {
+        if let Some(value) = cache
+            .lock()
+            .unwrap()
+            .get(&(sample_rate, render_quantum_size))
+        {
             return value.clone();
         }
     }
@@ -58,11 +66,14 @@ pub(crate) fn load_hrtf_processor(sample_rate: u32) -> (HrtfProcessor, usize) {
     let len = hrir_sphere.len();
 
     let interpolation_steps = 1; // TODO?
-    let samples_per_step = RENDER_QUANTUM_SIZE / interpolation_steps;
+    let samples_per_step = render_quantum_size / interpolation_steps;
     let processor = HrtfProcessor::new(hrir_sphere, interpolation_steps, samples_per_step);
 
     let value = (processor, len);
-    cache.lock().unwrap().insert(sample_rate, value.clone());
+    cache
+        .lock()
+        .unwrap()
+        .insert((sample_rate, render_quantum_size), value.clone());
 
     value
 }
@@ -224,11 +235,11 @@ struct HrtfState {
 }
 
 impl HrtfState {
-    fn new(processor: HrtfProcessor, len: usize) -> Self {
+    fn new(processor: HrtfProcessor, len: usize, render_quantum_size: usize) -> Self {
         Self {
             len,
             processor,
-            output_interleaved: vec![(0., 0.); RENDER_QUANTUM_SIZE],
+            output_interleaved: vec![(0., 0.); render_quantum_size],
             prev_sample_vector: Vec3::new(0., 0., 1.),
             prev_left_samples: vec![],  // will resize accordingly
             prev_right_samples: vec![], // will resize accordingly
@@ -642,8 +653,9 @@ impl PannerNode {
             PanningModelType::EqualPower => None,
             PanningModelType::HRTF => {
                 let sample_rate = self.context().sample_rate() as u32;
-                let (processor, len) = load_hrtf_processor(sample_rate);
-                Some(HrtfState::new(processor, len))
+                let render_quantum_size = self.context().render_quantum_size();
+                let (processor, len) = load_hrtf_processor(sample_rate, render_quantum_size);
+                Some(HrtfState::new(processor, len, render_quantum_size))
             }
         };
 
@@ -685,7 +697,7 @@ impl AudioProcessor for PannerRenderer {
         inputs: &[AudioRenderQuantum],
         outputs: &mut [AudioRenderQuantum],
         params: AudioParamValues<'_>,
-        _scope: &AudioWorkletGlobalScope,
+        scope: &AudioWorkletGlobalScope,
     ) -> bool {
         // Single input/output node
         let input = &inputs[0];
@@ -704,7 +716,7 @@ impl AudioProcessor for PannerRenderer {
                 return false;
             }
 
-            self.tail_time_counter += RENDER_QUANTUM_SIZE;
+            self.tail_time_counter += scope.render_quantum_size;
         }
 
         // for borrow reasons, take the hrtf_state out of self
@@ -1062,6 +1074,7 @@ mod tests {
     use crate::AudioBuffer;
 
     use super::*;
+    use crate::RENDER_QUANTUM_SIZE;
 
     #[test]
     fn test_audioparam_value_applies_immediately() {
@@ -1,36 +1,133 @@
 use super::AudioNode;
-use crate::events::{Event, EventHandler, EventType};
+use crate::context::BaseAudioContext;
+use crate::events::{
+    Event, EventHandler, EventListenerHandle, EventStream, EventType, LocalEventHandler,
+};
+use crate::{expect_valid, DomException};
+
+use std::ops::ControlFlow;
+
+/// Check that a source node can be started, given the number of `start` and `stop` calls so far
+///
+/// # Errors
+///
+/// Returns an `InvalidStateError` if the source was already started
+pub(crate) fn check_can_start(start_stop_count: u8) -> Result<(), DomException> {
+    if start_stop_count == 0 {
+        Ok(())
+    } else {
+        Err(DomException::InvalidStateError(String::from(
+            "Cannot call `start` twice",
+        )))
+    }
+}
+
+/// Check that a source node can be stopped, given the number of `start` and `stop` calls so far
+///
+/// # Errors
+///
+/// Returns an `InvalidStateError` if the source was not started or already stopped
+pub(crate) fn check_can_stop(start_stop_count: u8) -> Result<(), DomException> {
+    match start_stop_count {
+        0 => Err(DomException::InvalidStateError(String::from(
+            "Cannot call `stop` before `start`",
+        ))),
+        1 => Ok(()),
+        _ => Err(DomException::InvalidStateError(String::from(
+            "Cannot call `stop` twice",
+        ))),
+    }
+}
 
 /// Interface of source nodes, controlling start and stop times.
 /// The node will emit silence before it is started, and after it has ended.
+///
+/// Implementors must provide either [`start_at`](Self::start_at) or
+/// [`try_start_at`](Self::try_start_at), and either [`stop_at`](Self::stop_at) or
+/// [`try_stop_at`](Self::try_stop_at). Each of these is implemented in terms of its counterpart.
 pub trait AudioScheduledSourceNode: AudioNode {
     /// Play immediately
     ///
     /// # Panics
     ///
     /// Panics if the source was already started
-    fn start(&mut self);
+    fn start(&mut self) {
+        expect_valid(self.try_start());
+    }
+
+    /// Play immediately, see [`Self::start`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidStateError` if the source was already started
+    fn try_start(&mut self) -> Result<(), DomException> {
+        let when = BaseAudioContext::current_time(self.context());
+        self.try_start_at(when)
+    }
 
     /// Schedule playback start at given timestamp
     ///
     /// # Panics
     ///
-    /// Panics if the source was already started
-    fn start_at(&mut self, when: f64);
+    /// Panics if the source was already started, or if `when` is negative or not finite
+    fn start_at(&mut self, when: f64) {
+        expect_valid(self.try_start_at(when));
+    }
+
+    /// Schedule playback start at given timestamp, see [`Self::start_at`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidStateError` if the source was already started, a `TypeError` or
+    /// `RangeError` if `when` is not finite or negative
+    ///
+    /// The default implementation calls [`Self::start_at`], which may panic instead.
+    fn try_start_at(&mut self, when: f64) -> Result<(), DomException> {
+        self.start_at(when);
+        Ok(())
+    }
 
     /// Stop immediately
     ///
     /// # Panics
     ///
-    /// Panics if the source was already stopped
-    fn stop(&mut self);
+    /// Panics if the source was not started or already stopped
+    fn stop(&mut self) {
+        expect_valid(self.try_stop());
+    }
+
+    /// Stop immediately, see [`Self::stop`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidStateError` if the source was not started or already stopped
+    fn try_stop(&mut self) -> Result<(), DomException> {
+        let when = BaseAudioContext::current_time(self.context());
+        self.try_stop_at(when)
+    }
 
     /// Schedule playback stop at given timestamp
     ///
     /// # Panics
     ///
-    /// Panics if the source was already stopped
-    fn stop_at(&mut self, when: f64);
+    /// Panics if the source was not started or already stopped, or if `when` is negative or not
+    /// finite
+    fn stop_at(&mut self, when: f64) {
+        expect_valid(self.try_stop_at(when));
+    }
+
+    /// Schedule playback stop at given timestamp, see [`Self::stop_at`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidStateError` if the source was not started or already stopped, a
+    /// `TypeError` or `RangeError` if `when` is not finite or negative
+    ///
+    /// The default implementation calls [`Self::stop_at`], which may panic instead.
+    fn try_stop_at(&mut self, when: f64) -> Result<(), DomException> {
+        self.stop_at(when);
+        Ok(())
+    }
 
     /// Register callback to run when the source node has stopped playing
     ///
@@ -40,7 +137,8 @@ pub trait AudioScheduledSourceNode: AudioNode {
     /// because the duration has been reached or if the entire buffer has been played.
     ///
     /// Only a single event handler is active at any time. Calling this method multiple times will
-    /// override the previous event handler.
+    /// override the previous event handler. Use
+    /// [`add_ended_listener`](Self::add_ended_listener) to register additional callbacks.
     fn set_onended<F: FnOnce(Event) + Send + 'static>(&self, callback: F) {
         let callback = move |_| callback(Event { type_: "ended" });
 
@@ -55,12 +153,59 @@ pub trait AudioScheduledSourceNode: AudioNode {
         self.context()
             .clear_event_handler(EventType::Ended(self.registration().id()));
     }
+
+    /// Register an additional callback to run when the source node has stopped playing
+    ///
+    /// Unlike [`set_onended`](Self::set_onended), multiple listeners can be active at the same
+    /// time. The ended event fires at most once, so the listener is removed after it has run.
+    /// Use the returned handle to remove the listener earlier with
+    /// [`remove_event_listener`](crate::context::BaseAudioContext::remove_event_listener).
+    fn add_ended_listener<F: FnOnce(Event) + Send + 'static>(
+        &self,
+        callback: F,
+    ) -> EventListenerHandle {
+        let callback = move |_| callback(Event { type_: "ended" });
+
+        self.context().add_event_handler(
+            EventType::Ended(self.registration().id()),
+            EventHandler::Once(Box::new(callback)),
+        )
+    }
+
+    /// Register an additional callback that need not be `Send` to run when the source node has
+    /// stopped playing
+    ///
+    /// The callback only runs when the events are dispatched on the thread that registered it,
+    /// see [`add_local_event_listener`](crate::context::BaseAudioContext::add_local_event_listener).
+    fn add_local_ended_listener<F: FnOnce(Event) + 'static>(
+        &self,
+        callback: F,
+    ) -> EventListenerHandle {
+        let callback = move |_| callback(Event { type_: "ended" });
+
+        self.context().add_local_event_handler(
+            EventType::Ended(self.registration().id()),
+            LocalEventHandler::Once(Box::new(callback)),
+        )
+    }
+
+    /// Stream that yields a single item when the source node has stopped playing
+    ///
+    /// See [`set_onended`](Self::set_onended) for when the ended event is dispatched.
+    fn ended_stream(&self) -> EventStream<Event> {
+        EventStream::new(
+            self.context(),
+            EventType::Ended(self.registration().id()),
+            |_| ControlFlow::Break(Event { type_: "ended" }),
+        )
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::context::{AudioContextRegistration, BaseAudioContext, OfflineAudioContext};
     use crate::node::{AudioNode, AudioScheduledSourceNode, ChannelConfig};
+    use crate::DomException;
 
     use std::sync::atomic::{AtomicBool, Ordering};
     use std::sync::Arc;
@@ -107,35 +252,19 @@ mod tests {
     }
 
     impl AudioScheduledSourceNode for ConcreteAudioScheduledSourceNode {
-        fn start(&mut self) {
-            match self {
-                Buffer(n) => n.start(),
-                Constant(n) => n.start(),
-                Oscillator(n) => n.start(),
-            }
-        }
-
-        fn start_at(&mut self, when: f64) {
+        fn try_start_at(&mut self, when: f64) -> Result<(), DomException> {
             match self {
-                Buffer(n) => n.start_at(when),
-                Constant(n) => n.start_at(when),
-                Oscillator(n) => n.start_at(when),
+                Buffer(n) => n.try_start_at(when),
+                Constant(n) => n.try_start_at(when),
+                Oscillator(n) => n.try_start_at(when),
             }
         }
 
-        fn stop(&mut self) {
+        fn try_stop_at(&mut self, when: f64) -> Result<(), DomException> {
             match self {
-                Buffer(n) => n.stop(),
-                Constant(n) => n.stop(),
-                Oscillator(n) => n.stop(),
-            }
-        }
-
-        fn stop_at(&mut self, when: f64) {
-            match self {
-                Buffer(n) => n.stop_at(when),
-                Constant(n) => n.stop_at(when),
-                Oscillator(n) => n.stop_at(when),
+                Buffer(n) => n.try_stop_at(when),
+                Constant(n) => n.try_stop_at(when),
+                Oscillator(n) => n.try_stop_at(when),
             }
         }
     }
@@ -200,6 +329,50 @@ mod tests {
         run_no_ended_event(|c| Oscillator(c.create_oscillator()));
     }
 
+    /// Source implemented in terms of the panicking methods only
+    struct PanickingSource(crate::node::ConstantSourceNode);
+
+    impl AudioNode for PanickingSource {
+        fn registration(&self) -> &AudioContextRegistration {
+            self.0.registration()
+        }
+
+        fn channel_config(&self) -> &ChannelConfig {
+            self.0.channel_config()
+        }
+
+        fn number_of_inputs(&self) -> usize {
+            self.0.number_of_inputs()
+        }
+
+        fn number_of_outputs(&self) -> usize {
+            self.0.number_of_outputs()
+        }
+    }
+
+    impl AudioScheduledSourceNode for PanickingSource {
+        fn start_at(&mut self, when: f64) {
+            self.0.start_at(when);
+        }
+
+        fn stop_at(&mut self, when: f64) {
+            self.0.stop_at(when);
+        }
+    }
+
+    #[test]
+    fn test_default_try_methods() {
+        let mut context = OfflineAudioContext::new(1, 128, 44_100.);
+        let mut src = PanickingSource(context.create_constant_source());
+        src.connect(&context.destination());
+        assert!(src.try_start().is_ok());
+        assert!(src.try_stop_at(64. / 44_100.).is_ok());
+
+        let output = context.start_rendering_sync();
+        assert_eq!(output.get_channel_data(0)[63], 1.);
+        assert_eq!(output.get_channel_data(0)[64], 0.);
+    }
+
     fn run_exact_ended_event(
         f: impl FnOnce(&OfflineAudioContext) -> ConcreteAudioScheduledSourceNode,
     ) {
@@ -4,7 +4,7 @@ use crate::events::{AudioProcessingEvent, EventHandler, EventPayload, EventType}
 use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
-use crate::{AudioBuffer, RENDER_QUANTUM_SIZE};
+use crate::AudioBuffer;
 
 use std::any::Any;
 
@@ -75,6 +75,7 @@ impl ScriptProcessorNode {
     /// - `buffer_size` is not 256, 512, 1024, 2048, 4096, 8192, or 16384
     /// - the number of input and output channels are both zero
     /// - either of the channel counts exceed [`crate::MAX_CHANNELS`]
+    /// - `buffer_size` is not a multiple of the render quantum size of the context
     pub fn new<C: BaseAudioContext>(context: &C, options: ScriptProcessorOptions) -> Self {
         let ScriptProcessorOptions {
             buffer_size,
@@ -96,13 +97,21 @@ impl ScriptProcessorNode {
             }
         };
 
+        let render_quantum_size = context.render_quantum_size();
+        assert!(
+            buffer_size % render_quantum_size == 0,
+            "NotSupportedError - bufferSize must be a multiple of the render quantum size {:?}",
+            render_quantum_size,
+        );
+
         context.base().register(move |registration| {
-            let number_of_quanta = buffer_size / RENDER_QUANTUM_SIZE;
+            let number_of_quanta = buffer_size / render_quantum_size;
             let render = ScriptProcessorRenderer {
                 input_buffer: Vec::with_capacity(number_of_quanta),
                 output_buffer: Vec::with_capacity(number_of_quanta),
                 next_output_buffer: Vec::with_capacity(number_of_quanta),
                 buffer_size,
+                render_quantum_size,
                 number_of_output_channels,
             };
 
@@ -177,6 +186,7 @@ struct ScriptProcessorRenderer {
     output_buffer: Vec<AudioRenderQuantum>,
     next_output_buffer: Vec<AudioRenderQuantum>,
     buffer_size: usize,
+    render_quantum_size: usize,
     number_of_output_channels: usize,
 }
 
@@ -222,12 +232,12 @@ impl AudioProcessor for ScriptProcessorRenderer {
                 .unwrap();
             let mut input_samples = vec![vec![0.; self.buffer_size]; number_of_input_channels];
             self.input_buffer.iter().enumerate().for_each(|(i, b)| {
-                let offset = RENDER_QUANTUM_SIZE * i;
+                let offset = self.render_quantum_size * i;
                 b.channels()
                     .iter()
                     .zip(input_samples.iter_mut())
                     .for_each(|(c, o)| {
-                        o[offset..(offset + RENDER_QUANTUM_SIZE)].copy_from_slice(c);
+                        o[offset..(offset + self.render_quantum_size)].copy_from_slice(c);
                     });
             });
             let input_buffer = AudioBuffer::from(input_samples, scope.sample_rate);
@@ -262,7 +272,7 @@ impl AudioProcessor for ScriptProcessorRenderer {
         if let Some(buffer) = msg.downcast_mut::<AudioBuffer>() {
             buffer.channels().iter().enumerate().for_each(|(i, c)| {
                 c.as_slice()
-                    .chunks(RENDER_QUANTUM_SIZE)
+                    .chunks(self.render_quantum_size)
                     .zip(self.next_output_buffer.iter_mut())
                     .for_each(|(s, o)| o.channel_data_mut(i).copy_from_slice(s))
             });
@@ -4,8 +4,9 @@ use rubato::{FftFixedInOut, Resampler as _};
 
 use crate::{
     context::{AudioContextRegistration, BaseAudioContext},
+    expect_valid,
     render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope},
-    RENDER_QUANTUM_SIZE,
+    DomException,
 };
 
 use super::{AudioNode, AudioNodeOptions, ChannelConfig};
@@ -164,10 +165,12 @@ impl WaveShaperNode {
 
         let mut node = context.base().register(move |registration| {
             let sample_rate = context.sample_rate() as usize;
+            let render_quantum_size = context.render_quantum_size();
 
             let renderer = WaveShaperRenderer::new(RendererConfig {
                 oversample,
                 sample_rate,
+                render_quantum_size,
             });
 
             let node = Self {
@@ -205,15 +208,26 @@ impl WaveShaperNode {
     /// Panics if a curve has already been given to the source (though `new` or through
     /// `set_curve`)
     pub fn set_curve(&mut self, curve: Vec<f32>) {
-        assert!(
-            self.curve.is_none(),
-            "InvalidStateError - cannot assign curve twice",
-        );
+        expect_valid(self.try_set_curve(curve));
+    }
+
+    /// Set the distortion `curve` of this node, see [`Self::set_curve`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidStateError` if a curve has already been given to the source
+    pub fn try_set_curve(&mut self, curve: Vec<f32>) -> Result<(), DomException> {
+        if self.curve.is_some() {
+            return Err(DomException::InvalidStateError(String::from(
+                "cannot assign curve twice",
+            )));
+        }
 
         let clone = curve.clone();
 
         self.curve = Some(curve);
         self.registration.post_message(Some(clone));
+        Ok(())
     }
 
     /// Returns the `oversample` faactor of this node
@@ -242,8 +256,8 @@ struct ResamplerConfig {
 }
 
 impl ResamplerConfig {
-    fn upsample_x2(channels: usize, sample_rate: usize) -> Self {
-        let chunk_size_in = RENDER_QUANTUM_SIZE * 2;
+    fn upsample_x2(channels: usize, sample_rate: usize, render_quantum_size: usize) -> Self {
+        let chunk_size_in = render_quantum_size * 2;
         let sample_rate_in = sample_rate;
         let sample_rate_out = sample_rate * 2;
         Self {
@@ -254,8 +268,8 @@ impl ResamplerConfig {
         }
     }
 
-    fn upsample_x4(channels: usize, sample_rate: usize) -> Self {
-        let chunk_size_in = RENDER_QUANTUM_SIZE * 4;
+    fn upsample_x4(channels: usize, sample_rate: usize, render_quantum_size: usize) -> Self {
+        let chunk_size_in = render_quantum_size * 4;
         let sample_rate_in = sample_rate;
         let sample_rate_out = sample_rate * 4;
         Self {
@@ -266,8 +280,8 @@ impl ResamplerConfig {
         }
     }
 
-    fn downsample_x2(channels: usize, sample_rate: usize) -> Self {
-        let chunk_size_in = RENDER_QUANTUM_SIZE;
+    fn downsample_x2(channels: usize, sample_rate: usize, render_quantum_size: usize) -> Self {
+        let chunk_size_in = render_quantum_size;
         let sample_rate_in = sample_rate * 2;
         let sample_rate_out = sample_rate;
         Self {
@@ -278,8 +292,8 @@ impl ResamplerConfig {
         }
     }
 
-    fn downsample_x4(channels: usize, sample_rate: usize) -> Self {
-        let chunk_size_in = RENDER_QUANTUM_SIZE;
+    fn downsample_x4(channels: usize, sample_rate: usize, render_quantum_size: usize) -> Self {
+        let chunk_size_in = render_quantum_size;
         let sample_rate_in = sample_rate * 4;
         let sample_rate_out = sample_rate;
         Self {
@@ -357,6 +371,8 @@ struct RendererConfig {
     oversample: OverSampleType,
     /// Sample rate (equals to audio context sample rate)
     sample_rate: usize,
+    /// Render quantum size (equals to audio context render quantum size)
+    render_quantum_size: usize,
 }
 
 /// `WaveShaperRenderer` represents the rendering part of `WaveShaperNode`
@@ -367,6 +383,8 @@ struct WaveShaperRenderer {
     curve: Option<Vec<f32>>,
     /// Sample rate (equals to audio context sample rate)
     sample_rate: usize,
+    /// Render quantum size (equals to audio context render quantum size)
+    render_quantum_size: usize,
     /// Number of channels used to build the up/down sampler X2
     channels_x2: usize,
     /// Number of channels used to build the up/down sampler X4
@@ -420,11 +438,13 @@ impl AudioProcessor for WaveShaperRenderer {
                         self.upsampler_x2 = Resampler::new(ResamplerConfig::upsample_x2(
                             self.channels_x2,
                             self.sample_rate,
+                            self.render_quantum_size,
                         ));
 
                         self.downsampler_x2 = Resampler::new(ResamplerConfig::downsample_x2(
                             self.channels_x2,
                             self.sample_rate,
+                            self.render_quantum_size,
                         ));
                     }
 
@@ -456,11 +476,13 @@ impl AudioProcessor for WaveShaperRenderer {
                         self.upsampler_x4 = Resampler::new(ResamplerConfig::upsample_x4(
                             self.channels_x4,
                             self.sample_rate,
+                            self.render_quantum_size,
                         ));
 
                         self.downsampler_x4 = Resampler::new(ResamplerConfig::downsample_x4(
                             self.channels_x4,
                             self.sample_rate,
+                            self.render_quantum_size,
                         ));
                     }
 
@@ -525,25 +547,41 @@ impl WaveShaperRenderer {
         let RendererConfig {
             sample_rate,
             oversample,
+            render_quantum_size,
         } = config;
 
         let channels_x2 = 1;
         let channels_x4 = 1;
 
-        let upsampler_x2 = Resampler::new(ResamplerConfig::upsample_x2(channels_x2, sample_rate));
+        let upsampler_x2 = Resampler::new(ResamplerConfig::upsample_x2(
+            channels_x2,
+            sample_rate,
+            render_quantum_size,
+        ));
 
-        let downsampler_x2 =
-            Resampler::new(ResamplerConfig::downsample_x2(channels_x2, sample_rate));
+        let downsampler_x2 = Resampler::new(ResamplerConfig::downsample_x2(
+            channels_x2,
+            sample_rate,
+            render_quantum_size,
+        ));
 
-        let upsampler_x4 = Resampler::new(ResamplerConfig::upsample_x4(channels_x2, sample_rate));
+        let upsampler_x4 = Resampler::new(ResamplerConfig::upsample_x4(
+            channels_x2,
+            sample_rate,
+            render_quantum_size,
+        ));
 
-        let downsampler_x4 =
-            Resampler::new(ResamplerConfig::downsample_x4(channels_x2, sample_rate));
+        let downsampler_x4 = Resampler::new(ResamplerConfig::downsample_x4(
+            channels_x2,
+            sample_rate,
+            render_quantum_size,
+        ));
 
         Self {
             oversample,
             curve: None,
             sample_rate,
+            render_quantum_size,
             channels_x2,
             channels_x4,
             upsampler_x2,
@@ -581,6 +619,7 @@ mod tests {
 
     use crate::context::OfflineAudioContext;
     use crate::node::AudioScheduledSourceNode;
+    use crate::RENDER_QUANTUM_SIZE;
 
     use super::*;
 
@@ -649,6 +688,18 @@ mod tests {
         assert_eq!(shaper.oversample(), OverSampleType::X4);
     }
 
+    #[test]
+    fn try_set_curve_twice() {
+        let context = OfflineAudioContext::new(2, LENGTH, 44_100.);
+
+        let mut shaper = context.create_wave_shaper();
+        assert!(shaper.try_set_curve(vec![1.0]).is_ok());
+
+        let result = shaper.try_set_curve(vec![2.0]);
+        assert!(matches!(result, Err(DomException::InvalidStateError(_))));
+        assert_eq!(shaper.curve(), Some(&[1.0][..]));
+    }
+
     #[test]
     fn change_none_for_curve_after_build() {
         let mut context = OfflineAudioContext::new(2, LENGTH, 44_100.);
@@ -5,8 +5,6 @@ use std::slice::{Iter, IterMut};
 use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex, OnceLock};
 
-use arrayvec::ArrayVec;
-
 use crate::context::AudioContextRegistration;
 use crate::node::{
     AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation,
@@ -14,51 +12,62 @@ use crate::node::{
 use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
-use crate::{assert_valid_time_value, AtomicF32, RENDER_QUANTUM_SIZE};
+use crate::{check_valid_time_value, expect_valid, AtomicF32, DomException};
 
 /// For SetTargetAtTime event, that theoretically cannot end, if the diff between
 /// the current value and the target is below this threshold, the value is set
 /// to target value and the event is considered ended.
 const SNAP_TO_TARGET: f32 = 1e-10;
 
+fn check_is_finite(value: f32) -> Result<(), DomException> {
+    if value.is_finite() {
+        Ok(())
+    } else {
+        Err(DomException::TypeError(String::from(
+            "The provided value is non-finite.",
+        )))
+    }
+}
+
 #[track_caller]
 fn assert_is_finite(value: f32) {
-    assert!(
-        value.is_finite(),
-        "TypeError - The provided value is non-finite."
-    );
+    expect_valid(check_is_finite(value));
 }
 
-#[track_caller]
-fn assert_strictly_positive(value: f64) {
-    assert!(
-        value.is_finite(),
-        "TypeError - The provided value is non-finite."
-    );
-    assert!(
-        value > 0.,
-        "RangeError - duration ({:?}) should be strictly positive",
-        value
-    );
+fn check_strictly_positive(value: f64) -> Result<(), DomException> {
+    if !value.is_finite() {
+        return Err(DomException::TypeError(String::from(
+            "The provided value is non-finite.",
+        )));
+    }
+    if value <= 0. {
+        return Err(DomException::RangeError(format!(
+            "duration ({:?}) should be strictly positive",
+            value
+        )));
+    }
+    Ok(())
 }
 
-#[track_caller]
-fn assert_not_zero(value: f32) {
-    assert_is_finite(value);
-    assert_ne!(
-        value, 0.,
-        "RangeError - value ({:?}) should not be equal to zero",
-        value
-    );
+fn check_not_zero(value: f32) -> Result<(), DomException> {
+    check_is_finite(value)?;
+    if value == 0. {
+        return Err(DomException::RangeError(format!(
+            "value ({:?}) should not be equal to zero",
+            value
+        )));
+    }
+    Ok(())
 }
 
-#[track_caller]
-fn assert_sequence_length(values: &[f32]) {
-    assert!(
-        values.len() >= 2,
-        "InvalidStateError - sequence length ({:?}) should not be less than 2",
-        values.len()
-    );
+fn check_sequence_length(values: &[f32]) -> Result<(), DomException> {
+    if values.len() < 2 {
+        return Err(DomException::InvalidStateError(format!(
+            "sequence length ({:?}) should not be less than 2",
+            values.len()
+        )));
+    }
+    Ok(())
 }
 
 // 𝑣(𝑡) = 𝑉0 + (𝑉1−𝑉0) * ((𝑡−𝑇0) / (𝑇1−𝑇0))
@@ -264,6 +273,10 @@ impl AudioParamEventTimeline {
 }
 
 /// AudioParam controls an individual aspect of an AudioNode's functionality, such as volume.
+///
+/// Besides the errors listed for each method, the fallible `try_` methods return a
+/// `QuotaExceededError` without any effect when the control message channel is full with the
+/// [`ControlOverflowPolicy::Reject`](crate::ControlOverflowPolicy::Reject) policy.
 #[derive(Clone)] // `Clone` for the node bindings, see #378
 pub struct AudioParam {
     registration: Arc<AudioContextRegistration>,
@@ -404,14 +417,39 @@ impl AudioParam {
         self.send_event(self.set_value_raw(value))
     }
 
+    /// Set the value of the `AudioParam`, see [`Self::set_value`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a `TypeError` if `value` is not finite
+    pub fn try_set_value(&self, value: f32) -> Result<&Self, DomException> {
+        check_is_finite(value)?;
+        // only update the current value when the event is accepted
+        self.try_send_event(self.set_value_event(value))?;
+        self.store_current_value(value);
+        Ok(self)
+    }
+
+    #[track_caller]
     fn set_value_raw(&self, value: f32) -> AudioParamEvent {
-        assert_is_finite(value);
+        expect_valid(self.try_set_value_raw(value))
+    }
+
+    fn try_set_value_raw(&self, value: f32) -> Result<AudioParamEvent, DomException> {
+        check_is_finite(value)?;
+        self.store_current_value(value);
+        Ok(self.set_value_event(value))
+    }
+
+    fn store_current_value(&self, value: f32) {
         // current_value should always be clamped
         let clamped = value.clamp(self.raw_parts.min_value, self.raw_parts.max_value);
         self.raw_parts
             .current_value
             .store(clamped, Ordering::Release);
+    }
 
+    fn set_value_event(&self, value: f32) -> AudioParamEvent {
         // this event is meant to update param intrinsic value before any calculation
         // is done, will behave as SetValueAtTime with `time == block_timestamp`
         AudioParamEvent {
@@ -434,11 +472,33 @@ impl AudioParam {
         self.send_event(self.set_value_at_time_raw(value, start_time))
     }
 
+    /// Schedules a parameter value change at the given time, see [`Self::set_value_at_time`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a `TypeError` if a value is not finite, a `RangeError` if `start_time` is negative
+    pub fn try_set_value_at_time(
+        &self,
+        value: f32,
+        start_time: f64,
+    ) -> Result<&Self, DomException> {
+        self.try_send_event(self.try_set_value_at_time_raw(value, start_time)?)
+    }
+
+    #[track_caller]
     fn set_value_at_time_raw(&self, value: f32, start_time: f64) -> AudioParamEvent {
-        assert_is_finite(value);
-        assert_valid_time_value(start_time);
+        expect_valid(self.try_set_value_at_time_raw(value, start_time))
+    }
 
-        AudioParamEvent {
+    fn try_set_value_at_time_raw(
+        &self,
+        value: f32,
+        start_time: f64,
+    ) -> Result<AudioParamEvent, DomException> {
+        check_is_finite(value)?;
+        check_valid_time_value(start_time)?;
+
+        Ok(AudioParamEvent {
             event_type: AudioParamEventType::SetValueAtTime,
             value,
             time: start_time,
@@ -446,7 +506,7 @@ impl AudioParam {
             cancel_time: None,
             duration: None,
             values: None,
-        }
+        })
     }
 
     /// Schedules a linear continuous change in parameter value from the
@@ -459,11 +519,34 @@ impl AudioParam {
         self.send_event(self.linear_ramp_to_value_at_time_raw(value, end_time))
     }
 
+    /// Schedules a linear continuous change in parameter value, see
+    /// [`Self::linear_ramp_to_value_at_time`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a `TypeError` if a value is not finite, a `RangeError` if `end_time` is negative
+    pub fn try_linear_ramp_to_value_at_time(
+        &self,
+        value: f32,
+        end_time: f64,
+    ) -> Result<&Self, DomException> {
+        self.try_send_event(self.try_linear_ramp_to_value_at_time_raw(value, end_time)?)
+    }
+
+    #[track_caller]
     fn linear_ramp_to_value_at_time_raw(&self, value: f32, end_time: f64) -> AudioParamEvent {
-        assert_is_finite(value);
-        assert_valid_time_value(end_time);
+        expect_valid(self.try_linear_ramp_to_value_at_time_raw(value, end_time))
+    }
 
-        AudioParamEvent {
+    fn try_linear_ramp_to_value_at_time_raw(
+        &self,
+        value: f32,
+        end_time: f64,
+    ) -> Result<AudioParamEvent, DomException> {
+        check_is_finite(value)?;
+        check_valid_time_value(end_time)?;
+
+        Ok(AudioParamEvent {
             event_type: AudioParamEventType::LinearRampToValueAtTime,
             value,
             time: end_time,
@@ -471,7 +554,7 @@ impl AudioParam {
             cancel_time: None,
             duration: None,
             values: None,
-        }
+        })
     }
 
     /// Schedules an exponential continuous change in parameter value from the
@@ -486,11 +569,35 @@ impl AudioParam {
         self.send_event(self.exponential_ramp_to_value_at_time_raw(value, end_time))
     }
 
+    /// Schedules an exponential continuous change in parameter value, see
+    /// [`Self::exponential_ramp_to_value_at_time`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a `TypeError` if a value is not finite, a `RangeError` if `value` is zero or
+    /// `end_time` is negative
+    pub fn try_exponential_ramp_to_value_at_time(
+        &self,
+        value: f32,
+        end_time: f64,
+    ) -> Result<&Self, DomException> {
+        self.try_send_event(self.try_exponential_ramp_to_value_at_time_raw(value, end_time)?)
+    }
+
+    #[track_caller]
     fn exponential_ramp_to_value_at_time_raw(&self, value: f32, end_time: f64) -> AudioParamEvent {
-        assert_not_zero(value);
-        assert_valid_time_value(end_time);
+        expect_valid(self.try_exponential_ramp_to_value_at_time_raw(value, end_time))
+    }
 
-        AudioParamEvent {
+    fn try_exponential_ramp_to_value_at_time_raw(
+        &self,
+        value: f32,
+        end_time: f64,
+    ) -> Result<AudioParamEvent, DomException> {
+        check_not_zero(value)?;
+        check_valid_time_value(end_time)?;
+
+        Ok(AudioParamEvent {
             event_type: AudioParamEventType::ExponentialRampToValueAtTime,
             value,
             time: end_time,
@@ -498,7 +605,7 @@ impl AudioParam {
             cancel_time: None,
             duration: None,
             values: None,
-        }
+        })
     }
 
     /// Start exponentially approaching the target value at the given time with
@@ -513,19 +620,45 @@ impl AudioParam {
         self.send_event(self.set_target_at_time_raw(value, start_time, time_constant))
     }
 
+    /// Start exponentially approaching the target value at the given time, see
+    /// [`Self::set_target_at_time`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a `TypeError` if a value is not finite, a `RangeError` if `start_time` or
+    /// `time_constant` is negative
+    pub fn try_set_target_at_time(
+        &self,
+        value: f32,
+        start_time: f64,
+        time_constant: f64,
+    ) -> Result<&Self, DomException> {
+        self.try_send_event(self.try_set_target_at_time_raw(value, start_time, time_constant)?)
+    }
+
+    #[track_caller]
     fn set_target_at_time_raw(
         &self,
         value: f32,
         start_time: f64,
         time_constant: f64,
     ) -> AudioParamEvent {
-        assert_is_finite(value);
-        assert_valid_time_value(start_time);
-        assert_valid_time_value(time_constant);
+        expect_valid(self.try_set_target_at_time_raw(value, start_time, time_constant))
+    }
+
+    fn try_set_target_at_time_raw(
+        &self,
+        value: f32,
+        start_time: f64,
+        time_constant: f64,
+    ) -> Result<AudioParamEvent, DomException> {
+        check_is_finite(value)?;
+        check_valid_time_value(start_time)?;
+        check_valid_time_value(time_constant)?;
 
         // [spec] If timeConstant is zero, the output value jumps immediately to the final value.
         if time_constant == 0. {
-            AudioParamEvent {
+            Ok(AudioParamEvent {
                 event_type: AudioParamEventType::SetValueAtTime,
                 value,
                 time: start_time,
@@ -533,9 +666,9 @@ impl AudioParam {
                 cancel_time: None,
                 duration: None,
                 values: None,
-            }
+            })
         } else {
-            AudioParamEvent {
+            Ok(AudioParamEvent {
                 event_type: AudioParamEventType::SetTargetAtTime,
                 value,
                 time: start_time,
@@ -543,7 +676,7 @@ impl AudioParam {
                 cancel_time: None,
                 duration: None,
                 values: None,
-            }
+            })
         }
     }
 
@@ -557,10 +690,27 @@ impl AudioParam {
         self.send_event(self.cancel_scheduled_values_raw(cancel_time))
     }
 
+    /// Cancels all scheduled parameter changes, see [`Self::cancel_scheduled_values`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a `TypeError` if `cancel_time` is not finite, a `RangeError` if it is negative
+    pub fn try_cancel_scheduled_values(&self, cancel_time: f64) -> Result<&Self, DomException> {
+        self.try_send_event(self.try_cancel_scheduled_values_raw(cancel_time)?)
+    }
+
+    #[track_caller]
     fn cancel_scheduled_values_raw(&self, cancel_time: f64) -> AudioParamEvent {
-        assert_valid_time_value(cancel_time);
+        expect_valid(self.try_cancel_scheduled_values_raw(cancel_time))
+    }
 
-        AudioParamEvent {
+    fn try_cancel_scheduled_values_raw(
+        &self,
+        cancel_time: f64,
+    ) -> Result<AudioParamEvent, DomException> {
+        check_valid_time_value(cancel_time)?;
+
+        Ok(AudioParamEvent {
             event_type: AudioParamEventType::CancelScheduledValues,
             value: 0., // no value
             time: cancel_time,
@@ -568,7 +718,7 @@ impl AudioParam {
             cancel_time: None,
             duration: None,
             values: None,
-        }
+        })
     }
 
     /// Cancels all scheduled parameter changes with times greater than or equal
@@ -582,10 +732,28 @@ impl AudioParam {
         self.send_event(self.cancel_and_hold_at_time_raw(cancel_time))
     }
 
+    /// Cancels all scheduled parameter changes and holds the current automation value, see
+    /// [`Self::cancel_and_hold_at_time`]
+    ///
+    /// # Errors
+    ///
+    /// Returns a `TypeError` if `cancel_time` is not finite, a `RangeError` if it is negative
+    pub fn try_cancel_and_hold_at_time(&self, cancel_time: f64) -> Result<&Self, DomException> {
+        self.try_send_event(self.try_cancel_and_hold_at_time_raw(cancel_time)?)
+    }
+
+    #[track_caller]
     fn cancel_and_hold_at_time_raw(&self, cancel_time: f64) -> AudioParamEvent {
-        assert_valid_time_value(cancel_time);
+        expect_valid(self.try_cancel_and_hold_at_time_raw(cancel_time))
+    }
 
-        AudioParamEvent {
+    fn try_cancel_and_hold_at_time_raw(
+        &self,
+        cancel_time: f64,
+    ) -> Result<AudioParamEvent, DomException> {
+        check_valid_time_value(cancel_time)?;
+
+        Ok(AudioParamEvent {
             event_type: AudioParamEventType::CancelAndHoldAtTime,
             value: 0., // value will be defined by cancel event
             time: cancel_time,
@@ -593,7 +761,7 @@ impl AudioParam {
             cancel_time: None,
             duration: None,
             values: None,
-        }
+        })
     }
 
     /// Sets an array of arbitrary parameter values starting at the given time
@@ -609,22 +777,48 @@ impl AudioParam {
         self.send_event(self.set_value_curve_at_time_raw(values, start_time, duration))
     }
 
+    /// Sets an array of arbitrary parameter values, see [`Self::set_value_curve_at_time`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidStateError` if `values` contains less than 2 items, a `TypeError` if a
+    /// time is not finite and a `RangeError` if `start_time` is negative or `duration` is not
+    /// strictly positive
+    pub fn try_set_value_curve_at_time(
+        &self,
+        values: &[f32],
+        start_time: f64,
+        duration: f64,
+    ) -> Result<&Self, DomException> {
+        self.try_send_event(self.try_set_value_curve_at_time_raw(values, start_time, duration)?)
+    }
+
+    #[track_caller]
     fn set_value_curve_at_time_raw(
         &self,
         values: &[f32],
         start_time: f64,
         duration: f64,
     ) -> AudioParamEvent {
-        assert_sequence_length(values);
-        assert_valid_time_value(start_time);
-        assert_strictly_positive(duration);
+        expect_valid(self.try_set_value_curve_at_time_raw(values, start_time, duration))
+    }
+
+    fn try_set_value_curve_at_time_raw(
+        &self,
+        values: &[f32],
+        start_time: f64,
+        duration: f64,
+    ) -> Result<AudioParamEvent, DomException> {
+        check_sequence_length(values)?;
+        check_valid_time_value(start_time)?;
+        check_strictly_positive(duration)?;
 
         // When this method is called, an internal copy of the curve is
         // created for automation purposes.
         let copy = values.to_vec();
         let boxed_copy = copy.into_boxed_slice();
 
-        AudioParamEvent {
+        Ok(AudioParamEvent {
             event_type: AudioParamEventType::SetValueCurveAtTime,
             value: 0., // value will be defined at the end of the event
             time: start_time,
@@ -632,7 +826,7 @@ impl AudioParam {
             cancel_time: None,
             duration: Some(duration),
             values: Some(boxed_copy),
-        }
+        })
     }
 
     // helper function to detach from context (for borrow reasons)
@@ -659,6 +853,11 @@ impl AudioParam {
         self.registration().post_message(event);
         self
     }
+
+    fn try_send_event(&self, event: AudioParamEvent) -> Result<&Self, DomException> {
+        self.registration().try_post_message(event)?;
+        Ok(self)
+    }
 }
 
 struct BlockInfos {
@@ -679,7 +878,8 @@ pub(crate) struct AudioParamProcessor {
     current_value: Arc<AtomicF32>,
     event_timeline: AudioParamEventTimeline,
     last_event: Option<AudioParamEvent>,
-    buffer: ArrayVec<f32, RENDER_QUANTUM_SIZE>,
+    /// computed values of the current render quantum, preallocated to the render quantum size
+    buffer: Vec<f32>,
 }
 
 impl AudioProcessor for AudioParamProcessor {
@@ -695,7 +895,7 @@ impl AudioProcessor for AudioParamProcessor {
         let input = &inputs[0]; // single input mode
         let output = &mut outputs[0];
 
-        self.compute_intrinsic_values(scope.current_time, period, RENDER_QUANTUM_SIZE);
+        self.compute_intrinsic_values(scope.current_time, period, scope.render_quantum_size);
         self.mix_to_output(input, output);
 
         true // has intrinsic value
@@ -738,7 +938,7 @@ impl AudioParamProcessor {
 
     fn mix_to_output(&mut self, input: &AudioRenderQuantum, output: &mut AudioRenderQuantum) {
         #[cfg(test)]
-        assert!(self.buffer.len() == 1 || self.buffer.len() == RENDER_QUANTUM_SIZE);
+        assert!(self.buffer.len() == 1 || self.buffer.len() == input.channel_data(0).len());
 
         // handle all k-rate and inactive a-rate processing
         if self.buffer.len() == 1 || !self.automation_rate.is_a_rate() {
@@ -1564,9 +1764,9 @@ impl AudioParamProcessor {
             let exit_loop = match next_event_type {
                 None => {
                     if is_a_rate {
-                        // we use `count` rather then `buffer.remaining_capacity`
+                        // we use `count` rather then `buffer.capacity`
                         // to correctly handle unit tests where `count` is lower than
-                        // RENDER_QUANTUM_SIZE
+                        // the render quantum size
                         for _ in self.buffer.len()..count {
                             self.buffer.push(self.intrinsic_value);
                         }
@@ -1626,6 +1826,7 @@ pub(crate) fn audio_param_pair(
     );
 
     let current_value = Arc::new(AtomicF32::new(default_value));
+    let render_quantum_size = registration.context().render_quantum_size();
 
     let param = AudioParam {
         registration: registration.into(),
@@ -1648,7 +1849,7 @@ pub(crate) fn audio_param_pair(
         automation_rate,
         event_timeline: AudioParamEventTimeline::new(),
         last_event: None,
-        buffer: ArrayVec::new(),
+        buffer: Vec::with_capacity(render_quantum_size),
     };
 
     (param, processor)
@@ -1664,37 +1865,52 @@ mod tests {
     use super::*;
 
     #[test]
-    #[should_panic]
-    fn test_assert_strictly_positive_fail() {
-        assert_strictly_positive(0.);
+    fn test_check_strictly_positive() {
+        assert!(check_strictly_positive(0.).is_err());
+        assert!(check_strictly_positive(f64::INFINITY).is_err());
+        assert!(check_strictly_positive(0.1).is_ok());
     }
 
     #[test]
-    fn test_assert_strictly_positive() {
-        assert_strictly_positive(0.1);
+    fn test_check_not_zero() {
+        assert!(check_not_zero(0.).is_err());
+        assert!(check_not_zero(-0.1).is_ok());
+        assert!(check_not_zero(0.1).is_ok());
     }
 
     #[test]
-    #[should_panic]
-    fn test_assert_not_zero_fail() {
-        assert_not_zero(0.);
+    fn test_check_sequence_length() {
+        assert!(check_sequence_length(&[0.; 1]).is_err());
+        assert!(check_sequence_length(&[0.; 2]).is_ok());
     }
 
     #[test]
-    fn test_assert_not_zero() {
-        assert_not_zero(-0.1);
-        assert_not_zero(0.1);
-    }
+    fn test_try_automation_errors() {
+        let context = OfflineAudioContext::new(1, 1, 48000.);
+        let param = context.create_gain().gain().clone();
 
-    #[test]
-    #[should_panic]
-    fn test_assert_sequence_length_fail() {
-        assert_sequence_length(&[0.; 1]);
+        let error = param.try_set_value(f32::NAN).unwrap_err();
+        assert!(matches!(error, DomException::TypeError(_)));
+        let error = param.try_set_value_at_time(1., -1.).unwrap_err();
+        assert!(matches!(error, DomException::RangeError(_)));
+        let error = param
+            .try_exponential_ramp_to_value_at_time(0., 1.)
+            .unwrap_err();
+        assert!(matches!(error, DomException::RangeError(_)));
+        let error = param
+            .try_set_value_curve_at_time(&[1.], 0., 1.)
+            .unwrap_err();
+        assert!(matches!(error, DomException::InvalidStateError(_)));
+
+        assert!(param.try_linear_ramp_to_value_at_time(1., 1.).is_ok());
     }
 
     #[test]
-    fn test_assert_sequence_length() {
-        assert_sequence_length(&[0.; 2]);
+    #[should_panic(expected = "RangeError - value (0.0) should not be equal to zero")]
+    fn test_exponential_ramp_zero_panics() {
+        let context = OfflineAudioContext::new(1, 1, 48000.);
+        let gain = context.create_gain();
+        gain.gain().exponential_ramp_to_value_at_time(0., 1.);
     }
 
     #[test]
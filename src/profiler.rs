@@ -0,0 +1,345 @@
+use crossbeam_channel::{RecvTimeoutError, Sender};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::context::{AudioContextState, AudioNodeId, BaseAudioContext, ConcreteBaseAudioContext};
+use crate::events::{EventHandler, EventPayload, EventType};
+use crate::message::ControlMessage;
+use crate::Event;
+
+/// Number of node records the render thread can store without allocating
+const PROFILE_CAPACITY: usize = 256;
+
+/// Time spent in the `AudioProcessor` of a single node, filled by the render thread
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct NodeProfile {
+    pub id: AudioNodeId,
+    pub name: &'static str,
+    pub process_time: Duration,
+    pub render_quanta: u64,
+}
+
+/// Profile of a single collection period, as sent by the render thread
+#[derive(Debug)]
+pub(crate) struct RenderProfile {
+    pub timestamp: f64,
+    pub duration: f64,
+    pub nodes: Vec<NodeProfile>,
+}
+
+/// Options for constructing an `AudioRenderProfiler`
+#[derive(Clone, Debug)]
+pub struct AudioRenderProfilerOptions {
+    /// An update interval (in seconds) for dispatching [`AudioRenderProfileEvent`]s
+    pub update_interval: f64,
+}
+
+impl Default for AudioRenderProfilerOptions {
+    fn default() -> Self {
+        Self {
+            update_interval: 1.,
+        }
+    }
+}
+
+/// Time spent rendering a single node over the update interval
+#[derive(Clone, Debug)]
+pub struct AudioNodeProfile {
+    /// Unique identifier of the node within its context
+    pub id: u64,
+    /// Type name of the node's render thread processor
+    pub name: &'static str,
+    /// Total time spent in the processor
+    pub process_time: Duration,
+    /// Number of render quanta processed by the node
+    pub render_quanta: u64,
+    /// Ratio between `process_time` and the duration of the update interval
+    pub load: f64,
+}
+
+/// Time spent rendering all nodes of a processor type over the update interval
+#[derive(Clone, Debug)]
+pub struct AudioProcessorProfile {
+    /// Type name of the render thread processor
+    pub name: &'static str,
+    /// Number of nodes with this processor that were rendered
+    pub node_count: usize,
+    /// Total time spent in these processors
+    pub process_time: Duration,
+    /// Total number of render quanta processed by these nodes
+    pub render_quanta: u64,
+    /// Ratio between `process_time` and the duration of the update interval
+    pub load: f64,
+}
+
+/// Per node performance metrics of the rendering thread
+#[derive(Clone, Debug)]
+pub struct AudioRenderProfileEvent {
+    /// The start time of the data collection period in terms of the associated AudioContext's currentTime
+    pub timestamp: f64,
+    /// The duration of the data collection period in seconds of rendered audio
+    pub duration: f64,
+    /// Profiles of all nodes that were rendered, sorted by descending process time
+    pub nodes: Vec<AudioNodeProfile>,
+    /// Profiles aggregated per processor type, sorted by descending process time
+    pub processors: Vec<AudioProcessorProfile>,
+    /// Inherits from this base Event
+    pub event: Event,
+}
+
+impl AudioRenderProfileEvent {
+    fn new(profile: RenderProfile) -> Self {
+        let RenderProfile {
+            timestamp,
+            duration,
+            nodes,
+        } = profile;
+
+        let load = |process_time: Duration| {
+            if duration > 0. {
+                process_time.as_secs_f64() / duration
+            } else {
+                0.
+            }
+        };
+
+        let mut nodes: Vec<_> = nodes
+            .into_iter()
+            .map(|node| AudioNodeProfile {
+                id: node.id.0,
+                name: node.name,
+                process_time: node.process_time,
+                render_quanta: node.render_quanta,
+                load: load(node.process_time),
+            })
+            .collect();
+        nodes.sort_by_key(|node| Reverse(node.process_time));
+
+        let mut processors: HashMap<&'static str, AudioProcessorProfile> = HashMap::new();
+        for node in &nodes {
+            let processor = processors
+                .entry(node.name)
+                .or_insert_with(|| AudioProcessorProfile {
+                    name: node.name,
+                    node_count: 0,
+                    process_time: Duration::ZERO,
+                    render_quanta: 0,
+                    load: 0.,
+                });
+            processor.node_count += 1;
+            processor.process_time += node.process_time;
+            processor.render_quanta += node.render_quanta;
+        }
+        let mut processors: Vec<_> = processors
+            .into_values()
+            .map(|mut processor| {
+                processor.load = load(processor.process_time);
+                processor
+            })
+            .collect();
+        processors.sort_by_key(|processor| Reverse(processor.process_time));
+
+        Self {
+            timestamp,
+            duration,
+            nodes,
+            processors,
+            event: Event {
+                type_: "AudioRenderProfileEvent",
+            },
+        }
+    }
+}
+
+/// Provider for per node rendering performance metrics
+///
+/// While profiling is active, the render thread measures the time spent in the processor of each
+/// node. This adds two clock reads per node per render quantum, which is cheap enough to leave
+/// enabled during development.
+///
+/// The load values are computed by dividing the time spent in a processor by the duration of the
+/// audio rendered during the update interval, analogous to the load values of
+/// [`AudioRenderCapacity`](crate::AudioRenderCapacity).
+#[derive(Clone)]
+pub struct AudioRenderProfiler {
+    context: ConcreteBaseAudioContext,
+    stop_send: Arc<Mutex<Option<Sender<()>>>>,
+}
+
+impl std::fmt::Debug for AudioRenderProfiler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AudioRenderProfiler")
+            .field(
+                "context",
+                &format!("BaseAudioContext@{}", self.context.address()),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl AudioRenderProfiler {
+    pub(crate) fn new(context: ConcreteBaseAudioContext) -> Self {
+        Self {
+            context,
+            stop_send: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Start profiling the render thread
+    #[allow(clippy::missing_panics_doc)]
+    pub fn start(&self, options: AudioRenderProfilerOptions) {
+        // stop current profiling, if any
+        self.stop();
+
+        let (stop_send, stop_recv) = crossbeam_channel::bounded(0);
+        *self.stop_send.lock().unwrap() = Some(stop_send);
+
+        self.context
+            .send_control_msg(ControlMessage::SetProfiling { enabled: true });
+
+        let update_interval = Duration::from_secs_f64(options.update_interval);
+        let base_context = self.context.clone();
+        std::thread::spawn(move || loop {
+            match stop_recv.recv_timeout(update_interval) {
+                Err(RecvTimeoutError::Timeout) => (),
+                _ => return,
+            }
+
+            // stop thread when render thread has shut down
+            if base_context.state() == AudioContextState::Closed {
+                return;
+            }
+
+            // the render thread swaps the buffer with its current records
+            let buffer = Vec::with_capacity(PROFILE_CAPACITY);
+            base_context.send_control_msg(ControlMessage::CollectProfile { buffer });
+        });
+    }
+
+    /// Stop profiling the render thread
+    #[allow(clippy::missing_panics_doc)]
+    pub fn stop(&self) {
+        // halt collection thread
+        if let Some(stop_send) = self.stop_send.lock().unwrap().take() {
+            let _ = stop_send.send(());
+            self.context
+                .send_control_msg(ControlMessage::SetProfiling { enabled: false });
+        }
+    }
+
+    /// The EventHandler for [`AudioRenderProfileEvent`].
+    ///
+    /// Only a single event handler is active at any time. Calling this method multiple times will
+    /// override the previous event handler.
+    pub fn set_onupdate<F: FnMut(AudioRenderProfileEvent) + Send + 'static>(
+        &self,
+        mut callback: F,
+    ) {
+        let callback = move |v| match v {
+            EventPayload::RenderProfile(v) => callback(AudioRenderProfileEvent::new(v)),
+            _ => unreachable!(),
+        };
+
+        self.context.set_event_handler(
+            EventType::RenderProfile,
+            EventHandler::Multiple(Box::new(callback)),
+        );
+    }
+
+    /// Unset the EventHandler for [`AudioRenderProfileEvent`].
+    pub fn clear_onupdate(&self) {
+        self.context.clear_event_handler(EventType::RenderProfile);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{AudioContext, AudioContextOptions};
+    use crate::node::{AudioNode, AudioScheduledSourceNode};
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_profile_event() {
+        let node = |id, name, millis| NodeProfile {
+            id: AudioNodeId(id),
+            name,
+            process_time: Duration::from_millis(millis),
+            render_quanta: 10,
+        };
+        let profile = RenderProfile {
+            timestamp: 1.,
+            duration: 0.5,
+            nodes: vec![node(0, "a", 10), node(1, "b", 50), node(2, "a", 100)],
+        };
+
+        let event = AudioRenderProfileEvent::new(profile);
+        assert_eq!(event.timestamp, 1.);
+        assert_eq!(event.duration, 0.5);
+
+        let ids: Vec<_> = event.nodes.iter().map(|n| n.id).collect();
+        assert_eq!(ids, [2, 1, 0]);
+        assert_float_eq!(event.nodes[0].load, 0.2, abs <= 1e-9);
+
+        assert_eq!(event.processors.len(), 2);
+        assert_eq!(event.processors[0].name, "a");
+        assert_eq!(event.processors[0].node_count, 2);
+        assert_eq!(event.processors[0].render_quanta, 20);
+        assert_eq!(event.processors[0].process_time, Duration::from_millis(110));
+        assert_float_eq!(event.processors[0].load, 0.22, abs <= 1e-9);
+        assert_eq!(event.processors[1].name, "b");
+
+        assert_eq!(event.event.type_, "AudioRenderProfileEvent");
+    }
+
+    #[test]
+    fn test_stop_when_not_running() {
+        let options = AudioContextOptions {
+            sink_id: "none".into(),
+            ..AudioContextOptions::default()
+        };
+        let context = AudioContext::new(options);
+
+        let profiler = context.render_profiler();
+        profiler.stop();
+    }
+
+    #[test]
+    fn test_render_profiler() {
+        let options = AudioContextOptions {
+            sink_id: "none".into(),
+            ..AudioContextOptions::default()
+        };
+        let context = AudioContext::new(options);
+        let mut osc = context.create_oscillator();
+        osc.connect(&context.destination());
+        osc.start();
+
+        let profiler = context.render_profiler();
+        let (send, recv) = crossbeam_channel::bounded(1);
+        profiler.set_onupdate(move |e| {
+            let _ = send.try_send(e);
+        });
+        profiler.start(AudioRenderProfilerOptions {
+            update_interval: 0.05,
+        });
+        let event = recv.recv().unwrap();
+        profiler.stop();
+
+        assert!(event.timestamp >= 0.);
+        assert!(event.duration >= 0.);
+        let osc = event
+            .nodes
+            .iter()
+            .find(|n| n.name.ends_with("OscillatorRenderer"))
+            .unwrap();
+        assert!(osc.render_quanta > 0);
+        assert!(osc.load.is_finite());
+        assert!(event
+            .processors
+            .iter()
+            .any(|p| p.name.ends_with("OscillatorRenderer") && p.node_count == 1));
+    }
+}
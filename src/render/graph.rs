@@ -4,15 +4,19 @@
 mod test;
 
 use std::any::Any;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::ops::Range;
 use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
 
 use crate::context::AudioNodeId;
 use smallvec::{smallvec, SmallVec};
 
+use super::worker_pool::WorkerPool;
 use super::{Alloc, AudioParamValues, AudioProcessor, AudioRenderQuantum, NodeCollection};
 use crate::node::{ChannelConfigInner, ChannelCountMode, ChannelInterpretation};
 use crate::render::AudioWorkletGlobalScope;
+use crate::{AudioEdgeDiagnostics, AudioGraphDiagnostics, AudioNodeDiagnostics, NodeProfile};
 
 /// Connection between two audio nodes
 struct OutgoingEdge {
@@ -22,6 +26,8 @@ struct OutgoingEdge {
     other_id: AudioNodeId,
     /// index of the other Nodes input port
     other_index: usize,
+    /// pending fades of connections and disconnections at scheduled frames, sorted by start frame
+    fades: Vec<EdgeFade>,
 }
 
 impl std::fmt::Debug for OutgoingEdge {
@@ -35,10 +41,124 @@ impl std::fmt::Debug for OutgoingEdge {
         } else {
             format.field("other_index", &self.other_index);
         }
+        if !self.fades.is_empty() {
+            format.field("fades", &self.fades);
+        }
         format.finish()
     }
 }
 
+impl OutgoingEdge {
+    fn connects(&self, source: (AudioNodeId, usize), dest: (AudioNodeId, usize)) -> bool {
+        self.self_index == source.1 && self.other_id == dest.0 && self.other_index == dest.1
+    }
+
+    /// Insert a fade after the fades that start at the same frame or earlier
+    fn schedule_fade(&mut self, fade: EdgeFade) {
+        let index = self
+            .fades
+            .partition_point(|other| other.start_frame <= fade.start_frame);
+        self.fades.insert(index, fade);
+    }
+
+    /// Gain of the connection at the given frame, determined by the last fade that has started
+    fn gain(&self, frame: u64) -> f32 {
+        self.fades
+            .iter()
+            .rev()
+            .find(|fade| fade.start_frame <= frame)
+            .map_or(1., |fade| fade.gain(frame))
+    }
+
+    /// Check if the gain of the connection changes within the given frames
+    fn is_fading(&self, frames: &Range<u64>) -> bool {
+        self.fades.iter().any(|fade| {
+            fade.start_frame < frames.end && fade.start_frame + fade.fade_frames > frames.start
+        })
+    }
+
+    /// Drop the fades that completed before the given frame
+    ///
+    /// Returns false when the edge has faded out and should be removed from the graph.
+    fn settle_fades(&mut self, next_frame: u64) -> bool {
+        // only the last completed fade determines the current gain
+        let completed = self.fades.iter().rposition(|fade| fade.is_done(next_frame));
+        let Some(completed) = completed else {
+            return true;
+        };
+        self.fades.drain(..completed);
+
+        let connected = self.fades[0].fade_in;
+        if connected || self.fades.len() == 1 {
+            self.fades.remove(0);
+        }
+
+        connected || !self.fades.is_empty()
+    }
+}
+
+/// Gain ramp of a connection that is scheduled to be made or broken at a given frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct EdgeFade {
+    /// first frame of the ramp
+    pub start_frame: u64,
+    /// length of the ramp, zero for an instantaneous switch
+    pub fade_frames: u64,
+    /// ramp up for connections, ramp down for disconnections
+    pub fade_in: bool,
+}
+
+impl EdgeFade {
+    /// Gain of the connection at the given frame
+    fn gain(&self, frame: u64) -> f32 {
+        let progress = if frame < self.start_frame {
+            0.
+        } else if frame >= self.start_frame + self.fade_frames {
+            1.
+        } else {
+            (frame - self.start_frame) as f32 / self.fade_frames as f32
+        };
+
+        if self.fade_in {
+            progress
+        } else {
+            1. - progress
+        }
+    }
+
+    /// Check if the ramp has completed before the given frame
+    fn is_done(&self, frame: u64) -> bool {
+        frame >= self.start_frame + self.fade_frames
+    }
+}
+
+/// Sum the signal of an edge into the input of its destination node, applying the edge fades
+fn add_edge_signal(
+    input: &mut AudioRenderQuantum,
+    signal: &AudioRenderQuantum,
+    channel_config: &ChannelConfigInner,
+    edge: &OutgoingEdge,
+    scope: &AudioWorkletGlobalScope,
+) {
+    if edge.fades.is_empty() {
+        return input.add(signal, channel_config);
+    }
+
+    let frames = scope.current_frame..scope.current_frame + scope.render_quantum_size as u64;
+    if edge.is_fading(&frames) {
+        let mut faded = signal.clone();
+        faded.modify_channels(|channel| {
+            channel
+                .iter_mut()
+                .zip(frames.clone())
+                .for_each(|(s, frame)| *s *= edge.gain(frame));
+        });
+        input.add(&faded, channel_config);
+    } else if edge.gain(frames.start) != 0. {
+        input.add(signal, channel_config);
+    }
+}
+
 /// Renderer Node in the Audio Graph
 pub struct Node {
     /// AudioNodeId, to be sent back to the control thread when this node is dropped
@@ -59,6 +179,16 @@ pub struct Node {
     has_inputs_connected: bool,
     /// Indicates if the node can act as a cycle breaker (only DelayNode for now)
     cycle_breaker: bool,
+    /// Number of graph edits of scheduled transactions that refer to this node, the node is kept
+    /// alive until these are applied
+    pending_edits: usize,
+    /// Indicates if the node has reached its end of life during parallel rendering, it will be
+    /// removed from the graph after all nodes have rendered
+    end_of_life: bool,
+    /// Time spent in the processor since the last profile collection (when profiling)
+    process_time: Duration,
+    /// Number of render quanta processed since the last profile collection (when profiling)
+    render_quanta: u64,
 }
 
 impl std::fmt::Debug for Node {
@@ -75,10 +205,40 @@ impl std::fmt::Debug for Node {
 }
 
 impl Node {
-    /// Render an audio quantum
-    fn process(&mut self, params: AudioParamValues<'_>, scope: &AudioWorkletGlobalScope) -> bool {
-        self.processor
-            .process(&self.inputs[..], &mut self.outputs[..], params, scope)
+    /// Render an audio quantum, measuring the time spent in the processor when profiling
+    fn process(
+        &mut self,
+        params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+        profiling: bool,
+    ) -> bool {
+        if !profiling {
+            return self
+                .processor
+                .process(&self.inputs[..], &mut self.outputs[..], params, scope);
+        }
+
+        let start = Instant::now();
+        let tail_time =
+            self.processor
+                .process(&self.inputs[..], &mut self.outputs[..], params, scope);
+        self.process_time += start.elapsed();
+        self.render_quanta += 1;
+
+        tail_time
+    }
+
+    /// Take the processing time measured since the last call
+    fn take_profile(&mut self, id: AudioNodeId) -> NodeProfile {
+        let profile = NodeProfile {
+            id,
+            name: self.processor.name(),
+            process_time: self.process_time,
+            render_quanta: self.render_quanta,
+        };
+        self.process_time = Duration::ZERO;
+        self.render_quanta = 0;
+        profile
     }
 
     /// Determine if this node is done playing and can be removed from the audio graph
@@ -89,6 +249,11 @@ impl Node {
             return false;
         }
 
+        // Scheduled graph edits may still refer to this node
+        if self.pending_edits > 0 {
+            return false;
+        }
+
         // When the nodes has no incoming connections:
         if !self.has_inputs_connected {
             // Drop when the processor reports it won't yield output.
@@ -118,6 +283,243 @@ impl Node {
     }
 }
 
+/// Node in the parallel render schedule
+#[derive(Debug)]
+struct ScheduledNode {
+    id: AudioNodeId,
+    /// Position of the node in the topological ordering
+    position: usize,
+    /// Render level, all nodes this node depends on have a lower level
+    level: usize,
+    /// Indicates if the node must be rendered by the render thread
+    pinned: bool,
+    /// Range of the incoming connections of this node in `Schedule::incoming`
+    incoming: Range<usize>,
+}
+
+/// Group of nodes that can be rendered simultaneously
+///
+/// The pinned nodes `start..pinned_end` are rendered in order by the render thread, the other
+/// nodes `pinned_end..end` are distributed over the worker threads.
+#[derive(Debug)]
+struct Level {
+    start: usize,
+    pinned_end: usize,
+    end: usize,
+}
+
+/// Parallel render schedule, derived from the topological ordering of the nodes
+#[derive(Debug, Default)]
+struct Schedule {
+    /// Scheduled nodes, sorted by level
+    nodes: Vec<ScheduledNode>,
+    /// Levels of the schedule
+    levels: Vec<Level>,
+    /// Incoming connections, as tuples of the position of the destination and the source node
+    incoming: Vec<(usize, usize)>,
+    /// Lookup of the position in the topological ordering, indexed by AudioNodeId
+    positions: Vec<usize>,
+    /// Indicates if the schedule must be rebuilt before rendering
+    stale: bool,
+}
+
+impl Schedule {
+    /// Assign the nodes to levels, such that nodes in the same level do not depend on each other
+    ///
+    /// For performance: no new allocations when the graph does not grow (reuse Vecs)
+    fn build(&mut self, ordered: &[AudioNodeId], nodes: &NodeCollection) {
+        let len = ordered
+            .iter()
+            .map(|id| id.0 as usize + 1)
+            .max()
+            .unwrap_or(0);
+        self.positions.clear();
+        self.positions.resize(len, usize::MAX);
+        ordered
+            .iter()
+            .enumerate()
+            .for_each(|(position, id)| self.positions[id.0 as usize] = position);
+
+        self.nodes.clear();
+        self.nodes
+            .extend(ordered.iter().enumerate().map(|(position, &id)| {
+                let node = nodes.get_unchecked(id).borrow();
+                ScheduledNode {
+                    id,
+                    position,
+                    level: 0,
+                    pinned: node.processor.requires_render_thread(),
+                    incoming: 0..0,
+                }
+            }));
+
+        // Propagate the levels in topological order. Pinned nodes must keep their relative
+        // ordering, so their level cannot be lower than the level of the previous pinned node.
+        self.incoming.clear();
+        let mut pinned_level = 0;
+        for (position, &id) in ordered.iter().enumerate() {
+            let mut level = self.nodes[position].level;
+            if self.nodes[position].pinned {
+                level = level.max(pinned_level);
+                pinned_level = level;
+                self.nodes[position].level = level;
+            }
+
+            let node = nodes.get_unchecked(id).borrow();
+            for edge in node.outgoing_edges.iter() {
+                let dest = match self.positions.get(edge.other_id.0 as usize) {
+                    Some(&dest) if dest != usize::MAX => dest,
+                    _ => continue, // node is not rendered because it is part of a cycle
+                };
+                self.incoming.push((dest, position));
+                let dest = &mut self.nodes[dest];
+                dest.level = dest.level.max(level + 1);
+            }
+        }
+
+        // Sort the incoming connections of each node by topological ordering of the source, so the
+        // inputs are summed in the same order as with serial rendering
+        self.incoming.sort_unstable();
+        self.incoming.dedup();
+        for node in self.nodes.iter_mut() {
+            let start = self.incoming.partition_point(|&(d, _)| d < node.position);
+            let end = self.incoming.partition_point(|&(d, _)| d <= node.position);
+            node.incoming = start..end;
+        }
+
+        self.nodes
+            .sort_unstable_by_key(|n| (n.level, !n.pinned, n.position));
+
+        self.levels.clear();
+        let mut start = 0;
+        while start < self.nodes.len() {
+            let level = self.nodes[start].level;
+            let end = start
+                + self.nodes[start..]
+                    .iter()
+                    .take_while(|n| n.level == level)
+                    .count();
+            let pinned_end = start
+                + self.nodes[start..end]
+                    .iter()
+                    .take_while(|n| n.pinned)
+                    .count();
+            self.levels.push(Level {
+                start,
+                pinned_end,
+                end,
+            });
+            start = end;
+        }
+
+        self.stale = false;
+    }
+}
+
+/// Shared access to the nodes for the worker threads
+///
+/// The nodes are not `Sync`: they are kept in `RefCell`s and their audio buffers refer to the
+/// buffer pool of the render thread. Only [`Graph::render_parallel`] creates this wrapper.
+struct SharedNodes<'a>(&'a NodeCollection);
+
+// SAFETY: sharing the nodes with the worker threads is sound because `render_parallel` upholds
+// the following invariants for the duration of the borrow:
+// - The node collection is not modified: nodes are only added or removed by the render thread
+//   before or after a render pass, when no worker holds a reference.
+// - Each node is rendered exactly once per render pass, by a single thread. Only that thread
+//   touches the `RefCell` borrow flag of the node, with `borrow_mut` in `render_scheduled_node`.
+// - The nodes of a level only read the outputs of nodes from lower levels, which have finished
+//   rendering before the level started (`WorkerPool::run` synchronizes with acquire/release).
+//   These reads use `try_borrow_unguarded` and do not touch the borrow flag.
+// - Nodes whose processor must stay on the render thread (`requires_render_thread`) are pinned
+//   to the render thread, so non thread safe state shared between processors is never accessed
+//   concurrently.
+// - The audio buffers are reference counted atomically. Buffers are taken from and released to
+//   the pool of the current thread, the workers have their own pools (see `init_worker_pool`).
+unsafe impl Sync for SharedNodes<'_> {}
+
+impl SharedNodes<'_> {
+    fn get(&self) -> &NodeCollection {
+        self.0
+    }
+}
+
+/// Render a single node, as part of a parallel render pass
+///
+/// The inputs of the node are gathered from its source nodes, which have rendered already.
+fn render_scheduled_node(
+    nodes: &NodeCollection,
+    ordered: &[AudioNodeId],
+    incoming: &[(usize, usize)],
+    id: AudioNodeId,
+    scope: &AudioWorkletGlobalScope,
+    profiling: bool,
+) {
+    // acquire a mutable borrow of the current processing node
+    let mut node = nodes.get_unchecked(id).borrow_mut();
+    let node = &mut *node;
+
+    // iterate all incoming edges, lookup the source nodes and add their output to our input
+    for &(_, source_position) in incoming {
+        // SAFETY: source nodes have rendered at a lower level and are not mutated during this
+        // level, so multiple nodes can read their outputs simultaneously.
+        let source = unsafe {
+            nodes
+                .get_unchecked(ordered[source_position])
+                .try_borrow_unguarded()
+        }
+        .expect("InvalidStateError - source node is being rendered");
+        source
+            .outgoing_edges
+            .iter()
+            // audio params are connected to the 'hidden' usize::MAX output, ignore them here
+            .filter(|edge| edge.other_id == id && edge.other_index != usize::MAX)
+            .for_each(|edge| {
+                node.has_inputs_connected = true;
+                let signal = &source.outputs[edge.self_index];
+                add_edge_signal(
+                    &mut node.inputs[edge.other_index],
+                    signal,
+                    &node.channel_config,
+                    edge,
+                    scope,
+                );
+            });
+    }
+
+    // let the current node process (catch any panics that may occur)
+    let params = AudioParamValues::from(nodes);
+    scope.node_id.set(id);
+    let (success, tail_time) = {
+        // We are abusing AssertUnwindSafe here, we cannot guarantee it upholds.
+        // This may lead to logic bugs later on, but it is the best that we can do.
+        // The alternative is to crash and reboot the render thread.
+        let catch_me = AssertUnwindSafe(|| node.process(params, scope, profiling));
+
+        match panic::catch_unwind(catch_me) {
+            Ok(tail_time) => (true, tail_time),
+            Err(e) => {
+                node.outgoing_edges.clear();
+                scope.report_error(e);
+                (false, false)
+            }
+        }
+    };
+
+    node.end_of_life = !success || node.can_free(tail_time);
+
+    // Node is not dropped.
+    if !node.end_of_life {
+        // Reset input buffers as they will be summed up in the next render quantum.
+        node.inputs
+            .iter_mut()
+            .for_each(AudioRenderQuantum::make_silent);
+
+        // Reset input state
+        node.has_inputs_connected = false;
+    }
+}
+
 /// The audio graph
 pub(crate) struct Graph {
     /// Processing Nodes
@@ -136,6 +538,16 @@ pub(crate) struct Graph {
     in_cycle: Vec<AudioNodeId>,
     /// Topological sorting helper
     cycle_breakers: Vec<AudioNodeId>,
+    /// Worker threads for parallel rendering, disabled when `None`
+    pool: Option<WorkerPool>,
+    /// Parallel render schedule (boxed, the graph is shipped to the render thread by value)
+    schedule: Box<Schedule>,
+    /// Measure the time spent in each processor
+    profiling: bool,
+    /// Processing times of the nodes that were dropped since the last profile collection
+    profile: Vec<NodeProfile>,
+    /// Indicates if any edge has a pending fade
+    has_edge_fades: bool,
 }
 
 impl std::fmt::Debug for Graph {
@@ -148,17 +560,59 @@ impl std::fmt::Debug for Graph {
 }
 
 impl Graph {
-    pub fn new(reclaim_id_channel: llq::Producer<AudioNodeId>) -> Self {
+    pub fn new(reclaim_id_channel: llq::Producer<AudioNodeId>, render_quantum_size: usize) -> Self {
         Graph {
             nodes: NodeCollection::new(),
-            alloc: Alloc::with_capacity(64),
+            alloc: Alloc::with_render_quantum_size(64, render_quantum_size),
             reclaim_id_channel,
             ordered: vec![],
             marked: vec![],
             marked_temp: vec![],
             in_cycle: vec![],
             cycle_breakers: vec![],
+            pool: None,
+            schedule: Box::default(),
+            profiling: false,
+            profile: vec![],
+            has_edge_fades: false,
+        }
+    }
+
+    /// Render independent nodes of the graph in parallel on the given number of worker threads
+    ///
+    /// Parallel rendering is disabled when the number of threads is zero.
+    pub fn set_render_worker_threads(&mut self, number_of_threads: usize) {
+        self.pool = (number_of_threads > 0).then(|| WorkerPool::new(number_of_threads));
+        self.schedule.stale = true;
+    }
+
+    /// Enable or disable measuring the time spent in each processor
+    ///
+    /// Measurements collected so far are discarded.
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profiling = enabled;
+        self.profile.clear();
+        self.nodes.iter_mut().for_each(|(id, node)| {
+            node.get_mut().take_profile(id);
+        });
+    }
+
+    /// Collect the processing times of all nodes since the previous collection
+    ///
+    /// The given buffer stores the measurements until the next collection, so it should have
+    /// enough capacity to prevent allocations on the render thread.
+    pub fn collect_profile(&mut self, buffer: Vec<NodeProfile>) -> Vec<NodeProfile> {
+        let mut profile = std::mem::replace(&mut self.profile, buffer);
+        self.profile.clear();
+        if self.profiling {
+            profile.extend(
+                self.nodes
+                    .iter_mut()
+                    .map(|(id, node)| node.get_mut().take_profile(id))
+                    .filter(|profile| profile.render_quanta > 0),
+            );
         }
+        profile
     }
 
     /// Check if the graph is fully initialized and can start rendering
@@ -195,6 +649,10 @@ impl Graph {
                 control_handle_dropped: false,
                 has_inputs_connected: false,
                 cycle_breaker: false,
+                pending_edits: 0,
+                end_of_life: false,
+                process_time: Duration::ZERO,
+                render_quanta: 0,
             }),
         );
     }
@@ -207,11 +665,49 @@ impl Graph {
                 self_index: source.1,
                 other_id: dest.0,
                 other_index: dest.1,
+                fades: vec![],
             });
 
         self.ordered.clear(); // void current ordering
     }
 
+    /// Add an edge that becomes audible at the frame of the given fade
+    ///
+    /// When the edge exists with pending fades (e.g. it is scheduled to be disconnected), the fade
+    /// is added to the existing edge instead.
+    pub fn add_edge_at(
+        &mut self,
+        source: (AudioNodeId, usize),
+        dest: (AudioNodeId, usize),
+        fade: EdgeFade,
+    ) {
+        let edges = &mut self.nodes.get_unchecked_mut(source.0).outgoing_edges;
+        let pending = edges
+            .iter_mut()
+            .find(|edge| !edge.fades.is_empty() && edge.connects(source, dest));
+
+        match pending {
+            Some(edge) => edge.schedule_fade(fade),
+            None => {
+                // the new edge is silent until the fade starts
+                let silent = EdgeFade {
+                    start_frame: 0,
+                    fade_frames: 0,
+                    fade_in: false,
+                };
+                edges.push(OutgoingEdge {
+                    self_index: source.1,
+                    other_id: dest.0,
+                    other_index: dest.1,
+                    fades: vec![silent, fade],
+                });
+                self.ordered.clear(); // void current ordering
+            }
+        }
+
+        self.has_edge_fades = true;
+    }
+
     pub fn remove_edge(&mut self, source: (AudioNodeId, usize), dest: (AudioNodeId, usize)) {
         self.nodes
             .get_unchecked_mut(source.0)
@@ -223,6 +719,46 @@ impl Graph {
         self.ordered.clear(); // void current ordering
     }
 
+    /// Fade out an edge from the frame of the given fade, it is removed when the fade completes
+    pub fn remove_edge_at(
+        &mut self,
+        source: (AudioNodeId, usize),
+        dest: (AudioNodeId, usize),
+        fade: EdgeFade,
+    ) {
+        self.nodes
+            .get_unchecked_mut(source.0)
+            .outgoing_edges
+            .iter_mut()
+            .filter(|edge| edge.connects(source, dest))
+            .for_each(|edge| {
+                edge.schedule_fade(fade);
+                self.has_edge_fades = true;
+            });
+    }
+
+    /// Settle the edge fades that completed before the given frame
+    ///
+    /// Faded in edges become regular edges, faded out edges are removed from the graph.
+    fn settle_edge_fades(&mut self, next_frame: u64) {
+        let mut pending = false;
+        let mut removed = false;
+
+        self.nodes.values_mut().for_each(|node| {
+            node.get_mut().outgoing_edges.retain(|edge| {
+                let retain = edge.fades.is_empty() || edge.settle_fades(next_frame);
+                pending |= !edge.fades.is_empty();
+                removed |= !retain;
+                retain
+            });
+        });
+
+        self.has_edge_fades = pending;
+        if removed {
+            self.ordered.clear(); // void current ordering
+        }
+    }
+
     pub fn mark_control_handle_dropped(&mut self, index: AudioNodeId) {
         // Issue #92, a race condition can occur for AudioParams. They may have already been
         // removed from the audio graph if the node they feed into was dropped.
@@ -232,6 +768,21 @@ impl Graph {
         }
     }
 
+    /// Keep the node alive until a scheduled graph edit that refers to it has been applied
+    pub fn pin_node(&mut self, index: AudioNodeId) {
+        if let Some(node) = self.nodes.get_mut(index) {
+            node.get_mut().pending_edits += 1;
+        }
+    }
+
+    /// Release a node that was pinned with [`Self::pin_node`]
+    pub fn unpin_node(&mut self, index: AudioNodeId) {
+        if let Some(node) = self.nodes.get_mut(index) {
+            let node = node.get_mut();
+            node.pending_edits = node.pending_edits.saturating_sub(1);
+        }
+    }
+
     pub fn mark_cycle_breaker(&mut self, index: AudioNodeId) {
         self.nodes.get_unchecked_mut(index).cycle_breaker = true;
     }
@@ -419,8 +970,69 @@ impl Graph {
         self.cycle_breakers = cycle_breakers;
     }
 
+    /// Take a typed snapshot of the current graph state
+    pub fn diagnostics(&mut self) -> AudioGraphDiagnostics {
+        // make sure the cycle administration reflects the current topology
+        if self.ordered.is_empty() {
+            self.order_nodes();
+            self.schedule.stale = true;
+        }
+
+        // position of each node in the render order, indexed by node id
+        let len = self
+            .ordered
+            .iter()
+            .map(|id| id.0 as usize + 1)
+            .max()
+            .unwrap_or(0);
+        let mut render_order = vec![None; len];
+        self.ordered
+            .iter()
+            .enumerate()
+            .for_each(|(position, id)| render_order[id.0 as usize] = Some(position));
+
+        let nodes = self
+            .nodes
+            .keys()
+            .map(|id| {
+                let node = self.nodes.get_unchecked(id).borrow();
+                let outgoing_edges = node
+                    .outgoing_edges
+                    .iter()
+                    .map(|edge| AudioEdgeDiagnostics {
+                        output: edge.self_index,
+                        destination: edge.other_id.0,
+                        input: (edge.other_index != usize::MAX).then_some(edge.other_index),
+                    })
+                    .collect();
+
+                AudioNodeDiagnostics {
+                    id: id.0,
+                    name: node.processor.name(),
+                    channel_count: node.channel_config.count,
+                    channel_count_mode: node.channel_config.count_mode,
+                    channel_interpretation: node.channel_config.interpretation,
+                    outgoing_edges,
+                    control_handle_dropped: node.control_handle_dropped,
+                    cycle_breaker: node.cycle_breaker,
+                    muted: self.in_cycle.contains(&id),
+                    render_order: render_order.get(id.0 as usize).copied().flatten(),
+                }
+            })
+            .collect();
+
+        AudioGraphDiagnostics {
+            nodes,
+            buffer_pool_size: self.alloc.pool_size(),
+        }
+    }
+
     /// Render a single audio quantum by traversing the node list
     pub fn render(&mut self, scope: &AudioWorkletGlobalScope) -> &AudioRenderQuantum {
+        if self.pool.is_some() {
+            return self.render_parallel(scope);
+        }
+
         // if the audio graph was changed, determine the new ordering
         if self.ordered.is_empty() {
             self.order_nodes();
@@ -428,6 +1040,7 @@ impl Graph {
 
         // keep track of end-of-lifecyle nodes
         let mut nodes_dropped = false;
+        let profiling = self.profiling;
 
         // process every node, in topological sorted order
         self.ordered.iter().for_each(|index| {
@@ -441,7 +1054,7 @@ impl Graph {
                 // We are abusing AssertUnwindSafe here, we cannot guarantee it upholds.
                 // This may lead to logic bugs later on, but it is the best that we can do.
                 // The alternative is to crash and reboot the render thread.
-                let catch_me = AssertUnwindSafe(|| node.process(params, scope));
+                let catch_me = AssertUnwindSafe(|| node.process(params, scope, profiling));
 
                 match panic::catch_unwind(catch_me) {
                     Ok(tail_time) => (true, tail_time),
@@ -464,7 +1077,13 @@ impl Graph {
                     let signal = &node.outputs[edge.self_index];
                     let channel_config = &output_node.channel_config.clone();
 
-                    output_node.inputs[edge.other_index].add(signal, channel_config);
+                    add_edge_signal(
+                        &mut output_node.inputs[edge.other_index],
+                        signal,
+                        channel_config,
+                        edge,
+                        scope,
+                    );
                 });
 
             let can_free = !success || node.can_free(tail_time);
@@ -489,6 +1108,9 @@ impl Graph {
                 self.reclaim_id_channel
                     .push(node.reclaim_id.take().unwrap());
                 node.processor.before_drop(scope);
+                if self.profiling {
+                    self.profile.push(node.take_profile(*index));
+                }
                 drop(node);
 
                 // And remove it from the ordering after we have processed all nodes
@@ -519,6 +1141,115 @@ impl Graph {
             }
         }
 
+        // Resolve connections that were scheduled to be made or broken during this quantum
+        if self.has_edge_fades {
+            self.settle_edge_fades(scope.current_frame + scope.render_quantum_size as u64);
+        }
+
+        // Return the output buffer of destination node
+        &self.nodes.get_unchecked_mut(AudioNodeId(0)).outputs[0]
+    }
+
+    /// Render a single audio quantum by traversing the levels of the parallel render schedule
+    ///
+    /// The nodes of a level are distributed over the worker threads. Inputs are summed in the
+    /// topological order of their sources, so the output is identical to the serial render.
+    fn render_parallel(&mut self, scope: &AudioWorkletGlobalScope) -> &AudioRenderQuantum {
+        // if the audio graph was changed, determine the new ordering and schedule
+        if self.ordered.is_empty() {
+            self.order_nodes();
+            self.schedule.stale = true;
+        }
+        if self.schedule.stale {
+            self.schedule.build(&self.ordered, &self.nodes);
+        }
+
+        let pool = self.pool.as_ref().unwrap();
+        let nodes = SharedNodes(&self.nodes);
+        let ordered = &self.ordered[..];
+        let schedule = &self.schedule;
+
+        // the render scope is not shared with the worker threads, they construct their own
+        let current_frame = scope.current_frame;
+        let current_time = scope.current_time;
+        let sample_rate = scope.sample_rate;
+        let render_quantum_size = scope.render_quantum_size;
+        let event_sender = &scope.event_sender;
+        let profiling = self.profiling;
+
+        let render_local = |node: &ScheduledNode| {
+            let incoming = &schedule.incoming[node.incoming.clone()];
+            render_scheduled_node(nodes.get(), ordered, incoming, node.id, scope, profiling);
+        };
+
+        for level in &schedule.levels {
+            let pinned = &schedule.nodes[level.start..level.pinned_end];
+            let parallel = &schedule.nodes[level.pinned_end..level.end];
+
+            // not worth the synchronization overhead
+            if parallel.len() < 2 {
+                pinned.iter().chain(parallel).for_each(render_local);
+                continue;
+            }
+
+            let task = |i: usize| {
+                let node = &parallel[i];
+                let scope = AudioWorkletGlobalScope {
+                    current_frame,
+                    current_time,
+                    sample_rate,
+                    render_quantum_size,
+                    node_id: Cell::new(node.id),
+                    event_sender: event_sender.clone(),
+                };
+                let incoming = &schedule.incoming[node.incoming.clone()];
+                render_scheduled_node(nodes.get(), ordered, incoming, node.id, &scope, profiling);
+            };
+            pool.run(parallel.len(), &task, || {
+                pinned.iter().for_each(render_local)
+            });
+        }
+
+        // Decommission the nodes that reached their end of life, in topological order
+        let mut nodes_dropped = false;
+        for index in self.ordered.iter() {
+            if !self.nodes.get_unchecked(*index).borrow().end_of_life {
+                continue;
+            }
+
+            // Node is dropped, remove it from the node list
+            let mut node = self.nodes.remove(*index).into_inner();
+            self.reclaim_id_channel
+                .push(node.reclaim_id.take().unwrap());
+            scope.node_id.set(*index);
+            node.processor.before_drop(scope);
+            if self.profiling {
+                self.profile.push(node.take_profile(*index));
+            }
+            drop(node);
+
+            nodes_dropped = true;
+
+            // Disconnect the AudioParams and AudioListener that fed into the dropped node
+            self.nodes.values_mut().for_each(|node| {
+                node.get_mut()
+                    .outgoing_edges
+                    .retain(|e| e.other_id != *index);
+            });
+        }
+
+        // If there were any nodes decommissioned, remove from graph order
+        if nodes_dropped {
+            let nodes = &self.nodes;
+            self.ordered.retain(|index| nodes.contains(*index));
+            self.schedule.stale = true;
+        }
+
+        // Resolve connections that were scheduled to be made or broken during this quantum
+        if self.has_edge_fades {
+            self.settle_edge_fades(scope.current_frame + scope.render_quantum_size as u64);
+        }
+
         // Return the output buffer of destination node
         &self.nodes.get_unchecked_mut(AudioNodeId(0)).outputs[0]
     }
@@ -535,6 +1266,7 @@ impl Graph {
 mod tests {
     use super::*;
     use crate::context::DESTINATION_NODE_ID;
+    use crate::RENDER_QUANTUM_SIZE;
 
     #[derive(Debug, Clone)]
     struct TestNode {
@@ -579,7 +1311,7 @@ mod tests {
     // https://github.com/orottier/web-audio-api-rs/issues/389
     #[test]
     fn test_active() {
-        let mut graph = Graph::new(llq::Queue::new().split().0);
+        let mut graph = Graph::new(llq::Queue::new().split().0, RENDER_QUANTUM_SIZE);
         assert!(!graph.is_active());
         // graph is active only when AudioDestination is set up
         let node = Box::new(TestNode { tail_time: false });
@@ -589,7 +1321,7 @@ mod tests {
 
     #[test]
     fn test_add_remove() {
-        let mut graph = Graph::new(llq::Queue::new().split().0);
+        let mut graph = Graph::new(llq::Queue::new().split().0, RENDER_QUANTUM_SIZE);
 
         let node = Box::new(TestNode { tail_time: false });
         add_node(&mut graph, 0, node.clone());
@@ -640,7 +1372,7 @@ mod tests {
 
     #[test]
     fn test_cycle() {
-        let mut graph = Graph::new(llq::Queue::new().split().0);
+        let mut graph = Graph::new(llq::Queue::new().split().0, RENDER_QUANTUM_SIZE);
 
         let node = Box::new(TestNode { tail_time: false });
         add_node(&mut graph, 0, node.clone());
@@ -673,10 +1405,201 @@ mod tests {
         assert!(pos3.unwrap() < pos0.unwrap());
     }
 
+    #[test]
+    fn test_diagnostics() {
+        let mut graph = Graph::new(llq::Queue::new().split().0, RENDER_QUANTUM_SIZE);
+
+        let node = Box::new(TestNode { tail_time: false });
+        add_node(&mut graph, 0, node.clone());
+        add_node(&mut graph, 1, node.clone());
+        add_node(&mut graph, 2, node.clone());
+        add_node(&mut graph, 3, node);
+
+        // link 1->0, 1<>2 (cycle), 3->param of 1
+        add_edge(&mut graph, 1, 0);
+        add_edge(&mut graph, 1, 2);
+        add_edge(&mut graph, 2, 1);
+        add_audioparam(&mut graph, 3, 1);
+        graph.mark_control_handle_dropped(AudioNodeId(3));
+
+        let diagnostics = graph.diagnostics();
+        assert_eq!(diagnostics.nodes.len(), 4);
+        assert_eq!(diagnostics.buffer_pool_size, graph.alloc.pool_size());
+
+        let node0 = diagnostics.node(0).unwrap();
+        assert!(!node0.muted);
+        assert!(node0.render_order.is_some());
+
+        let node1 = diagnostics.node(1).unwrap();
+        assert!(node1.muted);
+        assert_eq!(node1.render_order, None);
+        assert_eq!(node1.outgoing_edges.len(), 2);
+        assert!(diagnostics.node(2).unwrap().muted);
+
+        let node3 = diagnostics.node(3).unwrap();
+        assert!(node3.control_handle_dropped);
+        assert_eq!(
+            node3.outgoing_edges,
+            vec![AudioEdgeDiagnostics {
+                output: 0,
+                destination: 1,
+                input: None,
+            }]
+        );
+        assert_eq!(diagnostics.nodes_by_name(node3.name).count(), 4);
+    }
+
+    #[test]
+    fn test_profiling() {
+        let (node_id_producer, _node_id_consumer) = llq::Queue::new().split();
+        let mut graph = Graph::new(node_id_producer, RENDER_QUANTUM_SIZE);
+
+        let node = Box::new(TestNode { tail_time: false });
+        add_node(&mut graph, 0, node.clone());
+        add_node(&mut graph, 1, node);
+        add_edge(&mut graph, 1, 0);
+
+        let scope = AudioWorkletGlobalScope {
+            current_frame: 0,
+            current_time: 0.,
+            sample_rate: 48000.,
+            render_quantum_size: RENDER_QUANTUM_SIZE,
+            node_id: Cell::new(AudioNodeId(0)),
+            event_sender: crossbeam_channel::unbounded().0.into(),
+        };
+
+        // nothing is measured when profiling is disabled
+        graph.render(&scope);
+        assert!(graph.collect_profile(vec![]).is_empty());
+
+        graph.set_profiling(true);
+        graph.render(&scope);
+        graph.render(&scope);
+
+        // node 1 is dropped, its measurements are retained
+        graph.mark_control_handle_dropped(AudioNodeId(1));
+        graph.render(&scope);
+        assert!(!graph.nodes.contains(AudioNodeId(1)));
+
+        let mut profile = graph.collect_profile(Vec::with_capacity(2));
+        profile.sort_by_key(|p| p.id.0);
+        assert_eq!(profile.len(), 2);
+        assert_eq!(profile[0].id, AudioNodeId(0));
+        assert_eq!(profile[0].render_quanta, 3);
+        assert_eq!(profile[1].id, AudioNodeId(1));
+        assert_eq!(profile[1].render_quanta, 3);
+
+        // measurements are reset after collection
+        graph.render(&scope);
+        let profile = graph.collect_profile(vec![]);
+        assert_eq!(profile.len(), 1);
+        assert_eq!(profile[0].render_quanta, 1);
+
+        graph.set_profiling(false);
+        graph.render(&scope);
+        assert!(graph.collect_profile(vec![]).is_empty());
+    }
+
+    #[test]
+    fn test_edge_fade_gain() {
+        let fade_in = EdgeFade {
+            start_frame: 10,
+            fade_frames: 4,
+            fade_in: true,
+        };
+        let gains: Vec<_> = (9..16).map(|frame| fade_in.gain(frame)).collect();
+        assert_eq!(gains, [0., 0., 0.25, 0.5, 0.75, 1., 1.]);
+        assert!(!fade_in.is_done(13));
+        assert!(fade_in.is_done(14));
+
+        let switch_off = EdgeFade {
+            start_frame: 10,
+            fade_frames: 0,
+            fade_in: false,
+        };
+        assert_eq!(switch_off.gain(9), 1.);
+        assert_eq!(switch_off.gain(10), 0.);
+        assert!(switch_off.is_done(10));
+    }
+
+    #[test]
+    fn test_scheduled_edges() {
+        let (node_id_producer, _node_id_consumer) = llq::Queue::new().split();
+        let mut graph = Graph::new(node_id_producer, RENDER_QUANTUM_SIZE);
+
+        let node = Box::new(TestNode { tail_time: false });
+        add_node(&mut graph, 0, node.clone());
+        add_node(&mut graph, 1, node);
+
+        let fade = |start_frame, fade_in| EdgeFade {
+            start_frame,
+            fade_frames: 64,
+            fade_in,
+        };
+        let scope = |quantum: u64| AudioWorkletGlobalScope {
+            current_frame: quantum * RENDER_QUANTUM_SIZE as u64,
+            current_time: 0.,
+            sample_rate: 48000.,
+            render_quantum_size: RENDER_QUANTUM_SIZE,
+            node_id: Cell::new(AudioNodeId(0)),
+            event_sender: crossbeam_channel::unbounded().0.into(),
+        };
+        let edges = |graph: &Graph| {
+            graph
+                .nodes
+                .get_unchecked(AudioNodeId(1))
+                .borrow()
+                .outgoing_edges
+                .iter()
+                .map(|edge| edge.fades.clone())
+                .collect::<Vec<_>>()
+        };
+        let silent = EdgeFade {
+            start_frame: 0,
+            fade_frames: 0,
+            fade_in: false,
+        };
+
+        // fade in over the second and third quantum
+        graph.add_edge_at((AudioNodeId(1), 0), (AudioNodeId(0), 0), fade(192, true));
+        graph.render(&scope(0));
+        assert_eq!(edges(&graph), [vec![silent, fade(192, true)]]);
+        graph.render(&scope(1));
+        assert_eq!(edges(&graph), [vec![]]);
+        assert!(!graph.has_edge_fades);
+
+        // a scheduled disconnect removes the edge when the fade completes
+        graph.remove_edge_at((AudioNodeId(1), 0), (AudioNodeId(0), 0), fade(384, false));
+        graph.render(&scope(2));
+        assert_eq!(edges(&graph), [vec![fade(384, false)]]);
+
+        // unless the edge is connected again afterwards
+        graph.add_edge_at((AudioNodeId(1), 0), (AudioNodeId(0), 0), fade(448, true));
+        {
+            let node = graph.nodes.get_unchecked(AudioNodeId(1)).borrow();
+            let edge = &node.outgoing_edges[0];
+            assert_eq!(edge.gain(383), 1.);
+            assert_eq!(edge.gain(416), 0.5);
+            assert_eq!(edge.gain(448), 0.);
+            assert_eq!(edge.gain(480), 0.5);
+            assert_eq!(edge.gain(512), 1.);
+            assert!(edge.is_fading(&(384..512)));
+            assert!(!edge.is_fading(&(512..640)));
+        }
+        graph.render(&scope(3));
+        assert_eq!(edges(&graph), [vec![]]);
+
+        graph.remove_edge_at((AudioNodeId(1), 0), (AudioNodeId(0), 0), fade(512, false));
+        graph.render(&scope(4));
+        assert!(edges(&graph).is_empty());
+        assert!(graph.ordered.is_empty());
+        assert!(!graph.has_edge_fades);
+    }
+
     #[test]
     fn test_lifecycle_and_reclaim() {
         let (node_id_producer, mut node_id_consumer) = llq::Queue::new().split();
-        let mut graph = Graph::new(node_id_producer);
+        let mut graph = Graph::new(node_id_producer, RENDER_QUANTUM_SIZE);
 
         let node = Box::new(TestNode { tail_time: false });
 
@@ -703,8 +1626,9 @@ mod tests {
             current_frame: 0,
             current_time: 0.,
             sample_rate: 48000.,
+            render_quantum_size: RENDER_QUANTUM_SIZE,
             node_id: std::cell::Cell::new(AudioNodeId(0)),
-            event_sender: crossbeam_channel::unbounded().0,
+            event_sender: crossbeam_channel::unbounded().0.into(),
         };
         graph.render(&scope);
 
@@ -721,7 +1645,7 @@ mod tests {
     #[test]
     fn test_audio_param_lifecycle() {
         let (node_id_producer, mut node_id_consumer) = llq::Queue::new().split();
-        let mut graph = Graph::new(node_id_producer);
+        let mut graph = Graph::new(node_id_producer, RENDER_QUANTUM_SIZE);
 
         let node = Box::new(TestNode { tail_time: false });
 
@@ -760,8 +1684,9 @@ mod tests {
             current_frame: 0,
             current_time: 0.,
             sample_rate: 48000.,
+            render_quantum_size: RENDER_QUANTUM_SIZE,
             node_id: std::cell::Cell::new(AudioNodeId(0)),
-            event_sender: crossbeam_channel::unbounded().0,
+            event_sender: crossbeam_channel::unbounded().0.into(),
         };
 
         // render twice
@@ -779,7 +1704,7 @@ mod tests {
     #[test]
     fn test_audio_param_with_signal_lifecycle() {
         let (node_id_producer, mut node_id_consumer) = llq::Queue::new().split();
-        let mut graph = Graph::new(node_id_producer);
+        let mut graph = Graph::new(node_id_producer, RENDER_QUANTUM_SIZE);
 
         let node = Box::new(TestNode { tail_time: false });
 
@@ -828,8 +1753,9 @@ mod tests {
             current_frame: 0,
             current_time: 0.,
             sample_rate: 48000.,
+            render_quantum_size: RENDER_QUANTUM_SIZE,
             node_id: std::cell::Cell::new(AudioNodeId(0)),
-            event_sender: crossbeam_channel::unbounded().0,
+            event_sender: crossbeam_channel::unbounded().0.into(),
         };
 
         // render twice
@@ -851,7 +1777,7 @@ mod tests {
     #[test]
     fn test_release_orphaned_source_nodes() {
         let (node_id_producer, mut node_id_consumer) = llq::Queue::new().split();
-        let mut graph = Graph::new(node_id_producer);
+        let mut graph = Graph::new(node_id_producer, RENDER_QUANTUM_SIZE);
 
         let node = Box::new(TestNode { tail_time: true });
 
@@ -877,8 +1803,9 @@ mod tests {
             current_frame: 0,
             current_time: 0.,
             sample_rate: 48000.,
+            render_quantum_size: RENDER_QUANTUM_SIZE,
             node_id: std::cell::Cell::new(AudioNodeId(0)),
-            event_sender: crossbeam_channel::unbounded().0,
+            event_sender: crossbeam_channel::unbounded().0.into(),
         };
         graph.render(&scope);
 
@@ -13,6 +13,7 @@ pub use processor::*;
 mod quantum;
 
 mod node_collection;
+mod worker_pool;
 pub(crate) use node_collection::NodeCollection;
 
 pub use quantum::*;
@@ -1,11 +1,11 @@
 //! Audio processing code that runs on the audio rendering thread
+use crate::channel::EventSender;
 use crate::context::{AudioNodeId, AudioParamId};
 use crate::events::{AudioProcessingEvent, ErrorEvent, EventDispatch};
-use crate::{AudioBuffer, Event, RENDER_QUANTUM_SIZE};
+use crate::{AudioBuffer, Event};
 
 use super::{graph::Node, AudioRenderQuantum, NodeCollection};
 
-use crossbeam_channel::Sender;
 use std::cell::Cell;
 
 use std::any::Any;
@@ -21,9 +21,11 @@ pub struct AudioWorkletGlobalScope {
     pub current_frame: u64,
     pub current_time: f64,
     pub sample_rate: f32,
+    /// Number of sample frames in a render quantum of this context (default: 128)
+    pub render_quantum_size: usize,
 
     pub(crate) node_id: Cell<AudioNodeId>,
-    pub(crate) event_sender: Sender<EventDispatch>,
+    pub(crate) event_sender: EventSender,
 }
 
 impl std::fmt::Debug for AudioWorkletGlobalScope {
@@ -33,6 +35,7 @@ impl std::fmt::Debug for AudioWorkletGlobalScope {
             .field("current_frame", &self.current_frame)
             .field("current_time", &self.current_time)
             .field("sample_rate", &self.sample_rate)
+            .field("render_quantum_size", &self.render_quantum_size)
             .finish_non_exhaustive()
     }
 }
@@ -174,6 +177,16 @@ pub trait AudioProcessor: Send {
         false
     }
 
+    /// Indicates if this processor must be rendered by the render thread itself
+    ///
+    /// When parallel rendering is enabled, processors may be invoked from a pool of worker
+    /// threads. Processors that share state with other processors, or that are not truly `Send`,
+    /// should return `true`. They will be rendered by the render thread, in the same order as with
+    /// serial rendering.
+    fn requires_render_thread(&self) -> bool {
+        false
+    }
+
     fn before_drop(&mut self, _scope: &AudioWorkletGlobalScope) {}
 }
 
@@ -183,20 +196,19 @@ impl std::fmt::Debug for dyn AudioProcessor {
     }
 }
 
-struct DerefAudioRenderQuantumChannel<'a>(std::cell::Ref<'a, Node>);
+struct DerefAudioRenderQuantumChannel<'a>(&'a Node);
 
 impl Deref for DerefAudioRenderQuantumChannel<'_> {
     type Target = [f32];
 
     fn deref(&self) -> &Self::Target {
         let buffer = self.0.get_buffer();
-        let len = if buffer.single_valued() {
-            1
+        let channel = buffer.channel_data(0);
+        if buffer.single_valued() {
+            &channel[..1]
         } else {
-            RENDER_QUANTUM_SIZE
-        };
-
-        &buffer.channel_data(0)[..len]
+            &channel[..]
+        }
     }
 }
 
@@ -225,7 +237,13 @@ impl<'a> AudioParamValues<'a> {
     /// provide a slice of length equal to the render quantum size (default: 128)
     #[allow(clippy::missing_panics_doc)]
     pub fn get(&self, index: &AudioParamId) -> impl Deref<Target = [f32]> + '_ {
-        DerefAudioRenderQuantumChannel(self.nodes.get_unchecked(index.into()).borrow())
+        let node = self.nodes.get_unchecked(index.into());
+        // SAFETY: AudioParams are rendered before the processors they feed into and are not
+        // mutated while these processors are rendering. Borrowing without updating the borrow
+        // flag allows multiple worker threads to read the same AudioParam simultaneously.
+        let node = unsafe { node.try_borrow_unguarded() }
+            .expect("InvalidStateError - AudioParam is being rendered");
+        DerefAudioRenderQuantumChannel(node)
     }
 
     pub(crate) fn listener_params(&self) -> [impl Deref<Target = [f32]> + '_; 9] {
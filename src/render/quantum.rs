@@ -1,85 +1,135 @@
 //! Optimized audio signal data structures, used in `AudioProcessors`
 use arrayvec::ArrayVec;
 use std::cell::RefCell;
-use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::node::{ChannelConfigInner, ChannelCountMode, ChannelInterpretation};
 
 use crate::assert_valid_number_of_channels;
-use crate::{MAX_CHANNELS, RENDER_QUANTUM_SIZE};
+use crate::MAX_CHANNELS;
+#[cfg(test)]
+use crate::RENDER_QUANTUM_SIZE;
 
 // object pool for `AudioRenderQuantumChannel`s, only allocate if the pool is empty
+//
+// The pool is owned by the thread that drives the render graph and is not locked. The worker
+// threads of the parallel renderer each have their own pool (see `WORKER_POOL`), so they never
+// touch this one. The buffers themselves are reference counted atomically, because the workers
+// share the outputs of the nodes they have rendered.
 pub(crate) struct Alloc {
-    inner: Rc<AllocInner>,
+    inner: Arc<AllocInner>,
 }
 
 #[derive(Debug)]
 struct AllocInner {
-    pool: RefCell<Vec<Rc<[f32; RENDER_QUANTUM_SIZE]>>>,
-    zeroes: Rc<[f32; RENDER_QUANTUM_SIZE]>,
+    pool: RefCell<Vec<Arc<[f32]>>>,
+    zeroes: Arc<[f32]>,
+    render_quantum_size: usize,
+}
+
+/// Maximum number of buffers kept by the pool of a worker thread
+///
+/// Buffers migrate between the pools when they are allocated and released on different threads,
+/// the surplus of a worker pool is deallocated.
+const WORKER_POOL_CAPACITY: usize = 64;
+
+thread_local! {
+    /// Buffer pool of a render worker thread, `None` on all other threads
+    static WORKER_POOL: RefCell<Option<Vec<Arc<[f32]>>>> = const { RefCell::new(None) };
+}
+
+/// Give the current thread its own buffer pool, for rendering as a parallel render worker
+pub(crate) fn init_worker_pool() {
+    WORKER_POOL.with(|pool| *pool.borrow_mut() = Some(Vec::with_capacity(WORKER_POOL_CAPACITY)));
 }
 
 impl Alloc {
+    #[cfg(test)]
     pub fn with_capacity(n: usize) -> Self {
-        let pool: Vec<_> = (0..n).map(|_| Rc::new([0.; RENDER_QUANTUM_SIZE])).collect();
-        let zeroes = Rc::new([0.; RENDER_QUANTUM_SIZE]);
+        Self::with_render_quantum_size(n, RENDER_QUANTUM_SIZE)
+    }
+
+    pub fn with_render_quantum_size(n: usize, render_quantum_size: usize) -> Self {
+        let pool: Vec<_> = (0..n)
+            .map(|_| Arc::from(vec![0.; render_quantum_size]))
+            .collect();
+        let zeroes = Arc::from(vec![0.; render_quantum_size]);
 
         let inner = AllocInner {
             pool: RefCell::new(pool),
             zeroes,
+            render_quantum_size,
         };
 
-        Self {
-            inner: Rc::new(inner),
-        }
+        // The reference count is atomic because the parallel render workers share the buffers,
+        // while the pool itself is only used by the thread driving the graph
+        #[allow(clippy::arc_with_non_send_sync)]
+        let inner = Arc::new(inner);
+
+        Self { inner }
     }
 
     #[cfg(test)]
     pub fn allocate(&self) -> AudioRenderQuantumChannel {
         AudioRenderQuantumChannel {
             data: self.inner.allocate(),
-            alloc: Rc::clone(&self.inner),
+            alloc: Arc::clone(&self.inner),
         }
     }
 
     pub fn silence(&self) -> AudioRenderQuantumChannel {
         AudioRenderQuantumChannel {
-            data: Rc::clone(&self.inner.zeroes),
-            alloc: Rc::clone(&self.inner),
+            data: Arc::clone(&self.inner.zeroes),
+            alloc: Arc::clone(&self.inner),
         }
     }
 
-    #[cfg(test)]
+    /// Number of buffers available for reuse by the thread driving the render graph
     pub fn pool_size(&self) -> usize {
         self.inner.pool.borrow().len()
     }
 }
 
 impl AllocInner {
-    fn allocate(&self) -> Rc<[f32; RENDER_QUANTUM_SIZE]> {
-        if let Some(rc) = self.pool.borrow_mut().pop() {
+    fn allocate(&self) -> Arc<[f32]> {
+        let reused = WORKER_POOL.with(|worker_pool| match worker_pool.borrow_mut().as_mut() {
+            // pools of worker threads are shared by all graphs, check the buffer size
+            Some(pool) => pool
+                .pop()
+                .filter(|data| data.len() == self.render_quantum_size),
+            None => self.pool.borrow_mut().pop(),
+        });
+
+        if let Some(data) = reused {
             // reuse from pool
-            rc
+            data
         } else {
             // allocate
-            Rc::new([0.; RENDER_QUANTUM_SIZE])
+            Arc::from(vec![0.; self.render_quantum_size])
         }
     }
 
-    fn push(&self, data: Rc<[f32; RENDER_QUANTUM_SIZE]>) {
-        self.pool
-            .borrow_mut() // infallible when single threaded
-            .push(data);
+    fn push(&self, data: Arc<[f32]>) {
+        // the thread local is gone when a worker thread shuts down, deallocate the buffer then
+        let _ = WORKER_POOL.try_with(|worker_pool| match worker_pool.borrow_mut().as_mut() {
+            Some(pool) => {
+                if pool.len() < WORKER_POOL_CAPACITY {
+                    pool.push(data);
+                }
+            }
+            None => self.pool.borrow_mut().push(data),
+        });
     }
 }
 
 /// Render thread channel buffer
 ///
-/// Basically wraps a `Rc<[f32; render_quantum_size]>`, which means it derefs to a (mutable) slice
-/// of `[f32]` sample values. Plus it has copy-on-write semantics, so it is cheap to clone.
+/// Basically wraps an `Arc<[f32]>` of `render_quantum_size` samples, which means it derefs to a
+/// (mutable) slice of `[f32]` sample values. Plus it has copy-on-write semantics, so it is cheap
+/// to clone.
 ///
-/// The `render_quantum_size` is equal to 128 by default, but in future versions it may be equal to
-/// the hardware preferred render quantum size or any other value.
+/// The `render_quantum_size` is equal to 128 by default, but can be configured via the
+/// `render_size_hint` of the audio context options.
 ///
 /// # Usage
 ///
@@ -88,26 +138,29 @@ impl AllocInner {
 /// mutate it from there.
 #[derive(Clone, Debug)]
 pub struct AudioRenderQuantumChannel {
-    data: Rc<[f32; RENDER_QUANTUM_SIZE]>,
-    alloc: Rc<AllocInner>,
+    data: Arc<[f32]>,
+    alloc: Arc<AllocInner>,
 }
 
 impl AudioRenderQuantumChannel {
-    fn make_mut(&mut self) -> &mut [f32; RENDER_QUANTUM_SIZE] {
-        if Rc::strong_count(&self.data) != 1 {
+    fn make_mut(&mut self) -> &mut [f32] {
+        if Arc::get_mut(&mut self.data).is_none() {
             let mut new = self.alloc.allocate();
-            Rc::make_mut(&mut new).copy_from_slice(self.data.deref());
+            Arc::get_mut(&mut new)
+                .unwrap()
+                .copy_from_slice(self.data.deref());
             self.data = new;
         }
 
-        Rc::make_mut(&mut self.data)
+        // infallible, there are no other references to the data at this point
+        Arc::get_mut(&mut self.data).unwrap()
     }
 
     /// `O(1)` check if this buffer is equal to the 'silence buffer'
     ///
     /// If this function returns false, it is still possible for all samples to be zero.
     pub(crate) fn is_silent(&self) -> bool {
-        Rc::ptr_eq(&self.data, &self.alloc.zeroes)
+        Arc::ptr_eq(&self.data, &self.alloc.zeroes)
     }
 
     /// Sum two channels
@@ -121,8 +174,8 @@ impl AudioRenderQuantumChannel {
 
     pub(crate) fn silence(&self) -> Self {
         Self {
-            data: Rc::clone(&self.alloc.zeroes),
-            alloc: Rc::clone(&self.alloc),
+            data: Arc::clone(&self.alloc.zeroes),
+            alloc: Arc::clone(&self.alloc),
         }
     }
 }
@@ -133,7 +186,7 @@ impl Deref for AudioRenderQuantumChannel {
     type Target = [f32];
 
     fn deref(&self) -> &Self::Target {
-        self.data.as_slice()
+        &self.data[..]
     }
 }
 
@@ -151,10 +204,12 @@ impl AsRef<[f32]> for AudioRenderQuantumChannel {
 
 impl std::ops::Drop for AudioRenderQuantumChannel {
     fn drop(&mut self) {
-        if Rc::strong_count(&self.data) == 1 {
-            let zeroes = Rc::clone(&self.alloc.zeroes);
-            let rc = std::mem::replace(&mut self.data, zeroes);
-            self.alloc.push(rc);
+        // When clones of a buffer are dropped concurrently by multiple worker threads, the buffer
+        // may be deallocated instead of returned to a pool. The pool will be refilled when needed.
+        if Arc::strong_count(&self.data) == 1 {
+            let zeroes = Arc::clone(&self.alloc.zeroes);
+            let arc = std::mem::replace(&mut self.data, zeroes);
+            self.alloc.push(arc);
         }
     }
 }
@@ -165,8 +220,8 @@ impl std::ops::Drop for AudioRenderQuantumChannel {
 /// basically a list of [`AudioRenderQuantumChannel`]s cf.
 /// <https://webaudio.github.io/web-audio-api/#render-quantum>
 ///
-/// The `render_quantum_size` is equal to 128 by default, but in future versions it may be equal to
-/// the hardware preferred render quantum size or any other value.
+/// The `render_quantum_size` is equal to 128 by default, but can be configured via the
+/// `render_size_hint` of the audio context options.
 ///
 /// An `AudioRenderQuantum` has copy-on-write semantics, so it is cheap to clone.
 ///
@@ -649,7 +704,7 @@ impl AudioRenderQuantum {
         let mut channels = self.channels.iter();
         let first = channels.next().unwrap();
         for c in channels {
-            if !Rc::ptr_eq(&first.data, &c.data) {
+            if !Arc::ptr_eq(&first.data, &c.data) {
                 return false;
             }
         }
@@ -743,6 +798,24 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_worker_pool() {
+        let alloc = Alloc::with_capacity(2);
+
+        // a worker thread does not touch the pool of the render thread
+        init_worker_pool();
+        let mut a = alloc.allocate();
+        a.deref_mut().fill(1.);
+        drop(a);
+        assert_eq!(alloc.pool_size(), 2);
+
+        // the released buffer is reused by the worker
+        alloc_counter::deny_alloc(|| {
+            let a = alloc.allocate();
+            assert_float_eq!(&a[..], &[1.; RENDER_QUANTUM_SIZE][..], abs_all <= 0.);
+        });
+    }
+
     #[test]
     fn test_silence() {
         let alloc = Alloc::with_capacity(1);
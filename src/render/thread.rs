@@ -2,9 +2,10 @@
 
 use std::any::Any;
 use std::cell::Cell;
+use std::collections::VecDeque;
 use std::ops::ControlFlow;
 use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use crossbeam_channel::{Receiver, Sender};
@@ -14,21 +15,29 @@ use futures_util::StreamExt as _;
 
 use super::AudioRenderQuantum;
 use crate::buffer::AudioBuffer;
+use crate::channel::EventSender;
 use crate::context::{
-    AudioContextState, AudioNodeId, OfflineAudioContext, OfflineAudioContextCallback,
+    AudioContextState, AudioNodeId, AudioTimestamp, OfflineAudioContext,
+    OfflineAudioContextCallback,
 };
 use crate::events::{EventDispatch, EventLoop};
+use crate::io::{DeviceFailure, DuplexInput};
 use crate::message::ControlMessage;
 use crate::node::ChannelInterpretation;
 use crate::render::AudioWorkletGlobalScope;
-use crate::{AudioRenderCapacityLoad, RENDER_QUANTUM_SIZE};
+use crate::{AudioRenderCapacityLoad, PlayoutStats, RenderProfile};
 
 use super::graph::Graph;
 
+/// Number of scheduled transactions that can be pending without allocating on the render thread
+const PENDING_TRANSACTIONS_CAPACITY: usize = 32;
+
 /// Operations running off the system-level audio callback
 pub(crate) struct RenderThread {
     graph: Option<Graph>,
     sample_rate: f32,
+    /// number of sample frames rendered per render quantum
+    render_quantum_size: usize,
     buffer_size: usize,
     /// number of channels of the backend stream, i.e. sound card number of
     /// channels clamped to MAX_CHANNELS
@@ -39,15 +48,28 @@ pub(crate) struct RenderThread {
     receiver: Option<Receiver<ControlMessage>>,
     buffer_offset: Option<(usize, AudioRenderQuantum)>,
     load_value_sender: Option<Sender<AudioRenderCapacityLoad>>,
-    event_sender: Sender<EventDispatch>,
+    output_timestamp: Option<Arc<Mutex<Option<AudioTimestamp>>>>,
+    playout_stats: Option<Arc<Mutex<PlayoutStats>>>,
+    /// input captured in the same stream as the output, if any
+    duplex_input: Option<DuplexInput>,
+    /// playout statistics that could not be published yet because the lock was taken
+    pending_playout_stats: PlayoutStats,
+    /// start of the current profile collection period, in seconds
+    profile_timestamp: f64,
+    /// transactions scheduled for a later render quantum, sorted by application time
+    pending_transactions: VecDeque<(f64, Vec<ControlMessage>)>,
+    event_sender: EventSender,
+    /// signal for the sink fallback when the output device fails
+    device_failure: Option<DeviceFailure>,
     garbage_collector: Option<llq::Producer<Box<dyn Any + Send>>>,
 }
 
 // SAFETY:
-// The RenderThread is not Send/Sync since it contains `AudioRenderQuantum`s (which use Rc), but
-// these are only accessed within the same thread (the render thread). Due to the cpal constraints
-// we can neither move the RenderThread object into the render thread, nor can we initialize the
-// Rc's in that thread.
+// The RenderThread is not Send/Sync since it contains `AudioRenderQuantum`s (which refer to an
+// unsynchronized buffer pool), but these are only accessed within the same thread (the render
+// thread), or by the parallel render workers under the rules of `SharedNodes`. Due to the cpal
+// constraints we can neither move the RenderThread object into the render thread, nor can we
+// initialize the buffer pool in that thread.
 #[allow(clippy::non_send_fields_in_send_ty)]
 unsafe impl Send for Graph {}
 unsafe impl Sync for Graph {}
@@ -58,6 +80,7 @@ impl std::fmt::Debug for RenderThread {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("RenderThread")
             .field("sample_rate", &self.sample_rate)
+            .field("render_quantum_size", &self.render_quantum_size)
             .field("buffer_size", &self.buffer_size)
             .field("frames_played", &self.frames_played.load(Ordering::Relaxed))
             .field("number_of_channels", &self.number_of_channels)
@@ -68,15 +91,17 @@ impl std::fmt::Debug for RenderThread {
 impl RenderThread {
     pub fn new(
         sample_rate: f32,
+        render_quantum_size: usize,
         number_of_channels: usize,
         receiver: Receiver<ControlMessage>,
         state: Arc<AtomicU8>,
         frames_played: Arc<AtomicU64>,
-        event_sender: Sender<EventDispatch>,
+        event_sender: EventSender,
     ) -> Self {
         Self {
             graph: None,
             sample_rate,
+            render_quantum_size,
             buffer_size: 0,
             number_of_channels,
             suspended: false,
@@ -85,7 +110,14 @@ impl RenderThread {
             receiver: Some(receiver),
             buffer_offset: None,
             load_value_sender: None,
+            output_timestamp: None,
+            playout_stats: None,
+            duplex_input: None,
+            pending_playout_stats: PlayoutStats::default(),
+            profile_timestamp: 0.,
+            pending_transactions: VecDeque::with_capacity(PENDING_TRANSACTIONS_CAPACITY),
             event_sender,
+            device_failure: None,
             garbage_collector: None,
         }
     }
@@ -97,6 +129,60 @@ impl RenderThread {
         self.load_value_sender = Some(load_value_sender);
     }
 
+    pub(crate) fn set_output_timestamp(
+        &mut self,
+        output_timestamp: Arc<Mutex<Option<AudioTimestamp>>>,
+    ) {
+        self.output_timestamp = Some(output_timestamp);
+    }
+
+    pub(crate) fn set_playout_stats(&mut self, playout_stats: Arc<Mutex<PlayoutStats>>) {
+        self.playout_stats = Some(playout_stats);
+    }
+
+    /// Hand out the input of a duplex stream to the audio graph, one render quantum at a time
+    ///
+    /// The audio backend must push the input of a callback before rendering the output of that
+    /// callback.
+    pub(crate) fn set_duplex_input(&mut self, duplex_input: DuplexInput) {
+        self.duplex_input = Some(duplex_input);
+    }
+
+    pub(crate) fn set_device_failure(&mut self, device_failure: DeviceFailure) {
+        self.device_failure = Some(device_failure);
+    }
+
+    /// Update the number of channels of the interleaved output buffer
+    ///
+    /// Frames that were left over from the previous render call are discarded when the number of
+    /// channels changes.
+    pub(crate) fn set_number_of_channels(&mut self, number_of_channels: usize) {
+        if number_of_channels != self.number_of_channels {
+            self.number_of_channels = number_of_channels;
+            self.buffer_offset = None;
+        }
+    }
+
+    /// Callback for the audio backend to report that the output device has failed
+    ///
+    /// The error is dispatched as an `error` event of the `AudioContext` and triggers the sink
+    /// fallback, if enabled. Only the first error is reported, the stream of a failed device does
+    /// not recover.
+    pub(crate) fn device_error_handler(&self) -> impl FnMut(String) + Send + Sync + 'static {
+        let event_sender = self.event_sender.clone();
+        let device_failure = self.device_failure.clone();
+        let mut reported = false;
+        move |message| {
+            if !reported {
+                reported = true;
+                event_sender.try_send(EventDispatch::error(message)).ok();
+                if let Some(device_failure) = &device_failure {
+                    device_failure.notify();
+                }
+            }
+        }
+    }
+
     pub(crate) fn spawn_garbage_collector_thread(&mut self) {
         if self.garbage_collector.is_none() {
             let (gc_producer, gc_consumer) = llq::Queue::new().split();
@@ -119,7 +205,70 @@ impl RenderThread {
         }
     }
 
+    /// Handle the pending control messages without rendering
+    ///
+    /// This is used by backends that are not driven by a hardware clock, so control operations
+    /// take effect while the clock is not advancing.
+    pub(crate) fn handle_pending_control_messages(&mut self) {
+        self.handle_control_messages();
+    }
+
+    /// Apply the scheduled transactions that are due at the upcoming render quantum
+    fn apply_due_transactions(&mut self) {
+        let current_time =
+            self.frames_played.load(Ordering::Relaxed) as f64 / self.sample_rate as f64;
+        while self
+            .pending_transactions
+            .front()
+            .is_some_and(|(when, _)| *when <= current_time)
+        {
+            let (_, messages) = self.pending_transactions.pop_front().unwrap();
+            self.apply_scheduled_transaction(messages);
+        }
+    }
+
+    /// Apply the graph edits of a transaction that was held back
+    fn apply_scheduled_transaction(&mut self, messages: Vec<ControlMessage>) {
+        for msg in messages {
+            let graph = self.graph.as_mut().unwrap();
+            msg.node_ids().for_each(|id| graph.unpin_node(id));
+            let _ = self.apply_control_message(msg);
+        }
+    }
+
     fn handle_control_message(&mut self, msg: ControlMessage) -> ControlFlow<()> {
+        // Only the graph edits of a scheduled transaction are held back, all other messages are
+        // applied right away
+        if let ControlMessage::Transaction { messages, when } = msg {
+            let current_time =
+                self.frames_played.load(Ordering::Relaxed) as f64 / self.sample_rate as f64;
+            match when {
+                Some(when) if when > current_time => {
+                    // keep the nodes alive until the transaction is applied
+                    let graph = self.graph.as_mut().unwrap();
+                    messages
+                        .iter()
+                        .flat_map(ControlMessage::node_ids)
+                        .for_each(|id| graph.pin_node(id));
+
+                    let index = self
+                        .pending_transactions
+                        .partition_point(|(previous, _)| *previous <= when);
+                    self.pending_transactions.insert(index, (when, messages));
+                }
+                _ => {
+                    for msg in messages {
+                        let _ = self.apply_control_message(msg);
+                    }
+                }
+            }
+            return ControlFlow::Continue(());
+        }
+
+        self.apply_control_message(msg)
+    }
+
+    fn apply_control_message(&mut self, msg: ControlMessage) -> ControlFlow<()> {
         use ControlMessage::*;
 
         match msg {
@@ -162,6 +311,30 @@ impl RenderThread {
                     .unwrap()
                     .remove_edge((from, output), (to, input));
             }
+            ConnectNodeAt {
+                from,
+                to,
+                output,
+                input,
+                fade,
+            } => {
+                self.graph
+                    .as_mut()
+                    .unwrap()
+                    .add_edge_at((from, output), (to, input), fade);
+            }
+            DisconnectNodeAt {
+                from,
+                output,
+                to,
+                input,
+                fade,
+            } => {
+                self.graph
+                    .as_mut()
+                    .unwrap()
+                    .remove_edge_at((from, output), (to, input), fade);
+            }
             ControlHandleDropped { id } => {
                 self.graph.as_mut().unwrap().mark_control_handle_dropped(id);
             }
@@ -170,14 +343,16 @@ impl RenderThread {
             }
             CloseAndRecycle { sender } => {
                 self.set_state(AudioContextState::Suspended);
-                let _ = sender.send(self.graph.take().unwrap());
-                self.receiver = None;
+                self.recycle_graph(sender);
                 return ControlFlow::Break(()); // no further handling of ctrl msgs
             }
             Startup { graph } => {
                 debug_assert!(self.graph.is_none());
                 self.graph = Some(graph);
-                self.set_state(AudioContextState::Running);
+                // a graph recycled from a dropped render thread is still running, see `Drop`
+                if self.state.load(Ordering::Relaxed) != AudioContextState::Running as u8 {
+                    self.set_state(AudioContextState::Running);
+                }
             }
             NodeMessage { id, mut msg } => {
                 self.graph.as_mut().unwrap().route_message(id, msg.as_mut());
@@ -189,9 +364,37 @@ impl RenderThread {
                 use std::io::Write;
                 writeln!(&mut buffer, "{:#?}", &self).ok();
                 writeln!(&mut buffer, "{:?}", &self.graph).ok();
-                self.event_sender
-                    .try_send(EventDispatch::diagnostics(buffer))
-                    .expect("Unable to send diagnostics - channel is full");
+                // drop the diagnostics rather than blocking when the event channel is full
+                let _ = self
+                    .event_sender
+                    .try_send(EventDispatch::diagnostics(buffer));
+            }
+            SetProfiling { enabled } => {
+                self.graph.as_mut().unwrap().set_profiling(enabled);
+                self.profile_timestamp =
+                    self.frames_played.load(Ordering::Relaxed) as f64 / self.sample_rate as f64;
+            }
+            CollectProfile { buffer } => {
+                let nodes = self.graph.as_mut().unwrap().collect_profile(buffer);
+                let current_time =
+                    self.frames_played.load(Ordering::Relaxed) as f64 / self.sample_rate as f64;
+                let profile = RenderProfile {
+                    timestamp: self.profile_timestamp,
+                    duration: current_time - self.profile_timestamp,
+                    nodes,
+                };
+                self.profile_timestamp = current_time;
+                // drop the measurements rather than blocking when the event channel is full
+                let _ = self
+                    .event_sender
+                    .try_send(EventDispatch::render_profile(profile));
+            }
+            RunGraphDiagnostics => {
+                let diagnostics = self.graph.as_mut().unwrap().diagnostics();
+                // drop the snapshot rather than blocking when the event channel is full
+                let _ = self
+                    .event_sender
+                    .try_send(EventDispatch::graph_diagnostics(diagnostics));
             }
             Suspend { notify } => {
                 self.suspended = true;
@@ -226,6 +429,8 @@ impl RenderThread {
                     .unwrap()
                     .set_channel_interpretation(id, interpretation);
             }
+
+            Transaction { .. } => unreachable!("transactions are handled before being applied"),
         }
 
         ControlFlow::Continue(()) // continue handling more messages
@@ -238,19 +443,41 @@ impl RenderThread {
     //
     // cf. https://webaudio.github.io/web-audio-api/#dom-offlineaudiocontext-startrendering
     pub fn render_audiobuffer_sync(
+        self,
+        context: &mut OfflineAudioContext,
+        suspend_callbacks: Vec<(usize, Box<OfflineAudioContextCallback>)>,
+        event_loop: &EventLoop,
+    ) -> AudioBuffer {
+        // render the full length in a single chunk
+        let length = context.length();
+        let mut result = None;
+        self.render_audiobuffer_chunks_sync(
+            context,
+            suspend_callbacks,
+            event_loop,
+            length,
+            |chunk| result = Some(chunk),
+        );
+        result.unwrap()
+    }
+
+    // Render method of the `OfflineAudioContext::start_rendering_chunked_sync`
+    //
+    // The chunk length must be a multiple of the render quantum size, except for the single chunk
+    // case where it equals the total length. The last chunk is truncated to the total length.
+    pub fn render_audiobuffer_chunks_sync(
         mut self,
         context: &mut OfflineAudioContext,
         mut suspend_callbacks: Vec<(usize, Box<OfflineAudioContextCallback>)>,
         event_loop: &EventLoop,
-    ) -> AudioBuffer {
+        chunk_length: usize,
+        mut on_chunk: impl FnMut(AudioBuffer),
+    ) {
         let length = context.length();
-        let sample_rate = self.sample_rate;
+        let num_frames = (length + self.render_quantum_size - 1) / self.render_quantum_size;
 
         // construct a properly sized output buffer
-        let mut buffer = Vec::with_capacity(self.number_of_channels);
-        buffer.resize_with(buffer.capacity(), || Vec::with_capacity(length));
-
-        let num_frames = (length + RENDER_QUANTUM_SIZE - 1) / RENDER_QUANTUM_SIZE;
+        let mut buffer = self.chunk_buffer(chunk_length.min(length));
 
         // Handle initial control messages
         self.handle_control_messages();
@@ -272,13 +499,14 @@ impl RenderThread {
                 // Handle any control messages that may have been submitted by the handler
                 self.handle_control_messages();
             }
+
+            let rendered = ((quantum + 1) * self.render_quantum_size).min(length);
+            self.emit_full_chunk(&mut buffer, rendered, length, chunk_length, &mut on_chunk);
         }
 
         // call destructors of all alive nodes and handle any resulting events
         self.unload_graph();
         event_loop.handle_pending_events();
-
-        AudioBuffer::from(buffer, sample_rate)
     }
 
     // Render method of the `OfflineAudioContext::start_rendering`
@@ -287,19 +515,42 @@ impl RenderThread {
     //
     // cf. https://webaudio.github.io/web-audio-api/#dom-offlineaudiocontext-startrendering
     pub async fn render_audiobuffer(
+        self,
+        length: usize,
+        suspend_callbacks: Vec<(usize, oneshot::Sender<()>)>,
+        resume_receiver: mpsc::Receiver<()>,
+        event_loop: &EventLoop,
+    ) -> AudioBuffer {
+        // render the full length in a single chunk
+        let mut result = None;
+        self.render_audiobuffer_chunks(
+            length,
+            suspend_callbacks,
+            resume_receiver,
+            event_loop,
+            length,
+            |chunk| result = Some(chunk),
+        )
+        .await;
+        result.unwrap()
+    }
+
+    // Render method of the `OfflineAudioContext::start_rendering_chunked`
+    //
+    // This is the async interface, as compared to render_audiobuffer_chunks_sync
+    pub async fn render_audiobuffer_chunks(
         mut self,
         length: usize,
         mut suspend_callbacks: Vec<(usize, oneshot::Sender<()>)>,
         mut resume_receiver: mpsc::Receiver<()>,
         event_loop: &EventLoop,
-    ) -> AudioBuffer {
-        let sample_rate = self.sample_rate;
+        chunk_length: usize,
+        mut on_chunk: impl FnMut(AudioBuffer),
+    ) {
+        let num_frames = (length + self.render_quantum_size - 1) / self.render_quantum_size;
 
         // construct a properly sized output buffer
-        let mut buffer = Vec::with_capacity(self.number_of_channels);
-        buffer.resize_with(buffer.capacity(), || Vec::with_capacity(length));
-
-        let num_frames = (length + RENDER_QUANTUM_SIZE - 1) / RENDER_QUANTUM_SIZE;
+        let mut buffer = self.chunk_buffer(chunk_length.min(length));
 
         // Handle addition/removal of nodes/edges
         self.handle_control_messages();
@@ -322,27 +573,60 @@ impl RenderThread {
                 // Handle any control messages that may have been submitted by the handler
                 self.handle_control_messages();
             }
+
+            let rendered = ((quantum + 1) * self.render_quantum_size).min(length);
+            self.emit_full_chunk(&mut buffer, rendered, length, chunk_length, &mut on_chunk);
         }
 
         // call destructors of all alive nodes and handle any resulting events
         self.unload_graph();
         event_loop.handle_pending_events();
+    }
+
+    /// Allocate the output buffer for a chunk of the given length
+    fn chunk_buffer(&self, chunk_length: usize) -> Vec<Vec<f32>> {
+        let mut buffer = Vec::with_capacity(self.number_of_channels);
+        buffer.resize_with(buffer.capacity(), || Vec::with_capacity(chunk_length));
+        buffer
+    }
 
-        AudioBuffer::from(buffer, sample_rate)
+    /// Hand out the output buffer when it is full, and allocate the buffer for the next chunk
+    fn emit_full_chunk(
+        &self,
+        buffer: &mut Vec<Vec<f32>>,
+        rendered: usize,
+        length: usize,
+        chunk_length: usize,
+        on_chunk: &mut impl FnMut(AudioBuffer),
+    ) {
+        if buffer[0].len() < buffer[0].capacity() {
+            return;
+        }
+
+        let next = if rendered < length {
+            self.chunk_buffer(chunk_length.min(length - rendered))
+        } else {
+            vec![]
+        };
+        let chunk = std::mem::replace(buffer, next);
+        on_chunk(AudioBuffer::from(chunk, self.sample_rate));
     }
 
     /// Render a single quantum into an AudioBuffer
     fn render_offline_quantum(&mut self, buffer: &mut [Vec<f32>]) {
+        self.apply_due_transactions();
+
         // Update time
         let current_frame = self
             .frames_played
-            .fetch_add(RENDER_QUANTUM_SIZE as u64, Ordering::Relaxed);
+            .fetch_add(self.render_quantum_size as u64, Ordering::Relaxed);
         let current_time = current_frame as f64 / self.sample_rate as f64;
 
         let scope = AudioWorkletGlobalScope {
             current_frame,
             current_time,
             sample_rate: self.sample_rate,
+            render_quantum_size: self.render_quantum_size,
             event_sender: self.event_sender.clone(),
             node_id: Cell::new(AudioNodeId(0)), // placeholder value
         };
@@ -357,15 +641,15 @@ impl RenderThread {
         let rendered = graph.render(&scope);
 
         // Use a specialized copyToChannel implementation for performance
-        let remaining = (buffer[0].capacity() - buffer[0].len()).min(RENDER_QUANTUM_SIZE);
+        let remaining = (buffer[0].capacity() - buffer[0].len()).min(self.render_quantum_size);
         let channels = rendered.channels();
+        let silence = channels[0].silence();
         buffer.iter_mut().enumerate().for_each(|(i, b)| {
             let c = channels
                 .get(i)
-                .map(AsRef::as_ref)
                 // When there are no input nodes for the destination, only a single silent channel
                 // is emitted. So manually pad the missing channels with silence
-                .unwrap_or(&[0.; RENDER_QUANTUM_SIZE]);
+                .unwrap_or(&silence);
             b.extend_from_slice(&c[..remaining]);
         });
     }
@@ -379,6 +663,7 @@ impl RenderThread {
             current_frame,
             current_time,
             sample_rate: self.sample_rate,
+            render_quantum_size: self.render_quantum_size,
             event_sender: self.event_sender.clone(),
             node_id: Cell::new(AudioNodeId(0)), // placeholder value
         };
@@ -400,7 +685,7 @@ impl RenderThread {
         // calculate load value and ship to control thread
         if let Some(load_value_sender) = &self.load_value_sender {
             let duration = render_start.elapsed().as_micros() as f64 / 1E6;
-            let max_duration = RENDER_QUANTUM_SIZE as f64 / self.sample_rate as f64;
+            let max_duration = self.render_quantum_size as f64 / self.sample_rate as f64;
             let load_value = duration / max_duration;
             let render_timestamp =
                 self.frames_played.load(Ordering::Relaxed) as f64 / self.sample_rate as f64;
@@ -412,13 +697,86 @@ impl RenderThread {
         }
     }
 
+    /// Render into the output buffer and record the output timestamp and playout statistics
+    ///
+    /// `playback_time` is the estimated instant at which the first frame of `output_buffer` will
+    /// be played by the audio output device.
+    pub fn render_timestamped<S: FromSample<f32> + Clone>(
+        &mut self,
+        output_buffer: &mut [S],
+        playback_time: Instant,
+    ) {
+        let callback_start = Instant::now();
+
+        // The first frame of the output buffer is either a frame left over from the previous
+        // render call, or the first frame of the next render quantum
+        let leftover_frames = self
+            .buffer_offset
+            .as_ref()
+            .map_or(0, |(offset, _)| self.render_quantum_size - offset);
+        let frame = self.frames_played.load(Ordering::Relaxed) - leftover_frames as u64;
+
+        self.render(output_buffer);
+
+        // Only advance the timestamp when the audio graph is actually playing
+        if self.suspended || !self.graph.as_ref().is_some_and(Graph::is_active) {
+            // the input will be realigned when rendering starts
+            if let Some(duplex_input) = &self.duplex_input {
+                duplex_input.reset();
+            }
+            return;
+        }
+
+        if let Some(duplex_input) = &self.duplex_input {
+            let leftover_frames = self
+                .buffer_offset
+                .as_ref()
+                .map_or(0, |(offset, _)| self.render_quantum_size - offset);
+            duplex_input.update_latency(leftover_frames);
+        }
+
+        if let Some(output_timestamp) = &self.output_timestamp {
+            // never block the render thread
+            if let Ok(mut guard) = output_timestamp.try_lock() {
+                *guard = Some(AudioTimestamp {
+                    context_time: frame as f64 / self.sample_rate as f64,
+                    performance_time: playback_time,
+                });
+            }
+        }
+
+        if let Some(playout_stats) = &self.playout_stats {
+            // The audio output device plays fallback audio when rendering takes longer than the
+            // duration of the buffer
+            let frames = (output_buffer.len() / self.number_of_channels) as u64;
+            let overrun =
+                callback_start.elapsed().as_secs_f64() - frames as f64 / self.sample_rate as f64;
+            let underrun_frames = if overrun > 0. {
+                ((overrun * self.sample_rate as f64).ceil() as u64).min(frames)
+            } else {
+                0
+            };
+            let latency = playback_time
+                .saturating_duration_since(callback_start)
+                .as_secs_f64();
+            self.pending_playout_stats
+                .record(frames, underrun_frames, latency);
+
+            // never block the render thread, keep the statistics for the next callback instead
+            if let Ok(mut guard) = playout_stats.try_lock() {
+                guard.merge(&self.pending_playout_stats);
+                self.pending_playout_stats = PlayoutStats::default();
+            }
+        }
+    }
+
     fn render_inner<S: FromSample<f32> + Clone>(&mut self, mut output_buffer: &mut [S]) {
         self.buffer_size = output_buffer.len();
 
         // There may be audio frames left over from the previous render call,
-        // if the cpal buffer size did not align with our internal RENDER_QUANTUM_SIZE
+        // if the cpal buffer size did not align with our internal render quantum size
         if let Some((offset, prev_rendered)) = self.buffer_offset.take() {
-            let leftover_len = (RENDER_QUANTUM_SIZE - offset) * self.number_of_channels;
+            let leftover_len = (self.render_quantum_size - offset) * self.number_of_channels;
             // split the leftover frames slice, to fit in `buffer`
             let (first, next) = output_buffer.split_at_mut(leftover_len.min(output_buffer.len()));
 
@@ -454,25 +812,33 @@ impl RenderThread {
             return;
         }
 
-        // The audio graph is rendered in chunks of RENDER_QUANTUM_SIZE frames.  But some audio backends
-        // may not be able to emit chunks of this size.
-        let chunk_size = RENDER_QUANTUM_SIZE * self.number_of_channels;
+        // The audio graph is rendered in chunks of render quantum size frames. But some audio
+        // backends may not be able to emit chunks of this size.
+        let chunk_size = self.render_quantum_size * self.number_of_channels;
 
         for data in output_buffer.chunks_mut(chunk_size) {
+            self.apply_due_transactions();
+
             // update time
             let current_frame = self
                 .frames_played
-                .fetch_add(RENDER_QUANTUM_SIZE as u64, Ordering::Relaxed);
+                .fetch_add(self.render_quantum_size as u64, Ordering::Relaxed);
             let current_time = current_frame as f64 / self.sample_rate as f64;
 
             let scope = AudioWorkletGlobalScope {
                 current_frame,
                 current_time,
                 sample_rate: self.sample_rate,
+                render_quantum_size: self.render_quantum_size,
                 event_sender: self.event_sender.clone(),
                 node_id: Cell::new(AudioNodeId(0)), // placeholder value
             };
 
+            // provide the input frames of this render quantum
+            if let Some(duplex_input) = &self.duplex_input {
+                duplex_input.advance();
+            }
+
             // render audio graph, clone it in case we need to mutate/store the value later
             let mut destination_buffer = self.graph.as_mut().unwrap().render(&scope).clone();
 
@@ -494,9 +860,9 @@ impl RenderThread {
             }
 
             if data.len() != chunk_size {
-                // this is the last chunk, and it contained less than RENDER_QUANTUM_SIZE samples
+                // this is the last chunk, and it contained less than render quantum size samples
                 let channel_offset = data.len() / self.number_of_channels;
-                debug_assert!(channel_offset < RENDER_QUANTUM_SIZE);
+                debug_assert!(channel_offset < self.render_quantum_size);
                 self.buffer_offset = Some((channel_offset, destination_buffer));
             }
 
@@ -505,6 +871,16 @@ impl RenderThread {
         }
     }
 
+    /// Hand over the audio graph to the control thread, to be rendered by another render thread
+    fn recycle_graph(&mut self, sender: Sender<Graph>) {
+        // the new render thread does not know about the scheduled transactions
+        while let Some((_, messages)) = self.pending_transactions.pop_front() {
+            self.apply_scheduled_transaction(messages);
+        }
+        let _ = sender.send(self.graph.take().unwrap());
+        self.receiver = None;
+    }
+
     fn set_state(&self, state: AudioContextState) {
         self.state.store(state as u8, Ordering::Relaxed);
         self.event_sender
@@ -515,6 +891,28 @@ impl RenderThread {
 
 impl Drop for RenderThread {
     fn drop(&mut self) {
+        // The audio backend may close the stream without rendering again, e.g. when the output
+        // device has disappeared. Hand over the audio graph if it has been requested for reuse.
+        // The messages queued before the request are applied first. The state is left as is,
+        // the stream was not suspended by the user and the next render thread continues it.
+        if self.graph.is_some() {
+            if let Some(receiver) = self.receiver.clone() {
+                for msg in receiver.try_iter() {
+                    match msg {
+                        ControlMessage::CloseAndRecycle { sender } => {
+                            self.recycle_graph(sender);
+                            break;
+                        }
+                        msg => {
+                            if self.handle_control_message(msg).is_break() {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
         if let Some(gc) = self.garbage_collector.as_mut() {
             gc.push(llq::Node::new(Box::new(TerminateGarbageCollectorThread)))
         }
@@ -0,0 +1,280 @@
+//! Pool of worker threads for parallel rendering of the audio graph
+
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle, Thread};
+
+use arc_swap::ArcSwapOption;
+
+use super::quantum::init_worker_pool;
+
+/// Number of times the submitting thread checks for the workers to finish before it parks
+const SPIN_LIMIT: usize = 1024;
+
+/// A batch of tasks that is executed by the worker threads and the render thread together
+struct Job<'a> {
+    /// Unique identifier of this job, so workers do not pick up a finished job again
+    epoch: u64,
+    /// Number of tasks
+    len: usize,
+    /// Index of the next task to be claimed
+    next: AtomicUsize,
+    /// Number of tasks that have not finished yet
+    pending: AtomicUsize,
+    /// The task runner, called with the task index
+    task: &'a (dyn Fn(usize) + Sync),
+}
+
+impl Job<'_> {
+    /// Claim and run tasks until all tasks have been claimed
+    ///
+    /// Returns `true` if this call has finished the last pending task of the job.
+    fn run_tasks(&self) -> bool {
+        let mut finished = false;
+        loop {
+            let index = self.next.fetch_add(1, Ordering::Relaxed);
+            if index >= self.len {
+                return finished;
+            }
+            (self.task)(index);
+            finished = self.pending.fetch_sub(1, Ordering::AcqRel) == 1;
+        }
+    }
+}
+
+struct Shared {
+    /// The current job, null when no job is active
+    job: AtomicPtr<Job<'static>>,
+    /// Number of workers currently holding a reference to the current job
+    active: AtomicUsize,
+    /// Source of job identifiers
+    epoch: AtomicU64,
+    /// Signal workers to exit
+    shutdown: AtomicBool,
+    /// Thread that submits the jobs, it is unparked when the workers are done with a job
+    owner: ArcSwapOption<Thread>,
+}
+
+impl Shared {
+    fn unpark_owner(&self) {
+        if let Some(owner) = &*self.owner.load() {
+            owner.unpark();
+        }
+    }
+}
+
+/// Fixed size pool of worker threads
+///
+/// Jobs are submitted by the render thread with [`WorkerPool::run`], which does not allocate and
+/// returns when all tasks of the job have finished. Idle workers are parked, and so is the render
+/// thread when it runs out of tasks while the workers are still busy. Each worker has its own
+/// pool of audio buffers.
+pub(crate) struct WorkerPool {
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for WorkerPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WorkerPool")
+            .field("number_of_threads", &self.workers.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl WorkerPool {
+    pub fn new(number_of_threads: usize) -> Self {
+        let shared = Arc::new(Shared {
+            job: AtomicPtr::default(),
+            active: AtomicUsize::new(0),
+            epoch: AtomicU64::new(0),
+            shutdown: AtomicBool::new(false),
+            owner: ArcSwapOption::empty(),
+        });
+
+        let workers = (0..number_of_threads)
+            .map(|i| {
+                let shared = Arc::clone(&shared);
+                thread::Builder::new()
+                    .name(format!("web-audio-render-worker-{i}"))
+                    .spawn(move || {
+                        init_worker_pool();
+
+                        // Process with denormal floats disabled, same as the render thread
+                        #[cfg(any(
+                            target_arch = "x86",
+                            target_arch = "x86_64",
+                            target_arch = "aarch64"
+                        ))]
+                        no_denormals::no_denormals(|| run_worker(&shared));
+                        #[cfg(not(any(
+                            target_arch = "x86",
+                            target_arch = "x86_64",
+                            target_arch = "aarch64"
+                        )))]
+                        run_worker(&shared);
+                    })
+                    .expect("Unable to spawn render worker thread")
+            })
+            .collect();
+
+        Self { shared, workers }
+    }
+
+    /// Run `task` for every index in `0..len` on the worker threads and the current thread
+    ///
+    /// The `local` closure is run on the current thread first, concurrently with the tasks that
+    /// are picked up by the workers.
+    pub fn run(&self, len: usize, task: &(dyn Fn(usize) + Sync), local: impl FnOnce()) {
+        // only allocates when the jobs are submitted by another thread than before
+        let current = thread::current();
+        let owner = self.shared.owner.load();
+        if !owner
+            .as_ref()
+            .is_some_and(|owner| owner.id() == current.id())
+        {
+            self.shared.owner.store(Some(Arc::new(current)));
+        }
+        drop(owner);
+
+        let epoch = self.shared.epoch.fetch_add(1, Ordering::Relaxed) + 1;
+        let job = Job {
+            epoch,
+            len,
+            next: AtomicUsize::new(0),
+            pending: AtomicUsize::new(len),
+            task,
+        };
+
+        // SAFETY: the lifetime is erased to share the job with the workers. We make sure no worker
+        // holds a reference to the job when this function returns.
+        let job_ptr = &job as *const Job<'_> as *mut Job<'static>;
+        self.shared.job.store(job_ptr, Ordering::SeqCst);
+        self.workers.iter().for_each(|w| w.thread().unpark());
+
+        local();
+        job.run_tasks();
+
+        // wait for the workers to finish their tasks
+        wait_until(|| job.pending.load(Ordering::Acquire) == 0);
+
+        // retract the job and wait for the workers to release their reference
+        self.shared
+            .job
+            .store(std::ptr::null_mut(), Ordering::SeqCst);
+        wait_until(|| self.shared.active.load(Ordering::SeqCst) == 0);
+    }
+}
+
+/// Spin briefly, as the workers are usually about to finish, then park until they unpark us
+fn wait_until(done: impl Fn() -> bool) {
+    for _ in 0..SPIN_LIMIT {
+        if done() {
+            return;
+        }
+        std::hint::spin_loop();
+    }
+
+    // park may return spuriously, or because of an unpark for a previous job
+    while !done() {
+        thread::park();
+    }
+}
+
+fn run_worker(shared: &Shared) {
+    let mut last_epoch = 0;
+
+    loop {
+        if shared.shutdown.load(Ordering::Acquire) {
+            return;
+        }
+
+        shared.active.fetch_add(1, Ordering::SeqCst);
+        let job_ptr = shared.job.load(Ordering::SeqCst);
+        // SAFETY: the job is kept alive by the render thread while `active` is non-zero
+        let job = unsafe { job_ptr.as_ref() };
+        let ran = match job {
+            Some(job) if job.epoch != last_epoch => {
+                last_epoch = job.epoch;
+                if job.run_tasks() {
+                    // the owner may be waiting for the last task
+                    shared.unpark_owner();
+                }
+                true
+            }
+            _ => false,
+        };
+        let released = shared.active.fetch_sub(1, Ordering::SeqCst) == 1;
+        if released && shared.job.load(Ordering::SeqCst).is_null() {
+            // the owner may be waiting for the retracted job to be released
+            shared.unpark_owner();
+        }
+
+        if !ran {
+            thread::park();
+        }
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::Release);
+        for worker in self.workers.drain(..) {
+            worker.thread().unpark();
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run() {
+        let pool = WorkerPool::new(3);
+
+        let results: Vec<_> = (0..100).map(|_| AtomicUsize::new(0)).collect();
+        let local_ran = AtomicBool::new(false);
+
+        for _ in 0..10 {
+            let task = |i: usize| {
+                results[i].fetch_add(i, Ordering::Relaxed);
+            };
+            pool.run(results.len(), &task, || {
+                local_ran.store(true, Ordering::Relaxed)
+            });
+        }
+
+        assert!(local_ran.load(Ordering::Relaxed));
+        results
+            .iter()
+            .enumerate()
+            .for_each(|(i, r)| assert_eq!(r.load(Ordering::Relaxed), 10 * i));
+    }
+
+    #[test]
+    fn test_empty_pool() {
+        let pool = WorkerPool::new(0);
+        let count = AtomicUsize::new(0);
+        let task = |_| {
+            count.fetch_add(1, Ordering::Relaxed);
+        };
+        pool.run(5, &task, || ());
+        assert_eq!(count.load(Ordering::Relaxed), 5);
+    }
+    #[test]
+    fn test_slow_tasks() {
+        // the render thread parks while waiting for the workers and is unparked by them
+        let pool = WorkerPool::new(2);
+        let count = AtomicUsize::new(0);
+        let task = |_| {
+            thread::sleep(std::time::Duration::from_millis(5));
+            count.fetch_add(1, Ordering::Relaxed);
+        };
+        for _ in 0..5 {
+            pool.run(3, &task, || ());
+        }
+        assert_eq!(count.load(Ordering::Relaxed), 15);
+    }
+}
@@ -1,7 +1,21 @@
 use std::error::Error;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use rubato::{FastFixedOut, PolynomialDegree, Resampler as _};
 
 use crate::buffer::{AudioBuffer, AudioBufferOptions};
-use crate::AudioBufferIter;
+use crate::node::MediaStreamSourceStats;
+use crate::{AtomicF64, AudioBufferIter};
+
+/// Maximum clock drift of a live stream that is compensated, relative to the nominal rate
+const MAX_DRIFT: f64 = 0.01;
+/// Clock drift of a live stream below which its frames are passed through without resampling
+const DRIFT_TOLERANCE: f64 = 0.0001;
+/// Time constant of the smoothing of the fill level, in seconds
+const FILL_LEVEL_SMOOTHING: f64 = 0.5;
+/// Time in which a deviation from the target fill level is compensated, in seconds
+const DRIFT_CORRECTION_TIME: f64 = 2.;
 
 /// Sample rate converter and buffer chunk splitter.
 ///
@@ -110,6 +124,348 @@ impl<M: AudioBufferIter> Iterator for Resampler<M> {
     }
 }
 
+/// Adaptive jitter buffer and sample rate converter of a media stream
+///
+/// A stream is either pulled on demand (e.g. a decoded file) or produced in real time on the
+/// clock of another device (e.g. a microphone). A live stream signals that no frames are
+/// available yet by yielding an empty buffer. Its clock drifts against the clock of the audio
+/// context, so the nominal sample rate conversion slowly under- or overruns. The buffer estimates
+/// the drift from its smoothed fill level and fine-tunes the resampling ratio to keep the fill
+/// level at its target. The target adapts to the largest buffer yielded by the stream.
+///
+/// An underrun is filled with silence and an overflow drops the oldest frames, both restore the
+/// target fill level. A stream is not resampled when its sample rate matches the desired sample
+/// rate, until the estimated drift of a live stream exceeds the tolerance.
+pub(crate) struct JitterBuffer<I> {
+    /// desired sample rate
+    sample_rate: f32,
+    /// desired sample length
+    render_quantum_size: usize,
+    /// input stream
+    input: I,
+    /// number of channels of the input, zero until the first frames are received
+    number_of_channels: usize,
+    /// sample rate of the input
+    input_sample_rate: f32,
+    /// input frames that have not been played yet
+    pending: Vec<Vec<f32>>,
+    /// length of the largest buffer yielded by the input
+    largest_buffer: usize,
+    /// the input is produced in real time
+    live: bool,
+    /// the input has ended
+    ended: bool,
+    /// smoothed number of pending frames of a live input
+    fill_level: Option<f64>,
+    /// estimated clock drift of a live input
+    drift: f64,
+    resampler: Option<FastFixedOut<f32>>,
+    stats: JitterBufferStats,
+}
+
+impl<M: AudioBufferIter> JitterBuffer<M> {
+    pub fn new(
+        sample_rate: f32,
+        render_quantum_size: usize,
+        input: M,
+        stats: JitterBufferStats,
+    ) -> Self {
+        Self {
+            sample_rate,
+            render_quantum_size,
+            input,
+            number_of_channels: 0,
+            input_sample_rate: sample_rate,
+            pending: vec![],
+            largest_buffer: 0,
+            live: false,
+            ended: false,
+            fill_level: None,
+            drift: 0.,
+            resampler: None,
+            stats,
+        }
+    }
+
+    fn pending_frames(&self) -> usize {
+        self.pending.first().map(Vec::len).unwrap_or(0)
+    }
+
+    /// Number of input frames played per render quantum at the nominal sample rates
+    fn quantum_frames(&self) -> usize {
+        let frames = self.render_quantum_size as f64 * f64::from(self.input_sample_rate)
+            / f64::from(self.sample_rate);
+        frames.ceil() as usize
+    }
+
+    /// Fill level that covers the largest input buffer and the next render quantum
+    fn target_frames(&self) -> usize {
+        self.largest_buffer + self.quantum_frames()
+    }
+
+    /// Fill level above which a live input overflows
+    fn capacity(&self) -> usize {
+        4 * self.target_frames()
+    }
+
+    /// Number of input frames needed to render the next quantum
+    fn input_frames_next(&self) -> usize {
+        match &self.resampler {
+            Some(resampler) => resampler.input_frames_next(),
+            None => self.render_quantum_size,
+        }
+    }
+
+    /// Pull the input until the next quantum can be rendered, or until a live input runs dry
+    fn fill(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        while !self.ended {
+            let limit = if self.live {
+                self.capacity()
+            } else {
+                self.input_frames_next()
+            };
+            if self.number_of_channels > 0 && self.pending_frames() >= limit {
+                break;
+            }
+
+            match self.input.next() {
+                None => self.ended = true,
+                Some(Err(e)) => return Err(e),
+                Some(Ok(buffer)) if buffer.length() == 0 => {
+                    self.live = true;
+                    break;
+                }
+                Some(Ok(buffer)) => self.append(&buffer),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn append(&mut self, buffer: &AudioBuffer) {
+        if buffer.number_of_channels() != self.number_of_channels
+            || buffer.sample_rate() != self.input_sample_rate
+        {
+            if self.number_of_channels > 0 {
+                log::debug!("media stream format changed, dropping buffered frames");
+                let dropped = self.pending_frames() as u64;
+                self.stats
+                    .inner
+                    .dropped_frames
+                    .fetch_add(dropped, Ordering::Relaxed);
+            }
+
+            self.number_of_channels = buffer.number_of_channels();
+            self.input_sample_rate = buffer.sample_rate();
+            self.pending = vec![vec![]; self.number_of_channels];
+            self.largest_buffer = 0;
+            self.fill_level = None;
+            self.resampler = None;
+        }
+
+        self.largest_buffer = self.largest_buffer.max(buffer.length());
+        self.pending
+            .iter_mut()
+            .zip(buffer.channels())
+            .for_each(|(pending, channel)| pending.extend_from_slice(channel.as_slice()));
+
+        self.configure();
+    }
+
+    /// Set up the resampler when the input has a different sample rate or drifts beyond tolerance
+    fn configure(&mut self) {
+        let resample =
+            self.input_sample_rate != self.sample_rate || self.drift.abs() > DRIFT_TOLERANCE;
+        if !resample || self.resampler.is_some() || self.number_of_channels == 0 {
+            return;
+        }
+
+        let ratio = f64::from(self.sample_rate) / f64::from(self.input_sample_rate);
+        let resampler = FastFixedOut::new(
+            ratio,
+            1. + 2. * MAX_DRIFT,
+            PolynomialDegree::Septic,
+            self.render_quantum_size,
+            self.number_of_channels,
+        )
+        .unwrap(); // the sample rates are valid
+        self.resampler = Some(resampler);
+    }
+
+    /// Estimate the drift of a live input and tune the resampling ratio accordingly
+    fn compensate(&mut self) {
+        let pending = self.pending_frames();
+        let target = self.target_frames();
+
+        if pending >= self.capacity() {
+            // overflow, restore the target fill level by dropping the oldest frames
+            log::debug!("media stream overflow: input frames dropped");
+            let excess = pending - target;
+            self.pending.iter_mut().for_each(|channel| {
+                channel.drain(..excess);
+            });
+            self.stats
+                .inner
+                .dropped_frames
+                .fetch_add(excess as u64, Ordering::Relaxed);
+            self.fill_level = Some(target as f64);
+        }
+
+        let quantum_duration = self.render_quantum_size as f64 / f64::from(self.sample_rate);
+        let smoothing = (quantum_duration / FILL_LEVEL_SMOOTHING).min(1.);
+        let pending = self.pending_frames() as f64;
+        let fill_level = self.fill_level.get_or_insert(target as f64);
+        *fill_level += (pending - *fill_level) * smoothing;
+
+        // the frames buffered above target are accumulated by the drift of the input clock
+        let excess = (*fill_level - target as f64) / f64::from(self.input_sample_rate);
+        self.drift = (excess / DRIFT_CORRECTION_TIME).clamp(-MAX_DRIFT, MAX_DRIFT);
+
+        self.configure();
+        if let Some(resampler) = &mut self.resampler {
+            // consume the input faster when its clock runs fast
+            resampler
+                .set_resample_ratio_relative(1. / (1. + self.drift), true)
+                .unwrap(); // the drift is clamped to the allowed range
+        }
+    }
+
+    fn process(&mut self) -> AudioBuffer {
+        let render_quantum_size = self.render_quantum_size;
+
+        let channels = match &mut self.resampler {
+            Some(resampler) => {
+                let mut output = vec![vec![0.; render_quantum_size]; self.number_of_channels];
+                // the buffers are sized for the resampler, so processing does not fail
+                let (used, _) = resampler
+                    .process_into_buffer(&self.pending, &mut output, None)
+                    .unwrap();
+                self.pending.iter_mut().for_each(|channel| {
+                    channel.drain(..used);
+                });
+                output
+            }
+            None => self
+                .pending
+                .iter_mut()
+                .map(|channel| channel.drain(..render_quantum_size).collect())
+                .collect(),
+        };
+
+        AudioBuffer::from(channels, self.sample_rate)
+    }
+}
+
+impl<M: AudioBufferIter> Iterator for JitterBuffer<M> {
+    type Item = Result<AudioBuffer, Box<dyn Error + Send + Sync>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Err(e) = self.fill() {
+            return Some(Err(e));
+        }
+
+        if self.number_of_channels == 0 {
+            if self.ended {
+                return None;
+            }
+
+            // a live input has not produced any frames yet
+            let options = AudioBufferOptions {
+                number_of_channels: 1,
+                length: self.render_quantum_size,
+                sample_rate: self.sample_rate,
+            };
+            return Some(Ok(AudioBuffer::new(options)));
+        }
+
+        if self.ended && self.pending_frames() == 0 {
+            return None;
+        }
+
+        if self.live {
+            self.compensate();
+        }
+
+        let needed = self.input_frames_next();
+        let pending = self.pending_frames();
+        if pending < needed {
+            let length = if self.ended {
+                // pad the end of the stream with silence
+                needed
+            } else {
+                // underrun, restore the target fill level with silence
+                log::debug!("media stream underrun: silence inserted");
+                let length = needed.max(self.target_frames());
+                self.stats
+                    .inner
+                    .inserted_frames
+                    .fetch_add((length - pending) as u64, Ordering::Relaxed);
+                self.fill_level = Some(length as f64);
+                length
+            };
+            self.pending
+                .iter_mut()
+                .for_each(|channel| channel.resize(length, 0.));
+        }
+
+        let output = self.process();
+
+        self.stats
+            .inner
+            .buffered_frames
+            .store(self.pending_frames(), Ordering::Relaxed);
+        self.stats
+            .inner
+            .target_frames
+            .store(self.target_frames(), Ordering::Relaxed);
+        self.stats.inner.drift.store(self.drift, Ordering::Relaxed);
+
+        Some(Ok(output))
+    }
+}
+
+/// Statistics of a [`JitterBuffer`], shared with the control thread
+#[derive(Clone, Debug)]
+pub(crate) struct JitterBufferStats {
+    inner: Arc<JitterBufferStatsInner>,
+}
+
+#[derive(Debug)]
+struct JitterBufferStatsInner {
+    buffered_frames: AtomicUsize,
+    target_frames: AtomicUsize,
+    drift: AtomicF64,
+    inserted_frames: AtomicU64,
+    dropped_frames: AtomicU64,
+}
+
+impl JitterBufferStats {
+    pub fn new() -> Self {
+        let inner = JitterBufferStatsInner {
+            buffered_frames: AtomicUsize::new(0),
+            target_frames: AtomicUsize::new(0),
+            drift: AtomicF64::new(0.),
+            inserted_frames: AtomicU64::new(0),
+            dropped_frames: AtomicU64::new(0),
+        };
+
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+
+    pub fn load(&self) -> MediaStreamSourceStats {
+        let inner = &self.inner;
+        MediaStreamSourceStats {
+            buffered_frames: inner.buffered_frames.load(Ordering::Relaxed),
+            target_frames: inner.target_frames.load(Ordering::Relaxed),
+            estimated_drift: inner.drift.load(Ordering::Relaxed),
+            inserted_frames: inner.inserted_frames.load(Ordering::Relaxed),
+            dropped_frames: inner.dropped_frames.load(Ordering::Relaxed),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use float_eq::assert_float_eq;
@@ -168,4 +524,145 @@ mod tests {
 
         assert!(resampler.next().is_none());
     }
+
+    #[test]
+    fn test_jitter_buffer_pull() {
+        let channel = ChannelData::from(vec![1., 2., 3., 4., 5.]);
+        let input_buf = AudioBuffer::from_channels(vec![channel], 44_100.);
+        let input = vec![input_buf; 3].into_iter().map(Ok);
+        let stats = JitterBufferStats::new();
+        let mut jitter_buffer = JitterBuffer::new(44_100., 10, input, stats.clone());
+
+        let next = jitter_buffer.next().unwrap().unwrap();
+        assert_float_eq!(
+            next.channel_data(0).as_slice(),
+            &[1., 2., 3., 4., 5., 1., 2., 3., 4., 5.,][..],
+            abs_all <= 0.
+        );
+
+        let next = jitter_buffer.next().unwrap().unwrap();
+        assert_float_eq!(
+            next.channel_data(0).as_slice(),
+            &[1., 2., 3., 4., 5., 0., 0., 0., 0., 0.][..],
+            abs_all <= 0.
+        );
+
+        assert!(jitter_buffer.next().is_none());
+
+        let stats = stats.load();
+        assert_eq!(stats.inserted_frames, 0);
+        assert_eq!(stats.dropped_frames, 0);
+    }
+
+    fn live_input(
+        buffers: Vec<usize>,
+    ) -> impl Iterator<Item = Result<AudioBuffer, Box<dyn Error + Send + Sync>>> {
+        // zero length buffers signal no frames are available yet
+        buffers
+            .into_iter()
+            .chain(std::iter::repeat(0))
+            .map(|length| Ok(AudioBuffer::from(vec![vec![1.; length]], 48000.)))
+    }
+
+    #[test]
+    fn test_jitter_buffer_underrun() {
+        let input = live_input(vec![0, 256]);
+        let stats = JitterBufferStats::new();
+        let mut jitter_buffer = JitterBuffer::new(48000., 128, input, stats.clone());
+
+        // silence until the first frames are received
+        let next = jitter_buffer.next().unwrap().unwrap();
+        assert_eq!(next.length(), 128);
+        assert!(next.get_channel_data(0).iter().all(|&v| v == 0.));
+
+        // the frames are passed through
+        for _ in 0..2 {
+            let next = jitter_buffer.next().unwrap().unwrap();
+            assert!(next.get_channel_data(0).iter().all(|&v| v == 1.));
+        }
+        assert_eq!(stats.load().inserted_frames, 0);
+
+        // the buffer runs dry and is refilled to the target fill level
+        jitter_buffer.next().unwrap().unwrap();
+        let stats = stats.load();
+        assert_eq!(stats.target_frames, 256 + 128);
+        assert!(stats.inserted_frames > 0);
+        assert_eq!(stats.dropped_frames, 0);
+    }
+
+    #[test]
+    fn test_jitter_buffer_overflow() {
+        let input = live_input(vec![0, 128, 128, 128, 128, 128, 128, 128, 128, 128]);
+        let stats = JitterBufferStats::new();
+        let mut jitter_buffer = JitterBuffer::new(48000., 128, input, stats.clone());
+
+        jitter_buffer.next().unwrap().unwrap();
+
+        // the capacity of 4 times the target is exceeded, drop down to the target
+        jitter_buffer.next().unwrap().unwrap();
+        let stats = stats.load();
+        assert_eq!(stats.target_frames, 256);
+        assert_eq!(stats.dropped_frames, 4 * 256 - 256);
+        assert_eq!(stats.inserted_frames, 0);
+    }
+
+    /// Live input that produces buffers on a clock running `speed` times the context clock
+    struct DriftingInput {
+        clock: Arc<AtomicUsize>,
+        speed: f64,
+        produced: usize,
+    }
+
+    impl Iterator for DriftingInput {
+        type Item = Result<AudioBuffer, Box<dyn Error + Send + Sync>>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            let available = (self.clock.load(Ordering::Relaxed) as f64 * self.speed) as usize;
+            let length = if available >= self.produced + 480 {
+                480
+            } else {
+                0
+            };
+            self.produced += length;
+
+            Some(Ok(AudioBuffer::from(vec![vec![0.5; length]], 48000.)))
+        }
+    }
+
+    #[test]
+    fn test_jitter_buffer_drift() {
+        for speed in [1.001, 0.999] {
+            let clock = Arc::new(AtomicUsize::new(0));
+            let input = DriftingInput {
+                clock: Arc::clone(&clock),
+                speed,
+                produced: 0,
+            };
+            let stats = JitterBufferStats::new();
+            let mut jitter_buffer = JitterBuffer::new(48000., 128, input, stats.clone());
+
+            let mut render = |seconds: usize| {
+                let mut last = None;
+                for _ in 0..seconds * 48000 / 128 {
+                    clock.fetch_add(128, Ordering::Relaxed);
+                    last = jitter_buffer.next();
+                }
+                last.unwrap().unwrap()
+            };
+
+            // the drift is estimated once the fill level has settled
+            render(20);
+            let settled = stats.load();
+            assert_float_eq!(settled.estimated_drift, speed - 1., abs <= 0.0002);
+
+            // no frames are inserted or dropped from now on
+            let output = render(20);
+            let stats = stats.load();
+            assert_float_eq!(stats.estimated_drift, speed - 1., abs <= 0.0002);
+            assert_eq!(stats.inserted_frames, settled.inserted_frames);
+            assert_eq!(stats.dropped_frames, settled.dropped_frames);
+            assert!(stats.buffered_frames < 4 * stats.target_frames);
+            assert_float_eq!(output.get_channel_data(0), &[0.5; 128][..], abs_all <= 1e-3);
+        }
+    }
 }
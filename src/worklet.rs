@@ -442,6 +442,10 @@ impl<P: AudioWorkletProcessor> AudioProcessor for AudioWorkletRenderer<P> {
     fn has_side_effects(&self) -> bool {
         true // could be IO, message passing, ..
     }
+
+    fn requires_render_thread(&self) -> bool {
+        true // the AudioWorkletProcessor is not required to be Send
+    }
 }
 
 #[cfg(test)]
@@ -1,9 +1,16 @@
 use float_eq::assert_float_eq;
 use web_audio_api::context::BaseAudioContext;
-use web_audio_api::context::OfflineAudioContext;
+use web_audio_api::context::{
+    AudioContextRenderSizeCategory, OfflineAudioContext, OfflineAudioContextOptions,
+};
 use web_audio_api::node::{
     AudioNode, AudioScheduledSourceNode, OscillatorNode, OscillatorOptions, OscillatorType,
+    PanningModelType,
 };
+use web_audio_api::{AddEventListenerOptions, AudioContextEventType, AudioFileFormat};
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 const RENDER_QUANTUM_SIZE: usize = 128;
 
@@ -242,3 +249,422 @@ fn test_cycle_breaker() {
         abs_all <= 0.
     );
 }
+
+fn render_with_size_hint(render_size_hint: AudioContextRenderSizeCategory) -> Vec<f32> {
+    let sample_rate = 48000.;
+    let mut context = OfflineAudioContext::with_options(OfflineAudioContextOptions {
+        number_of_channels: 1,
+        length: 2000,
+        sample_rate,
+        render_size_hint,
+        render_worker_threads: 0,
+    });
+
+    let opts = OscillatorOptions {
+        type_: OscillatorType::Sawtooth,
+        frequency: 220.,
+        ..Default::default()
+    };
+    let mut osc = OscillatorNode::new(&context, opts);
+    osc.frequency()
+        .linear_ramp_to_value_at_time(880., 1500. / sample_rate as f64);
+
+    let biquad = context.create_biquad_filter();
+    biquad.frequency().set_value(1000.);
+
+    let delay = context.create_delay(1.);
+    delay.delay_time().set_value(300. / sample_rate);
+
+    osc.connect(&biquad);
+    biquad.connect(&delay);
+    delay.connect(&context.destination());
+    biquad.connect(&context.destination());
+
+    osc.start_at(100. / sample_rate as f64);
+    osc.stop_at(1700.5 / sample_rate as f64);
+
+    let output = context.start_rendering_sync();
+    assert_eq!(output.length(), 2000);
+    output.get_channel_data(0).to_vec()
+}
+
+#[test]
+fn test_custom_render_size() {
+    let expected = render_with_size_hint(AudioContextRenderSizeCategory::Default);
+
+    for size in [1, 64, 100, 512] {
+        let output = render_with_size_hint(AudioContextRenderSizeCategory::Custom(size));
+        assert_float_eq!(output[..], expected[..], abs_all <= 1e-5);
+    }
+}
+
+#[test]
+fn test_custom_render_size_suspend() {
+    let mut context = OfflineAudioContext::with_options(OfflineAudioContextOptions {
+        number_of_channels: 1,
+        length: 2000,
+        sample_rate: 2000.,
+        render_size_hint: AudioContextRenderSizeCategory::Custom(100),
+        render_worker_threads: 0,
+    });
+    assert_eq!(context.render_quantum_size(), 100);
+
+    // suspend time is rounded up to the next render quantum
+    context.suspend_sync(0.125, |context| {
+        assert_float_eq!(context.current_time(), 0.15, abs <= 0.);
+    });
+
+    let output = context.start_rendering_sync();
+    assert_eq!(output.length(), 2000);
+}
+
+fn render_with_worker_threads(render_worker_threads: usize) -> Vec<Vec<f32>> {
+    let sample_rate = 48000.;
+    let mut context = OfflineAudioContext::with_options(OfflineAudioContextOptions {
+        number_of_channels: 2,
+        length: 10_000,
+        sample_rate,
+        render_size_hint: AudioContextRenderSizeCategory::Default,
+        render_worker_threads,
+    });
+
+    // feedback delay line, shared by all voices
+    let delay = context.create_delay(1.);
+    delay.delay_time().set_value(0.01);
+    let feedback = context.create_gain();
+    feedback.gain().set_value(0.5);
+    delay.connect(&feedback);
+    feedback.connect(&delay);
+    delay.connect(&context.destination());
+
+    // modulation source, shared by all voices
+    let mut lfo = context.create_constant_source();
+    lfo.offset().set_value(0.2);
+    lfo.offset().linear_ramp_to_value_at_time(0.8, 0.1);
+    lfo.start();
+
+    for i in 0..8 {
+        let opts = OscillatorOptions {
+            type_: OscillatorType::Sawtooth,
+            frequency: 110. * (i + 1) as f32,
+            ..Default::default()
+        };
+        let mut osc = OscillatorNode::new(&context, opts);
+        osc.frequency()
+            .exponential_ramp_to_value_at_time(55. * (i + 1) as f32, 0.2);
+
+        let biquad = context.create_biquad_filter();
+        biquad.frequency().set_value(500. + 200. * i as f32);
+
+        let gain = context.create_gain();
+        gain.gain().set_value(0.1);
+        lfo.connect(gain.gain());
+
+        let mut panner = context.create_panner();
+        if i % 2 == 0 {
+            panner.set_panning_model(PanningModelType::HRTF);
+        }
+        panner.position_x().set_value(i as f32 - 4.);
+        panner.position_z().set_value(-1.);
+
+        osc.connect(&biquad);
+        biquad.connect(&gain);
+        gain.connect(&panner);
+        panner.connect(&context.destination());
+        panner.connect(&delay);
+
+        // some voices end early, so nodes are removed from the graph while rendering
+        osc.start_at(i as f64 * 0.01);
+        if i % 3 == 0 {
+            osc.stop_at(0.1);
+        }
+    }
+
+    let output = context.start_rendering_sync();
+    (0..2)
+        .map(|c| output.get_channel_data(c).to_vec())
+        .collect()
+}
+
+#[test]
+fn test_parallel_render() {
+    let expected = render_with_worker_threads(0);
+    assert!(expected[0].iter().any(|v| *v != 0.));
+
+    for render_worker_threads in [1, 4] {
+        let output = render_with_worker_threads(render_worker_threads);
+        for (o, e) in output.iter().zip(&expected) {
+            assert_float_eq!(o[..], e[..], abs_all <= 0.);
+        }
+    }
+}
+
+#[test]
+fn test_render_to_file() {
+    let path = std::env::temp_dir().join("web_audio_api_test_render_to_file.flac");
+    let mut context = OfflineAudioContext::new(2, 10_000, 48000.);
+    let mut osc = context.create_oscillator();
+    osc.connect(&context.destination());
+    osc.start();
+    context
+        .start_rendering_to_file_sync(&path, AudioFileFormat::Flac24)
+        .unwrap();
+
+    let file = std::fs::File::open(&path).unwrap();
+    let context = OfflineAudioContext::new(1, 1, 48000.);
+    let output = context.decode_audio_data_sync(file).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(output.number_of_channels(), 2);
+    assert_eq!(output.length(), 10_000);
+    assert_eq!(output.sample_rate(), 48000.);
+
+    let mut context = OfflineAudioContext::new(2, 10_000, 48000.);
+    let mut osc = context.create_oscillator();
+    osc.connect(&context.destination());
+    osc.start();
+    let expected = context.start_rendering_sync();
+    for c in 0..2 {
+        assert_float_eq!(
+            output.get_channel_data(c),
+            expected.get_channel_data(c),
+            abs_all <= 1. / 8_388_608.
+        );
+    }
+}
+
+#[test]
+fn test_render_to_file_twice_keeps_file() {
+    let path = std::env::temp_dir().join("web_audio_api_test_render_to_file_twice.wav");
+    let mut context = OfflineAudioContext::new(1, 1_000, 48000.);
+    context
+        .start_rendering_to_file_sync(&path, AudioFileFormat::WavInt16)
+        .unwrap();
+    let expected = std::fs::read(&path).unwrap();
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        context.start_rendering_to_file_sync(&path, AudioFileFormat::WavInt16)
+    }));
+    assert!(result.is_err());
+
+    let contents = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(contents, expected);
+}
+
+#[test]
+fn test_transaction() {
+    let len = RENDER_QUANTUM_SIZE * 4;
+    let sample_rate = 48000.;
+
+    let mut context = OfflineAudioContext::new(1, len, sample_rate);
+
+    let mut first = context.create_constant_source();
+    first.connect(&context.destination());
+    first.start();
+
+    // swap the sources at once
+    let second = context.transaction(|| {
+        first.disconnect();
+        let mut second = context.create_constant_source();
+        second.offset().set_value(2.);
+        second.connect(&context.destination());
+        second.start();
+        second
+    });
+
+    // apply the next swap at 1.5 render quanta, i.e. at the start of the third render quantum
+    context.transaction_at(
+        1.5 * RENDER_QUANTUM_SIZE as f64 / sample_rate as f64,
+        || {
+            second.disconnect();
+            first.connect(&context.destination());
+        },
+    );
+
+    // graph edits after a scheduled transaction are not held back
+    let mut third = context.create_constant_source();
+    third.offset().set_value(4.);
+    third.connect(&context.destination());
+    third.start();
+
+    let output = context.start_rendering_sync();
+
+    let mut expected = vec![6.; 2 * RENDER_QUANTUM_SIZE];
+    expected.append(&mut vec![5.; 2 * RENDER_QUANTUM_SIZE]);
+    assert_float_eq!(
+        output.get_channel_data(0),
+        expected.as_slice(),
+        abs_all <= 0.
+    );
+}
+
+#[test]
+fn test_transaction_at_out_of_order() {
+    let len = RENDER_QUANTUM_SIZE * 4;
+    let sample_rate = 48000.;
+    let quantum = RENDER_QUANTUM_SIZE as f64 / sample_rate as f64;
+
+    let mut context = OfflineAudioContext::new(1, len, sample_rate);
+
+    let mut first = context.create_constant_source();
+    first.start();
+    let mut second = context.create_constant_source();
+    second.offset().set_value(2.);
+    second.start();
+
+    context.transaction_at(3. * quantum, || {
+        first.connect(&context.destination());
+    });
+    context.transaction_at(quantum, || {
+        second.connect(&context.destination());
+    });
+
+    // the nodes are kept alive until the scheduled connections are applied
+    drop(first);
+    drop(second);
+
+    let output = context.start_rendering_sync();
+
+    let mut expected = vec![0.; RENDER_QUANTUM_SIZE];
+    expected.append(&mut vec![2.; 2 * RENDER_QUANTUM_SIZE]);
+    expected.append(&mut vec![3.; RENDER_QUANTUM_SIZE]);
+    assert_float_eq!(
+        output.get_channel_data(0),
+        expected.as_slice(),
+        abs_all <= 0.
+    );
+}
+
+#[test]
+fn test_scheduled_connect_disconnect() {
+    let len = RENDER_QUANTUM_SIZE * 4;
+    let sample_rate = 48000.;
+
+    let mut context = OfflineAudioContext::new(1, len, sample_rate);
+
+    let mut src = context.create_constant_source();
+    src.start();
+
+    // switch on at frame 100, switch off after a 64 frame fade starting at frame 300
+    src.connect_at(&context.destination(), 100. / sample_rate as f64, 0.);
+    src.disconnect_at(300. / sample_rate as f64, 64. / sample_rate as f64);
+
+    let output = context.start_rendering_sync();
+    let output = output.get_channel_data(0);
+
+    let mut expected = vec![0.; 100];
+    expected.append(&mut vec![1.; 200]);
+    expected.extend((0..64).map(|i| 1. - i as f32 / 64.));
+    expected.resize(len, 0.);
+    assert_float_eq!(output, expected.as_slice(), abs_all <= 1e-6);
+}
+
+#[test]
+fn test_scheduled_connect_fade_in() {
+    let len = RENDER_QUANTUM_SIZE * 2;
+    let sample_rate = 48000.;
+
+    let mut context = OfflineAudioContext::new(1, len, sample_rate);
+
+    let mut src = context.create_constant_source();
+    src.start();
+
+    // fade in across the quantum boundary
+    src.connect_at(
+        &context.destination(),
+        100. / sample_rate as f64,
+        50. / sample_rate as f64,
+    );
+
+    let output = context.start_rendering_sync();
+    let output = output.get_channel_data(0);
+
+    let mut expected = vec![0.; 100];
+    expected.extend((0..50).map(|i| i as f32 / 50.));
+    expected.resize(len, 1.);
+    assert_float_eq!(output, expected.as_slice(), abs_all <= 1e-6);
+}
+
+#[test]
+#[should_panic]
+fn test_scheduled_connect_invalid_time() {
+    let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 48000.);
+    let src = context.create_constant_source();
+    src.connect_at(&context.destination(), f64::NAN, 0.);
+}
+
+#[test]
+#[should_panic]
+fn test_transaction_at_invalid_time() {
+    let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 48000.);
+    context.transaction_at(-1., || ());
+}
+
+#[test]
+fn test_event_listeners() {
+    let mut context = OfflineAudioContext::new(1, 512, 44_100.);
+
+    let ended = Arc::new(AtomicUsize::new(0));
+    let mut src = context.create_constant_source();
+    src.connect(&context.destination());
+    src.start();
+    src.stop_at(128. / 44_100.);
+    for _ in 0..2 {
+        let ended = Arc::clone(&ended);
+        src.add_ended_listener(move |_| {
+            ended.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+    let ended_clone = Arc::clone(&ended);
+    src.set_onended(move |_| {
+        ended_clone.fetch_add(1, Ordering::SeqCst);
+    });
+    let removed = src.add_ended_listener(|_| panic!("listener was removed"));
+    context.remove_event_listener(removed);
+
+    let state_changes = Arc::new(AtomicUsize::new(0));
+    let state_changes_clone = Arc::clone(&state_changes);
+    context.add_event_listener(
+        AudioContextEventType::StateChange,
+        AddEventListenerOptions { once: true },
+        move |_| {
+            state_changes_clone.fetch_add(1, Ordering::SeqCst);
+        },
+    );
+
+    let _ = context.start_rendering_sync();
+
+    assert_eq!(ended.load(Ordering::SeqCst), 3);
+    assert_eq!(state_changes.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_event_streams() {
+    use futures::StreamExt;
+    use web_audio_api::context::AudioContextState;
+
+    let mut context = OfflineAudioContext::new(1, 512, 44_100.);
+    let states = context.state_change_stream();
+
+    let mut src = context.create_constant_source();
+    src.connect(&context.destination());
+    src.start();
+    src.stop_at(128. / 44_100.);
+    let ended = src.ended_stream();
+
+    // dropping a stream removes its listener
+    drop(context.state_change_stream());
+
+    let _ = context.start_rendering_sync();
+
+    let ended: Vec<_> = futures::executor::block_on(ended.collect());
+    assert_eq!(ended.len(), 1);
+    assert_eq!(ended[0].type_, "ended");
+
+    let states: Vec<_> = futures::executor::block_on(states.collect());
+    assert_eq!(
+        states,
+        [AudioContextState::Running, AudioContextState::Closed]
+    );
+}
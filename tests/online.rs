@@ -4,13 +4,21 @@
 //! using the 'none' audio backend.
 
 use web_audio_api::context::{
-    AudioContext, AudioContextOptions, AudioContextState, BaseAudioContext,
+    AudioContext, AudioContextInputOptions, AudioContextLatencyCategory, AudioContextOptions,
+    AudioContextRenderSizeCategory, AudioContextState, BaseAudioContext, FileSinkPacing,
+    OfflineAudioContext,
 };
-use web_audio_api::node::AudioNode;
+use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
 
+use std::cell::Cell;
+use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
-use web_audio_api::MAX_CHANNELS;
+use web_audio_api::{
+    AddEventListenerOptions, AudioBuffer, AudioContextEventType, ControlOverflowPolicy,
+    DomException, EventDispatchMode, MAX_CHANNELS,
+};
 
 fn require_send_sync_static<T: Send + Sync + 'static>(_: T) {}
 
@@ -129,6 +137,171 @@ fn test_none_sink_id() {
     assert_eq!(state_changes.load(Ordering::Relaxed), 4); // closed
 }
 
+#[test]
+fn test_output_timestamp() {
+    let options = AudioContextOptions {
+        sink_id: "none".into(),
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+
+    std::thread::sleep(Duration::from_millis(50));
+    let first = context.get_output_timestamp().unwrap();
+    assert!(first.context_time <= context.current_time());
+
+    std::thread::sleep(Duration::from_millis(50));
+    let second = context.get_output_timestamp().unwrap();
+    assert!(second.context_time > first.context_time);
+
+    // the 'none' backend follows an emulated clock, so both timelines advance at the same rate
+    let context_delta = second.context_time - first.context_time;
+    let performance_delta = second
+        .performance_time
+        .duration_since(first.performance_time)
+        .as_secs_f64();
+    assert!((context_delta - performance_delta).abs() < 1e-3);
+
+    // the timestamp does not advance while suspended
+    context.suspend_sync();
+    let suspended = context.get_output_timestamp().unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+    assert_eq!(context.get_output_timestamp().unwrap(), suspended);
+
+    context.close_sync();
+}
+
+#[test]
+fn test_playout_stats() {
+    let options = AudioContextOptions {
+        sink_id: "none".into(),
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+
+    std::thread::sleep(Duration::from_millis(50));
+    let stats = context.playout_stats();
+    assert!(stats.total_frames > 0);
+    assert!(stats.total_duration > 0.);
+    assert!(stats.estimated_underrun_frames <= stats.total_frames);
+    // the 'none' backend has no output latency
+    assert_eq!(stats.maximum_latency, 0.);
+
+    // frames are not counted while suspended
+    context.suspend_sync();
+    let suspended = context.playout_stats();
+    std::thread::sleep(Duration::from_millis(50));
+    assert_eq!(context.playout_stats().total_frames, suspended.total_frames);
+
+    context.close_sync();
+}
+
+#[test]
+fn test_control_overflow_grow() {
+    let options = AudioContextOptions {
+        sink_id: "none".into(),
+        control_overflow_policy: ControlOverflowPolicy::Grow,
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+    context.suspend_sync();
+
+    // the render thread does not drain the channel, but the control thread does not block
+    let nodes: Vec<_> = (0..500).map(|_| context.create_gain()).collect();
+    let stats = context.message_channel_stats();
+    assert!(stats.control_messages_queued >= 1000);
+    assert!(stats.control_messages_overflowed > 0);
+    assert!(stats.control_messages_pending > 0);
+    assert_eq!(stats.control_messages_rejected, 0);
+
+    // all messages reach the render thread after resuming
+    context.resume_sync();
+    let start = std::time::Instant::now();
+    while context.message_channel_stats().control_messages_pending > 0 {
+        assert!(start.elapsed() < Duration::from_secs(5));
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    drop(nodes);
+}
+
+#[test]
+fn test_control_overflow_reject() {
+    let options = AudioContextOptions {
+        sink_id: "none".into(),
+        control_overflow_policy: ControlOverflowPolicy::Reject,
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+    let gain = context.create_gain();
+    context.suspend_sync();
+
+    // the render thread does not drain the channel, the fallible methods reject the events
+    let accepted = (0..1000)
+        .take_while(|_| gain.gain().try_set_value_at_time(0.5, 0.).is_ok())
+        .count();
+    assert!(accepted < 1000);
+    let error = gain.gain().try_set_value(0.25).unwrap_err();
+    assert!(matches!(error, DomException::QuotaExceededError(_)));
+    assert_eq!(context.message_channel_stats().control_messages_rejected, 2);
+
+    // a rejected call has no effect on the control thread
+    assert_eq!(gain.gain().value(), 1.);
+
+    // infallible calls wait for the render thread instead
+    context.resume_sync();
+    gain.gain().set_value(0.25);
+    assert_eq!(gain.gain().value(), 0.25);
+    context.close_sync();
+}
+
+#[test]
+fn test_graph_diagnostics() {
+    let options = AudioContextOptions {
+        sink_id: "none".into(),
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+
+    let gain = context.create_gain();
+    gain.set_channel_count(1);
+    gain.connect(&context.destination());
+    let mut osc = context.create_oscillator();
+    osc.connect(&gain);
+    osc.start();
+    drop(osc);
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    context.graph_diagnostics(move |diagnostics| sender.send(diagnostics).unwrap());
+    let diagnostics = receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+
+    let find = |name: &str| {
+        let mut nodes = diagnostics
+            .nodes
+            .iter()
+            .filter(|node| node.name.ends_with(name));
+        let node = nodes.next().unwrap();
+        assert!(nodes.next().is_none());
+        node
+    };
+    let destination = find("DestinationRenderer");
+    let gain = find("GainRenderer");
+    let osc = find("OscillatorRenderer");
+
+    assert_eq!(gain.channel_count, 1);
+    assert_eq!(gain.outgoing_edges.len(), 1);
+    assert_eq!(gain.outgoing_edges[0].destination, destination.id);
+    assert_eq!(gain.outgoing_edges[0].input, Some(0));
+    assert!(!gain.control_handle_dropped);
+    assert!(osc.control_handle_dropped);
+    assert_eq!(osc.outgoing_edges[0].destination, gain.id);
+
+    // the oscillator renders before the gain, which renders before the destination
+    assert!(osc.render_order.unwrap() < gain.render_order.unwrap());
+    assert!(gain.render_order.unwrap() < destination.render_order.unwrap());
+    assert!(diagnostics.nodes.iter().all(|node| !node.muted));
+
+    context.close_sync();
+}
+
 #[test]
 fn test_weird_sample_rate() {
     let options = AudioContextOptions {
@@ -141,6 +314,407 @@ fn test_weird_sample_rate() {
     let _ = AudioContext::new(options);
 }
 
+#[test]
+fn test_render_size_hint() {
+    let options = AudioContextOptions {
+        sink_id: "none".into(),
+        render_size_hint: AudioContextRenderSizeCategory::Custom(256),
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+    assert_eq!(context.render_quantum_size(), 256);
+
+    // render size is kept when changing sink
+    context.set_sink_id_sync("none".into()).unwrap();
+    assert_eq!(context.render_quantum_size(), 256);
+
+    let options = AudioContextOptions {
+        sink_id: "none".into(),
+        latency_hint: AudioContextLatencyCategory::Balanced,
+        render_size_hint: AudioContextRenderSizeCategory::Hardware,
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+    assert_eq!(context.render_quantum_size(), 512);
+}
+
+#[test]
+fn test_pull_sink_id() {
+    let options = AudioContextOptions {
+        sink_id: "pull".into(),
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+    assert_eq!(context.sink_id(), "pull");
+
+    let mut src = context.create_constant_source();
+    src.offset().set_value(0.5);
+    src.connect(&context.destination());
+    src.start();
+
+    // the host application drives the rendering, with a buffer size unrelated to the render
+    // quantum size
+    let mut output = vec![0.; 3 * 100];
+    context.render_into(&mut output, 3);
+    assert!(output.chunks(3).all(|frame| frame == [0.5, 0.5, 0.]));
+    assert_eq!(context.state(), AudioContextState::Running);
+
+    for _ in 0..10 {
+        context.render_into(&mut output, 3);
+    }
+    // 1100 frames requested, rendered in chunks of 128 frames
+    assert_eq!(context.current_time(), 1152. / context.sample_rate() as f64);
+
+    // an invalid buffer layout yields silence rather than panicking in the host callback
+    output.fill(1.);
+    context.render_into(&mut output, 0);
+    assert!(output.iter().all(|&v| v == 0.));
+    context.render_into(&mut output[..299], 3);
+    assert_eq!(context.current_time(), 1152. / context.sample_rate() as f64);
+
+    // time does not progress when suspended
+    let done = AtomicBool::new(false);
+    std::thread::scope(|s| {
+        let host = s.spawn(|| {
+            let mut output = vec![0.; 2 * 100];
+            while !done.load(Ordering::SeqCst) {
+                context.render_into(&mut output, 2);
+            }
+        });
+        context.suspend_sync();
+        done.store(true, Ordering::SeqCst);
+        host.join().unwrap();
+    });
+    let suspended_time = context.current_time();
+    context.render_into(&mut output, 3);
+    assert!(output.iter().all(|&v| v == 0.));
+    assert_eq!(context.current_time(), suspended_time);
+
+    // switching to another sink stops the host rendering
+    done.store(false, Ordering::SeqCst);
+    std::thread::scope(|s| {
+        let host = s.spawn(|| {
+            let mut output = vec![0.; 2 * 100];
+            while !done.load(Ordering::SeqCst) {
+                context.render_into(&mut output, 2);
+            }
+        });
+        context.set_sink_id_sync("none".into()).unwrap();
+        done.store(true, Ordering::SeqCst);
+        host.join().unwrap();
+    });
+    assert_eq!(context.sink_id(), "none");
+    output.fill(1.);
+    context.render_into(&mut output, 3);
+    assert!(output.iter().all(|&v| v == 0.));
+}
+
+#[test]
+fn test_set_sink_id_with_queued_messages() {
+    let options = AudioContextOptions {
+        sink_id: "none".into(),
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+    context.suspend_sync();
+
+    // these messages are still queued when the sink is changed
+    let mut src = context.create_constant_source();
+    src.offset().set_value(0.5);
+    src.connect(&context.destination());
+    src.start();
+
+    context.set_sink_id_sync("loopback".into()).unwrap();
+    assert_eq!(context.state(), AudioContextState::Suspended);
+
+    let loopback = context.loopback();
+    context.resume_sync();
+    loopback.advance(128);
+    let block = loopback.try_recv().unwrap();
+    assert!(block.get_channel_data(0).iter().all(|&v| v == 0.5));
+
+    context.close_sync();
+}
+
+#[test]
+fn test_loopback_sink_id() {
+    let options = AudioContextOptions {
+        sink_id: "loopback".into(),
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+    assert_eq!(context.sink_id(), "loopback");
+    let loopback = context.loopback();
+
+    let mut src = context.create_constant_source();
+    src.offset().set_value(0.5);
+    src.connect(&context.destination());
+    src.start_at(512. / context.sample_rate() as f64);
+
+    // time only progresses when the virtual clock is advanced, rounded up to render quanta
+    loopback.advance(1000);
+    assert_eq!(context.current_time(), 1024. / context.sample_rate() as f64);
+
+    let blocks: Vec<_> = loopback.drain().collect();
+    assert_eq!(blocks.len(), 8);
+    let played: Vec<_> = blocks
+        .iter()
+        .flat_map(|block| block.get_channel_data(0).to_vec())
+        .collect();
+    assert!(played[..512].iter().all(|&v| v == 0.));
+    assert!(played[512..].iter().all(|&v| v == 0.5));
+    assert_eq!(blocks[0].number_of_channels(), 2);
+
+    // nothing is rendered when suspended
+    context.suspend_sync();
+    assert_eq!(context.state(), AudioContextState::Suspended);
+    let suspended_time = context.current_time();
+    loopback.advance(1000);
+    assert_eq!(context.current_time(), suspended_time);
+    assert!(loopback.try_recv().is_none());
+
+    context.resume_sync();
+    assert_eq!(context.state(), AudioContextState::Running);
+    loopback.advance(128);
+    assert_eq!(
+        context.current_time(),
+        suspended_time + 128. / context.sample_rate() as f64
+    );
+    let block = loopback.try_recv().unwrap();
+    assert!(block.get_channel_data(0).iter().all(|&v| v == 0.5));
+
+    // the handle is idle while another sink is active
+    context.set_sink_id_sync("none".into()).unwrap();
+    loopback.advance(128);
+    assert!(loopback.try_recv().is_none());
+
+    // wait for the none backend to start rendering, the context is suspended until then
+    while context.state() != AudioContextState::Running {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    // the audio graph is recycled when switching back
+    context.set_sink_id_sync("loopback".into()).unwrap();
+    loopback.advance(128);
+    let block = loopback.try_recv().unwrap();
+    assert!(block.get_channel_data(0).iter().all(|&v| v == 0.5));
+
+    context.close_sync();
+    assert_eq!(context.state(), AudioContextState::Closed);
+    loopback.advance(128);
+    assert!(loopback.try_recv().is_none());
+}
+
+#[test]
+fn test_loopback_microphone() {
+    let options = AudioContextOptions {
+        sink_id: "loopback".into(),
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+    let loopback = context.loopback();
+
+    let microphone = loopback.microphone();
+    let src = context.create_media_stream_source(&microphone);
+    src.connect(&context.destination());
+
+    // silence is played when there is no input
+    loopback.advance(128);
+    let block = loopback.try_recv().unwrap();
+    assert!(block.get_channel_data(0).iter().all(|&v| v == 0.));
+
+    let input = AudioBuffer::from(vec![vec![0.25; 256]], context.sample_rate());
+    loopback.push_input(input);
+    loopback.advance(384);
+
+    let played: Vec<_> = loopback
+        .drain()
+        .flat_map(|block| block.get_channel_data(0).to_vec())
+        .collect();
+    assert_eq!(played.len(), 384);
+    assert!(played[..256].iter().all(|&v| v == 0.25));
+    assert!(played[256..].iter().all(|&v| v == 0.));
+}
+
+#[test]
+fn test_duplex_input() {
+    let options = AudioContextOptions {
+        sink_id: "loopback".into(),
+        input: Some(AudioContextInputOptions {
+            number_of_channels: Some(2),
+            ..AudioContextInputOptions::default()
+        }),
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+    let loopback = context.loopback();
+
+    let src = context.create_input_source();
+    src.connect(&context.destination());
+
+    // the input is played in the render quantum it is captured in
+    let left: Vec<f32> = (0..256).map(|i| i as f32 / 256.).collect();
+    let right: Vec<f32> = left.iter().map(|v| -v).collect();
+    let input = AudioBuffer::from(vec![left.clone(), right.clone()], context.sample_rate());
+    loopback.push_input(input);
+    loopback.advance(384);
+
+    let blocks: Vec<_> = loopback.drain().collect();
+    let played_left: Vec<f32> = blocks
+        .iter()
+        .flat_map(|block| block.get_channel_data(0).to_vec())
+        .collect();
+    let played_right: Vec<f32> = blocks
+        .iter()
+        .flat_map(|block| block.get_channel_data(1).to_vec())
+        .collect();
+    assert_eq!(played_left.len(), 384);
+    assert_eq!(&played_left[..256], &left[..]);
+    assert_eq!(&played_right[..256], &right[..]);
+    assert!(played_left[256..].iter().all(|&v| v == 0.));
+    assert_eq!(context.input_latency(), 0.);
+
+    // the input is retained when the sink is changed
+    for sink_id in ["none", "loopback"] {
+        context.set_sink_id_sync(sink_id.into()).unwrap();
+        while context.state() != AudioContextState::Running {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+    let src = context.create_input_source();
+    src.connect(&context.destination());
+
+    let input = AudioBuffer::from(vec![vec![0.5; 128]], context.sample_rate());
+    loopback.push_input(input);
+    loopback.advance(128);
+    let block = loopback.drain().last().unwrap();
+    // two sources play the same input, the missing right channel is silent
+    assert!(block.get_channel_data(0).iter().all(|&v| v == 1.));
+    assert!(block.get_channel_data(1).iter().all(|&v| v == 0.));
+}
+
+#[test]
+#[should_panic]
+fn test_input_source_without_input() {
+    let options = AudioContextOptions {
+        sink_id: "none".into(),
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+    let _ = context.create_input_source();
+}
+
+#[test]
+fn test_file_sink_id() {
+    let path = std::env::temp_dir().join("web_audio_api_test_file_sink_id.wav");
+    let sink_id = format!("file:{}", path.display());
+    let options = AudioContextOptions {
+        sink_id: sink_id.clone(),
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+    assert_eq!(context.sink_id(), sink_id);
+
+    let mut src = context.create_constant_source();
+    src.offset().set_value(0.5);
+    src.connect(&context.destination());
+    src.start();
+
+    std::thread::sleep(Duration::from_millis(50));
+    assert_eq!(context.state(), AudioContextState::Running);
+    context.suspend_sync();
+    let played_frames = (context.current_time() * context.sample_rate() as f64) as usize;
+
+    // the file is finalized when switching to another sink
+    context.set_sink_id_sync("none".into()).unwrap();
+    context.close_sync();
+
+    let file = std::fs::File::open(&path).unwrap();
+    let output = context.decode_audio_data_sync(file).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(output.number_of_channels(), 2);
+    assert_eq!(output.sample_rate(), context.sample_rate());
+
+    // silence is played while the graph is starting up, or when rendering is too slow
+    let played = output.get_channel_data(0);
+    assert!(played.len() >= played_frames);
+    assert!(played.iter().all(|&v| v == 0. || v == 0.5));
+    assert!(played.contains(&0.5));
+
+    // only supported file formats in existing directories are accepted
+    assert!(context.set_sink_id_sync("file:out.mp3".into()).is_err());
+    let path = std::env::temp_dir().join("web_audio_api_does_not_exist/out.wav");
+    assert!(context
+        .set_sink_id_sync(format!("file:{}", path.display()))
+        .is_err());
+}
+
+#[test]
+fn test_file_sink_finalized_on_drop() {
+    let path = std::env::temp_dir().join("web_audio_api_test_file_sink_finalized_on_drop.wav");
+    let options = AudioContextOptions {
+        sink_id: format!("file:{}", path.display()),
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+    while context.state() != AudioContextState::Running {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    std::thread::sleep(Duration::from_millis(20));
+
+    // the running context is dropped, the file is complete nonetheless
+    drop(context);
+    let file = std::fs::File::open(&path).unwrap();
+    let decoder = OfflineAudioContext::new(1, 1, 44100.);
+    let output = decoder.decode_audio_data_sync(file).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(output.length() > 0);
+}
+
+#[test]
+fn test_file_sink_free_running() {
+    let path = std::env::temp_dir().join("web_audio_api_test_file_sink_free_running.flac");
+    let options = AudioContextOptions {
+        sink_id: format!("file:{}", path.display()),
+        file_sink_pacing: FileSinkPacing::FreeRunning,
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+    while context.state() != AudioContextState::Running {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    context.suspend_sync();
+
+    let start_time = context.current_time();
+    let mut src = context.create_constant_source();
+    src.offset().set_value(0.5);
+    src.connect(&context.destination());
+    src.start_at(start_time);
+
+    // rendering is not limited to real time
+    let start = std::time::Instant::now();
+    context.resume_sync();
+    while context.current_time() < start_time + 2. {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    context.suspend_sync();
+    assert!(start.elapsed() < Duration::from_secs(2));
+
+    let played_time = context.current_time() - start_time;
+    let played_frames = (played_time * context.sample_rate() as f64).round() as usize;
+    context.close_sync();
+
+    let file = std::fs::File::open(&path).unwrap();
+    let output = context.decode_audio_data_sync(file).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // all frames played after the resume are recorded, there are no underruns
+    let played = output.get_channel_data(0);
+    let count = played.iter().filter(|&&v| v == 0.5).count();
+    assert_eq!(count, played_frames);
+}
+
 #[test]
 fn test_channels() {
     let options = AudioContextOptions {
@@ -295,3 +869,40 @@ fn test_suspend_then_close() {
     context.close_sync();
     assert_eq!(context.state(), AudioContextState::Closed);
 }
+
+#[test]
+fn test_manual_event_dispatch() {
+    let options = AudioContextOptions {
+        sink_id: "none".into(),
+        event_dispatch_mode: EventDispatchMode::Manual,
+        ..AudioContextOptions::default()
+    };
+    let context = AudioContext::new(options);
+
+    // local listeners need not be Send
+    let state_changes = Rc::new(Cell::new(0));
+    let state_changes_clone = Rc::clone(&state_changes);
+    context.add_local_event_listener(
+        AudioContextEventType::StateChange,
+        AddEventListenerOptions::default(),
+        move |_| state_changes_clone.set(state_changes_clone.get() + 1),
+    );
+
+    let handled = Arc::new(AtomicBool::new(false));
+    let handled_clone = Arc::clone(&handled);
+    context.set_onstatechange(move |_| handled_clone.store(true, Ordering::SeqCst));
+
+    // no events are handled without dispatching
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(!handled.load(Ordering::SeqCst));
+
+    // wait for the render thread to start
+    let start = std::time::Instant::now();
+    while state_changes.get() == 0 && start.elapsed() < Duration::from_secs(1) {
+        context.dispatch_pending_events();
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert!(handled.load(Ordering::SeqCst));
+    assert_eq!(state_changes.get(), 1); // started
+    assert!(!context.dispatch_pending_events());
+}